@@ -50,7 +50,7 @@ use isolate::{
 use keybroker::Identity;
 use model::{
     file_storage::FileStorageId,
-    session_requests::types::SessionRequestIdentifier,
+    session_requests::types::MutationIdentifier,
 };
 use serde_json::Value as JsonValue;
 use sync_types::{
@@ -138,7 +138,7 @@ pub trait ApplicationApi: Send + Sync {
         args: Vec<JsonValue>,
         caller: FunctionCaller,
         // Identifier used to make this mutation idempotent.
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>>;
 
     /// Execute an admin mutation for a particular component for the dashboard.
@@ -150,7 +150,7 @@ pub trait ApplicationApi: Send + Sync {
         path: CanonicalizedComponentFunctionPath,
         args: Vec<JsonValue>,
         caller: FunctionCaller,
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>>;
 
     /// Execute a public action on the root app.
@@ -341,7 +341,7 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         args: Vec<JsonValue>,
         caller: FunctionCaller,
         // Identifier used to make this mutation idempotent.
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>> {
         anyhow::ensure!(
             caller.allowed_visibility() == AllowedVisibility::PublicOnly,
@@ -367,7 +367,7 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         path: CanonicalizedComponentFunctionPath,
         args: Vec<JsonValue>,
         caller: FunctionCaller,
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>> {
         anyhow::ensure!(
             path.component.is_root() || identity.is_admin() || identity.is_system(),
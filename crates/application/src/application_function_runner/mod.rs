@@ -33,6 +33,7 @@ use common::{
     errors::JsError,
     execution_context::ExecutionContext,
     http::fetch::FetchClient,
+    identity::InertIdentity,
     knobs::{
         APPLICATION_FUNCTION_RUNNER_SEMAPHORE_TIMEOUT,
         APPLICATION_MAX_CONCURRENT_HTTP_ACTIONS,
@@ -41,6 +42,7 @@ use common::{
         APPLICATION_MAX_CONCURRENT_QUERIES,
         APPLICATION_MAX_CONCURRENT_V8_ACTIONS,
         BACKEND_ISOLATE_ACTIVE_THREADS_PERCENT,
+        IDEMPOTENCY_KEY_TTL,
         ISOLATE_MAX_USER_HEAP_SIZE,
         UDF_EXECUTOR_OCC_INITIAL_BACKOFF,
         UDF_EXECUTOR_OCC_MAX_BACKOFF,
@@ -145,6 +147,13 @@ use model::{
         types::FileStorageEntry,
         FileStorageId,
     },
+    idempotent_requests::{
+        types::{
+            IdempotencyKeyScope,
+            IdempotentRequestRecord,
+        },
+        IdempotentRequestModel,
+    },
     modules::{
         module_versions::{
             AnalyzedModule,
@@ -156,7 +165,7 @@ use model::{
     scheduled_jobs::VirtualSchedulerModel,
     session_requests::{
         types::{
-            SessionRequestIdentifier,
+            MutationIdentifier,
             SessionRequestOutcome,
             SessionRequestRecord,
         },
@@ -731,7 +740,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         path: PublicFunctionPath,
         arguments: Vec<JsonValue>,
         identity: Identity,
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
         caller: FunctionCaller,
         pause_client: PauseClient,
     ) -> anyhow::Result<Result<MutationReturn, MutationError>> {
@@ -762,7 +771,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         path: PublicFunctionPath,
         arguments: Vec<JsonValue>,
         identity: Identity,
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
         caller: FunctionCaller,
         pause_client: PauseClient,
     ) -> anyhow::Result<Result<MutationReturn, MutationError>> {
@@ -801,7 +810,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
 
             // Return the previous execution's result if the mutation was committed already.
             if let Some(result) = self
-                .check_mutation_status(&mut tx, &mutation_identifier)
+                .check_mutation_status(&mut tx, &mutation_identifier, &path, &arguments, &identity)
                 .await?
             {
                 return Ok(result);
@@ -834,7 +843,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
 
             // Save a CommittedMutation object so we won't rerun this mutation if
             // successful.
-            self.write_mutation_status(&mut tx, &mutation_identifier, &outcome)
+            self.write_mutation_status(&mut tx, &mutation_identifier, &path, &arguments, &outcome)
                 .await?;
 
             let stats = tx.take_stats();
@@ -1733,14 +1742,46 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
     async fn check_mutation_status(
         &self,
         tx: &mut Transaction<RT>,
-        mutation_identifier: &Option<SessionRequestIdentifier>,
+        mutation_identifier: &Option<MutationIdentifier>,
+        path: &PublicFunctionPath,
+        arguments: &ConvexArray,
+        identity: &InertIdentity,
     ) -> anyhow::Result<Option<Result<MutationReturn, MutationError>>> {
         let Some(ref identifier) = mutation_identifier else {
             return Ok(None);
         };
-        let mutation_status = SessionRequestModel::new(tx)
-            .get_session_request_record(identifier, Identity::system())
-            .await?;
+        let mutation_status = match identifier {
+            MutationIdentifier::Session(identifier) => {
+                SessionRequestModel::new(tx)
+                    .get_session_request_record(identifier, Identity::system())
+                    .await?
+            },
+            MutationIdentifier::IdempotencyKey(key) => {
+                let scope = IdempotencyKeyScope::new(key.clone(), identity.clone(), path);
+                let Some((ts, record)) = IdempotentRequestModel::new(tx)
+                    .get_idempotent_request(&scope, Identity::system())
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                // Expired records are replaced when this execution commits.
+                if record.expires_ts <= *tx.begin_timestamp() {
+                    return Ok(None);
+                }
+                anyhow::ensure!(
+                    record.args_hash == IdempotentRequestRecord::hash_args(arguments)?,
+                    ErrorMetadata::bad_request(
+                        "IdempotencyKeyArgsMismatch",
+                        format!(
+                            "Idempotency key {key} was already used to call {} with different \
+                             arguments",
+                            path.udf_path()
+                        ),
+                    )
+                );
+                Some((ts, record.into_value().outcome))
+            },
+        };
         let result = match mutation_status {
             Some((ts, SessionRequestOutcome::Mutation { result, log_lines })) => {
                 tracing::info!("Mutation already executed so skipping {:?}", identifier);
@@ -1760,25 +1801,47 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
     async fn write_mutation_status(
         &self,
         tx: &mut Transaction<RT>,
-        mutation_identifier: &Option<SessionRequestIdentifier>,
+        mutation_identifier: &Option<MutationIdentifier>,
+        path: &PublicFunctionPath,
+        arguments: &ConvexArray,
         outcome: &ValidatedUdfOutcome,
     ) -> anyhow::Result<()> {
         let Some(ref identifier) = mutation_identifier else {
             return Ok(());
         };
         if let Ok(ref value) = outcome.result {
-            let record = SessionRequestRecord {
-                session_id: identifier.session_id,
-                request_id: identifier.request_id,
-                outcome: SessionRequestOutcome::Mutation {
-                    result: value.unpack(),
-                    log_lines: outcome.log_lines.clone(),
-                },
-                identity: outcome.identity.clone(),
+            let request_outcome = SessionRequestOutcome::Mutation {
+                result: value.unpack(),
+                log_lines: outcome.log_lines.clone(),
             };
-            SessionRequestModel::new(tx)
-                .record_session_request(record, Identity::system())
-                .await?;
+            match identifier {
+                MutationIdentifier::Session(identifier) => {
+                    let record = SessionRequestRecord {
+                        session_id: identifier.session_id,
+                        request_id: identifier.request_id,
+                        outcome: request_outcome,
+                        identity: outcome.identity.clone(),
+                    };
+                    SessionRequestModel::new(tx)
+                        .record_session_request(record, Identity::system())
+                        .await?;
+                },
+                MutationIdentifier::IdempotencyKey(key) => {
+                    let record = IdempotentRequestRecord {
+                        scope: IdempotencyKeyScope::new(
+                            key.clone(),
+                            outcome.identity.clone(),
+                            path,
+                        ),
+                        args_hash: IdempotentRequestRecord::hash_args(arguments)?,
+                        outcome: request_outcome,
+                        expires_ts: tx.begin_timestamp().add(*IDEMPOTENCY_KEY_TTL)?,
+                    };
+                    IdempotentRequestModel::new(tx)
+                        .record_idempotent_request(record, Identity::system())
+                        .await?;
+                },
+            }
         }
         Ok(())
     }
@@ -1954,6 +2017,7 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
         udf_args: Vec<JsonValue>,
         scheduled_ts: UnixTimestamp,
         context: ExecutionContext,
+        dedup_key: Option<String>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         let (_ts, virtual_id, _stats) = self
             .database
@@ -1966,6 +2030,7 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                     let path = scheduled_path.clone();
                     let args = udf_args.clone();
                     let context = context.clone();
+                    let dedup_key = dedup_key.clone();
                     async move {
                         let (path, udf_args) = validate_schedule_args(
                             path,
@@ -1979,7 +2044,7 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                         .await?;
                         let virtual_id =
                             VirtualSchedulerModel::new(tx, scheduling_component.into())
-                                .schedule(path, udf_args, scheduled_ts, context)
+                                .schedule(path, udf_args, scheduled_ts, context, dedup_key)
                                 .await?;
                         Ok(virtual_id)
                     }
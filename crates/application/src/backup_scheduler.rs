@@ -0,0 +1,474 @@
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    document::ParsedDocument,
+    errors::report_error,
+    maybe_val,
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+    },
+    runtime::Runtime,
+    types::Timestamp,
+};
+use database::{
+    Database,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use futures::{
+    future::Either,
+    select_biased,
+    Future,
+    FutureExt,
+};
+use keybroker::Identity;
+use model::exports::{
+    types::{
+        Export,
+        ExportFilter,
+        ExportFormat,
+        ExportObjectKeys,
+        ExportRequestor,
+    },
+    EXPORTS_BY_STATE_AND_TS_INDEX,
+    EXPORTS_STATE_FIELD,
+    EXPORTS_TABLE,
+};
+use storage::Storage;
+use value::TableNamespace;
+
+use crate::export_worker::ExportWorker;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often to back up the deployment, and which backups to keep.
+#[derive(Clone, Debug)]
+pub struct BackupPolicy {
+    /// Time between the snapshots of consecutive backups.
+    pub interval: Duration,
+    /// Whether backups include file storage.
+    pub include_storage: bool,
+    /// The components and tables to back up.
+    pub filter: ExportFilter,
+    /// Keep the latest backup from each of this many most recent days.
+    pub daily_copies: usize,
+    /// Keep the latest backup from each of this many most recent weeks.
+    pub weekly_copies: usize,
+}
+
+/// Requests a zip export every `interval`, and deletes the backups that the
+/// policy no longer keeps. The export worker does the exporting.
+pub struct BackupScheduler<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    storage: Arc<dyn Storage>,
+    policy: BackupPolicy,
+}
+
+impl<RT: Runtime> BackupScheduler<RT> {
+    pub fn start(
+        runtime: RT,
+        database: Database<RT>,
+        storage: Arc<dyn Storage>,
+        policy: BackupPolicy,
+    ) -> impl Future<Output = ()> + Send {
+        let scheduler = Self {
+            runtime,
+            database,
+            storage,
+            policy,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = scheduler.run(&mut backoff).await {
+                let delay = scheduler.runtime.with_rng(|rng| backoff.fail(rng));
+                tracing::error!("Backup scheduler failed, sleeping {delay:?}");
+                report_error(&mut e);
+                scheduler.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let backups = list_backups(&mut tx).await?;
+            let keep = backups_to_keep(&self.policy, backups.iter().map(backup_snapshot_ts));
+            let expired: Vec<_> = backups
+                .iter()
+                .filter(|backup| !keep.contains(&backup_snapshot_ts(backup)))
+                .collect();
+            if !expired.is_empty() {
+                tracing::info!("Deleting {} expired backups", expired.len());
+                // Delete the objects first so a failure leaves the backups
+                // listed, to be deleted on the next attempt.
+                for backup in &expired {
+                    delete_backup_object(self.storage.as_ref(), backup).await?;
+                }
+                let mut tx = self.database.begin(Identity::system()).await?;
+                for backup in expired {
+                    SystemMetadataModel::new_global(&mut tx)
+                        .delete(backup.id())
+                        .await?;
+                }
+                self.database
+                    .commit_with_write_source(tx, "backup_scheduler_delete_expired")
+                    .await?;
+                continue;
+            }
+
+            // Failed backups count towards the interval so that a backup that
+            // keeps failing isn't retried in a tight loop.
+            let last_failed_ts = exports_in_state(&mut tx, "failed")
+                .await?
+                .into_iter()
+                .filter(|export| export.requestor() == ExportRequestor::ScheduledBackup)
+                .map(|export| backup_snapshot_ts(&export))
+                .max();
+            let last_backup_ts = backups.first().map(backup_snapshot_ts).max(last_failed_ts);
+            let export_pending = ExportWorker::export_in_state(&mut tx, "requested")
+                .await?
+                .is_some()
+                || ExportWorker::export_in_state(&mut tx, "in_progress")
+                    .await?
+                    .is_some();
+
+            // If another export is pending, wait for it to finish before
+            // requesting the next backup.
+            let next_backup_future = if export_pending {
+                Either::Right(std::future::pending())
+            } else {
+                let now = self.runtime.generate_timestamp()?;
+                let next_backup_ts = match last_backup_ts {
+                    Some(ts) => ts.add(self.policy.interval)?,
+                    None => now,
+                };
+                if next_backup_ts <= now {
+                    tracing::info!("Requesting scheduled backup");
+                    let format = ExportFormat::Zip {
+                        include_storage: self.policy.include_storage,
+                    };
+                    SystemMetadataModel::new_global(&mut tx)
+                        .insert(
+                            &EXPORTS_TABLE,
+                            Export::requested(
+                                format,
+                                ExportRequestor::ScheduledBackup,
+                                self.policy.filter.clone(),
+                                None,
+                            )
+                            .try_into()?,
+                        )
+                        .await?;
+                    self.database
+                        .commit_with_write_source(tx, "backup_scheduler_request_backup")
+                        .await?;
+                    continue;
+                }
+                Either::Left(self.runtime.wait(next_backup_ts - now))
+            };
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            select_biased! {
+                _ = next_backup_future.fuse() => {
+                }
+                _ = subscription.wait_for_invalidation().fuse() => {
+                },
+            }
+            backoff.reset();
+        }
+    }
+}
+
+async fn exports_in_state<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    export_state: &str,
+) -> anyhow::Result<Vec<ParsedDocument<Export>>> {
+    let index_range = IndexRange {
+        index_name: EXPORTS_BY_STATE_AND_TS_INDEX.clone(),
+        range: vec![IndexRangeExpression::Eq(
+            EXPORTS_STATE_FIELD.clone(),
+            maybe_val!(export_state),
+        )],
+        order: Order::Desc,
+    };
+    let query = common::query::Query::index_range(index_range);
+    let mut query_stream = ResolvedQuery::new(tx, TableNamespace::Global, query)?;
+    let mut exports = vec![];
+    while let Some(doc) = query_stream.next(tx, None).await? {
+        exports.push(doc.try_into()?);
+    }
+    Ok(exports)
+}
+
+/// Completed scheduled backups, newest first.
+pub async fn list_backups<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+) -> anyhow::Result<Vec<ParsedDocument<Export>>> {
+    let backups = exports_in_state(tx, "completed")
+        .await?
+        .into_iter()
+        .filter(|export| export.requestor() == ExportRequestor::ScheduledBackup)
+        .collect();
+    Ok(backups)
+}
+
+/// Deletes the zip of a completed backup from exports storage.
+pub async fn delete_backup_object(storage: &dyn Storage, backup: &Export) -> anyhow::Result<()> {
+    match backup {
+        Export::Completed {
+            object_keys: ExportObjectKeys::Zip(object_key),
+            ..
+        } => storage.delete_object(object_key).await,
+        _ => anyhow::bail!("Backup {backup} doesn't have a zip to delete"),
+    }
+}
+
+fn backup_snapshot_ts(backup: &ParsedDocument<Export>) -> Timestamp {
+    match **backup {
+        Export::InProgress { start_ts, .. }
+        | Export::Completed { start_ts, .. }
+        | Export::Failed { start_ts, .. } => start_ts,
+        Export::Requested { .. } => Timestamp::MIN,
+    }
+}
+
+/// Returns the snapshot timestamps of the backups that `policy` keeps: the
+/// latest backup in each of the `daily_copies` most recent days and the
+/// `weekly_copies` most recent weeks that have a backup. The latest backup is
+/// always kept.
+fn backups_to_keep(
+    policy: &BackupPolicy,
+    snapshot_tss: impl IntoIterator<Item = Timestamp>,
+) -> BTreeSet<Timestamp> {
+    let mut snapshot_tss: Vec<_> = snapshot_tss.into_iter().collect();
+    snapshot_tss.sort_by(|a, b| b.cmp(a));
+
+    let mut days = BTreeSet::new();
+    let mut weeks = BTreeSet::new();
+    let mut keep = BTreeSet::new();
+    for (i, ts) in snapshot_tss.into_iter().enumerate() {
+        let day = u64::from(ts) / DAY.as_nanos() as u64;
+        let week = day / 7;
+        let mut kept = i == 0;
+        if days.len() < policy.daily_copies && days.insert(day) {
+            kept = true;
+        }
+        if weeks.len() < policy.weekly_copies && weeks.insert(week) {
+            kept = true;
+        }
+        if kept {
+            keep.insert(ts);
+        }
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use common::{
+        document::ParsedDocument,
+        runtime::Runtime,
+        types::Timestamp,
+    };
+    use database::{
+        test_helpers::DbFixtures,
+        Database,
+        SystemMetadataModel,
+    };
+    use keybroker::Identity;
+    use model::exports::{
+        types::{
+            Export,
+            ExportFilter,
+            ExportFormat,
+            ExportObjectKeys,
+            ExportRequestor,
+        },
+        EXPORTS_TABLE,
+    };
+    use runtime::testing::TestRuntime;
+    use storage::{
+        LocalDirStorage,
+        Storage,
+        Upload,
+    };
+
+    use super::{
+        backup_snapshot_ts,
+        backups_to_keep,
+        exports_in_state,
+        list_backups,
+        BackupPolicy,
+        BackupScheduler,
+        DAY,
+    };
+
+    fn requested_backup() -> Export {
+        Export::requested(
+            ExportFormat::Zip {
+                include_storage: false,
+            },
+            ExportRequestor::ScheduledBackup,
+            ExportFilter::default(),
+            None,
+        )
+    }
+
+    async fn upload_backup(storage: &Arc<dyn Storage>) -> anyhow::Result<ExportObjectKeys> {
+        let mut upload = storage.start_upload().await?;
+        upload.write(Bytes::from_static(b"backup")).await?;
+        Ok(ExportObjectKeys::Zip(upload.complete().await?))
+    }
+
+    async fn wait_for_requested_export(
+        rt: &TestRuntime,
+        db: &Database<TestRuntime>,
+    ) -> anyhow::Result<ParsedDocument<Export>> {
+        loop {
+            let mut tx = db.begin(Identity::system()).await?;
+            if let Some(export) = exports_in_state(&mut tx, "requested").await?.pop() {
+                return Ok(export);
+            }
+            rt.wait(Duration::from_secs(10 * 60)).await;
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_backup_scheduler(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let policy = BackupPolicy {
+            interval: Duration::from_secs(6 * 60 * 60),
+            include_storage: false,
+            filter: ExportFilter {
+                exclude_tables: BTreeSet::from(["logs".parse()?]),
+                ..Default::default()
+            },
+            daily_copies: 1,
+            weekly_copies: 0,
+        };
+
+        // Backups from each of the last three days, of which only the latest
+        // is kept.
+        let now = rt.generate_timestamp()?;
+        let mut object_keys = vec![];
+        for days_ago in [3, 2, 1] {
+            let snapshot_ts = now.sub(DAY * days_ago)?;
+            let object_key = upload_backup(&storage).await?;
+            let backup = requested_backup().in_progress(snapshot_ts)?.completed(
+                snapshot_ts,
+                snapshot_ts,
+                object_key.clone(),
+            )?;
+            let mut tx = db.begin(Identity::system()).await?;
+            SystemMetadataModel::new_global(&mut tx)
+                .insert(&EXPORTS_TABLE, backup.try_into()?)
+                .await?;
+            db.commit(tx).await?;
+            object_keys.push(object_key);
+        }
+
+        let _handle = rt.spawn(
+            "backup_scheduler",
+            BackupScheduler::start(rt.clone(), db.clone(), storage.clone(), policy.clone()),
+        );
+
+        // The expired backups are deleted, and since the latest backup is
+        // older than the interval, the next one is requested right away.
+        let requested = wait_for_requested_export(&rt, &db).await?;
+        assert_eq!(requested.requestor(), ExportRequestor::ScheduledBackup);
+        assert_eq!(requested.filter(), &policy.filter);
+        let mut tx = db.begin(Identity::system()).await?;
+        let backups = list_backups(&mut tx).await?;
+        assert_eq!(
+            backups.iter().map(backup_snapshot_ts).collect::<Vec<_>>(),
+            vec![now.sub(DAY)?]
+        );
+        for (i, object_key) in object_keys.iter().enumerate() {
+            let ExportObjectKeys::Zip(object_key) = object_key else {
+                unreachable!();
+            };
+            let attributes = storage.get_object_attributes(object_key).await?;
+            assert_eq!(attributes.is_some(), i == 2);
+        }
+
+        // Once the export worker completes the backup, the next one is only
+        // requested after the interval.
+        let snapshot_ts = rt.generate_timestamp()?;
+        let (id, requested) = requested.into_id_and_value();
+        let completed = requested.in_progress(snapshot_ts)?.completed(
+            snapshot_ts,
+            snapshot_ts,
+            upload_backup(&storage).await?,
+        )?;
+        let mut tx = db.begin(Identity::system()).await?;
+        SystemMetadataModel::new_global(&mut tx)
+            .replace(id, completed.try_into()?)
+            .await?;
+        db.commit(tx).await?;
+
+        wait_for_requested_export(&rt, &db).await?;
+        assert!(rt.generate_timestamp()? >= snapshot_ts.add(policy.interval)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_backups_to_keep() -> anyhow::Result<()> {
+        let policy = BackupPolicy {
+            interval: Duration::from_secs(6 * 60 * 60),
+            include_storage: false,
+            filter: ExportFilter::default(),
+            daily_copies: 3,
+            weekly_copies: 2,
+        };
+        // Four backups a day for 21 days, starting on a week boundary.
+        let start = Timestamp::try_from(DAY.as_nanos() as u64 * 7 * 1000)?;
+        let snapshot_tss: Vec<_> = (0..21 * 4)
+            .map(|i| start.add(policy.interval * i))
+            .collect::<anyhow::Result<_>>()?;
+        let backup = |day: u32, i: u32| start.add(DAY * day + policy.interval * i);
+
+        let expected: BTreeSet<_> = [
+            // The latest backup of each of the last three days.
+            backup(20, 3)?,
+            backup(19, 3)?,
+            backup(18, 3)?,
+            // The latest backup of the previous week.
+            backup(13, 3)?,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(backups_to_keep(&policy, snapshot_tss.clone()), expected);
+
+        let policy = BackupPolicy {
+            daily_copies: 0,
+            weekly_copies: 0,
+            ..policy
+        };
+        assert_eq!(
+            backups_to_keep(&policy, snapshot_tss),
+            [backup(20, 3)?].into_iter().collect()
+        );
+        assert!(backups_to_keep(&policy, vec![]).is_empty());
+        Ok(())
+    }
+}
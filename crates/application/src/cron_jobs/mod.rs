@@ -3,6 +3,7 @@ use std::{
         BTreeMap,
         HashSet,
     },
+    mem,
     sync::Arc,
     time::Duration,
 };
@@ -23,6 +24,7 @@ use common::{
     execution_context::ExecutionContext,
     identity::InertIdentity,
     knobs::{
+        CRON_MAX_CONCURRENT_RUNS,
         SCHEDULED_JOB_EXECUTION_PARALLELISM,
         UDF_EXECUTOR_OCC_MAX_RETRIES,
     },
@@ -72,6 +74,7 @@ use model::{
             CronJobResult,
             CronJobState,
             CronJobStatus,
+            CronOverlapPolicy,
         },
         CronModel,
         CRON_JOBS_INDEX_BY_NEXT_TS,
@@ -88,13 +91,19 @@ use value::{
 
 use crate::{
     application_function_runner::ApplicationFunctionRunner,
-    function_log::FunctionExecutionLog,
+    function_log::{
+        ActionCompletion,
+        FunctionExecutionLog,
+    },
 };
 
 mod metrics;
 
 const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
 const MAX_BACKOFF: Duration = Duration::from_secs(5);
+// How many times to try to clean up a component's interrupted concurrent cron
+// runs on startup before leaving them for the next restart.
+const INTERRUPTED_RUNS_MAX_ATTEMPTS: u32 = 5;
 
 // Truncate result and log lines for cron job logs since they are only
 // used for the dashboard
@@ -129,6 +138,13 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         };
         async move {
             let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = executor.fail_interrupted_concurrent_runs().await {
+                report_error(&mut e);
+                let delay = executor.rt.with_rng(|rng| backoff.fail(rng));
+                tracing::error!("Failed to clean up interrupted cron runs, sleeping {delay:?}");
+                executor.rt.wait(delay).await;
+            }
+            backoff.reset();
             while let Err(mut e) = executor.run(&mut backoff).await {
                 // Only report OCCs that happen repeatedly
                 if !e.is_occ() || (backoff.failures() as usize) > *UDF_EXECUTOR_OCC_MAX_RETRIES {
@@ -493,6 +509,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                 &job,
                 UdfType::Mutation,
                 context.clone(),
+                None,
             )
             .await?;
             if let Err(err) = self
@@ -530,6 +547,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                 &job,
                 UdfType::Mutation,
                 context.clone(),
+                None,
             )
             .await?;
             // NOTE: We should not be getting developer errors here.
@@ -568,22 +586,98 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         let caller = FunctionCaller::Cron;
         match job.state {
             CronJobState::Pending => {
+                let context = ExecutionContext::new(request_id, &caller);
+                let path = CanonicalizedComponentFunctionPath {
+                    component: component_path,
+                    udf_path: job.cron_spec.udf_path.clone(),
+                };
+                if job.cron_spec.overlap_policy == CronOverlapPolicy::AllowConcurrent
+                    && job.concurrent_runs.len() >= *CRON_MAX_CONCURRENT_RUNS
+                {
+                    // Don't let runs of an action that doesn't finish pile up.
+                    tracing::info!(
+                        "Skipping run of {} because {} runs are still in progress",
+                        job.name,
+                        job.concurrent_runs.len()
+                    );
+                    let log_lines = CronJobLogLines {
+                        log_lines: vec![].into(),
+                        is_truncated: false,
+                    };
+                    CronModel::new(&mut tx, component)
+                        .insert_cron_job_log(
+                            &job,
+                            CronJobStatus::Skipped { num_skipped: 1 },
+                            log_lines,
+                            0.0,
+                        )
+                        .await?;
+                    self.complete_job_run(
+                        identity.into(),
+                        &mut tx,
+                        job_id,
+                        &job,
+                        UdfType::Action,
+                        context,
+                        None,
+                    )
+                    .await?;
+                    self.database
+                        .commit_with_write_source(tx, "cron_skip_concurrent_action")
+                        .await?;
+                    return Ok(());
+                }
+                if job.cron_spec.overlap_policy == CronOverlapPolicy::AllowConcurrent {
+                    // Reschedule the cron before running the action so the next
+                    // run starts on time even if this one is still in progress.
+                    // The run is tracked in `concurrent_runs` until it finishes
+                    // so it can be logged as failed if a restart interrupts it.
+                    let mut started_job = job.clone();
+                    started_job.concurrent_runs.push(job.next_ts);
+                    self.complete_job_run(
+                        identity.clone().into(),
+                        &mut tx,
+                        job_id,
+                        &started_job,
+                        UdfType::Action,
+                        context.clone(),
+                        None,
+                    )
+                    .await?;
+                    self.database
+                        .commit_with_write_source(tx, "cron_reschedule_concurrent_action")
+                        .await?;
+                    let executor = self.clone();
+                    self.rt.spawn("cron_concurrent_action", async move {
+                        if let Err(mut e) = executor
+                            .run_concurrent_action(
+                                job,
+                                job_id,
+                                path,
+                                identity,
+                                usage_tracker,
+                                context,
+                            )
+                            .await
+                        {
+                            report_error(&mut e);
+                        }
+                    });
+                    return Ok(());
+                }
+
                 // Set state to in progress
                 let mut updated_job = job.clone();
                 updated_job.state = CronJobState::InProgress;
                 CronModel::new(&mut tx, component)
                     .update_job_state(job_id, updated_job.clone())
                     .await?;
+                let run_started = self.rt.generate_timestamp()?;
                 self.database
                     .commit_with_write_source(tx, "cron_in_progress")
                     .await?;
 
                 // Execute the action
-                let context = ExecutionContext::new(request_id, &caller);
-                let path = CanonicalizedComponentFunctionPath {
-                    component: component_path,
-                    udf_path: job.cron_spec.udf_path.clone(),
-                };
                 let completion = self
                     .runner
                     .run_action_no_udf_log(
@@ -597,14 +691,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                     .await?;
                 let execution_time_f64 = completion.execution_time.as_secs_f64();
                 let truncated_log_lines = self.truncate_log_lines(completion.log_lines.clone());
-
-                let status = match completion.outcome.result.clone() {
-                    Ok(result) => {
-                        let truncated_result = self.truncate_result(result);
-                        CronJobStatus::Success(truncated_result)
-                    },
-                    Err(e) => CronJobStatus::Err(e.to_string()),
-                };
+                let status = self.action_status(&completion);
 
                 // Mark the job as completed. Keep trying until we succeed (or
                 // detect the job state has changed). Don't bubble up the error
@@ -619,6 +706,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                         status.clone(),
                         truncated_log_lines.clone(),
                         execution_time_f64,
+                        run_started,
                         usage_tracker.clone(),
                         context.clone(),
                     )
@@ -661,6 +749,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                     &job,
                     UdfType::Action,
                     context.clone(),
+                    None,
                 )
                 .await?;
                 self.database
@@ -717,6 +806,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         status: CronJobStatus,
         log_lines: CronJobLogLines,
         execution_time: f64,
+        run_started: Timestamp,
         usage_tracker: FunctionUsageTracker,
         context: ExecutionContext,
     ) -> anyhow::Result<()> {
@@ -743,6 +833,7 @@ impl<RT: Runtime> CronJobExecutor<RT> {
             expected_state,
             UdfType::Action,
             context,
+            Some(run_started),
         )
         .await?;
         self.database
@@ -751,6 +842,10 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         Ok(())
     }
 
+    // Reschedules the cron after a run. `run_started` is when an action
+    // started running, if it ran while marked in progress, and is used to tell
+    // runs that came due while it was running apart from ones that were missed
+    // because the executor was behind.
     async fn complete_job_run(
         &self,
         identity: InertIdentity,
@@ -759,16 +854,27 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         job: &CronJob,
         udf_type: UdfType,
         context: ExecutionContext,
+        run_started: Option<Timestamp>,
     ) -> anyhow::Result<()> {
         let now = self.rt.generate_timestamp()?;
         let prev_ts = job.next_ts;
         let mut next_ts = compute_next_ts(&job.cron_spec, Some(prev_ts), now)?;
         let mut num_skipped = 0;
         let first_skipped_ts = next_ts;
+        let mut num_overlapped = 0;
+        let mut overlapped_ts = None;
         let (component, component_path) = self.get_job_component(tx, job_id).await?;
         let mut model = CronModel::new(tx, component);
         while next_ts < now {
-            num_skipped += 1;
+            if run_started.is_some_and(|run_started| next_ts >= run_started) {
+                num_overlapped += 1;
+                overlapped_ts = Some(match overlapped_ts {
+                    Some((first, _)) => (first, next_ts),
+                    None => (next_ts, next_ts),
+                });
+            } else {
+                num_skipped += 1;
+            }
             next_ts = compute_next_ts(&job.cron_spec, Some(next_ts), now)?;
         }
         if num_skipped > 0 {
@@ -836,6 +942,35 @@ impl<RT: Runtime> CronJobExecutor<RT> {
                 .await?;
         }
 
+        if let Some((first_overlapped_ts, last_overlapped_ts)) = overlapped_ts {
+            let name = &job.name;
+            let mut num_dropped = num_overlapped;
+            if job.cron_spec.overlap_policy == CronOverlapPolicy::QueueOne {
+                // Run the latest overlapping run right away. Its schedule time
+                // is in the past, so the executor picks it up immediately.
+                next_ts = last_overlapped_ts;
+                num_dropped -= 1;
+            }
+            if num_dropped > 0 {
+                tracing::info!(
+                    "Skipping {num_dropped} run(s) of {name} because the previous run was still \
+                     in progress"
+                );
+                let status = CronJobStatus::Skipped {
+                    num_skipped: num_dropped,
+                };
+                let log_lines = CronJobLogLines {
+                    log_lines: vec![].into(),
+                    is_truncated: false,
+                };
+                let mut skipped_job = job.clone();
+                skipped_job.next_ts = first_overlapped_ts;
+                model
+                    .insert_cron_job_log(&skipped_job, status, log_lines, 0.0)
+                    .await?;
+            }
+        }
+
         let mut updated_job = job.clone();
         updated_job.state = CronJobState::Pending;
         updated_job.prev_ts = Some(prev_ts);
@@ -843,4 +978,199 @@ impl<RT: Runtime> CronJobExecutor<RT> {
         model.update_job_state(job_id, updated_job.clone()).await?;
         Ok(())
     }
+
+    // Logs an error for every `CronOverlapPolicy::AllowConcurrent` run that was
+    // still in progress when the backend last stopped. This must run before the
+    // executor starts any runs of its own, since those are tracked the same way.
+    // Each component is cleaned up in its own transaction, and one that keeps
+    // failing is left for the next restart so it doesn't hold up crons in the
+    // rest of the deployment.
+    pub async fn fail_interrupted_concurrent_runs(&self) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::Unknown).await?;
+        let components: Vec<_> = tx
+            .table_mapping()
+            .iter()
+            .filter(|(_, _, _, name)| **name == *CRON_JOBS_TABLE)
+            .map(|(_, namespace, ..)| match namespace {
+                TableNamespace::Global => ComponentId::Root,
+                TableNamespace::ByComponent(id) => ComponentId::Child(id),
+            })
+            .collect();
+        for component in components {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = self
+                .fail_interrupted_concurrent_runs_in_component(component)
+                .await
+            {
+                report_error(&mut e);
+                if backoff.failures() + 1 >= INTERRUPTED_RUNS_MAX_ATTEMPTS {
+                    tracing::error!(
+                        "Failed to clean up interrupted cron runs in {component:?}, leaving \
+                         them until the next restart"
+                    );
+                    break;
+                }
+                let delay = self.rt.with_rng(|rng| backoff.fail(rng));
+                self.rt.wait(delay).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fail_interrupted_concurrent_runs_in_component(
+        &self,
+        component: ComponentId,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::Unknown).await?;
+        let identity = tx.inert_identity();
+        let err = JsError::from_message("Transient error while executing action".to_string());
+        let mut interrupted = vec![];
+        let jobs = CronModel::new(&mut tx, component).list().await?;
+        for job in jobs.into_values() {
+            if job.concurrent_runs.is_empty() {
+                continue;
+            }
+            let (job_id, mut job) = job.into_id_and_value();
+            let component_path = BootstrapComponentsModel::new(&mut tx)
+                .get_component_path(component)
+                .await?;
+            let mut model = CronModel::new(&mut tx, component);
+            for ts in mem::take(&mut job.concurrent_runs) {
+                let mut run = job.clone();
+                run.next_ts = ts;
+                let log_lines = CronJobLogLines {
+                    log_lines: vec![].into(),
+                    is_truncated: false,
+                };
+                model
+                    .insert_cron_job_log(&run, CronJobStatus::Err(err.to_string()), log_lines, 0.0)
+                    .await?;
+                interrupted.push((
+                    CanonicalizedComponentFunctionPath {
+                        component: component_path.clone(),
+                        udf_path: job.cron_spec.udf_path.clone(),
+                    },
+                    job.cron_spec.udf_args.clone(),
+                ));
+            }
+            model.update_job_state(job_id, job).await?;
+        }
+        if interrupted.is_empty() {
+            return Ok(());
+        }
+        self.database
+            .commit_with_write_source(tx, "cron_fail_interrupted_concurrent_runs")
+            .await?;
+        for (path, args) in interrupted {
+            // As with runs marked in progress, we don't know the execution id
+            // the action was started with, so this logs with a new one.
+            let context = ExecutionContext::new(RequestId::new(), &FunctionCaller::Cron);
+            self.function_log.log_action_system_error(
+                &err.clone().into(),
+                path,
+                args,
+                identity.clone(),
+                self.rt.monotonic_now(),
+                FunctionCaller::Cron,
+                vec![].into(),
+                context,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn action_status(&self, completion: &ActionCompletion) -> CronJobStatus {
+        match completion.outcome.result.clone() {
+            Ok(result) => {
+                let truncated_result = self.truncate_result(result);
+                CronJobStatus::Success(truncated_result)
+            },
+            Err(e) => CronJobStatus::Err(e.to_string()),
+        }
+    }
+
+    // Runs an action for a cron with `CronOverlapPolicy::AllowConcurrent`.
+    // The cron has already been rescheduled, so this only needs to record the
+    // outcome.
+    async fn run_concurrent_action(
+        &self,
+        job: CronJob,
+        job_id: ResolvedDocumentId,
+        path: CanonicalizedComponentFunctionPath,
+        identity: Identity,
+        usage_tracker: FunctionUsageTracker,
+        context: ExecutionContext,
+    ) -> anyhow::Result<()> {
+        let completion = self
+            .runner
+            .run_action_no_udf_log(
+                PublicFunctionPath::Component(path),
+                job.cron_spec.udf_args.clone(),
+                identity,
+                FunctionCaller::Cron,
+                usage_tracker.clone(),
+                context,
+            )
+            .await?;
+        let execution_time_f64 = completion.execution_time.as_secs_f64();
+        let truncated_log_lines = self.truncate_log_lines(completion.log_lines.clone());
+        let status = self.action_status(&completion);
+
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        while let Err(mut err) = self
+            .log_concurrent_action_run(
+                job_id,
+                &job,
+                status.clone(),
+                truncated_log_lines.clone(),
+                execution_time_f64,
+                usage_tracker.clone(),
+            )
+            .await
+        {
+            let delay = self.rt.with_rng(|rng| backoff.fail(rng));
+            tracing::error!("Failed to log concurrent cron action, sleeping {delay:?}");
+            report_error(&mut err);
+            self.rt.wait(delay).await;
+        }
+        self.function_log.log_action(completion, usage_tracker);
+        Ok(())
+    }
+
+    async fn log_concurrent_action_run(
+        &self,
+        job_id: ResolvedDocumentId,
+        job: &CronJob,
+        status: CronJobStatus,
+        log_lines: CronJobLogLines,
+        execution_time: f64,
+        usage_tracker: FunctionUsageTracker,
+    ) -> anyhow::Result<()> {
+        let mut tx = self
+            .database
+            .begin_with_usage(Identity::Unknown, usage_tracker)
+            .await?;
+        // Deleting a cron also deletes its logs, so don't add a log for a cron
+        // that was deleted while the action was running.
+        let Some(current_job) = tx
+            .get(job_id)
+            .await?
+            .map(ParsedDocument::<CronJob>::try_from)
+            .transpose()?
+        else {
+            return Ok(());
+        };
+        let mut current_job = current_job.into_value();
+        let (component, _) = self.get_job_component(&mut tx, job_id).await?;
+        let mut model = CronModel::new(&mut tx, component);
+        current_job.concurrent_runs.retain(|ts| *ts != job.next_ts);
+        model.update_job_state(job_id, current_job).await?;
+        model
+            .insert_cron_job_log(job, status, log_lines, execution_time)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "cron_log_concurrent_action")
+            .await?;
+        Ok(())
+    }
 }
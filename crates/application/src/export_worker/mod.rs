@@ -3,6 +3,8 @@ use std::{
         BTreeMap,
         BTreeSet,
     },
+    num::NonZeroU32,
+    ops::Bound,
     sync::Arc,
     time::Duration,
 };
@@ -22,10 +24,14 @@ use bytes::Bytes;
 use common::{
     async_compat::TokioAsyncWriteCompatExt,
     backoff::Backoff,
-    bootstrap_model::tables::TABLES_TABLE,
+    bootstrap_model::{
+        schema::SchemaState,
+        tables::TABLES_TABLE,
+    },
     components::{
         ComponentId,
         ComponentName,
+        ComponentPath,
     },
     document::{
         ParsedDocument,
@@ -33,17 +39,25 @@ use common::{
     },
     errors::report_error,
     execution_context::ExecutionId,
+    knobs::{
+        DEFAULT_DOCUMENTS_PAGE_SIZE,
+        INCREMENTAL_EXPORT_PAGES_PER_SECOND,
+    },
     maybe_val,
+    persistence::TimestampRange,
     query::{
         IndexRange,
         IndexRangeExpression,
         Order,
     },
     runtime::{
+        new_rate_limiter,
         try_join_buffer_unordered,
         try_join_buffered,
         Runtime,
     },
+    schemas::DatabaseSchema,
+    try_chunks::TryChunksExt,
     types::{
         IndexId,
         ObjectKey,
@@ -57,6 +71,7 @@ use database::{
     Database,
     IndexModel,
     ResolvedQuery,
+    SchemaModel,
     SystemMetadataModel,
     TableSummary,
     Transaction,
@@ -72,13 +87,19 @@ use futures::{
     StreamExt,
     TryStreamExt,
 };
-use keybroker::Identity;
+use governor::Quota;
+use keybroker::{
+    Identity,
+    KeyBroker,
+};
 use mime2ext::mime2ext;
 use model::{
     components::ComponentsModel,
     exports::{
         types::{
             Export,
+            ExportEncryption,
+            ExportFilter,
             ExportFormat,
             ExportObjectKeys,
         },
@@ -122,16 +143,30 @@ use usage_tracking::{
 };
 use value::{
     export::ValueFormat,
+    DeveloperDocumentId,
+    InternalDocumentId,
     TableNamespace,
     TableNumber,
     TabletId,
 };
 
-use crate::metrics::{
-    export_timer,
-    log_worker_starting,
+use self::parquet::{
+    ParquetTableSchema,
+    ParquetTableWriter,
+};
+use crate::{
+    metrics::{
+        export_timer,
+        log_worker_starting,
+    },
+    snapshot_encryption::{
+        encrypt_stream,
+        export_key,
+    },
 };
 
+mod parquet;
+
 const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
 const MAX_BACKOFF: Duration = Duration::from_secs(900); // 15 minutes
 static BEGIN_JSON_ARRAY: Bytes = Bytes::from_static("[\n".as_bytes());
@@ -139,6 +174,9 @@ static BETWEEN_DOCUMENTS: Bytes = Bytes::from_static(",\n".as_bytes());
 static END_JSON_ARRAY: Bytes = Bytes::from_static("\n]\n".as_bytes());
 static AFTER_DOCUMENTS_CLEAN: Bytes = Bytes::from_static("\n".as_bytes());
 
+/// Marks a zip as an incremental export, and records its base snapshot.
+pub const INCREMENTAL_METADATA_PATH: &str = "incremental.json";
+
 // 0o644 => read-write for owner, read for everyone else.
 const ZIP_ENTRY_PERMISSIONS: u16 = 0o644;
 
@@ -147,7 +185,17 @@ static README_MD_CONTENTS: &str = r#"# Welcome to your Convex snapshot export!
 This ZIP file contains a snapshot of the tables in your Convex deployment.
 
 Documents for each table are listed as lines of JSON in
-<table_name>/documents.jsonl files.
+<table_name>/documents.jsonl files, or as rows in
+<table_name>/documents.parquet files for Parquet exports. In Parquet files,
+fields that don't fit the table's columns are in the `_extra` column as a JSON
+object. Parquet exports are meant for loading into other tools, and can't be
+imported with npx convex import.
+
+Incremental exports only contain the documents that changed since an earlier
+export, listed in incremental.json, and the IDs of documents deleted since then
+in <table_name>/deleted.jsonl files. Import them with `npx convex import
+--append` on top of the earlier export. Tables deleted since the earlier export
+aren't removed, and file storage isn't included.
 
 For details on the format and how to use this snapshot with npx convex import,
 check out [the docs](https://docs.convex.dev/database/import-export/export) or
@@ -161,16 +209,22 @@ pub struct ExportWorker<RT: Runtime> {
     file_storage: Arc<dyn Storage>,
     backoff: Backoff,
     usage_tracking: UsageCounter,
+    key_broker: KeyBroker,
 }
 
 struct ComponentTree {
     id: ComponentId,
+    path: ComponentPath,
     children: BTreeMap<ComponentName, Box<ComponentTree>>,
 }
 
 impl ComponentTree {
     #[async_recursion]
-    async fn new<RT>(tx: &mut Transaction<RT>, id: ComponentId) -> anyhow::Result<Self>
+    async fn new<RT>(
+        tx: &mut Transaction<RT>,
+        id: ComponentId,
+        path: ComponentPath,
+    ) -> anyhow::Result<Self>
     where
         RT: Runtime,
     {
@@ -178,9 +232,13 @@ impl ComponentTree {
         for (component_name, child_id) in
             ComponentsModel::new(tx).component_children_ids(id).await?
         {
-            children.insert(component_name, Box::new(Self::new(tx, child_id).await?));
+            let child_path = path.join(component_name.clone());
+            children.insert(
+                component_name,
+                Box::new(Self::new(tx, child_id, child_path).await?),
+            );
         }
-        Ok(Self { id, children })
+        Ok(Self { id, path, children })
     }
 }
 
@@ -192,6 +250,7 @@ impl<RT: Runtime> ExportWorker<RT> {
         storage: Arc<dyn Storage>,
         file_storage: Arc<dyn Storage>,
         usage_tracking: UsageCounter,
+        key_broker: KeyBroker,
     ) -> impl Future<Output = ()> + Send {
         let mut worker = Self {
             runtime,
@@ -200,6 +259,7 @@ impl<RT: Runtime> ExportWorker<RT> {
             file_storage,
             backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
             usage_tracking,
+            key_broker,
         };
         async move {
             loop {
@@ -230,6 +290,7 @@ impl<RT: Runtime> ExportWorker<RT> {
             file_storage,
             backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
             usage_tracking: UsageCounter::new(Arc::new(NoOpUsageEventLogger)),
+            key_broker: KeyBroker::dev(),
         }
     }
 
@@ -361,15 +422,18 @@ impl<RT: Runtime> ExportWorker<RT> {
     async fn export_inner(
         &mut self,
         format: ExportFormat,
+        filter: &ExportFilter,
+        encryption: Option<ExportEncryption>,
     ) -> anyhow::Result<(Timestamp, ExportObjectKeys, FunctionUsageTracker)> {
         tracing::info!("Beginning snapshot export...");
         let storage = &self.storage;
-        let (ts, tables, by_id_indexes, system_tables, component_tree) = {
+        let (ts, tables, by_id_indexes, system_tables, component_tree, declared_schemas) = {
             let mut tx = self.database.begin(Identity::system()).await?;
             let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
-            let component_tree = ComponentTree::new(&mut tx, ComponentId::Root).await?;
+            let component_tree =
+                ComponentTree::new(&mut tx, ComponentId::Root, ComponentPath::root()).await?;
             let snapshot = self.database.snapshot(tx.begin_timestamp())?;
-            let tables: BTreeMap<_, _> = snapshot
+            let mut tables: BTreeMap<_, _> = snapshot
                 .table_registry
                 .iter_active_user_tables()
                 .map(|(tablet_id, table_namespace, table_number, table_name)| {
@@ -384,28 +448,69 @@ impl<RT: Runtime> ExportWorker<RT> {
                     )
                 })
                 .collect();
+            let namespaces: BTreeSet<_> =
+                tables.values().map(|(namespace, ..)| *namespace).collect();
+            let mut component_paths = BTreeMap::new();
+            for namespace in namespaces {
+                let component_path = ComponentsModel::new(&mut tx)
+                    .get_component_path_for_namespace(namespace)
+                    .await?;
+                component_paths.insert(namespace, component_path);
+            }
+            tables.retain(|_, (namespace, _, table_name, _)| {
+                filter.includes_table(&component_paths[namespace], table_name)
+            });
             let system_tables = snapshot
                 .table_registry
                 .iter_active_system_tables()
                 .map(|(id, namespace, _, name)| ((namespace, name.clone()), id))
                 .collect();
+            // Parquet columns come from the declared schema when there is one.
+            let mut declared_schemas = BTreeMap::new();
+            if let ExportFormat::Parquet { .. } = format {
+                let namespaces: BTreeSet<_> =
+                    tables.values().map(|(namespace, ..)| *namespace).collect();
+                for namespace in namespaces {
+                    if let Some((_, schema)) = SchemaModel::new(&mut tx, namespace)
+                        .get_by_state(SchemaState::Active)
+                        .await?
+                    {
+                        declared_schemas.insert(namespace, schema);
+                    }
+                }
+            }
             (
                 tx.begin_timestamp(),
                 tables,
                 by_id_indexes,
                 system_tables,
                 component_tree,
+                declared_schemas,
             )
         };
         let tablet_ids: BTreeSet<TabletId> =
             tables.iter().map(|(tablet_id, ..)| *tablet_id).collect();
 
         match format {
-            ExportFormat::Zip { include_storage } => {
+            ExportFormat::Zip { .. }
+            | ExportFormat::Parquet { .. }
+            | ExportFormat::Incremental { .. } => {
+                let include_storage = match format {
+                    ExportFormat::Zip { include_storage }
+                    | ExportFormat::Parquet { include_storage } => include_storage,
+                    _ => false,
+                };
                 // Start upload.
                 let mut upload = storage.start_upload().await?;
                 let (sender, receiver) = mpsc::channel::<Bytes>(1);
-                let uploader = upload.try_write_parallel_and_hash(receiver.map(Ok));
+                let body = match encryption {
+                    Some(encryption) => {
+                        let (key_header, key) = export_key(&encryption, &self.key_broker)?;
+                        encrypt_stream(key_header, key, receiver).boxed()
+                    },
+                    None => receiver.map(Ok).boxed(),
+                };
+                let uploader = upload.try_write_parallel_and_hash(body);
                 let writer = ChannelWriter::new(sender, 5 * (1 << 20));
                 let usage = FunctionUsageTracker::new();
 
@@ -417,6 +522,9 @@ impl<RT: Runtime> ExportWorker<RT> {
                     by_id_indexes,
                     system_tables,
                     include_storage,
+                    format,
+                    filter,
+                    declared_schemas,
                     usage.clone(),
                 );
                 let (_, ()) = try_join!(uploader, zipper)?;
@@ -424,6 +532,7 @@ impl<RT: Runtime> ExportWorker<RT> {
                 Ok((*ts, object_keys, usage))
             },
             ExportFormat::CleanJsonl | ExportFormat::InternalJson => {
+                anyhow::ensure!(encryption.is_none(), "Only zip exports can be encrypted");
                 let mut table_uploads = Self::upload_tables(
                     &self.runtime,
                     self.storage.clone(),
@@ -492,6 +601,9 @@ impl<RT: Runtime> ExportWorker<RT> {
         by_id_indexes: &BTreeMap<TabletId, IndexId>,
         system_tables: &BTreeMap<(TableNamespace, TableName), TabletId>,
         include_storage: bool,
+        format: ExportFormat,
+        filter: &ExportFilter,
+        declared_schemas: &BTreeMap<TableNamespace, DatabaseSchema>,
         usage: FunctionUsageTracker,
     ) -> anyhow::Result<()> {
         let namespace: TableNamespace = component_tree.id.into();
@@ -500,8 +612,11 @@ impl<RT: Runtime> ExportWorker<RT> {
             .filter(|(_, (ns, ..))| *ns == namespace)
             .map(|(tablet_id, _)| *tablet_id)
             .collect();
+        // An excluded component is left out of the zip entirely, but the
+        // components inside it may still be included.
+        let includes_component = filter.includes_component(&component_tree.path);
 
-        {
+        if includes_component {
             // _tables
             let mut table_upload = zip_snapshot_upload
                 .start_system_table(path_prefix, TABLES_TABLE.clone())
@@ -526,7 +641,7 @@ impl<RT: Runtime> ExportWorker<RT> {
             table_upload.complete().await?;
         }
 
-        if include_storage {
+        if include_storage && includes_component {
             // _storage
             let tablet_id = system_tables
                 .get(&(namespace, FILE_STORAGE_TABLE.clone()))
@@ -613,12 +728,28 @@ impl<RT: Runtime> ExportWorker<RT> {
         }
 
         for tablet_id in tablet_ids.iter() {
-            let (_, _, table_name, table_summary) =
+            let (_, table_number, table_name, table_summary) =
                 tables.remove(tablet_id).expect("table should have details");
             let by_id = by_id_indexes
                 .get(tablet_id)
                 .ok_or_else(|| anyhow::anyhow!("no by_id index for {} found", tablet_id))?;
 
+            if let ExportFormat::Incremental { base_ts } = format {
+                self.write_incremental_table(
+                    path_prefix,
+                    zip_snapshot_upload,
+                    *tablet_id,
+                    table_number,
+                    table_name,
+                    table_summary,
+                    snapshot_ts,
+                    base_ts,
+                    usage.clone(),
+                )
+                .await?;
+                continue;
+            }
+
             let mut generated_schema = GeneratedSchema::new(table_summary.inferred_type().into());
             if ExportContext::is_ambiguous(table_summary.inferred_type()) {
                 let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
@@ -629,14 +760,44 @@ impl<RT: Runtime> ExportWorker<RT> {
                 }
             }
 
-            let mut table_upload = zip_snapshot_upload
-                .start_table(path_prefix, table_name.clone(), generated_schema)
-                .await?;
-
             let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
             let stream = table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
             pin_mut!(stream);
 
+            if let ExportFormat::Parquet { .. } = format {
+                let parquet_schema = declared_schemas
+                    .get(&namespace)
+                    .filter(|schema| schema.schema_validation)
+                    .and_then(|schema| schema.tables.get(&table_name))
+                    .and_then(|table| table.document_type.as_ref())
+                    .and_then(ParquetTableSchema::from_document_schema)
+                    .unwrap_or_else(|| {
+                        ParquetTableSchema::from_shape(table_summary.inferred_type())
+                    });
+                let mut table_upload = zip_snapshot_upload
+                    .start_parquet_table(
+                        path_prefix,
+                        table_name.clone(),
+                        generated_schema,
+                        &parquet_schema,
+                    )
+                    .await?;
+                while let Some((doc, _ts)) = stream.try_next().await? {
+                    usage.track_database_egress_size(
+                        table_name.to_string(),
+                        doc.size() as u64,
+                        false,
+                    );
+                    table_upload.write(doc).await?;
+                }
+                table_upload.complete().await?;
+                continue;
+            }
+
+            let mut table_upload = zip_snapshot_upload
+                .start_table(path_prefix, table_name.clone(), generated_schema)
+                .await?;
+
             // Write documents from stream to table uploads
             while let Some((doc, _ts)) = stream.try_next().await? {
                 usage.track_database_egress_size(table_name.to_string(), doc.size() as u64, false);
@@ -661,6 +822,9 @@ impl<RT: Runtime> ExportWorker<RT> {
                 by_id_indexes,
                 system_tables,
                 include_storage,
+                format,
+                filter,
+                declared_schemas,
                 usage.clone(),
             )
             .await?;
@@ -669,6 +833,139 @@ impl<RT: Runtime> ExportWorker<RT> {
         Ok(())
     }
 
+    /// Write the latest revision of each document in the table that changed
+    /// after `base_ts`, and the IDs of the documents deleted after `base_ts`,
+    /// from the document log. Tables without changes are skipped.
+    ///
+    /// The log is read a page at a time, so the table's changes are never all
+    /// held in memory. It's read once for the generated schema, if the table
+    /// needs one, once for the documents and once for the deleted IDs, since
+    /// each is a separate entry in the zip.
+    async fn write_incremental_table(
+        &self,
+        path_prefix: &str,
+        zip_snapshot_upload: &mut ZipSnapshotUpload<'_>,
+        tablet_id: TabletId,
+        table_number: TableNumber,
+        table_name: TableName,
+        table_summary: TableSummary,
+        snapshot_ts: RepeatableTimestamp,
+        base_ts: Timestamp,
+        usage: FunctionUsageTracker,
+    ) -> anyhow::Result<()> {
+        let rate_limiter = new_rate_limiter(
+            self.runtime.clone(),
+            Quota::per_second(
+                NonZeroU32::new(*DEFAULT_DOCUMENTS_PAGE_SIZE)
+                    .and_then(|val| val.checked_mul(*INCREMENTAL_EXPORT_PAGES_PER_SECOND))
+                    .context("Invalid row rate limit")?,
+            ),
+        );
+        let range = TimestampRange::new((Bound::Excluded(base_ts), Bound::Included(*snapshot_ts)))?;
+        let page_size = *DEFAULT_DOCUMENTS_PAGE_SIZE as usize;
+
+        let mut has_changes = false;
+        let mut generated_schema = GeneratedSchema::new(table_summary.inferred_type().into());
+        let is_ambiguous = ExportContext::is_ambiguous(table_summary.inferred_type());
+        {
+            let stream = self
+                .database
+                .load_documents_in_table(tablet_id, range, Order::Asc, &rate_limiter)
+                .try_chunks2(page_size);
+            pin_mut!(stream);
+            while let Some(page) = stream.try_next().await? {
+                has_changes = true;
+                if !is_ambiguous {
+                    break;
+                }
+                for (_, doc) in self.latest_revisions_in_page(page, *snapshot_ts).await? {
+                    if let Some(doc) = doc {
+                        generated_schema.insert(doc.value(), doc.developer_id());
+                    }
+                }
+            }
+        }
+        if !has_changes {
+            return Ok(());
+        }
+
+        let mut table_upload = zip_snapshot_upload
+            .start_table(path_prefix, table_name.clone(), generated_schema)
+            .await?;
+        {
+            let stream = self
+                .database
+                .load_documents_in_table(tablet_id, range, Order::Asc, &rate_limiter)
+                .try_chunks2(page_size);
+            pin_mut!(stream);
+            while let Some(page) = stream.try_next().await? {
+                for (_, doc) in self.latest_revisions_in_page(page, *snapshot_ts).await? {
+                    if let Some(doc) = doc {
+                        usage.track_database_egress_size(
+                            table_name.to_string(),
+                            doc.size() as u64,
+                            false,
+                        );
+                        table_upload.write(doc).await?;
+                    }
+                }
+            }
+        }
+        table_upload.complete().await?;
+
+        let mut deleted_upload = zip_snapshot_upload
+            .start_deleted_ids(path_prefix, table_name)
+            .await?;
+        {
+            let stream = self
+                .database
+                .load_documents_in_table(tablet_id, range, Order::Asc, &rate_limiter)
+                .try_chunks2(page_size);
+            pin_mut!(stream);
+            while let Some(page) = stream.try_next().await? {
+                for (id, doc) in self.latest_revisions_in_page(page, *snapshot_ts).await? {
+                    if doc.is_none() {
+                        let id = DeveloperDocumentId::new(table_number, id.internal_id());
+                        deleted_upload
+                            .write_json_line(json!({ "_id": id.encode() }))
+                            .await?;
+                    }
+                }
+            }
+        }
+        deleted_upload.complete().await?;
+        Ok(())
+    }
+
+    /// The entries in a page of the document log that are the latest
+    /// revision of their document at `snapshot_ts`, with `None` for documents
+    /// deleted by then. Every document in the log has its latest revision in
+    /// exactly one page, so each is returned once across the pages.
+    async fn latest_revisions_in_page(
+        &self,
+        page: Vec<(Timestamp, InternalDocumentId, Option<ResolvedDocument>)>,
+        snapshot_ts: Timestamp,
+    ) -> anyhow::Result<Vec<(InternalDocumentId, Option<ResolvedDocument>)>> {
+        // The page is in log order, so later entries replace earlier ones.
+        let mut page_revisions = BTreeMap::new();
+        for (ts, id, doc) in page {
+            page_revisions.insert(id, (ts, doc));
+        }
+        let latest_revisions = self
+            .database
+            .load_latest_revisions_at_ts(page_revisions.keys().copied().collect(), snapshot_ts)
+            .await?;
+        Ok(page_revisions
+            .into_iter()
+            .filter(|(id, (ts, _))| {
+                latest_revisions
+                    .get(id)
+                    .is_some_and(|(latest_ts, _)| latest_ts == ts)
+            })
+            .map(|(id, (_, doc))| (id, doc))
+            .collect())
+    }
+
     async fn construct_zip_snapshot(
         &self,
         mut writer: ChannelWriter,
@@ -678,9 +975,21 @@ impl<RT: Runtime> ExportWorker<RT> {
         by_id_indexes: BTreeMap<TabletId, IndexId>,
         system_tables: BTreeMap<(TableNamespace, TableName), TabletId>,
         include_storage: bool,
+        format: ExportFormat,
+        filter: &ExportFilter,
+        declared_schemas: BTreeMap<TableNamespace, DatabaseSchema>,
         usage: FunctionUsageTracker,
     ) -> anyhow::Result<()> {
         let mut zip_snapshot_upload = ZipSnapshotUpload::new(&mut writer).await?;
+        if let ExportFormat::Incremental { base_ts } = format {
+            let metadata = json!({
+                "baseSnapshotTs": i64::from(base_ts).to_string(),
+                "snapshotTs": i64::from(*snapshot_ts).to_string(),
+            });
+            zip_snapshot_upload
+                .write_full_file(INCREMENTAL_METADATA_PATH.to_string(), &metadata.to_string())
+                .await?;
+        }
 
         self.write_component(
             "",
@@ -691,6 +1000,9 @@ impl<RT: Runtime> ExportWorker<RT> {
             &by_id_indexes,
             &system_tables,
             include_storage,
+            format,
+            filter,
+            &declared_schemas,
             usage,
         )
         .await?;
@@ -705,7 +1017,38 @@ impl<RT: Runtime> ExportWorker<RT> {
         &mut self,
         export: ParsedDocument<Export>,
     ) -> anyhow::Result<()> {
-        let (ts, object_keys, usage) = self.export_inner(export.format()).await?;
+        if let ExportFormat::Incremental { base_ts } = export.format() {
+            let min_document_snapshot_ts = self
+                .database
+                .retention_validator()
+                .min_document_snapshot_ts()
+                .await?;
+            if base_ts < min_document_snapshot_ts {
+                // Retrying can't help once the document log no longer covers
+                // the changes since the base export.
+                tracing::warn!(
+                    "Incremental export base {base_ts} is older than the document log \
+                     ({min_document_snapshot_ts}), failing export"
+                );
+                let mut tx = self.database.begin(Identity::system()).await?;
+                let now = *tx.begin_timestamp();
+                let failed_export = (*export).clone().failed(now, now)?;
+                SystemMetadataModel::new_global(&mut tx)
+                    .replace(export.id(), failed_export.try_into()?)
+                    .await?;
+                self.database
+                    .commit_with_write_source(tx, "export_worker_mark_failed")
+                    .await?;
+                return Ok(());
+            }
+        }
+        let (ts, object_keys, usage) = self
+            .export_inner(
+                export.format(),
+                export.filter(),
+                export.encryption().cloned(),
+            )
+            .await?;
 
         let mut tx = self.database.begin(Identity::system()).await?;
         let completed_export =
@@ -749,16 +1092,20 @@ impl TableUpload {
 
     async fn write(mut self, doc: ResolvedDocument) -> anyhow::Result<Self> {
         let json = match self.format {
-            ExportFormat::CleanJsonl | ExportFormat::Zip { .. } => {
-                doc.export(ValueFormat::ConvexCleanJSON)
-            },
+            ExportFormat::CleanJsonl
+            | ExportFormat::Zip { .. }
+            | ExportFormat::Parquet { .. }
+            | ExportFormat::Incremental { .. } => doc.export(ValueFormat::ConvexCleanJSON),
             ExportFormat::InternalJson => doc.export(ValueFormat::ConvexEncodedJSON),
         };
         if !self.empty {
             // Between documents.
             match self.format {
                 ExportFormat::InternalJson => self.upload.write(BETWEEN_DOCUMENTS.clone()).await?,
-                ExportFormat::CleanJsonl | ExportFormat::Zip { .. } => {},
+                ExportFormat::CleanJsonl
+                | ExportFormat::Zip { .. }
+                | ExportFormat::Parquet { .. }
+                | ExportFormat::Incremental { .. } => {},
             }
         }
         self.empty = false;
@@ -768,7 +1115,10 @@ impl TableUpload {
 
         // After documents.
         match self.format {
-            ExportFormat::CleanJsonl | ExportFormat::Zip { .. } => {
+            ExportFormat::CleanJsonl
+            | ExportFormat::Zip { .. }
+            | ExportFormat::Parquet { .. }
+            | ExportFormat::Incremental { .. } => {
                 self.upload.write(AFTER_DOCUMENTS_CLEAN.clone()).await?
             },
             ExportFormat::InternalJson => {},
@@ -809,8 +1159,9 @@ impl<'a, 'b> ZipSnapshotTableUpload<'a, 'b> {
         zip_writer: &'b mut ZipFileWriter<&'a mut ChannelWriter>,
         path_prefix: &str,
         table_name: TableName,
+        file_name: &str,
     ) -> anyhow::Result<Self> {
-        let source_path = format!("{path_prefix}{table_name}/documents.jsonl");
+        let source_path = format!("{path_prefix}{table_name}/{file_name}");
         let builder = ZipEntryBuilder::new(source_path.clone(), Compression::Deflate)
             .unix_permissions(ZIP_ENTRY_PERMISSIONS);
         let entry_writer = zip_writer.write_entry_stream(builder.build()).await?;
@@ -838,6 +1189,44 @@ impl<'a, 'b> ZipSnapshotTableUpload<'a, 'b> {
     }
 }
 
+struct ZipSnapshotParquetTableUpload<'a, 'b> {
+    entry_writer: EntryStreamWriter<'b, &'a mut ChannelWriter>,
+    parquet_writer: ParquetTableWriter,
+}
+
+impl<'a, 'b> ZipSnapshotParquetTableUpload<'a, 'b> {
+    async fn new(
+        zip_writer: &'b mut ZipFileWriter<&'a mut ChannelWriter>,
+        path_prefix: &str,
+        table_name: TableName,
+        parquet_schema: &ParquetTableSchema,
+    ) -> anyhow::Result<Self> {
+        let source_path = format!("{path_prefix}{table_name}/documents.parquet");
+        // Parquet pages are already compressed.
+        let builder = ZipEntryBuilder::new(source_path, Compression::Stored)
+            .unix_permissions(ZIP_ENTRY_PERMISSIONS);
+        let entry_writer = zip_writer.write_entry_stream(builder.build()).await?;
+        Ok(Self {
+            entry_writer,
+            parquet_writer: ParquetTableWriter::new(parquet_schema)?,
+        })
+    }
+
+    async fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<()> {
+        if let Some(buf) = self.parquet_writer.write(doc)? {
+            self.entry_writer.compat_mut_write().write_all(&buf).await?;
+        }
+        Ok(())
+    }
+
+    async fn complete(mut self) -> anyhow::Result<()> {
+        let buf = self.parquet_writer.complete()?;
+        self.entry_writer.compat_mut_write().write_all(&buf).await?;
+        self.entry_writer.close().await?;
+        Ok(())
+    }
+}
+
 struct ZipSnapshotUpload<'a> {
     writer: ZipFileWriter<&'a mut ChannelWriter>,
 }
@@ -888,7 +1277,37 @@ impl<'a> ZipSnapshotUpload<'a> {
         self.write_generated_schema(path_prefix, &table_name, generated_schema)
             .await?;
 
-        ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name).await
+        ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name, "documents.jsonl")
+            .await
+    }
+
+    /// IDs of documents deleted since the base of an incremental export.
+    async fn start_deleted_ids(
+        &mut self,
+        path_prefix: &str,
+        table_name: TableName,
+    ) -> anyhow::Result<ZipSnapshotTableUpload<'a, '_>> {
+        ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name, "deleted.jsonl")
+            .await
+    }
+
+    async fn start_parquet_table<T: ShapeConfig>(
+        &mut self,
+        path_prefix: &str,
+        table_name: TableName,
+        generated_schema: GeneratedSchema<T>,
+        parquet_schema: &ParquetTableSchema,
+    ) -> anyhow::Result<ZipSnapshotParquetTableUpload<'a, '_>> {
+        self.write_generated_schema(path_prefix, &table_name, generated_schema)
+            .await?;
+
+        ZipSnapshotParquetTableUpload::new(
+            &mut self.writer,
+            path_prefix,
+            table_name,
+            parquet_schema,
+        )
+        .await
     }
 
     /// System tables have known shape, so we don't need to serialize it.
@@ -898,7 +1317,8 @@ impl<'a> ZipSnapshotUpload<'a> {
         table_name: TableName,
     ) -> anyhow::Result<ZipSnapshotTableUpload<'a, '_>> {
         anyhow::ensure!(table_name.is_system());
-        ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name).await
+        ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name, "documents.jsonl")
+            .await
     }
 
     async fn write_generated_schema<T: ShapeConfig>(
@@ -939,7 +1359,10 @@ impl<'a> ZipSnapshotUpload<'a> {
 #[cfg(test)]
 mod tests {
     use std::{
-        collections::BTreeMap,
+        collections::{
+            BTreeMap,
+            BTreeSet,
+        },
         str,
         sync::Arc,
         time::Duration,
@@ -955,6 +1378,7 @@ mod tests {
         },
         types::{
             ConvexOrigin,
+            MemberId,
             TableName,
         },
         value::ConvexObject,
@@ -969,20 +1393,40 @@ mod tests {
         FileStorage,
         TransactionalFileStorage,
     };
+    use futures::{
+        stream,
+        StreamExt,
+    };
     use headers::ContentType;
-    use keybroker::Identity;
+    use keybroker::{
+        AdminIdentity,
+        Identity,
+    };
     use model::{
         exports::types::{
             Export,
+            ExportFilter,
             ExportFormat,
             ExportObjectKeys,
+            ExportRequestor,
         },
         file_storage::types::FileStorageEntry,
+        snapshot_imports::types::{
+            ImportFormat,
+            ImportMode,
+        },
         test_helpers::DbFixturesWithModel,
     };
     use must_let::must_let;
+    use parquet::file::reader::{
+        FileReader,
+        SerializedFileReader,
+    };
     use runtime::testing::TestRuntime;
-    use serde_json::json;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
     use storage::{
         LocalDirStorage,
         Storage,
@@ -1002,7 +1446,17 @@ mod tests {
         TableUpload,
     };
     use crate::{
-        export_worker::README_MD_CONTENTS,
+        export_worker::{
+            INCREMENTAL_METADATA_PATH,
+            README_MD_CONTENTS,
+        },
+        snapshot_encryption::{
+            decrypt_stream,
+            new_export_encryption,
+            SnapshotDecryptionKey,
+            SnapshotEncryptionKey,
+        },
+        snapshot_import::do_import,
         test_helpers::ApplicationTestExt,
         Application,
     };
@@ -1024,7 +1478,9 @@ mod tests {
                 .await?;
             db.commit(tx).await?;
         }
-        let (_, tables, _) = export_worker.export_inner(ExportFormat::CleanJsonl).await?;
+        let (_, tables, _) = export_worker
+            .export_inner(ExportFormat::CleanJsonl, &ExportFilter::default(), None)
+            .await?;
         must_let!(let ExportObjectKeys::ByTable(tables) = tables);
         let mut expected_tables = BTreeMap::new();
         for i in 0..2 {
@@ -1133,9 +1589,13 @@ mod tests {
             db.commit(tx).await?;
         }
         let (_, object_keys, usage) = export_worker
-            .export_inner(ExportFormat::Zip {
-                include_storage: true,
-            })
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: true,
+                },
+                &ExportFilter::default(),
+                None,
+            )
             .await?;
         must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
 
@@ -1167,6 +1627,202 @@ mod tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_export_parquet(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let table: TableName = str::parse("messages")?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let id1 = UserFacingModel::new_root_for_test(&mut tx)
+            .insert(
+                table.clone(),
+                assert_obj!("body" => "hello", "likes" => 1i64, "tags" => ["a", "b"]),
+            )
+            .await?;
+        let id2 = UserFacingModel::new_root_for_test(&mut tx)
+            .insert(table, assert_obj!("body" => "world", "likes" => 2i64))
+            .await?;
+        db.commit(tx).await?;
+
+        let (_, object_keys, _) = export_worker
+            .export_inner(
+                ExportFormat::Parquet {
+                    include_storage: false,
+                },
+                &ExportFilter::default(),
+                None,
+            )
+            .await?;
+        must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
+        let storage_stream = storage
+            .get(&object_key)
+            .await?
+            .context("object missing from storage")?;
+        let stored_bytes = storage_stream.collect_as_bytes().await?;
+        let mut zip_reader = async_zip::read::mem::ZipFileReader::new(&stored_bytes).await?;
+        let index = zip_reader
+            .entries()
+            .iter()
+            .position(|entry| entry.filename() == "messages/documents.parquet")
+            .context("missing messages/documents.parquet")?;
+        let parquet_bytes = zip_reader
+            .entry_reader(index)
+            .await?
+            .read_to_end_crc()
+            .await?;
+
+        let reader = SerializedFileReader::new(Bytes::from(parquet_bytes))?;
+        let columns: Vec<_> = reader
+            .metadata()
+            .file_metadata()
+            .schema()
+            .get_fields()
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect();
+        assert_eq!(
+            columns,
+            vec!["_id", "_creationTime", "body", "likes", "tags", "_extra"]
+        );
+
+        let mut rows = vec![];
+        for row in reader.get_row_iter(None)? {
+            let row = row?;
+            let tags = row.get_string(4).ok().cloned();
+            rows.push((
+                row.get_string(0)?.clone(),
+                row.get_string(2)?.clone(),
+                row.get_long(3)?,
+                tags,
+            ));
+        }
+        rows.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    id1.encode(),
+                    "hello".to_string(),
+                    1,
+                    Some(json!(["a", "b"]).to_string())
+                ),
+                (id2.encode(), "world".to_string(), 2, None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_export_incremental(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let messages: TableName = str::parse("messages")?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = UserFacingModel::new_root_for_test(&mut tx);
+        let edited = model
+            .insert(messages.clone(), assert_obj!("body" => "a"))
+            .await?;
+        let deleted = model
+            .insert(messages.clone(), assert_obj!("body" => "b"))
+            .await?;
+        model
+            .insert(messages.clone(), assert_obj!("body" => "c"))
+            .await?;
+        model
+            .insert(str::parse("untouched")?, assert_obj!("body" => "d"))
+            .await?;
+        db.commit(tx).await?;
+
+        let (base_ts, ..) = export_worker
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                &ExportFilter::default(),
+                None,
+            )
+            .await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = UserFacingModel::new_root_for_test(&mut tx);
+        model
+            .replace(edited, assert_obj!("body" => "edited"))
+            .await?;
+        model.delete(deleted).await?;
+        let inserted = model
+            .insert(messages.clone(), assert_obj!("body" => "e"))
+            .await?;
+        let short_lived = model
+            .insert(messages.clone(), assert_obj!("body" => "f"))
+            .await?;
+        db.commit(tx).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        UserFacingModel::new_root_for_test(&mut tx)
+            .delete(short_lived)
+            .await?;
+        db.commit(tx).await?;
+
+        let (_, object_keys, _) = export_worker
+            .export_inner(
+                ExportFormat::Incremental { base_ts },
+                &ExportFilter::default(),
+                None,
+            )
+            .await?;
+        must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
+        let storage_stream = storage
+            .get(&object_key)
+            .await?
+            .context("object missing from storage")?;
+        let stored_bytes = storage_stream.collect_as_bytes().await?;
+        let mut zip_reader = async_zip::read::mem::ZipFileReader::new(&stored_bytes).await?;
+        let mut zip_entries = BTreeMap::new();
+        let filenames: Vec<_> = zip_reader
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().to_string())
+            .collect();
+        for (i, filename) in filenames.into_iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(i).await?;
+            let entry_contents = String::from_utf8(entry_reader.read_to_end_crc().await?)?;
+            zip_entries.insert(filename, entry_contents);
+        }
+
+        assert!(zip_entries.contains_key(INCREMENTAL_METADATA_PATH));
+        assert!(zip_entries.contains_key("_tables/documents.jsonl"));
+        assert!(!zip_entries.contains_key("untouched/documents.jsonl"));
+        let parse_lines = |contents: &str| -> anyhow::Result<BTreeMap<String, JsonValue>> {
+            contents
+                .lines()
+                .map(|line| {
+                    let value: JsonValue = serde_json::from_str(line)?;
+                    let id = value["_id"].as_str().context("missing _id")?.to_string();
+                    Ok((id, value))
+                })
+                .collect()
+        };
+        let documents = parse_lines(&zip_entries["messages/documents.jsonl"])?;
+        assert_eq!(
+            documents.keys().cloned().collect::<BTreeSet<_>>(),
+            BTreeSet::from([edited.encode(), inserted.encode()])
+        );
+        assert_eq!(documents[&edited.encode()]["body"], json!("edited"));
+        let deleted_ids = parse_lines(&zip_entries["messages/deleted.jsonl"])?;
+        assert_eq!(
+            deleted_ids.into_keys().collect::<BTreeSet<_>>(),
+            BTreeSet::from([deleted.encode(), short_lived.encode()])
+        );
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_export_components(rt: TestRuntime) -> anyhow::Result<()> {
         let application = Application::new_for_tests(&rt).await?;
@@ -1230,9 +1886,13 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
             db.commit(tx).await?;
         }
         let (_, object_keys, usage) = export_worker
-            .export_inner(ExportFormat::Zip {
-                include_storage: false,
-            })
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                &ExportFilter::default(),
+                None,
+            )
             .await?;
         must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
 
@@ -1262,6 +1922,77 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_export_filter(rt: TestRuntime) -> anyhow::Result<()> {
+        let application = Application::new_for_tests(&rt).await?;
+        application
+            .load_component_tests_modules("with-schema")
+            .await?;
+        let db = application.database().clone();
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let (_, child_component) = BootstrapComponentsModel::new(&mut tx)
+            .component_path_to_ids("component".parse()?)
+            .await?;
+        for component in [ComponentId::Root, child_component] {
+            for table in ["messages", "users"] {
+                UserFacingModel::new(&mut tx, component.into())
+                    .insert(
+                        table.parse()?,
+                        assert_obj!("channel" => "c", "text" => table),
+                    )
+                    .await?;
+            }
+        }
+        db.commit(tx).await?;
+
+        let filter = ExportFilter {
+            exclude_components: BTreeSet::from(["component".parse()?]),
+            exclude_tables: BTreeSet::from(["users".parse()?]),
+            ..Default::default()
+        };
+        let (_, object_keys, _) = export_worker
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                &filter,
+                None,
+            )
+            .await?;
+        must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
+        let stored_bytes = storage
+            .get(&object_key)
+            .await?
+            .context("object missing from storage")?
+            .collect_as_bytes()
+            .await?;
+        let zip_reader = async_zip::read::mem::ZipFileReader::new(&stored_bytes).await?;
+        let table_filenames: BTreeSet<_> = zip_reader
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().to_string())
+            .filter(|filename| filename.ends_with("/documents.jsonl"))
+            .collect();
+        assert_eq!(
+            table_filenames,
+            BTreeSet::from([
+                "_tables/documents.jsonl".to_string(),
+                "messages/documents.jsonl".to_string(),
+            ])
+        );
+        // Nothing is written for the excluded component.
+        assert!(!zip_reader
+            .entries()
+            .iter()
+            .any(|entry| entry.filename().starts_with("_components/")));
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_export_storage(rt: TestRuntime) -> anyhow::Result<()> {
         let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
@@ -1322,9 +2053,13 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
         );
 
         let (_, object_keys, usage) = export_worker
-            .export_inner(ExportFormat::Zip {
-                include_storage: true,
-            })
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: true,
+                },
+                &ExportFilter::default(),
+                None,
+            )
             .await?;
         must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
 
@@ -1355,6 +2090,83 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_export_encrypted_and_import(rt: TestRuntime) -> anyhow::Result<()> {
+        sodiumoxide::init().map_err(|()| anyhow::anyhow!("sodiumoxide initialization failed"))?;
+        let application = Application::new_for_tests(&rt).await?;
+        let db = application.database().clone();
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let table_name: TableName = "table_0".parse()?;
+        let mut tx = db.begin(Identity::system()).await?;
+        UserFacingModel::new_root_for_test(&mut tx)
+            .insert(table_name.clone(), assert_obj!("foo" => 1))
+            .await?;
+        db.commit(tx).await?;
+
+        let encryption = new_export_encryption(
+            SnapshotEncryptionKey::Passphrase("correct horse".to_string()),
+            &export_worker.key_broker,
+        )
+        .await?;
+        let (_, object_keys, _) = export_worker
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                &ExportFilter::default(),
+                Some(encryption),
+            )
+            .await?;
+        must_let!(let ExportObjectKeys::Zip(object_key) = object_keys);
+        let ciphertext = storage
+            .get(&object_key)
+            .await?
+            .context("object missing from storage")?
+            .collect_as_bytes()
+            .await?;
+
+        // Write another document, which importing the export replaces.
+        let mut tx = db.begin(Identity::system()).await?;
+        UserFacingModel::new_root_for_test(&mut tx)
+            .insert(table_name.clone(), assert_obj!("foo" => 2))
+            .await?;
+        db.commit(tx).await?;
+
+        let body = decrypt_stream(
+            SnapshotDecryptionKey::Passphrase("correct horse".to_string()),
+            stream::once(async move { anyhow::Ok(ciphertext) }).boxed(),
+        )
+        .boxed();
+        let identity = Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
+            "test".to_string(),
+            MemberId(1),
+        ));
+        let num_written = do_import(
+            &application,
+            identity,
+            ImportFormat::Zip,
+            ImportMode::Replace,
+            None,
+            None,
+            body,
+        )
+        .await?;
+        assert_eq!(num_written, 1);
+
+        let mut tx = db.begin(Identity::system()).await?;
+        assert_eq!(
+            TableModel::new(&mut tx)
+                .count(TableNamespace::test_user(), &table_name)
+                .await?,
+            1
+        );
+        Ok(())
+    }
+
     // Regression test: previously we were trying to export documents from deleted
     // tables and table_mapping was failing.
     #[convex_macro::test_runtime]
@@ -1382,7 +2194,9 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
             .await?;
         db.commit(tx).await?;
 
-        let (_, tables, _) = export_worker.export_inner(ExportFormat::CleanJsonl).await?;
+        let (_, tables, _) = export_worker
+            .export_inner(ExportFormat::CleanJsonl, &ExportFilter::default(), None)
+            .await?;
         must_let!(let ExportObjectKeys::ByTable(tables) = tables);
         let tables: Vec<_> = tables.into_keys().collect();
         assert_eq!(tables, vec!["table_1".parse()?]);
@@ -1459,7 +2273,12 @@ r#"{{"_creationTime": normalfloat64, "_id": "{id}", "channel": "c", "text": fiel
         let DbFixtures { db, .. } = DbFixtures::new(&rt).await?;
 
         // Requested
-        let requested_export = Export::requested(ExportFormat::CleanJsonl);
+        let requested_export = Export::requested(
+            ExportFormat::CleanJsonl,
+            ExportRequestor::SnapshotExport,
+            ExportFilter::default(),
+            None,
+        );
         let object: ConvexObject = requested_export.clone().try_into()?;
         let deserialized_export = object.try_into()?;
         assert_eq!(requested_export, deserialized_export);
@@ -0,0 +1,717 @@
+//! Writing a table's documents as a Parquet file for
+//! [`ExportFormat::Parquet`](model::exports::types::ExportFormat::Parquet).
+//!
+//! Each top-level field becomes a column. Fields whose values are always a
+//! single scalar type get a typed column, and anything else, like nested
+//! objects, arrays or unions of several types, is written as a JSON string
+//! column in the clean export format.
+//!
+//! Columns are derived before the table is read, so a document may not fit
+//! them. Field columns are always nullable: a missing field is null in its
+//! column, and a field that isn't in the columns, or whose value is `null` or
+//! doesn't match its column's type, is null in its column and written to the
+//! `_extra` JSON object column instead. No field is ever dropped.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    iter,
+    mem,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use common::{
+    document::ResolvedDocument,
+    schemas::{
+        validator::{
+            LiteralValidator,
+            ObjectValidator,
+            Validator,
+        },
+        DocumentSchema,
+    },
+};
+use parquet::{
+    basic::{
+        Compression,
+        LogicalType,
+        Repetition,
+        Type as PhysicalType,
+        ZstdLevel,
+    },
+    data_type::{
+        BoolType,
+        ByteArray,
+        ByteArrayType,
+        DoubleType,
+        Int64Type,
+    },
+    file::{
+        properties::WriterProperties,
+        writer::{
+            SerializedColumnWriter,
+            SerializedFileWriter,
+        },
+    },
+    schema::types::Type,
+};
+use shape_inference::{
+    Shape,
+    ShapeConfig,
+    ShapeCounter,
+    ShapeEnum,
+};
+use value::{
+    export::ValueFormat,
+    ConvexObject,
+    ConvexValue,
+    Namespace,
+};
+
+/// Flush a row group once this many documents are buffered...
+const ROW_GROUP_MAX_ROWS: usize = 64 * 1024;
+/// ...or once the buffered documents are this large.
+const ROW_GROUP_MAX_BYTES: usize = 64 * (1 << 20);
+
+/// Column holding all non-system fields as JSON, for tables without a fixed
+/// set of fields.
+const DOCUMENT_COLUMN: &str = "_document";
+/// Column holding, as a JSON object, the fields of a document that don't fit
+/// the table's field columns.
+const EXTRA_COLUMN: &str = "_extra";
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum ParquetColumnType {
+    Int64,
+    Float64,
+    Boolean,
+    String,
+    Bytes,
+    Json,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParquetColumn {
+    pub name: String,
+    pub column_type: ParquetColumnType,
+    /// Whether the column can hold nulls, which every field column can.
+    pub optional: bool,
+}
+
+/// The columns of a table's Parquet file. `_id` and `_creationTime` always
+/// come first, and `_extra` comes last in tables with field columns.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParquetTableSchema {
+    /// The table's non-system fields, or `None` if they aren't known ahead of
+    /// time and documents are written to a single JSON column.
+    fields: Option<Vec<ParquetColumn>>,
+}
+
+/// The non-null types a field was seen with, used to pick its column type.
+#[derive(Default)]
+struct FieldTypes {
+    types: BTreeSet<ParquetColumnType>,
+}
+
+impl FieldTypes {
+    fn into_column(self, name: String) -> ParquetColumn {
+        let column_type = match self.types.len() {
+            1 => *self.types.first().expect("checked length"),
+            _ => ParquetColumnType::Json,
+        };
+        ParquetColumn {
+            name,
+            column_type,
+            optional: true,
+        }
+    }
+
+    fn add_shape<C: ShapeConfig, S: ShapeCounter>(&mut self, shape: &Shape<C, S>) {
+        let options: Box<dyn Iterator<Item = &Shape<C, S>>> = match shape.variant() {
+            ShapeEnum::Union(union) => Box::new(union.iter()),
+            _ => Box::new(iter::once(shape)),
+        };
+        for option in options {
+            let column_type = match option.variant() {
+                ShapeEnum::Never | ShapeEnum::Null => continue,
+                ShapeEnum::Int64 => ParquetColumnType::Int64,
+                ShapeEnum::NegativeInf
+                | ShapeEnum::PositiveInf
+                | ShapeEnum::NegativeZero
+                | ShapeEnum::NaN
+                | ShapeEnum::NormalFloat64
+                | ShapeEnum::Float64 => ParquetColumnType::Float64,
+                ShapeEnum::Boolean => ParquetColumnType::Boolean,
+                ShapeEnum::StringLiteral(_)
+                | ShapeEnum::Id(_)
+                | ShapeEnum::FieldName
+                | ShapeEnum::String => ParquetColumnType::String,
+                ShapeEnum::Bytes => ParquetColumnType::Bytes,
+                ShapeEnum::Array(_)
+                | ShapeEnum::Set(_)
+                | ShapeEnum::Map(_)
+                | ShapeEnum::Object(_)
+                | ShapeEnum::Record(_)
+                | ShapeEnum::Union(_)
+                | ShapeEnum::Unknown => ParquetColumnType::Json,
+            };
+            self.types.insert(column_type);
+        }
+    }
+
+    fn add_validator(&mut self, validator: &Validator) {
+        let column_type = match validator {
+            Validator::Null => return,
+            Validator::Union(options) => {
+                for option in options {
+                    self.add_validator(option);
+                }
+                return;
+            },
+            Validator::Int64 | Validator::Literal(LiteralValidator::Int64(_)) => {
+                ParquetColumnType::Int64
+            },
+            Validator::Float64 | Validator::Literal(LiteralValidator::Float64(_)) => {
+                ParquetColumnType::Float64
+            },
+            Validator::Boolean | Validator::Literal(LiteralValidator::Boolean(_)) => {
+                ParquetColumnType::Boolean
+            },
+            Validator::Id(_)
+            | Validator::String
+            | Validator::Literal(LiteralValidator::String(_)) => ParquetColumnType::String,
+            Validator::Bytes => ParquetColumnType::Bytes,
+            Validator::Array(_)
+            | Validator::Set(_)
+            | Validator::Record(..)
+            | Validator::Map(..)
+            | Validator::Object(_)
+            | Validator::Any => ParquetColumnType::Json,
+        };
+        self.types.insert(column_type);
+    }
+}
+
+impl ParquetTableSchema {
+    /// Derive columns from a table's inferred shape. A table whose shape is an
+    /// object, or a union of objects, gets a column per field.
+    pub fn from_shape<C: ShapeConfig, S: ShapeCounter>(shape: &Shape<C, S>) -> Self {
+        let objects: Vec<_> = match shape.variant() {
+            ShapeEnum::Never => vec![],
+            ShapeEnum::Object(object) => vec![object],
+            ShapeEnum::Union(union) => {
+                let objects: Option<Vec<_>> = union
+                    .iter()
+                    .map(|option| match option.variant() {
+                        ShapeEnum::Object(object) => Some(object),
+                        _ => None,
+                    })
+                    .collect();
+                match objects {
+                    Some(objects) => objects,
+                    None => return Self { fields: None },
+                }
+            },
+            _ => return Self { fields: None },
+        };
+        let objects = objects.into_iter().map(|object| {
+            object
+                .iter()
+                .filter(|(field, _)| !field.is_system())
+                .map(|(field, field_shape)| {
+                    let mut types = FieldTypes::default();
+                    types.add_shape(&field_shape.value_shape);
+                    (field.to_string(), types)
+                })
+                .collect()
+        });
+        Self {
+            fields: Some(merge_objects(objects)),
+        }
+    }
+
+    /// Derive columns from a table's declared schema. Returns `None` if the
+    /// schema doesn't restrict the table's documents.
+    pub fn from_document_schema(schema: &DocumentSchema) -> Option<Self> {
+        let DocumentSchema::Union(objects) = schema else {
+            return None;
+        };
+        let objects = objects.iter().map(|ObjectValidator(fields)| {
+            fields
+                .iter()
+                .filter(|(field, _)| !field.is_system())
+                .map(|(field, field_validator)| {
+                    let mut types = FieldTypes::default();
+                    types.add_validator(field_validator.validator());
+                    (field.to_string(), types)
+                })
+                .collect()
+        });
+        Some(Self {
+            fields: Some(merge_objects(objects)),
+        })
+    }
+
+    pub fn columns(&self) -> Vec<ParquetColumn> {
+        let mut columns = vec![
+            ParquetColumn {
+                name: "_id".to_string(),
+                column_type: ParquetColumnType::String,
+                optional: false,
+            },
+            ParquetColumn {
+                name: "_creationTime".to_string(),
+                column_type: ParquetColumnType::Float64,
+                optional: false,
+            },
+        ];
+        match &self.fields {
+            Some(fields) => {
+                columns.extend(fields.iter().cloned());
+                columns.push(ParquetColumn {
+                    name: EXTRA_COLUMN.to_string(),
+                    column_type: ParquetColumnType::Json,
+                    optional: true,
+                });
+            },
+            None => columns.push(ParquetColumn {
+                name: DOCUMENT_COLUMN.to_string(),
+                column_type: ParquetColumnType::Json,
+                optional: false,
+            }),
+        }
+        columns
+    }
+
+    fn parquet_type(&self) -> anyhow::Result<Type> {
+        let fields = self
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let (physical_type, logical_type) = match column.column_type {
+                    ParquetColumnType::Int64 => (PhysicalType::INT64, None),
+                    ParquetColumnType::Float64 => (PhysicalType::DOUBLE, None),
+                    ParquetColumnType::Boolean => (PhysicalType::BOOLEAN, None),
+                    ParquetColumnType::String => {
+                        (PhysicalType::BYTE_ARRAY, Some(LogicalType::String))
+                    },
+                    ParquetColumnType::Bytes => (PhysicalType::BYTE_ARRAY, None),
+                    ParquetColumnType::Json => (PhysicalType::BYTE_ARRAY, Some(LogicalType::Json)),
+                };
+                let repetition = if column.optional {
+                    Repetition::OPTIONAL
+                } else {
+                    Repetition::REQUIRED
+                };
+                let field = Type::primitive_type_builder(&column.name, physical_type)
+                    .with_repetition(repetition)
+                    .with_logical_type(logical_type)
+                    .build()?;
+                anyhow::Ok(Arc::new(field))
+            })
+            .try_collect()?;
+        Ok(Type::group_type_builder("document")
+            .with_fields(fields)
+            .build()?)
+    }
+}
+
+/// Combine the fields of each object in a union.
+fn merge_objects(
+    objects: impl Iterator<Item = BTreeMap<String, FieldTypes>>,
+) -> Vec<ParquetColumn> {
+    let mut merged: BTreeMap<String, FieldTypes> = BTreeMap::new();
+    for object in objects {
+        for (field, types) in object {
+            merged.entry(field).or_default().types.extend(types.types);
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(field, types)| types.into_column(field))
+        .collect()
+}
+
+enum ColumnValues {
+    Int64(Vec<i64>),
+    Float64(Vec<f64>),
+    Boolean(Vec<bool>),
+    ByteArray(Vec<ByteArray>),
+}
+
+struct ColumnBuffer {
+    column: ParquetColumn,
+    values: ColumnValues,
+    /// Definition levels for optional columns: 1 for a value, 0 for null.
+    def_levels: Vec<i16>,
+}
+
+impl ColumnBuffer {
+    fn new(column: ParquetColumn) -> Self {
+        let values = match column.column_type {
+            ParquetColumnType::Int64 => ColumnValues::Int64(vec![]),
+            ParquetColumnType::Float64 => ColumnValues::Float64(vec![]),
+            ParquetColumnType::Boolean => ColumnValues::Boolean(vec![]),
+            ParquetColumnType::String | ParquetColumnType::Bytes | ParquetColumnType::Json => {
+                ColumnValues::ByteArray(vec![])
+            },
+        };
+        Self {
+            column,
+            values,
+            def_levels: vec![],
+        }
+    }
+
+    /// Buffer `value`, or a null if it's `None` or doesn't match the column's
+    /// type. Returns the value in the latter case, so the caller can keep it
+    /// elsewhere.
+    fn push(&mut self, value: Option<ConvexValue>) -> anyhow::Result<Option<ConvexValue>> {
+        let rejected = match value {
+            Some(value) => match self.push_value(value)? {
+                None => {
+                    self.def_levels.push(1);
+                    return Ok(None);
+                },
+                rejected => rejected,
+            },
+            None => None,
+        };
+        anyhow::ensure!(
+            self.column.optional,
+            "Missing or mismatched value for required column {}",
+            self.column.name
+        );
+        self.def_levels.push(0);
+        Ok(rejected)
+    }
+
+    /// Buffer `value` if it matches the column's type, and return it if not.
+    /// `null` only matches JSON columns.
+    fn push_value(&mut self, value: ConvexValue) -> anyhow::Result<Option<ConvexValue>> {
+        match (&mut self.values, self.column.column_type, value) {
+            (ColumnValues::Int64(values), _, ConvexValue::Int64(v)) => values.push(v),
+            (ColumnValues::Float64(values), _, ConvexValue::Float64(v)) => values.push(v),
+            (ColumnValues::Boolean(values), _, ConvexValue::Boolean(v)) => values.push(v),
+            (
+                ColumnValues::ByteArray(values),
+                ParquetColumnType::String,
+                ConvexValue::String(v),
+            ) => values.push(String::from(v).into_bytes().into()),
+            (ColumnValues::ByteArray(values), ParquetColumnType::Bytes, ConvexValue::Bytes(v)) => {
+                values.push(Vec::from(v).into())
+            },
+            (ColumnValues::ByteArray(values), ParquetColumnType::Json, value) => {
+                let json = value.export(ValueFormat::ConvexCleanJSON);
+                values.push(serde_json::to_vec(&json)?.into())
+            },
+            (_, _, value) => return Ok(Some(value)),
+        }
+        Ok(None)
+    }
+
+    fn write(&mut self, column_writer: &mut SerializedColumnWriter<'_>) -> anyhow::Result<()> {
+        let def_levels = mem::take(&mut self.def_levels);
+        let def_levels = self.column.optional.then_some(&def_levels[..]);
+        match &mut self.values {
+            ColumnValues::Int64(values) => {
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            },
+            ColumnValues::Float64(values) => {
+                column_writer
+                    .typed::<DoubleType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            },
+            ColumnValues::Boolean(values) => {
+                column_writer
+                    .typed::<BoolType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            },
+            ColumnValues::ByteArray(values) => {
+                column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Buffers documents into row groups and encodes them as a Parquet file.
+/// Encoded bytes are returned as each row group is written, so the file never
+/// needs to be held in memory all at once.
+pub struct ParquetTableWriter {
+    writer: SerializedFileWriter<Vec<u8>>,
+    columns: Vec<ColumnBuffer>,
+    has_document_column: bool,
+    buffered_rows: usize,
+    buffered_bytes: usize,
+}
+
+impl ParquetTableWriter {
+    pub fn new(schema: &ParquetTableSchema) -> anyhow::Result<Self> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let writer = SerializedFileWriter::new(
+            Vec::new(),
+            Arc::new(schema.parquet_type()?),
+            Arc::new(properties),
+        )?;
+        Ok(Self {
+            writer,
+            columns: schema
+                .columns()
+                .into_iter()
+                .map(ColumnBuffer::new)
+                .collect(),
+            has_document_column: schema.fields.is_none(),
+            buffered_rows: 0,
+            buffered_bytes: 0,
+        })
+    }
+
+    /// Buffer a document, returning encoded bytes if it completed a row group.
+    pub fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<Option<Vec<u8>>> {
+        self.buffered_rows += 1;
+        self.buffered_bytes += doc.size();
+        let id = doc.developer_id().encode();
+        let creation_time = f64::from(
+            doc.creation_time()
+                .context("Document should have creation time")?,
+        );
+        let object = doc.into_value().0.filter_system_fields();
+        // Required and JSON columns never return a rejected value.
+        let mut columns = self.columns.iter_mut();
+        columns
+            .next()
+            .context("Missing _id column")?
+            .push(Some(ConvexValue::try_from(id)?))?;
+        columns
+            .next()
+            .context("Missing _creationTime column")?
+            .push(Some(ConvexValue::from(creation_time)))?;
+        if self.has_document_column {
+            columns
+                .next()
+                .context("Missing document column")?
+                .push(Some(ConvexValue::Object(object)))?;
+        } else {
+            let mut columns: Vec<_> = columns.collect();
+            let extra_column = columns.pop().context("Missing _extra column")?;
+            let mut fields = BTreeMap::from(object);
+            let mut extra = BTreeMap::new();
+            for column in columns {
+                let Some((field, value)) = fields.remove_entry(&column.column.name[..]) else {
+                    column.push(None)?;
+                    continue;
+                };
+                if let Some(value) = column.push(Some(value))? {
+                    extra.insert(field, value);
+                }
+            }
+            // Fields that aren't in the columns at all.
+            extra.extend(fields);
+            let extra = if extra.is_empty() {
+                None
+            } else {
+                Some(ConvexValue::Object(ConvexObject::try_from(extra)?))
+            };
+            extra_column.push(extra)?;
+        }
+        if self.buffered_rows >= ROW_GROUP_MAX_ROWS || self.buffered_bytes >= ROW_GROUP_MAX_BYTES {
+            return Ok(Some(self.flush()?));
+        }
+        Ok(None)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<Vec<u8>> {
+        if self.buffered_rows > 0 {
+            let mut row_group_writer = self.writer.next_row_group()?;
+            for column in &mut self.columns {
+                let mut column_writer = row_group_writer
+                    .next_column()?
+                    .context("Parquet schema has fewer columns than the table")?;
+                column.write(&mut column_writer)?;
+                column_writer.close()?;
+            }
+            row_group_writer.close()?;
+            self.buffered_rows = 0;
+            self.buffered_bytes = 0;
+        }
+        Ok(mem::take(self.writer.inner_mut()))
+    }
+
+    /// Write any buffered documents and the file's footer, returning the
+    /// remaining encoded bytes.
+    pub fn complete(mut self) -> anyhow::Result<Vec<u8>> {
+        let mut buf = self.flush()?;
+        buf.extend(self.writer.into_inner()?);
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use common::{
+        assert_obj,
+        document::{
+            CreationTime,
+            ResolvedDocument,
+        },
+        schemas::{
+            validator::{
+                FieldValidator,
+                ObjectValidator,
+                Validator,
+            },
+            DocumentSchema,
+        },
+        testing::TestIdGenerator,
+    };
+    use maplit::btreemap;
+    use parquet::file::reader::{
+        FileReader,
+        SerializedFileReader,
+    };
+    use serde_json::json;
+    use shape_inference::{
+        CountedShape,
+        ProdConfigWithOptionalFields,
+    };
+
+    use super::{
+        ParquetColumn,
+        ParquetColumnType,
+        ParquetTableSchema,
+        ParquetTableWriter,
+    };
+
+    fn column(name: &str, column_type: ParquetColumnType, optional: bool) -> ParquetColumn {
+        ParquetColumn {
+            name: name.to_string(),
+            column_type,
+            optional,
+        }
+    }
+
+    fn system_columns() -> Vec<ParquetColumn> {
+        vec![
+            column("_id", ParquetColumnType::String, false),
+            column("_creationTime", ParquetColumnType::Float64, false),
+        ]
+    }
+
+    #[test]
+    fn test_columns_from_shape() -> anyhow::Result<()> {
+        let shape = CountedShape::<ProdConfigWithOptionalFields>::empty()
+            .insert(&assert_obj!(
+                "count" => 1i64,
+                "name" => "a",
+                "tags" => ["x"],
+                "mixed" => 1i64,
+            ))
+            .insert(&assert_obj!(
+                "count" => 2i64,
+                "name" => "b",
+                "mixed" => "one",
+                "maybe" => null,
+            ));
+        let mut expected = system_columns();
+        expected.extend([
+            column("count", ParquetColumnType::Int64, true),
+            column("maybe", ParquetColumnType::Json, true),
+            column("mixed", ParquetColumnType::Json, true),
+            column("name", ParquetColumnType::String, true),
+            column("tags", ParquetColumnType::Json, true),
+            column("_extra", ParquetColumnType::Json, true),
+        ]);
+        assert_eq!(ParquetTableSchema::from_shape(&shape).columns(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns_from_document_schema() -> anyhow::Result<()> {
+        let schema = DocumentSchema::Union(vec![ObjectValidator(btreemap! {
+            "author".parse()? => FieldValidator::required_field_type(Validator::Id(
+                "users".parse()?,
+            )),
+            "score".parse()? => FieldValidator::optional_field_type(Validator::Union(vec![
+                Validator::Float64,
+                Validator::Null,
+            ])),
+        })]);
+        let mut expected = system_columns();
+        expected.extend([
+            column("author", ParquetColumnType::String, true),
+            column("score", ParquetColumnType::Float64, true),
+            column("_extra", ParquetColumnType::Json, true),
+        ]);
+        assert_eq!(
+            ParquetTableSchema::from_document_schema(&schema)
+                .expect("schema has fields")
+                .columns(),
+            expected
+        );
+        assert_eq!(
+            ParquetTableSchema::from_document_schema(&DocumentSchema::Any),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatched_fields_written_to_extra() -> anyhow::Result<()> {
+        // The schema isn't enforced, so documents may not match it.
+        let schema = ParquetTableSchema::from_document_schema(&DocumentSchema::Union(vec![
+            ObjectValidator(btreemap! {
+                "score".parse()? => FieldValidator::required_field_type(Validator::Float64),
+            }),
+        ]))
+        .expect("schema has fields");
+        let mut id_generator = TestIdGenerator::new();
+        let table = "scores".parse()?;
+        let mut writer = ParquetTableWriter::new(&schema)?;
+        for value in [
+            assert_obj!("score" => 1.5),
+            assert_obj!("score" => "high", "note" => "typo"),
+            assert_obj!("score" => null),
+            assert_obj!(),
+        ] {
+            let id = id_generator.user_generate(&table);
+            let doc = ResolvedDocument::new(id, CreationTime::ONE, value)?;
+            assert_eq!(writer.write(doc)?, None);
+        }
+        let reader = SerializedFileReader::new(Bytes::from(writer.complete()?))?;
+        let mut rows = vec![];
+        for row in reader.get_row_iter(None)? {
+            let row = row?;
+            rows.push((
+                row.get_double(2).ok(),
+                row.get_string(3)
+                    .ok()
+                    .map(serde_json::from_str::<serde_json::Value>)
+                    .transpose()?,
+            ));
+        }
+        assert_eq!(
+            rows,
+            vec![
+                (Some(1.5), None),
+                (None, Some(json!({"note": "typo", "score": "high"}))),
+                (None, Some(json!({"score": null}))),
+                (None, None),
+            ]
+        );
+        Ok(())
+    }
+}
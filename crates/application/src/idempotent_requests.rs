@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    knobs::IDEMPOTENT_REQUESTS_GARBAGE_COLLECTION_BATCH_SIZE,
+    runtime::Runtime,
+};
+use database::Database;
+use futures::{
+    future::Either,
+    select_biased,
+    Future,
+    FutureExt,
+};
+use keybroker::Identity;
+use model::idempotent_requests::IdempotentRequestModel;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Deletes `_idempotent_requests` records once their key has expired.
+pub struct IdempotentRequestsGarbageCollector<RT: Runtime> {
+    rt: RT,
+    database: Database<RT>,
+}
+
+impl<RT: Runtime> IdempotentRequestsGarbageCollector<RT> {
+    pub fn start(rt: RT, database: Database<RT>) -> impl Future<Output = ()> + Send {
+        let garbage_collector = Self { rt, database };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = garbage_collector.run(&mut backoff).await {
+                let delay = garbage_collector.rt.with_rng(|rng| backoff.fail(rng));
+                tracing::error!("Idempotent requests garbage collector failed, sleeping {delay:?}");
+                report_error(&mut e);
+                garbage_collector.rt.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let now = self.rt.generate_timestamp()?;
+            let mut model = IdempotentRequestModel::new(&mut tx);
+            let deleted = model
+                .delete_expired(now, *IDEMPOTENT_REQUESTS_GARBAGE_COLLECTION_BATCH_SIZE)
+                .await?;
+            if deleted > 0 {
+                tracing::debug!("Garbage collecting {deleted} expired idempotent requests");
+                self.database
+                    .commit_with_write_source(tx, "idempotent_requests_gc")
+                    .await?;
+                continue;
+            }
+
+            // Everything before `now` has been deleted, so the next record
+            // expires at or after `now`.
+            let next_expiration_future = match model.next_expiration().await? {
+                Some(expires_ts) => Either::Left(self.rt.wait(expires_ts - now)),
+                None => Either::Right(std::future::pending()),
+            };
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            select_biased! {
+                _ = next_expiration_future.fuse() => {
+                }
+                _ = subscription.wait_for_invalidation().fuse() => {
+                },
+            }
+            backoff.reset();
+        }
+    }
+}
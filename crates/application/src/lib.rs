@@ -25,6 +25,10 @@ use authentication::{
     validate_id_token,
     Auth0IdToken,
 };
+use backup_scheduler::{
+    BackupPolicy,
+    BackupScheduler,
+};
 use bytes::Bytes;
 use common::{
     auth::AuthInfo,
@@ -59,6 +63,7 @@ use common::{
         report_error,
         JsError,
     },
+    execution_context::ExecutionContext,
     http::fetch::FetchClient,
     knobs::{
         MAX_JOBS_CANCEL_BATCH,
@@ -141,6 +146,8 @@ use function_runner::FunctionRunner;
 use futures::{
     channel::oneshot,
     stream::BoxStream,
+    StreamExt,
+    TryStreamExt,
 };
 use headers::{
     ContentLength,
@@ -150,6 +157,7 @@ use http_client::{
     cached_http_client_for,
     ClientPurpose,
 };
+use idempotent_requests::IdempotentRequestsGarbageCollector;
 use isolate::{
     parse_udf_args,
     AuthConfig,
@@ -205,8 +213,10 @@ use model::{
     exports::{
         types::{
             Export,
+            ExportFilter,
             ExportFormat,
             ExportObjectKeys,
+            ExportRequestor,
         },
         EXPORTS_TABLE,
     },
@@ -228,9 +238,14 @@ use model::{
         },
         ModuleModel,
     },
-    scheduled_jobs::SchedulerModel,
-    session_requests::types::SessionRequestIdentifier,
+    scheduled_jobs::{
+        types::ScheduledJobDeadLetter,
+        SchedulerModel,
+        SCHEDULED_JOB_DEAD_LETTERS_TABLE,
+    },
+    session_requests::types::MutationIdentifier,
     snapshot_imports::types::{
+        ImportCompression,
         ImportFormat,
         ImportMode,
     },
@@ -298,6 +313,7 @@ use usage_tracking::{
 use value::{
     id_v6::DeveloperDocumentId,
     sha256::Sha256Digest,
+    ConvexArray,
     ConvexValue,
     Namespace,
     ResolvedDocumentId,
@@ -327,23 +343,35 @@ use crate::{
         RedactedJsError,
         RedactedLogLines,
     },
+    snapshot_encryption::{
+        decrypt_stream,
+        new_export_encryption,
+        SnapshotDecryptionKey,
+        SnapshotEncryptionKey,
+    },
     snapshot_import::SnapshotImportWorker,
+    table_restore::TableRestoreCounts,
 };
 
 pub mod api;
 pub mod application_function_runner;
+pub mod backup_scheduler;
 mod cache;
 pub mod cron_jobs;
 pub mod deploy_config;
 mod export_worker;
 pub mod function_log;
+mod idempotent_requests;
 pub mod log_visibility;
 mod metrics;
 mod module_cache;
 pub mod redaction;
 pub mod scheduled_jobs;
 mod schema_worker;
+pub mod snapshot_encryption;
 pub mod snapshot_import;
+pub mod table_csv;
+pub mod table_restore;
 mod table_summary_worker;
 pub mod valid_identifier;
 
@@ -459,6 +487,18 @@ pub struct FunctionError {
     pub log_lines: RedactedLogLines,
 }
 
+/// How far a cancellation of scheduled jobs in a time range got.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CancelJobsProgress {
+    pub num_canceled: usize,
+    /// Next run time of the last job canceled. Jobs are canceled in order of
+    /// their next run time, so passing this as the start of the range resumes
+    /// the cancellation.
+    pub cursor: Option<Timestamp>,
+    /// Whether every matching job has been canceled.
+    pub is_done: bool,
+}
+
 // Ordered so that all unsets come before sets
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub enum EnvVarChange {
@@ -490,6 +530,8 @@ pub struct Application<RT: Runtime> {
     schema_worker: Arc<Mutex<RT::Handle>>,
     snapshot_import_worker: Arc<Mutex<RT::Handle>>,
     export_worker: Arc<Mutex<RT::Handle>>,
+    idempotent_requests_garbage_collector: Arc<Mutex<RT::Handle>>,
+    backup_scheduler: Option<Arc<Mutex<RT::Handle>>>,
     log_sender: Arc<dyn LogSender>,
     log_visibility: Arc<dyn LogVisibility<RT>>,
     module_cache: ModuleCache<RT>,
@@ -523,6 +565,10 @@ impl<RT: Runtime> Clone for Application<RT> {
             schema_worker: self.schema_worker.clone(),
             snapshot_import_worker: self.snapshot_import_worker.clone(),
             export_worker: self.export_worker.clone(),
+            idempotent_requests_garbage_collector: self
+                .idempotent_requests_garbage_collector
+                .clone(),
+            backup_scheduler: self.backup_scheduler.clone(),
             log_sender: self.log_sender.clone(),
             log_visibility: self.log_visibility.clone(),
             module_cache: self.module_cache.clone(),
@@ -559,6 +605,7 @@ impl<RT: Runtime> Application<RT> {
         snapshot_import_pause_client: PauseClient,
         scheduled_jobs_pause_client: PauseClient,
         app_auth: Arc<ApplicationAuth>,
+        backup_policy: Option<BackupPolicy>,
     ) -> anyhow::Result<Self> {
         let module_cache = ModuleCache::new(runtime.clone(), modules_storage.clone()).await;
         let module_loader = Arc::new(module_cache.clone());
@@ -647,6 +694,7 @@ impl<RT: Runtime> Application<RT> {
             exports_storage.clone(),
             files_storage.clone(),
             database.usage_counter().clone(),
+            key_broker.clone(),
         );
         let export_worker = Arc::new(Mutex::new(runtime.spawn("export_worker", export_worker)));
 
@@ -662,6 +710,23 @@ impl<RT: Runtime> Application<RT> {
             runtime.spawn("snapshot_import_worker", snapshot_import_worker),
         ));
 
+        let idempotent_requests_garbage_collector = Arc::new(Mutex::new(runtime.spawn(
+            "idempotent_requests_garbage_collector",
+            IdempotentRequestsGarbageCollector::start(runtime.clone(), database.clone()),
+        )));
+
+        let backup_scheduler = backup_policy.map(|policy| {
+            Arc::new(Mutex::new(runtime.spawn(
+                "backup_scheduler",
+                BackupScheduler::start(
+                    runtime.clone(),
+                    database.clone(),
+                    exports_storage.clone(),
+                    policy,
+                ),
+            )))
+        });
+
         Ok(Self {
             runtime,
             database,
@@ -686,6 +751,8 @@ impl<RT: Runtime> Application<RT> {
             schema_worker,
             export_worker,
             snapshot_import_worker,
+            idempotent_requests_garbage_collector,
+            backup_scheduler,
             log_sender,
             log_visibility,
             module_cache,
@@ -976,7 +1043,7 @@ impl<RT: Runtime> Application<RT> {
         args: Vec<JsonValue>,
         identity: Identity,
         // Identifier used to make this mutation idempotent.
-        mutation_identifier: Option<SessionRequestIdentifier>,
+        mutation_identifier: Option<MutationIdentifier>,
         caller: FunctionCaller,
         pause_client: PauseClient,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>> {
@@ -1286,13 +1353,38 @@ impl<RT: Runtime> Application<RT> {
         }
     }
 
+    /// Request a snapshot export in `format` of the tables `filter` includes,
+    /// encrypted with `encryption_key` if it's set. If `format` is `None`,
+    /// tables are exported individually in the JSON format the deployment's
+    /// npm version supports.
     pub async fn request_export(
         &self,
         identity: Identity,
-        zip: bool,
-        include_storage: bool,
+        format: Option<ExportFormat>,
+        filter: ExportFilter,
+        encryption_key: Option<SnapshotEncryptionKey>,
     ) -> anyhow::Result<()> {
         anyhow::ensure!(identity.is_admin(), unauthorized_error("request_export"));
+        let encryption = match encryption_key {
+            Some(encryption_key) => {
+                anyhow::ensure!(
+                    matches!(
+                        format,
+                        Some(
+                            ExportFormat::Zip { .. }
+                                | ExportFormat::Parquet { .. }
+                                | ExportFormat::Incremental { .. }
+                        )
+                    ),
+                    ErrorMetadata::bad_request(
+                        "EncryptedExportFormat",
+                        "Only zip exports can be encrypted.",
+                    )
+                );
+                Some(new_export_encryption(encryption_key, &self.key_broker).await?)
+            },
+            None => None,
+        };
         let snapshot = self.latest_snapshot()?;
         let user_table_count = snapshot.table_registry.user_table_names().count();
         if user_table_count == 0 {
@@ -1303,14 +1395,31 @@ impl<RT: Runtime> Application<RT> {
             .into());
         }
         let mut tx = self.begin(identity).await?;
+        if filter != ExportFilter::default() {
+            let mut filtered_table_count = 0;
+            for (_, namespace, _, table_name) in snapshot.table_registry.iter_active_user_tables() {
+                let component_path = BootstrapComponentsModel::new(&mut tx)
+                    .get_component_path(namespace.into())
+                    .await?;
+                if filter.includes_table(&component_path, table_name) {
+                    filtered_table_count += 1;
+                }
+            }
+            anyhow::ensure!(
+                filtered_table_count > 0,
+                ErrorMetadata::bad_request(
+                    "NoTables",
+                    "There are no tables matching the export filter.",
+                )
+            );
+        }
         let export_requested = ExportWorker::export_in_state(&mut tx, "requested").await?;
         let export_in_progress = ExportWorker::export_in_state(&mut tx, "in_progress").await?;
         match (export_requested, export_in_progress) {
             (None, None) => {
-                let format = if zip {
-                    ExportFormat::Zip { include_storage }
-                } else {
-                    match UdfConfigModel::new(&mut tx, TableNamespace::by_component_TODO())
+                let format = match format {
+                    Some(format) => format,
+                    None => match UdfConfigModel::new(&mut tx, TableNamespace::by_component_TODO())
                         .get()
                         .await?
                     {
@@ -1326,10 +1435,45 @@ impl<RT: Runtime> Application<RT> {
                         },
                         // They haven't pushed functions yet - give them clean export.
                         None => ExportFormat::CleanJsonl,
-                    }
+                    },
                 };
+                if let ExportFormat::Incremental { base_ts } = format {
+                    anyhow::ensure!(
+                        ExportWorker::completed_export_at_ts(&mut tx, base_ts)
+                            .await?
+                            .is_some(),
+                        ErrorMetadata::bad_request(
+                            "IncrementalExportBaseNotFound",
+                            format!("There is no completed export with snapshot {base_ts}."),
+                        )
+                    );
+                    let min_document_snapshot_ts = self
+                        .database
+                        .retention_validator()
+                        .min_document_snapshot_ts()
+                        .await?;
+                    anyhow::ensure!(
+                        base_ts >= min_document_snapshot_ts,
+                        ErrorMetadata::bad_request(
+                            "IncrementalExportBaseTooOld",
+                            format!(
+                                "The export with snapshot {base_ts} is too old to export changes \
+                                 since. Request a full export instead."
+                            ),
+                        )
+                    );
+                }
                 SystemMetadataModel::new_global(&mut tx)
-                    .insert(&EXPORTS_TABLE, Export::requested(format).try_into()?)
+                    .insert(
+                        &EXPORTS_TABLE,
+                        Export::requested(
+                            format,
+                            ExportRequestor::SnapshotExport,
+                            filter,
+                            encryption,
+                        )
+                        .try_into()?,
+                    )
                     .await?;
                 Ok(())
             },
@@ -1350,7 +1494,7 @@ impl<RT: Runtime> Application<RT> {
         identity: Identity,
         snapshot_ts: Timestamp,
     ) -> anyhow::Result<(StorageGetStream, String)> {
-        let stream = self
+        let (stream, encrypted) = self
             .get_export_inner(identity, snapshot_ts, move |keys| {
                 let key = match keys {
                     ExportObjectKeys::Zip(key) => key,
@@ -1362,11 +1506,14 @@ impl<RT: Runtime> Application<RT> {
                 Ok(key)
             })
             .await?;
-        let filename = format!(
+        let mut filename = format!(
             // This should match the format in SnapshotExport.tsx.
             "snapshot_{}_{snapshot_ts}.zip",
             self.instance_name
         );
+        if encrypted {
+            filename.push_str(".enc");
+        }
         Ok((stream, filename))
     }
 
@@ -1376,26 +1523,28 @@ impl<RT: Runtime> Application<RT> {
         snapshot_ts: Timestamp,
         table_name: TableName,
     ) -> anyhow::Result<StorageGetStream> {
-        self.get_export_inner(identity, snapshot_ts, move |keys| {
-            let key = match keys {
-                ExportObjectKeys::ByTable(tables) => tables
-                    .get(&table_name)
-                    .context(ErrorMetadata::bad_request(
+        let (stream, _) = self
+            .get_export_inner(identity, snapshot_ts, move |keys| {
+                let key = match keys {
+                    ExportObjectKeys::ByTable(tables) => tables
+                        .get(&table_name)
+                        .context(ErrorMetadata::bad_request(
+                            "NoExportForTable",
+                            format!(
+                                "The requested export {snapshot_ts} does not have an export for \
+                                 {table_name}"
+                            ),
+                        ))?
+                        .clone(),
+                    _ => anyhow::bail!(ErrorMetadata::bad_request(
                         "NoExportForTable",
-                        format!(
-                            "The requested export {snapshot_ts} does not have an export for \
-                             {table_name}"
-                        ),
-                    ))?
-                    .clone(),
-                _ => anyhow::bail!(ErrorMetadata::bad_request(
-                    "NoExportForTable",
-                    "Expected export with tables"
-                )),
-            };
-            Ok(key)
-        })
-        .await
+                        "Expected export with tables"
+                    )),
+                };
+                Ok(key)
+            })
+            .await?;
+        Ok(stream)
     }
 
     async fn get_export_inner(
@@ -1403,8 +1552,8 @@ impl<RT: Runtime> Application<RT> {
         identity: Identity,
         snapshot_ts: Timestamp,
         get_object_key: impl FnOnce(ExportObjectKeys) -> anyhow::Result<ObjectKey>,
-    ) -> anyhow::Result<StorageGetStream> {
-        let object_key = {
+    ) -> anyhow::Result<(StorageGetStream, bool)> {
+        let (object_key, encrypted) = {
             let mut tx = self.begin(identity).await?;
             let export_doc = ExportWorker::completed_export_at_ts(&mut tx, snapshot_ts).await?;
             let export: ParsedDocument<Export> = export_doc
@@ -1414,7 +1563,11 @@ impl<RT: Runtime> Application<RT> {
                 ))?
                 .try_into()?;
             match export.into_value() {
-                Export::Completed { object_keys, .. } => get_object_key(object_keys)?,
+                Export::Completed {
+                    object_keys,
+                    encrypted,
+                    ..
+                } => (get_object_key(object_keys)?, encrypted),
                 Export::Failed { .. } | Export::InProgress { .. } | Export::Requested { .. } => {
                     anyhow::bail!(ErrorMetadata::bad_request(
                         "ExportNotComplete",
@@ -1429,7 +1582,45 @@ impl<RT: Runtime> Application<RT> {
                 format!("The requested export {snapshot_ts}/{object_key:?} was not found"),
             ),
         )?;
-        Ok(storage_get_stream)
+        Ok((storage_get_stream, encrypted))
+    }
+
+    /// Completed scheduled backups, newest first. Download them with
+    /// [`Self::get_zip_export`].
+    pub async fn list_backups(&self, identity: Identity) -> anyhow::Result<Vec<Export>> {
+        anyhow::ensure!(identity.is_admin(), unauthorized_error("list_backups"));
+        let mut tx = self.begin(identity).await?;
+        let backups = backup_scheduler::list_backups(&mut tx).await?;
+        Ok(backups
+            .into_iter()
+            .map(|backup| backup.into_value())
+            .collect())
+    }
+
+    pub async fn delete_backup(
+        &self,
+        identity: Identity,
+        snapshot_ts: Timestamp,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(identity.is_admin(), unauthorized_error("delete_backup"));
+        let mut tx = self.begin(identity).await?;
+        let export: Option<ParsedDocument<Export>> =
+            ExportWorker::completed_export_at_ts(&mut tx, snapshot_ts)
+                .await?
+                .map(|doc| doc.try_into())
+                .transpose()?;
+        let backup = export
+            .filter(|export| export.requestor() == ExportRequestor::ScheduledBackup)
+            .context(ErrorMetadata::bad_request(
+                "BackupNotFound",
+                format!("There is no backup with snapshot {snapshot_ts}."),
+            ))?;
+        backup_scheduler::delete_backup_object(self.exports_storage.as_ref(), &backup).await?;
+        SystemMetadataModel::new_global(&mut tx)
+            .delete(backup.id())
+            .await?;
+        self.commit(tx, "delete_backup").await?;
+        Ok(())
     }
 
     pub async fn update_environment_variables(
@@ -2131,13 +2322,18 @@ impl<RT: Runtime> Application<RT> {
         Ok(part_token)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn import_finish_upload(
         &self,
         identity: Identity,
         format: ImportFormat,
         mode: ImportMode,
+        upsert_key: Option<FieldPath>,
+        compression: Option<ImportCompression>,
+        dry_run: bool,
         upload_token: ClientDrivenUploadToken,
         part_tokens: Vec<ClientDrivenUploadPartToken>,
+        decryption_key: Option<SnapshotDecryptionKey>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         if !identity.is_admin() {
             anyhow::bail!(ErrorMetadata::forbidden(
@@ -2145,11 +2341,38 @@ impl<RT: Runtime> Application<RT> {
                 "Only an admin of the deployment can import"
             ));
         }
-        let object_key = self
+        let mut object_key = self
             .snapshot_imports_storage
             .finish_client_driven_upload(upload_token, part_tokens)
             .await?;
-        store_uploaded_import(self, identity, format, mode, object_key).await
+        if let Some(decryption_key) = decryption_key {
+            // Client-driven uploads land in storage as-is, so decrypt them into
+            // a new object and drop the ciphertext.
+            let encrypted = self
+                .snapshot_imports_storage
+                .get(&object_key)
+                .await?
+                .context(format!("Uploaded import {object_key:?} not found"))?;
+            let ciphertext = encrypted.stream.map_err(anyhow::Error::from).boxed();
+            let decrypted_key = self
+                .upload_snapshot_import(decrypt_stream(decryption_key, ciphertext).boxed())
+                .await?;
+            self.snapshot_imports_storage
+                .delete_object(&object_key)
+                .await?;
+            object_key = decrypted_key;
+        }
+        store_uploaded_import(
+            self,
+            identity,
+            format,
+            mode,
+            upsert_key,
+            compression,
+            dry_run,
+            object_key,
+        )
+        .await
     }
 
     pub async fn upload_snapshot_import(
@@ -2466,6 +2689,41 @@ impl<RT: Runtime> Application<RT> {
         Ok(count)
     }
 
+    /// Counts the documents that restoring the given user tables to `ts` would
+    /// insert, update, and delete.
+    pub async fn preview_table_restore(
+        &self,
+        identity: &Identity,
+        table_names: Vec<TableName>,
+        table_namespace: TableNamespace,
+        ts: Timestamp,
+    ) -> anyhow::Result<BTreeMap<TableName, TableRestoreCounts>> {
+        table_restore::preview_table_restore(self, identity, table_namespace, table_names, ts).await
+    }
+
+    /// Restores the given user tables to their contents at `ts`, which must be
+    /// within document retention. Returns the documents written per table.
+    pub async fn restore_tables(
+        &self,
+        identity: &Identity,
+        table_names: Vec<TableName>,
+        table_namespace: TableNamespace,
+        ts: Timestamp,
+    ) -> anyhow::Result<BTreeMap<TableName, TableRestoreCounts>> {
+        table_restore::restore_tables(self, identity, table_namespace, table_names, ts).await
+    }
+
+    /// Streams a user table as a CSV file with a column for each of its
+    /// fields, which `ImportFormat::Csv` reads back into the same documents.
+    pub async fn export_table_csv(
+        &self,
+        identity: &Identity,
+        table_name: TableName,
+        table_namespace: TableNamespace,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<Bytes>>> {
+        table_csv::export_table_csv(self, identity, table_namespace, table_name).await
+    }
+
     /// Add system indexes if they do not already exist and update
     /// existing indexes if needed.
     pub async fn _add_system_indexes(
@@ -2747,8 +3005,33 @@ impl<RT: Runtime> Application<RT> {
         path: Option<CanonicalizedComponentFunctionPath>,
         identity: Identity,
     ) -> anyhow::Result<()> {
-        loop {
-            let count = self
+        self.cancel_jobs_in_range(component_id, path, None, None, None, identity)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancel pending scheduled jobs for `path` whose next run is in
+    /// [`start_ts`, `end_ts`), committing in batches of
+    /// `MAX_JOBS_CANCEL_BATCH`. Stops after `max_batches` batches if set, in
+    /// which case the returned cursor can be used to resume.
+    pub async fn cancel_jobs_in_range(
+        &self,
+        component_id: ComponentId,
+        path: Option<CanonicalizedComponentFunctionPath>,
+        start_ts: Option<Timestamp>,
+        end_ts: Option<Timestamp>,
+        max_batches: Option<usize>,
+        identity: Identity,
+    ) -> anyhow::Result<CancelJobsProgress> {
+        let mut progress = CancelJobsProgress {
+            num_canceled: 0,
+            cursor: None,
+            is_done: false,
+        };
+        let mut num_batches = 0;
+        while max_batches.map_or(true, |max_batches| num_batches < max_batches) {
+            let start_ts = progress.cursor.or(start_ts);
+            let (count, last_next_ts) = self
                 .execute_with_audit_log_events_and_occ_retries(
                     identity.clone(),
                     "application_cancel_all_jobs",
@@ -2757,29 +3040,101 @@ impl<RT: Runtime> Application<RT> {
                             tx,
                             component_id,
                             path.clone(),
+                            start_ts,
+                            end_ts,
                             *MAX_JOBS_CANCEL_BATCH,
                         )
                         .into()
                     },
                 )
                 .await?;
+            num_batches += 1;
+            progress.num_canceled += count;
+            progress.cursor = last_next_ts.or(progress.cursor);
+            tracing::info!(
+                "Canceled {} scheduled jobs in {component_id:?}",
+                progress.num_canceled
+            );
             if count < *MAX_JOBS_CANCEL_BATCH {
+                progress.is_done = true;
                 break;
             }
         }
-        Ok(())
+        Ok(progress)
     }
 
     async fn _cancel_all_jobs(
         tx: &mut Transaction<RT>,
         component_id: ComponentId,
         path: Option<CanonicalizedComponentFunctionPath>,
+        start_ts: Option<Timestamp>,
+        end_ts: Option<Timestamp>,
         max_jobs: usize,
-    ) -> anyhow::Result<(usize, Vec<DeploymentAuditLogEvent>)> {
-        let count = SchedulerModel::new(tx, component_id.into())
-            .cancel_all(path, max_jobs)
+    ) -> anyhow::Result<((usize, Option<Timestamp>), Vec<DeploymentAuditLogEvent>)> {
+        let result = SchedulerModel::new(tx, component_id.into())
+            .cancel_all_in_range(path, start_ts, end_ts, max_jobs)
             .await?;
-        Ok((count, vec![]))
+        Ok((result, vec![]))
+    }
+
+    /// Schedule dead-lettered jobs to run again, each with its original
+    /// arguments unless new ones are given. Returns the ids of the new jobs.
+    /// Dead-lettered scheduled jobs in a component, most recently failed first.
+    pub async fn list_scheduled_job_dead_letters(
+        &self,
+        identity: Identity,
+        component_id: ComponentId,
+        udf_path: Option<CanonicalizedUdfPath>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<ScheduledJobDeadLetter>>> {
+        anyhow::ensure!(
+            identity.is_admin(),
+            unauthorized_error("list_scheduled_job_dead_letters")
+        );
+        let mut tx = self.begin(identity).await?;
+        SchedulerModel::new(&mut tx, component_id.into())
+            .list_dead_letters(udf_path, limit)
+            .await
+    }
+
+    pub async fn replay_scheduled_job_dead_letters(
+        &self,
+        identity: Identity,
+        component_id: ComponentId,
+        replays: Vec<(DeveloperDocumentId, Option<ConvexArray>)>,
+        context: ExecutionContext,
+    ) -> anyhow::Result<Vec<DeveloperDocumentId>> {
+        self.execute_with_audit_log_events_and_occ_retries(
+            identity,
+            "application_replay_dead_letters",
+            |tx| {
+                async {
+                    let namespace = TableNamespace::from(component_id);
+                    let mut job_ids = vec![];
+                    for (id, udf_args) in replays.iter() {
+                        let table_mapping = tx.table_mapping().namespace(namespace);
+                        let id = id.to_resolved(&table_mapping.number_to_tablet())?;
+                        anyhow::ensure!(
+                            table_mapping.tablet_matches_name(
+                                id.tablet_id,
+                                &SCHEDULED_JOB_DEAD_LETTERS_TABLE
+                            ),
+                            ErrorMetadata::bad_request(
+                                "InvalidDeadLetterId",
+                                format!("{} is not a dead-lettered scheduled job", id.developer_id),
+                            )
+                        );
+                        let job_id = SchedulerModel::new(tx, namespace)
+                            .replay_dead_letter(id, udf_args.clone(), context.clone())
+                            .await?;
+                        job_ids.push(job_id.into());
+                    }
+                    Ok((job_ids, vec![]))
+                }
+                .into()
+            },
+        )
+        .await
     }
 
     /// Commit a transaction and send audit log events to the log manager if the
@@ -2951,6 +3306,10 @@ impl<RT: Runtime> Application<RT> {
         self.search_and_vector_bootstrap_worker.lock().shutdown();
         self.export_worker.lock().shutdown();
         self.snapshot_import_worker.lock().shutdown();
+        self.idempotent_requests_garbage_collector.lock().shutdown();
+        if let Some(backup_scheduler) = &self.backup_scheduler {
+            backup_scheduler.lock().shutdown();
+        }
         self.runner.shutdown().await?;
         self.scheduled_job_runner.shutdown();
         self.cron_job_executor.lock().shutdown();
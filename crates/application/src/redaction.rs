@@ -20,6 +20,7 @@ use serde_json::json;
 use sync_types::{
     types::ErrorPayload,
     LogLinesMessage,
+    QueryErrorCode,
 };
 use value::{
     sha256::Sha256,
@@ -108,6 +109,10 @@ impl RedactedJsError {
         }
     }
 
+    pub fn query_error_code(&self) -> Option<QueryErrorCode> {
+        self.error.query_error_code()
+    }
+
     pub fn custom_data_if_any(self) -> Option<ConvexValue> {
         self.error.custom_data
     }
@@ -19,6 +19,7 @@ use common::{
     },
     execution_context::ExecutionContext,
     knobs::{
+        SCHEDULED_JOB_DEAD_LETTER_RETENTION,
         SCHEDULED_JOB_EXECUTION_PARALLELISM,
         SCHEDULED_JOB_GARBAGE_COLLECTION_BATCH_SIZE,
         SCHEDULED_JOB_GARBAGE_COLLECTION_INITIAL_BACKOFF,
@@ -77,6 +78,7 @@ use model::{
     scheduled_jobs::{
         types::{
             ScheduledJob,
+            ScheduledJobDeadLetter,
             ScheduledJobState,
         },
         SchedulerModel,
@@ -85,6 +87,8 @@ use model::{
         SCHEDULED_JOBS_INDEX,
         SCHEDULED_JOBS_INDEX_BY_COMPLETED_TS,
         SCHEDULED_JOBS_TABLE,
+        SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_FAILED_TS,
+        SCHEDULED_JOB_DEAD_LETTERS_TABLE,
     },
 };
 use parking_lot::Mutex;
@@ -958,6 +962,47 @@ impl<RT: Runtime> ScheduledJobGarbageCollector<RT> {
                     deleted_jobs = true;
                 }
             }
+            for namespace in tx
+                .table_mapping()
+                .namespaces_for_name(&SCHEDULED_JOB_DEAD_LETTERS_TABLE)
+            {
+                let now = self.rt.generate_timestamp()?;
+                let index_query = Query::index_range(IndexRange {
+                    index_name: SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_FAILED_TS.clone(),
+                    range: vec![],
+                    order: Order::Asc,
+                })
+                .limit(*SCHEDULED_JOB_GARBAGE_COLLECTION_BATCH_SIZE);
+                let mut query_stream = ResolvedQuery::new(&mut tx, namespace, index_query)?;
+
+                let mut dead_letters_to_delete = vec![];
+                while let Some(doc) = query_stream.next(&mut tx, None).await? {
+                    let dead_letter: ParsedDocument<ScheduledJobDeadLetter> = doc.try_into()?;
+                    let expiration_ts = dead_letter
+                        .failed_ts
+                        .add(*SCHEDULED_JOB_DEAD_LETTER_RETENTION)?;
+                    if expiration_ts > now {
+                        let next_job_wait_ns = expiration_ts - now;
+                        next_job_wait = match next_job_wait {
+                            Some(next_job_wait) => Some(cmp::min(next_job_wait, next_job_wait_ns)),
+                            None => Some(next_job_wait_ns),
+                        };
+                        break;
+                    }
+                    dead_letters_to_delete.push(dead_letter.id());
+                }
+                if !dead_letters_to_delete.is_empty() {
+                    tracing::debug!(
+                        "Garbage collecting {} scheduled job dead letters",
+                        dead_letters_to_delete.len()
+                    );
+                    let mut model = SchedulerModel::new(&mut tx, namespace);
+                    for dead_letter_id in dead_letters_to_delete {
+                        model.delete_dead_letter(dead_letter_id).await?;
+                    }
+                    deleted_jobs = true;
+                }
+            }
             if deleted_jobs {
                 self.database
                     .commit_with_write_source(tx, "scheduled_job_gc")
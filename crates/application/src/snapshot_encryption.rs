@@ -0,0 +1,409 @@
+//! Encryption of snapshot export zips, and their decryption on import.
+//!
+//! An encrypted snapshot starts with a key header, which holds what's needed
+//! to recover the key: either the salt and limits to derive it from a
+//! passphrase with Argon2id, or the key sealed to a Curve25519 public key.
+//! The zip follows as a libsodium secretstream (XChaCha20-Poly1305), split
+//! into chunks that are each prefixed with their length.
+use anyhow::Context;
+use bytes::{
+    BufMut,
+    Bytes,
+    BytesMut,
+};
+use common::tokio::task::spawn_blocking;
+use errors::ErrorMetadata;
+use futures::{
+    stream::BoxStream,
+    Stream,
+    StreamExt,
+    TryStreamExt,
+};
+use futures_async_stream::try_stream;
+use keybroker::KeyBroker;
+use model::exports::types::ExportEncryption;
+use sodiumoxide::crypto::{
+    box_,
+    pwhash::argon2id13,
+    sealedbox,
+    secretstream::{
+        self,
+        Stream as SecretStream,
+        Tag,
+    },
+};
+
+const ENCRYPTED_SNAPSHOT_MAGIC: &[u8] = b"CVXENC01";
+const PASSPHRASE_KEY_HEADER: u8 = 1;
+const PUBLIC_KEY_HEADER: u8 = 2;
+
+/// The most plaintext encrypted into one chunk.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// The key a snapshot export is encrypted with.
+pub enum SnapshotEncryptionKey {
+    Passphrase(String),
+    /// A Curve25519 public key, as used by libsodium's `crypto_box`.
+    PublicKey(Vec<u8>),
+}
+
+/// The key an encrypted snapshot is imported with.
+pub enum SnapshotDecryptionKey {
+    Passphrase(String),
+    /// The secret key for the public key the snapshot was encrypted to.
+    SecretKey(Vec<u8>),
+}
+
+/// Derive the key for a passphrase. Argon2id is slow and uses a lot of memory
+/// by design, so this runs off the async threads.
+async fn derive_passphrase_key(
+    passphrase: String,
+    salt: argon2id13::Salt,
+    opslimit: argon2id13::OpsLimit,
+    memlimit: argon2id13::MemLimit,
+) -> anyhow::Result<secretstream::Key> {
+    spawn_blocking(move || {
+        let mut key = secretstream::Key([0; secretstream::KEYBYTES]);
+        argon2id13::derive_key(&mut key.0, passphrase.as_bytes(), &salt, opslimit, memlimit)
+            .map_err(|()| anyhow::anyhow!("Failed to derive key from passphrase"))?;
+        Ok(key)
+    })
+    .await?
+}
+
+/// How to encrypt a requested export. For a passphrase, the key is derived
+/// now and stored encrypted with the instance secret. For a public key, only
+/// the public key is stored, and the export worker generates the key.
+pub async fn new_export_encryption(
+    key: SnapshotEncryptionKey,
+    key_broker: &KeyBroker,
+) -> anyhow::Result<ExportEncryption> {
+    match key {
+        SnapshotEncryptionKey::Passphrase(passphrase) => {
+            anyhow::ensure!(
+                !passphrase.is_empty(),
+                ErrorMetadata::bad_request("InvalidEncryptionKey", "The passphrase is empty")
+            );
+            let salt = argon2id13::gen_salt();
+            let opslimit = argon2id13::OPSLIMIT_INTERACTIVE;
+            let memlimit = argon2id13::MEMLIMIT_INTERACTIVE;
+            let mut key_header = ENCRYPTED_SNAPSHOT_MAGIC.to_vec();
+            key_header.push(PASSPHRASE_KEY_HEADER);
+            key_header.extend_from_slice(&salt.0);
+            key_header.extend_from_slice(&u32::try_from(opslimit.0)?.to_le_bytes());
+            key_header.extend_from_slice(&u32::try_from(memlimit.0)?.to_le_bytes());
+            let stream_key = derive_passphrase_key(passphrase, salt, opslimit, memlimit).await?;
+            Ok(ExportEncryption::Passphrase {
+                key_header,
+                encrypted_key: key_broker.encrypt_export_key(stream_key.0.to_vec()),
+            })
+        },
+        SnapshotEncryptionKey::PublicKey(public_key) => {
+            box_::PublicKey::from_slice(&public_key).context(ErrorMetadata::bad_request(
+                "InvalidEncryptionKey",
+                format!("The public key must be {} bytes", box_::PUBLICKEYBYTES),
+            ))?;
+            Ok(ExportEncryption::PublicKey(public_key))
+        },
+    }
+}
+
+/// The key header and key to encrypt an export's zip with. For a public key,
+/// this generates a new key and seals it to the public key in the header.
+pub fn export_key(
+    encryption: &ExportEncryption,
+    key_broker: &KeyBroker,
+) -> anyhow::Result<(Vec<u8>, secretstream::Key)> {
+    match encryption {
+        ExportEncryption::PublicKey(public_key) => {
+            let public_key =
+                box_::PublicKey::from_slice(public_key).context("Invalid export public key")?;
+            let stream_key = secretstream::gen_key();
+            let mut key_header = ENCRYPTED_SNAPSHOT_MAGIC.to_vec();
+            key_header.push(PUBLIC_KEY_HEADER);
+            key_header.extend_from_slice(&sealedbox::seal(&stream_key.0, &public_key));
+            Ok((key_header, stream_key))
+        },
+        ExportEncryption::Passphrase {
+            key_header,
+            encrypted_key,
+        } => {
+            let stream_key =
+                secretstream::Key::from_slice(&key_broker.decrypt_export_key(encrypted_key)?)
+                    .context("Invalid export key")?;
+            Ok((key_header.clone(), stream_key))
+        },
+    }
+}
+
+fn length_prefixed(ciphertext: Vec<u8>) -> anyhow::Result<Bytes> {
+    let mut out = BytesMut::with_capacity(4 + ciphertext.len());
+    out.put_u32_le(u32::try_from(ciphertext.len())?);
+    out.extend_from_slice(&ciphertext);
+    Ok(out.freeze())
+}
+
+/// Encrypt `plaintext` with `key`, after its key header.
+#[try_stream(ok = Bytes, error = anyhow::Error)]
+pub async fn encrypt_stream(
+    key_header: Vec<u8>,
+    key: secretstream::Key,
+    mut plaintext: impl Stream<Item = Bytes> + Send + Unpin,
+) {
+    let (mut stream, header) = SecretStream::init_push(&key)
+        .map_err(|()| anyhow::anyhow!("Failed to start encrypting snapshot"))?;
+    let mut prefix = BytesMut::from(&key_header[..]);
+    prefix.extend_from_slice(&header.0);
+    yield prefix.freeze();
+    while let Some(bytes) = plaintext.next().await {
+        for chunk in bytes.chunks(CHUNK_SIZE) {
+            let ciphertext = stream
+                .push(chunk, None, Tag::Message)
+                .map_err(|()| anyhow::anyhow!("Failed to encrypt snapshot chunk"))?;
+            yield length_prefixed(ciphertext)?;
+        }
+    }
+    let ciphertext = stream
+        .push(&[], None, Tag::Final)
+        .map_err(|()| anyhow::anyhow!("Failed to encrypt snapshot chunk"))?;
+    yield length_prefixed(ciphertext)?;
+}
+
+fn invalid_snapshot_error(msg: &'static str) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidEncryptedSnapshot", msg)
+}
+
+/// Reads exact lengths from a stream of arbitrarily split bytes.
+struct ExactReader<'a> {
+    stream: BoxStream<'a, anyhow::Result<Bytes>>,
+    buffer: BytesMut,
+}
+
+impl ExactReader<'_> {
+    async fn fill(&mut self, len: usize) -> anyhow::Result<bool> {
+        while self.buffer.len() < len {
+            match self.stream.try_next().await? {
+                Some(bytes) => self.buffer.extend_from_slice(&bytes),
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    async fn read_exact(&mut self, len: usize) -> anyhow::Result<Bytes> {
+        anyhow::ensure!(
+            self.fill(len).await?,
+            invalid_snapshot_error("The encrypted snapshot is truncated")
+        );
+        Ok(self.buffer.split_to(len).freeze())
+    }
+
+    async fn read_u32(&mut self) -> anyhow::Result<u32> {
+        let bytes = self.read_exact(4).await?;
+        Ok(u32::from_le_bytes(bytes[..].try_into()?))
+    }
+}
+
+/// Decrypt a snapshot encrypted by `encrypt_stream`.
+#[try_stream(ok = Bytes, error = anyhow::Error)]
+pub async fn decrypt_stream<'a>(
+    key: SnapshotDecryptionKey,
+    ciphertext: BoxStream<'a, anyhow::Result<Bytes>>,
+) {
+    let mut reader = ExactReader {
+        stream: ciphertext,
+        buffer: BytesMut::new(),
+    };
+    if !reader.fill(ENCRYPTED_SNAPSHOT_MAGIC.len()).await?
+        || !reader.buffer.starts_with(ENCRYPTED_SNAPSHOT_MAGIC)
+    {
+        anyhow::bail!(invalid_snapshot_error(
+            "The snapshot isn't encrypted. Import it without a decryption key."
+        ));
+    }
+    reader.read_exact(ENCRYPTED_SNAPSHOT_MAGIC.len()).await?;
+    let key_header = reader.read_exact(1).await?[0];
+    let stream_key = match (key_header, key) {
+        (PASSPHRASE_KEY_HEADER, SnapshotDecryptionKey::Passphrase(passphrase)) => {
+            let salt =
+                argon2id13::Salt::from_slice(&reader.read_exact(argon2id13::SALTBYTES).await?)
+                    .context("Invalid salt")?;
+            let opslimit = reader.read_u32().await? as usize;
+            let memlimit = reader.read_u32().await? as usize;
+            // Exports are encrypted with the interactive limits, so don't let a
+            // snapshot ask for more.
+            anyhow::ensure!(
+                opslimit <= argon2id13::OPSLIMIT_INTERACTIVE.0
+                    && memlimit <= argon2id13::MEMLIMIT_INTERACTIVE.0,
+                invalid_snapshot_error("The encrypted snapshot's key header is invalid")
+            );
+            derive_passphrase_key(
+                passphrase,
+                salt,
+                argon2id13::OpsLimit(opslimit),
+                argon2id13::MemLimit(memlimit),
+            )
+            .await?
+        },
+        (PUBLIC_KEY_HEADER, SnapshotDecryptionKey::SecretKey(secret_key)) => {
+            let secret_key =
+                box_::SecretKey::from_slice(&secret_key).context(ErrorMetadata::bad_request(
+                    "InvalidDecryptionKey",
+                    format!("The secret key must be {} bytes", box_::SECRETKEYBYTES),
+                ))?;
+            let sealed_key = reader
+                .read_exact(sealedbox::SEALBYTES + secretstream::KEYBYTES)
+                .await?;
+            let stream_key = sealedbox::open(&sealed_key, &secret_key.public_key(), &secret_key)
+                .map_err(|()| {
+                    ErrorMetadata::bad_request(
+                        "InvalidDecryptionKey",
+                        "The snapshot wasn't encrypted to this secret key's public key",
+                    )
+                })?;
+            secretstream::Key::from_slice(&stream_key).context("Invalid sealed key")?
+        },
+        (PASSPHRASE_KEY_HEADER, SnapshotDecryptionKey::SecretKey(_)) => {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidDecryptionKey",
+                "The snapshot was encrypted with a passphrase, not a public key",
+            ))
+        },
+        (PUBLIC_KEY_HEADER, SnapshotDecryptionKey::Passphrase(_)) => {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidDecryptionKey",
+                "The snapshot was encrypted to a public key, not with a passphrase",
+            ))
+        },
+        _ => anyhow::bail!(invalid_snapshot_error(
+            "The encrypted snapshot's key header is invalid"
+        )),
+    };
+    let header =
+        secretstream::Header::from_slice(&reader.read_exact(secretstream::HEADERBYTES).await?)
+            .context("Invalid secretstream header")?;
+    let mut stream = SecretStream::init_pull(&header, &stream_key)
+        .map_err(|()| anyhow::anyhow!("Failed to start decrypting snapshot"))?;
+    while !stream.is_finalized() {
+        let len = reader.read_u32().await? as usize;
+        anyhow::ensure!(
+            len <= CHUNK_SIZE + secretstream::ABYTES,
+            invalid_snapshot_error("The encrypted snapshot has an invalid chunk")
+        );
+        let ciphertext = reader.read_exact(len).await?;
+        // The first chunk fails to decrypt if the passphrase is wrong.
+        let (plaintext, _) = stream.pull(&ciphertext, None).map_err(|()| {
+            ErrorMetadata::bad_request(
+                "InvalidDecryptionKey",
+                "Failed to decrypt the snapshot. Check the decryption key, and that the snapshot \
+                 isn't corrupted.",
+            )
+        })?;
+        if !plaintext.is_empty() {
+            yield Bytes::from(plaintext);
+        }
+    }
+    anyhow::ensure!(
+        !reader.fill(1).await?,
+        invalid_snapshot_error("The encrypted snapshot has data after its end")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use errors::ErrorMetadataAnyhowExt;
+    use futures::{
+        stream,
+        StreamExt,
+        TryStreamExt,
+    };
+    use keybroker::KeyBroker;
+    use runtime::testing::TestRuntime;
+    use sodiumoxide::crypto::box_;
+
+    use super::{
+        decrypt_stream,
+        encrypt_stream,
+        export_key,
+        new_export_encryption,
+        SnapshotDecryptionKey,
+        SnapshotEncryptionKey,
+        CHUNK_SIZE,
+    };
+
+    async fn roundtrip(
+        encryption_key: SnapshotEncryptionKey,
+        decryption_key: SnapshotDecryptionKey,
+        plaintext: Vec<Bytes>,
+    ) -> anyhow::Result<Vec<u8>> {
+        sodiumoxide::init().map_err(|()| anyhow::anyhow!("sodiumoxide initialization failed"))?;
+        let key_broker = KeyBroker::dev();
+        let encryption = new_export_encryption(encryption_key, &key_broker).await?;
+        let (key_header, key) = export_key(&encryption, &key_broker)?;
+        let ciphertext: Vec<Bytes> = encrypt_stream(key_header, key, stream::iter(plaintext))
+            .try_collect()
+            .await?;
+        // Split the ciphertext differently from how it was written.
+        let ciphertext: Vec<u8> = ciphertext.concat();
+        let ciphertext = stream::iter(
+            ciphertext
+                .chunks(1000)
+                .map(|chunk| anyhow::Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+        .boxed();
+        let plaintext: Vec<Bytes> = decrypt_stream(decryption_key, ciphertext)
+            .try_collect()
+            .await?;
+        Ok(plaintext.concat())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_passphrase_roundtrip(_rt: TestRuntime) -> anyhow::Result<()> {
+        let plaintext = vec![
+            Bytes::from_static(b"hello"),
+            Bytes::from(vec![7; CHUNK_SIZE + 1]),
+        ];
+        let decrypted = roundtrip(
+            SnapshotEncryptionKey::Passphrase("correct horse".to_string()),
+            SnapshotDecryptionKey::Passphrase("correct horse".to_string()),
+            plaintext.clone(),
+        )
+        .await?;
+        assert_eq!(decrypted, plaintext.concat());
+
+        let err = roundtrip(
+            SnapshotEncryptionKey::Passphrase("correct horse".to_string()),
+            SnapshotDecryptionKey::Passphrase("battery staple".to_string()),
+            plaintext,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidDecryptionKey");
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_public_key_roundtrip(_rt: TestRuntime) -> anyhow::Result<()> {
+        let (public_key, secret_key) = box_::gen_keypair();
+        let decrypted = roundtrip(
+            SnapshotEncryptionKey::PublicKey(public_key.0.to_vec()),
+            SnapshotDecryptionKey::SecretKey(secret_key.0.to_vec()),
+            vec![Bytes::from_static(b"hello")],
+        )
+        .await?;
+        assert_eq!(decrypted, b"hello");
+
+        let (_, other_secret_key) = box_::gen_keypair();
+        let err = roundtrip(
+            SnapshotEncryptionKey::PublicKey(public_key.0.to_vec()),
+            SnapshotDecryptionKey::SecretKey(other_secret_key.0.to_vec()),
+            vec![Bytes::from_static(b"hello")],
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidDecryptionKey");
+        Ok(())
+    }
+}
@@ -14,6 +14,10 @@ use std::{
 };
 
 use anyhow::Context;
+use async_compression::futures::bufread::{
+    GzipDecoder,
+    ZstdDecoder,
+};
 use async_trait::async_trait;
 use async_zip::{
     error::ZipError,
@@ -30,6 +34,10 @@ use common::{
         TokioAsyncReadCompatExt,
     },
     bootstrap_model::{
+        index::{
+            database_index::IndexedFields,
+            IndexConfig,
+        },
         schema::SchemaState,
         tables::TABLES_TABLE,
     },
@@ -53,14 +61,21 @@ use common::{
     pause::PauseClient,
     runtime::Runtime,
     schemas::DatabaseSchema,
+    tokio::{
+        self,
+        io::AsyncWriteExt,
+        task::spawn_blocking,
+    },
     types::{
         FieldName,
         MemberId,
         ObjectKey,
         StorageUuid,
         TableName,
+        TabletIndexName,
         UdfIdentifier,
     },
+    virtual_system_mapping::VirtualSystemMapping,
 };
 use database::{
     BootstrapComponentsModel,
@@ -118,15 +133,35 @@ use model::{
     },
     snapshot_imports::{
         types::{
+            ImportCompression,
             ImportFormat,
             ImportMode,
             ImportState,
+            ImportTableAction,
             ImportTableCheckpoint,
+            ImportValidationError,
+            ImportValidationReport,
+            ImportValidationTable,
             SnapshotImport,
         },
         SnapshotImportModel,
     },
 };
+use parquet::{
+    basic::{
+        ConvertedType,
+        LogicalType,
+    },
+    errors::ParquetError,
+    file::reader::{
+        FileReader,
+        SerializedFileReader,
+    },
+    record::{
+        Field as ParquetField,
+        Row as ParquetRow,
+    },
+};
 use regex::Regex;
 use serde_json::{
     json,
@@ -137,9 +172,12 @@ use shape_inference::{
         ExportContext,
         GeneratedSchema,
     },
+    CountedShape,
     ProdConfigWithOptionalFields,
     Shape,
     ShapeConfig,
+    ShapeEnum,
+    StructuralShape,
 };
 use storage::{
     Storage,
@@ -160,12 +198,17 @@ use usage_tracking::{
     UsageCounter,
 };
 use value::{
+    check_user_size,
+    export::ValueFormat,
     id_v6::DeveloperDocumentId,
     sha256::Sha256Digest,
     val,
     ConvexObject,
     ConvexValue,
+    FieldPath,
     IdentifierFieldName,
+    InternalId,
+    NamespacedTableMapping,
     ResolvedDocumentId,
     Size,
     TableMapping,
@@ -176,12 +219,20 @@ use value::{
 };
 
 use crate::{
-    export_worker::FileStorageZipMetadata,
+    export_worker::{
+        FileStorageZipMetadata,
+        INCREMENTAL_METADATA_PATH,
+    },
     metrics::{
         log_snapshot_import_age,
         log_worker_starting,
         snapshot_import_timer,
     },
+    table_csv::{
+        csv_row_to_object,
+        parse_typed_csv_headers,
+        CsvColumn,
+    },
     Application,
 };
 
@@ -238,16 +289,22 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
     }
 
     /// Subscribe to the _snapshot_imports table.
-    /// If an import has Uploaded, parse it and set to WaitingForConfirmation.
+    /// If an import has Uploaded, parse it and set to WaitingForConfirmation,
+    /// or to Validated if it's a dry run.
     /// If an import is InProgress, execute it.
     pub async fn run(&mut self) -> anyhow::Result<()> {
         let status = log_worker_starting("SnapshotImport");
         let mut tx = self.database.begin(Identity::system()).await?;
         let mut import_model = SnapshotImportModel::new(&mut tx);
         if let Some(import_uploaded) = import_model.import_in_state(ImportState::Uploaded).await? {
-            tracing::info!("Marking snapshot export as WaitingForConfirmation");
-            self.parse_and_mark_waiting_for_confirmation(import_uploaded)
-                .await?;
+            if import_uploaded.dry_run {
+                tracing::info!("Validating dry-run snapshot import");
+                self.validate_and_mark_validated(import_uploaded).await?;
+            } else {
+                tracing::info!("Marking snapshot export as WaitingForConfirmation");
+                self.parse_and_mark_waiting_for_confirmation(import_uploaded)
+                    .await?;
+            }
         } else if let Some(import_in_progress) = import_model
             .import_in_state(ImportState::InProgress {
                 progress_message: String::new(),
@@ -280,7 +337,8 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             ImportState::Completed { .. }
             | ImportState::Failed(..)
             | ImportState::InProgress { .. }
-            | ImportState::WaitingForConfirmation { .. } => {
+            | ImportState::WaitingForConfirmation { .. }
+            | ImportState::Validated { .. } => {
                 anyhow::bail!("unexpected state {snapshot_import:?}");
             },
         }
@@ -310,35 +368,259 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                     )
                     .await?;
             },
-            Err(e) => {
-                let e = wrap_import_err(e);
-                if e.is_bad_request() {
-                    self.database
-                        .execute_with_overloaded_retries(
-                            Identity::system(),
-                            FunctionUsageTracker::new(),
-                            PauseClient::new(),
-                            "snapshot_import_fail",
-                            |tx| {
-                                async {
-                                    let mut import_model = SnapshotImportModel::new(tx);
-                                    import_model
-                                        .fail_import(import_id, e.user_facing_message())
-                                        .await?;
-                                    Ok(())
-                                }
-                                .into()
-                            },
-                        )
-                        .await?;
-                } else {
-                    anyhow::bail!(e);
-                }
+            Err(e) => self.fail_import(import_id, e).await?,
+        }
+        Ok(())
+    }
+
+    /// Fail the import if `e` is the uploaded file's fault, and otherwise
+    /// return it so the import is retried.
+    async fn fail_import(
+        &self,
+        import_id: ResolvedDocumentId,
+        e: anyhow::Error,
+    ) -> anyhow::Result<()> {
+        let e = wrap_import_err(e);
+        if !e.is_bad_request() {
+            anyhow::bail!(e);
+        }
+        self.database
+            .execute_with_overloaded_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "snapshot_import_fail",
+                |tx| {
+                    async {
+                        let mut import_model = SnapshotImportModel::new(tx);
+                        import_model
+                            .fail_import(import_id, e.user_facing_message())
+                            .await?;
+                        Ok(())
+                    }
+                    .into()
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn validate_and_mark_validated(
+        &self,
+        snapshot_import: ParsedDocument<SnapshotImport>,
+    ) -> anyhow::Result<()> {
+        let import_id = snapshot_import.id();
+        match self.validate_import(snapshot_import).await {
+            Ok(report) => {
+                self.database
+                    .execute_with_overloaded_retries(
+                        Identity::system(),
+                        FunctionUsageTracker::new(),
+                        PauseClient::new(),
+                        "snapshot_import_validated",
+                        |tx| {
+                            async {
+                                let mut import_model = SnapshotImportModel::new(tx);
+                                import_model
+                                    .mark_validated(import_id, report.clone())
+                                    .await?;
+                                Ok(())
+                            }
+                            .into()
+                        },
+                    )
+                    .await?;
             },
+            Err(e) => self.fail_import(import_id, e).await?,
         }
         Ok(())
     }
 
+    /// Parse the whole uploaded file and check each row against the table's
+    /// generated schema and the active schema, without writing anything.
+    /// Errors in rows are collected into the report, but the file can't be
+    /// read past an error parsing it.
+    async fn validate_import(
+        &self,
+        snapshot_import: ParsedDocument<SnapshotImport>,
+    ) -> anyhow::Result<ImportValidationReport> {
+        let mode = snapshot_import.mode;
+        let (schemas, mut objects) = self.parse_import(snapshot_import.id()).await?;
+        let mut report = ImportValidationReport {
+            tables: vec![],
+            errors: vec![],
+            num_errors: 0,
+        };
+        let mut generated_schemas = BTreeMap::new();
+        let mut current_table: Option<(
+            ComponentPath,
+            ComponentId,
+            TableName,
+            NamespacedTableMapping,
+        )> = None;
+        let mut row_number = 0;
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let db_snapshot = self.database.latest_snapshot()?;
+        // The existing tables plus the tables listed in the import's `_tables`,
+        // so rows can reference tables the import creates.
+        let mut validation_table_mapping = tx.table_mapping().clone();
+        loop {
+            let unit = match objects.try_next().await {
+                Ok(Some(unit)) => unit,
+                Ok(None) => break,
+                Err(e) => {
+                    let (component_path, table_name) = match &current_table {
+                        Some((component_path, _, table_name, _)) => {
+                            (component_path.clone(), Some(table_name.clone()))
+                        },
+                        None => (ComponentPath::root(), None),
+                    };
+                    add_validation_error(
+                        &mut report,
+                        component_path,
+                        table_name,
+                        None,
+                        bad_request_message(e)?,
+                    );
+                    break;
+                },
+            };
+            match unit {
+                ImportUnit::Incremental => {
+                    if let Err(e) = ensure_incremental_import_mode(mode) {
+                        add_validation_error(
+                            &mut report,
+                            ComponentPath::root(),
+                            None,
+                            None,
+                            bad_request_message(e)?,
+                        );
+                    }
+                },
+                ImportUnit::GeneratedSchema(component_path, table_name, generated_schema) => {
+                    generated_schemas.insert((component_path, table_name), generated_schema);
+                },
+                ImportUnit::NewTable(component_path, table_name) => {
+                    row_number = 0;
+                    let (_, component_id) = BootstrapComponentsModel::new(&mut tx)
+                        .component_path_to_ids(component_path.clone())
+                        .await?;
+                    let existing_table_name = if table_name == *FILE_STORAGE_VIRTUAL_TABLE {
+                        &*FILE_STORAGE_TABLE
+                    } else {
+                        &table_name
+                    };
+                    let exists = tx
+                        .table_mapping()
+                        .namespace(component_id.into())
+                        .name_exists(existing_table_name);
+                    current_table = Some((
+                        component_path.clone(),
+                        component_id,
+                        table_name.clone(),
+                        validation_table_mapping.namespace(component_id.into()),
+                    ));
+                    if table_name == *TABLES_TABLE {
+                        continue;
+                    }
+                    let num_existing = db_snapshot
+                        .table_summary(component_id.into(), existing_table_name)
+                        .num_values();
+                    let action = match mode {
+                        _ if !exists => ImportTableAction::Create,
+                        ImportMode::Replace | ImportMode::RequireEmpty => ImportTableAction::Replace,
+                        ImportMode::Append => ImportTableAction::Append,
+                        ImportMode::Upsert => ImportTableAction::Upsert,
+                    };
+                    if mode == ImportMode::RequireEmpty && num_existing > 0 {
+                        add_validation_error(
+                            &mut report,
+                            component_path.clone(),
+                            Some(table_name.clone()),
+                            None,
+                            ImportError::TableExists(table_name.clone()).to_string(),
+                        );
+                    }
+                    report.tables.push(ImportValidationTable {
+                        component_path,
+                        table_name,
+                        action,
+                        num_rows: 0,
+                    });
+                },
+                ImportUnit::Object(exported_value) => {
+                    row_number += 1;
+                    let Some((component_path, component_id, table_name, table_mapping)) =
+                        &current_table
+                    else {
+                        continue;
+                    };
+                    if *table_name == *TABLES_TABLE {
+                        match table_in_import(row_number, &exported_value) {
+                            Ok((table_name, table_number)) => {
+                                let tablet_id =
+                                    TabletId(InternalId(self.runtime.new_uuid_v4().into_bytes()));
+                                validation_table_mapping.insert(
+                                    tablet_id,
+                                    (*component_id).into(),
+                                    table_number,
+                                    table_name,
+                                );
+                            },
+                            Err(e) => add_validation_error(
+                                &mut report,
+                                component_path.clone(),
+                                Some(table_name.clone()),
+                                Some(row_number as i64),
+                                bad_request_message(e)?,
+                            ),
+                        }
+                        continue;
+                    }
+                    if let Some(table) = report.tables.last_mut() {
+                        table.num_rows += 1;
+                    }
+                    // Table and file metadata are checked as they're imported.
+                    if table_name.is_system() {
+                        continue;
+                    }
+                    let active_schema = schemas.iter().find_map(|(namespace, state, schema)| {
+                        match schema {
+                            Some((_, schema))
+                                if *namespace == TableNamespace::from(*component_id)
+                                    && *state == SchemaState::Active =>
+                            {
+                                Some(schema)
+                            },
+                            _ => None,
+                        }
+                    });
+                    let generated_schema =
+                        generated_schemas.get_mut(&(component_path.clone(), table_name.clone()));
+                    if let Err(message) = validate_import_row(
+                        table_mapping,
+                        tx.virtual_system_mapping(),
+                        table_name,
+                        generated_schema,
+                        active_schema,
+                        row_number,
+                        exported_value,
+                    ) {
+                        add_validation_error(
+                            &mut report,
+                            component_path.clone(),
+                            Some(table_name.clone()),
+                            Some(row_number as i64),
+                            message,
+                        );
+                    }
+                },
+                ImportUnit::DeletedId(_) | ImportUnit::StorageFileChunk(..) => {},
+            }
+        }
+        Ok(report)
+    }
+
     /// Parse the uploaded import file, compare it to existing data, and return
     /// a message to display about the import before it begins.
     async fn info_message_for_import(
@@ -373,10 +655,12 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
         // Find all tables being written to.
         let mut count_by_table: BTreeMap<(ComponentPath, TableName), u64> = BTreeMap::new();
         let mut tables_missing_id_field: BTreeSet<(ComponentPath, TableName)> = BTreeSet::new();
+        let mut deleted_by_table: BTreeMap<(ComponentPath, TableName), usize> = BTreeMap::new();
         let mut current_table = None;
         let mut lineno = 0;
         while let Some(object) = objects.try_next().await? {
             match object {
+                ImportUnit::Incremental => ensure_incremental_import_mode(mode)?,
                 ImportUnit::NewTable(component_path, table_name) => {
                     lineno = 0;
                     count_by_table
@@ -419,6 +703,13 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                         tables_missing_id_field.insert(current_component_table.clone());
                     }
                 },
+                ImportUnit::DeletedId(_) => {
+                    if let Some(current_component_table) = &current_table {
+                        *deleted_by_table
+                            .entry(current_component_table.clone())
+                            .or_default() += 1;
+                    }
+                },
                 // Ignore storage file chunks and generated schemas.
                 ImportUnit::StorageFileChunk(..) | ImportUnit::GeneratedSchema(..) => {},
             }
@@ -450,11 +741,16 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                         // Overwriting nonempty user table.
                         table_summary.num_values()
                     },
-                    ImportMode::Append => 0,
+                    // Only incremental exports delete documents when appended.
+                    ImportMode::Append => deleted_by_table
+                        .get(component_and_table)
+                        .copied()
+                        .unwrap_or_default(),
                     ImportMode::RequireEmpty if table_summary.num_values() > 0 => {
                         anyhow::bail!(ImportError::TableExists(table_name.clone()))
                     },
                     ImportMode::RequireEmpty => 0,
+                    ImportMode::Upsert => 0,
                 };
                 table_changes.insert(
                     component_and_table.clone(),
@@ -475,7 +771,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                         // Overwriting nonempty file storage.
                         table_summary.num_values()
                     },
-                    ImportMode::Append => 0,
+                    ImportMode::Append | ImportMode::Upsert => 0,
                     ImportMode::RequireEmpty if table_summary.num_values() > 0 => {
                         anyhow::bail!(ImportError::TableExists(table_name.clone()))
                     },
@@ -506,7 +802,10 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                 */
         let mut parts = vec![(
             "table".to_string(),
-            "create".to_string(),
+            match mode {
+                ImportMode::Upsert => "upsert".to_string(),
+                _ => "create".to_string(),
+            },
             "delete".to_string(),
         )];
         for (
@@ -592,7 +891,8 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             ImportState::Completed { .. }
             | ImportState::Failed(..)
             | ImportState::Uploaded
-            | ImportState::WaitingForConfirmation { .. } => {
+            | ImportState::WaitingForConfirmation { .. }
+            | ImportState::Validated { .. } => {
                 anyhow::bail!("unexpected state {snapshot_import:?}");
             },
         }
@@ -617,31 +917,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                     )
                     .await?;
             },
-            Err(e) => {
-                let e = wrap_import_err(e);
-                if e.is_bad_request() {
-                    self.database
-                        .execute_with_overloaded_retries(
-                            Identity::system(),
-                            FunctionUsageTracker::new(),
-                            PauseClient::new(),
-                            "snapshot_import_fail",
-                            |tx| {
-                                async {
-                                    let mut import_model = SnapshotImportModel::new(tx);
-                                    import_model
-                                        .fail_import(import_id, e.user_facing_message())
-                                        .await?;
-                                    Ok(())
-                                }
-                                .into()
-                            },
-                        )
-                        .await?;
-                } else {
-                    anyhow::bail!(e);
-                }
-            },
+            Err(e) => self.fail_import(import_id, e).await?,
         }
         Ok(())
     }
@@ -678,6 +954,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             &self.file_storage,
             Identity::system(),
             snapshot_import.mode,
+            snapshot_import.upsert_key.as_ref(),
             objects,
             usage.clone(),
             Some(snapshot_import.id()),
@@ -727,20 +1004,21 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
         SchemasForImport,
         Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>,
     )> {
-        let (object_key, format) = {
+        let (object_key, format, compression) = {
             let mut tx = self.database.begin(Identity::system()).await?;
             let mut model = SnapshotImportModel::new(&mut tx);
             let snapshot_import = model.get(import_id).await?.context("import not found")?;
             (
                 snapshot_import.object_key.clone(),
                 snapshot_import.format.clone(),
+                snapshot_import.compression,
             )
         };
         let body_stream = move || {
             let object_key = object_key.clone();
             async move { self.read_snapshot_import(&object_key).await }
         };
-        let objects = parse_objects(format.clone(), body_stream).boxed();
+        let objects = parse_objects(format.clone(), compression, body_stream).boxed();
 
         // Remapping could be more extensive here, it's just relatively simple to handle
         // optional types. We do remapping after parsing rather than during parsing
@@ -795,6 +1073,9 @@ pub enum ImportError {
     #[error("Failed to parse CSV row {0}: {1}")]
     CsvInvalidRow(usize, csv_async::Error),
 
+    #[error("CSV headers aren't valid column-mapped headers: {0:#}")]
+    CsvInvalidTypedHeaders(anyhow::Error),
+
     #[error("CSV row {0} doesn't have all of the fields in the header")]
     CsvRowMissingFields(usize),
 
@@ -812,6 +1093,18 @@ pub enum ImportError {
 
     #[error("Not valid JSON: {0}")]
     NotJson(serde_json::Error),
+
+    #[error("Not a valid Parquet file: {0}")]
+    NotParquet(ParquetError),
+
+    #[error("Parquet column {0:?} isn't a valid field name: {1}")]
+    ParquetInvalidColumn(String, anyhow::Error),
+
+    #[error(
+        "{0} is from a Parquet export, which can't be imported. Import a zip export in the \
+         default format instead."
+    )]
+    ParquetZip(String),
 }
 
 impl ImportError {
@@ -827,7 +1120,12 @@ impl ImportError {
 
 #[derive(Debug)]
 enum ImportUnit {
+    /// The zip is an incremental export, applied on top of existing tables:
+    /// each Object replaces the document with the same _id, and each
+    /// DeletedId deletes a document.
+    Incremental,
     Object(JsonValue),
+    DeletedId(DeveloperDocumentId),
     NewTable(ComponentPath, TableName),
     GeneratedSchema(
         ComponentPath,
@@ -843,11 +1141,18 @@ static GENERATED_SCHEMA_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(.*/)?([^/]+)/generated_schema\.jsonl$").unwrap());
 static DOCUMENTS_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(.*/)?([^/]+)/documents\.jsonl$").unwrap());
+static DELETED_IDS_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*/)?([^/]+)/deleted\.jsonl$").unwrap());
+static PARQUET_DOCUMENTS_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*/)?([^/]+)/documents\.parquet$").unwrap());
 // _storage/(ID) with optional ignored prefix and extension like
 // snapshot/_storage/(ID).png
 static STORAGE_FILE_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(.*/)?_storage/([^/.]+)(?:\.[^/]+)?$").unwrap());
 
+/// Rows read ahead of the import while reading a Parquet file.
+const PARQUET_ROWS_BUFFER_SIZE: usize = 64;
+
 fn map_zip_error(e: ZipError) -> anyhow::Error {
     match e {
         // UpstreamReadError is probably a transient error from S3.
@@ -872,19 +1177,71 @@ fn map_zip_error(e: ZipError) -> anyhow::Error {
 ///    order.
 /// 4. If a table has a GeneratedSchema, the GeneratedSchema will be yielded
 ///    before any Objects in that table.
+/// 5. Incremental is yielded first, if at all, and DeletedIds for a table are
+///    yielded after its Objects.
+///
+/// CSV and JSONL files may be compressed; `compression` is the compression
+/// declared on upload, and otherwise it's detected from the file's contents.
 #[try_stream(ok = ImportUnit, error = anyhow::Error)]
-async fn parse_objects<'a, Fut>(format: ImportFormat, stream_body: impl Fn() -> Fut + 'a)
-where
+async fn parse_objects<'a, Fut>(
+    format: ImportFormat,
+    compression: Option<ImportCompression>,
+    stream_body: impl Fn() -> Fut + 'a,
+) where
     Fut: Future<Output = anyhow::Result<StorageObjectReader>> + 'a,
 {
     match format {
         ImportFormat::Csv(table_name) => {
-            let reader = stream_body().await?;
-            yield ImportUnit::NewTable(ComponentPath::TODO(), table_name);
+            let reader = decompress(stream_body().await?, compression).await?;
             let mut reader = csv_async::AsyncReader::from_reader(reader);
             if !reader.has_headers() {
                 anyhow::bail!(ImportError::CsvMissingHeaders);
             }
+            let typed_columns = parse_typed_csv_headers(
+                reader.headers().await?.iter().map(|s| s.trim_matches(' ')),
+            )
+            .map_err(ImportError::CsvInvalidTypedHeaders)?;
+            if let Some(columns) = typed_columns {
+                // Column-mapped CSVs carry the type of each column, so infer a
+                // schema from a first pass over the rows, like Parquet. Only
+                // an ambiguous schema needs a second pass for the documents it
+                // can't describe. Each pass downloads and parses the whole
+                // file again, so importing reads it two or three times.
+                let mut shape = CountedShape::<ProdConfigWithOptionalFields>::empty();
+                {
+                    let objects = typed_csv_objects(&columns, compression, &stream_body);
+                    pin_mut!(objects);
+                    while let Some(object) = objects.try_next().await? {
+                        shape = shape.insert(&object);
+                    }
+                }
+                let mut generated_schema = GeneratedSchema::new(StructuralShape::from(&shape));
+                if ExportContext::is_ambiguous(&shape) {
+                    let objects = typed_csv_objects(&columns, compression, &stream_body);
+                    pin_mut!(objects);
+                    while let Some(object) = objects.try_next().await? {
+                        if let Some(ConvexValue::String(id)) = object.get(&**ID_FIELD) {
+                            let id = DeveloperDocumentId::decode(id)?;
+                            generated_schema.insert(&object, id);
+                        }
+                    }
+                }
+                yield ImportUnit::GeneratedSchema(
+                    ComponentPath::TODO(),
+                    table_name.clone(),
+                    generated_schema,
+                );
+                yield ImportUnit::NewTable(ComponentPath::TODO(), table_name);
+                let objects = typed_csv_objects(&columns, compression, &stream_body);
+                pin_mut!(objects);
+                while let Some(object) = objects.try_next().await? {
+                    yield ImportUnit::Object(
+                        ConvexValue::Object(object).export(ValueFormat::ConvexCleanJSON),
+                    );
+                }
+                return Ok(());
+            }
+            yield ImportUnit::NewTable(ComponentPath::TODO(), table_name);
             let field_names = {
                 let headers = reader.headers().await?;
                 headers
@@ -916,7 +1273,7 @@ where
             }
         },
         ImportFormat::JsonLines(table_name) => {
-            let reader = stream_body().await?;
+            let reader = decompress(stream_body().await?, compression).await?;
             yield ImportUnit::NewTable(ComponentPath::TODO(), table_name);
             let mut reader = BufReader::new(reader);
             let mut line = String::new();
@@ -951,6 +1308,49 @@ where
                 yield ImportUnit::Object(value.clone());
             }
         },
+        ImportFormat::Parquet(table_name) => {
+            // The Parquet footer is at the end of the file, so copy it somewhere
+            // seekable first.
+            let mut reader = stream_body().await?;
+            let mut file = tokio::fs::File::from_std(spawn_blocking(tempfile::tempfile).await??);
+            let mut buf = vec![0u8; 1 << 16];
+            while let bytes_read = reader.read(&mut buf).await?
+                && bytes_read > 0
+            {
+                file.write_all(&buf[..bytes_read]).await?;
+            }
+            file.flush().await?;
+            let file = file.into_std().await;
+            let file_reader = Arc::new(
+                spawn_blocking(move || SerializedFileReader::new(file))
+                    .await?
+                    .map_err(ImportError::NotParquet)?,
+            );
+            // Parquet columns are typed, so infer a schema from a first pass over
+            // the rows that preserves those types through the exported JSON
+            // objects.
+            let mut shape = CountedShape::<ProdConfigWithOptionalFields>::empty();
+            {
+                let objects = parquet_objects(file_reader.clone());
+                pin_mut!(objects);
+                while let Some(object) = objects.try_next().await? {
+                    shape = shape.insert(&object);
+                }
+            }
+            yield ImportUnit::GeneratedSchema(
+                ComponentPath::TODO(),
+                table_name.clone(),
+                GeneratedSchema::new(StructuralShape::from(&shape)),
+            );
+            yield ImportUnit::NewTable(ComponentPath::TODO(), table_name);
+            let objects = parquet_objects(file_reader);
+            pin_mut!(objects);
+            while let Some(object) = objects.try_next().await? {
+                yield ImportUnit::Object(
+                    ConvexValue::Object(object).export(ValueFormat::ConvexCleanJSON),
+                );
+            }
+        },
         ImportFormat::Zip => {
             let mut reader = stream_body().await?.compat();
             let mut zip_reader = ZipFileReader::new(&mut reader)
@@ -961,6 +1361,18 @@ where
                 .into_iter()
                 .map(|entry| entry.filename().to_string())
                 .collect();
+            if let Some(filename) = filenames
+                .iter()
+                .find(|filename| PARQUET_DOCUMENTS_PATTERN.is_match(filename))
+            {
+                anyhow::bail!(ImportError::ParquetZip(filename.clone()));
+            }
+            let is_incremental = filenames
+                .iter()
+                .any(|filename| filename == INCREMENTAL_METADATA_PATH);
+            if is_incremental {
+                yield ImportUnit::Incremental;
+            }
             {
                 // First pass, all the things we can store in memory:
                 // a. _tables/documents.jsonl
@@ -1060,14 +1472,34 @@ where
 
             // Second pass: user tables.
             for (i, filename) in filenames.iter().enumerate() {
-                if let Some((_, table_name)) = parse_documents_jsonl_table_name(filename)?
+                if let Some((component_path, table_name)) =
+                    parse_documents_jsonl_table_name(filename)?
                     && !table_name.is_system()
                 {
-                    let entry_reader = zip_reader.entry_reader(i).await.map_err(map_zip_error)?;
-                    let stream = parse_documents_jsonl(entry_reader);
-                    pin_mut!(stream);
-                    while let Some(unit) = stream.try_next().await? {
-                        yield unit;
+                    {
+                        let entry_reader =
+                            zip_reader.entry_reader(i).await.map_err(map_zip_error)?;
+                        let stream = parse_documents_jsonl(entry_reader);
+                        pin_mut!(stream);
+                        while let Some(unit) = stream.try_next().await? {
+                            yield unit;
+                        }
+                    }
+                    if !is_incremental {
+                        continue;
+                    }
+                    for (j, deleted_filename) in filenames.iter().enumerate() {
+                        if parse_table_filename(deleted_filename, &DELETED_IDS_PATTERN)?
+                            == Some((component_path.clone(), table_name.clone()))
+                        {
+                            let entry_reader =
+                                zip_reader.entry_reader(j).await.map_err(map_zip_error)?;
+                            let stream = parse_deleted_ids_jsonl(entry_reader);
+                            pin_mut!(stream);
+                            while let Some(unit) = stream.try_next().await? {
+                                yield unit;
+                            }
+                        }
                     }
                 }
             }
@@ -1170,6 +1602,28 @@ async fn parse_documents_jsonl<R: TokioAsyncRead + Unpin>(entry_reader: ZipEntry
     }
 }
 
+#[try_stream(ok = ImportUnit, error = anyhow::Error)]
+async fn parse_deleted_ids_jsonl<R: TokioAsyncRead + Unpin>(entry_reader: ZipEntryReader<'_, R>) {
+    let mut reader = BufReader::new(entry_reader.compat());
+    let mut line = String::new();
+    let mut lineno = 1;
+    while reader.read_line(&mut line).await? > 0 {
+        let v: serde_json::Value =
+            serde_json::from_str(&line).map_err(|e| ImportError::JsonInvalidRow(lineno, e))?;
+        let id = v
+            .get(&**ID_FIELD)
+            .and_then(|id| id.as_str())
+            .with_context(|| {
+                ImportError::InvalidConvexValue(lineno, anyhow::anyhow!("deleted row requires _id"))
+            })?;
+        let id = DeveloperDocumentId::decode(id)
+            .map_err(|e| ImportError::InvalidConvexValue(lineno, e.into()))?;
+        yield ImportUnit::DeletedId(id);
+        line.clear();
+        lineno += 1;
+    }
+}
+
 async fn parse_generated_schema<'a, T: ShapeConfig, R: AsyncRead + Unpin>(
     filename: &str,
     mut entry_reader: BufReader<R>,
@@ -1230,7 +1684,31 @@ async fn parse_generated_schema<'a, T: ShapeConfig, R: AsyncRead + Unpin>(
     Ok(generated_schema)
 }
 
-// For now, we only parse out floats and strings in CSV files.
+/// Read the rows of a CSV file with column-mapped headers, as written by
+/// `table_csv::export_table_csv`, re-reading the file from the start.
+#[try_stream(ok = ConvexObject, error = anyhow::Error)]
+async fn typed_csv_objects<'a, Fut>(
+    columns: &'a [CsvColumn],
+    compression: Option<ImportCompression>,
+    stream_body: &'a (impl Fn() -> Fut + 'a),
+) where
+    Fut: Future<Output = anyhow::Result<StorageObjectReader>> + 'a,
+{
+    let reader = decompress(stream_body().await?, compression).await?;
+    let mut reader = csv_async::AsyncReader::from_reader(reader);
+    let mut enumerate_rows = reader.records().enumerate();
+    while let Some((i, row_r)) = enumerate_rows.next().await {
+        let lineno = i + 1;
+        let row = row_r.map_err(|e| ImportError::CsvInvalidRow(lineno, e))?;
+        if row.len() != columns.len() {
+            anyhow::bail!(ImportError::CsvRowMissingFields(lineno));
+        }
+        yield csv_row_to_object(columns, row.iter())
+            .map_err(|e| ImportError::InvalidConvexValue(lineno, e))?;
+    }
+}
+
+// For untyped headers, we only parse out floats and strings in CSV files.
 fn parse_csv_cell(s: &str) -> JsonValue {
     if let Ok(r) = s.parse::<f64>() {
         return json!(r);
@@ -1238,27 +1716,272 @@ fn parse_csv_cell(s: &str) -> JsonValue {
     json!(s)
 }
 
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Wrap a CSV or JSONL file in a decoder for its compression. If the
+/// compression wasn't declared on upload, it's detected from the file's magic
+/// bytes, and files that don't start with either are read as is.
+async fn decompress(
+    reader: StorageObjectReader,
+    compression: Option<ImportCompression>,
+) -> anyhow::Result<Pin<Box<dyn AsyncRead + Send>>> {
+    let mut reader = BufReader::new(reader);
+    let compression = match compression {
+        Some(compression) => Some(compression),
+        None => {
+            let prefix = reader.fill_buf().await?;
+            if prefix.starts_with(&GZIP_MAGIC) {
+                Some(ImportCompression::Gzip)
+            } else if prefix.starts_with(&ZSTD_MAGIC) {
+                Some(ImportCompression::Zstd)
+            } else {
+                None
+            }
+        },
+    };
+    let reader: Pin<Box<dyn AsyncRead + Send>> = match compression {
+        Some(ImportCompression::Gzip) => {
+            let mut decoder = GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Box::pin(decoder)
+        },
+        Some(ImportCompression::Zstd) => {
+            let mut decoder = ZstdDecoder::new(reader);
+            decoder.multiple_members(true);
+            Box::pin(decoder)
+        },
+        None => Box::pin(reader),
+    };
+    Ok(reader)
+}
+
+/// The rows of a Parquet file as objects. The reader does blocking IO, so the
+/// rows are read on a blocking thread and sent back over a channel.
+#[try_stream(ok = ConvexObject, error = anyhow::Error)]
+async fn parquet_objects(file_reader: Arc<SerializedFileReader<std::fs::File>>) {
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(PARQUET_ROWS_BUFFER_SIZE);
+    let handle = spawn_blocking(move || {
+        let json_columns = parquet_json_columns(&file_reader);
+        let rows = file_reader
+            .get_row_iter(None)
+            .map_err(ImportError::NotParquet)?;
+        for (i, row) in rows.enumerate() {
+            let object = parquet_row_to_object(
+                i + 1,
+                &row.map_err(ImportError::NotParquet)?,
+                &json_columns,
+            )?;
+            // The stream was dropped, so stop reading.
+            if sender.blocking_send(object).is_err() {
+                break;
+            }
+        }
+        anyhow::Ok(())
+    });
+    while let Some(object) = receiver.recv().await {
+        yield object;
+    }
+    handle.await??;
+}
+
+/// Top-level Parquet columns holding JSON, which are imported as the values
+/// they encode rather than as strings.
+fn parquet_json_columns(reader: &SerializedFileReader<std::fs::File>) -> BTreeSet<String> {
+    reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .filter(|field| {
+            let info = field.get_basic_info();
+            matches!(info.logical_type(), Some(LogicalType::Json))
+                || info.converted_type() == ConvertedType::JSON
+        })
+        .map(|field| field.name().to_string())
+        .collect()
+}
+
+fn parquet_row_to_object(
+    rowno: usize,
+    row: &ParquetRow,
+    json_columns: &BTreeSet<String>,
+) -> anyhow::Result<ConvexObject> {
+    let mut fields = BTreeMap::new();
+    for (name, field) in row.get_column_iter() {
+        let field_name = FieldName::from_str(name)
+            .map_err(|e| ImportError::ParquetInvalidColumn(name.clone(), e))?;
+        let value = match field {
+            ParquetField::Str(s) if json_columns.contains(name) => serde_json::from_str(s)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| {
+                    ExportContext::Infer.apply(
+                        json,
+                        &StructuralShape::<ProdConfigWithOptionalFields>::new(ShapeEnum::Unknown),
+                    )
+                }),
+            field => parquet_field_to_value(field),
+        }
+        .map_err(|e| ImportError::InvalidConvexValue(rowno, e))?;
+        fields.insert(field_name, value);
+    }
+    ConvexObject::try_from(fields).map_err(|e| ImportError::InvalidConvexValue(rowno, e).into())
+}
+
+/// Integers become int64s and other numbers float64s. Decimals that a float64
+/// can't represent exactly are rejected rather than rounded. Dates and
+/// timestamps become float64 milliseconds since the Unix epoch, like
+/// `_creationTime`, and times become milliseconds since midnight. Maps become
+/// arrays of `{ key, value }` objects, since their keys aren't necessarily
+/// strings.
+fn parquet_field_to_value(field: &ParquetField) -> anyhow::Result<ConvexValue> {
+    let value = match field {
+        ParquetField::Null => ConvexValue::Null,
+        ParquetField::Bool(b) => ConvexValue::from(*b),
+        ParquetField::Byte(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::Short(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::Int(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::Long(i) => ConvexValue::from(*i),
+        ParquetField::UByte(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::UShort(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::UInt(i) => ConvexValue::from(i64::from(*i)),
+        ParquetField::ULong(i) => ConvexValue::from(
+            i64::try_from(*i).with_context(|| format!("{i} doesn't fit in an int64"))?,
+        ),
+        ParquetField::Float16(f) => ConvexValue::from(f.to_f64()),
+        ParquetField::Float(f) => ConvexValue::from(f64::from(*f)),
+        ParquetField::Double(f) => ConvexValue::from(*f),
+        ParquetField::Decimal(decimal) => {
+            let bytes = decimal.data();
+            anyhow::ensure!(bytes.len() <= 16, "Decimal is too wide");
+            // Sign-extend the big-endian two's complement unscaled value.
+            let fill = if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+                0xff
+            } else {
+                0
+            };
+            let mut buf = [fill; 16];
+            buf[16 - bytes.len()..].copy_from_slice(bytes);
+            let unscaled = i128::from_be_bytes(buf);
+            let scale = usize::try_from(decimal.scale()).context("Decimal has a negative scale")?;
+            let decimal = decimal_string(unscaled, scale);
+            let value: f64 = decimal.parse()?;
+            // Rust formats floats with the fewest digits that parse back to
+            // the same float, so this only matches if the float is exact.
+            anyhow::ensure!(
+                value.to_string() == decimal,
+                "Decimal {decimal} can't be represented exactly as a float64. Cast the column to \
+                 a double or a string before importing it."
+            );
+            ConvexValue::from(value)
+        },
+        ParquetField::Str(s) => ConvexValue::try_from(s.clone())?,
+        ParquetField::Bytes(bytes) => ConvexValue::try_from(bytes.data().to_vec())?,
+        ParquetField::Date(days) => ConvexValue::from(f64::from(*days) * 86_400_000.),
+        ParquetField::TimeMillis(ms) => ConvexValue::from(f64::from(*ms)),
+        ParquetField::TimeMicros(us) => ConvexValue::from(*us as f64 / 1000.),
+        ParquetField::TimestampMillis(ms) => ConvexValue::from(*ms as f64),
+        ParquetField::TimestampMicros(us) => ConvexValue::from(*us as f64 / 1000.),
+        ParquetField::Group(row) => {
+            let mut fields = BTreeMap::new();
+            for (name, field) in row.get_column_iter() {
+                fields.insert(FieldName::from_str(name)?, parquet_field_to_value(field)?);
+            }
+            ConvexValue::Object(fields.try_into()?)
+        },
+        ParquetField::ListInternal(list) => ConvexValue::try_from(
+            list.elements()
+                .iter()
+                .map(parquet_field_to_value)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        )?,
+        ParquetField::MapInternal(map) => ConvexValue::try_from(
+            map.entries()
+                .iter()
+                .map(|(key, value)| {
+                    let entry = ConvexObject::try_from(BTreeMap::from([
+                        (FieldName::from_str("key")?, parquet_field_to_value(key)?),
+                        (
+                            FieldName::from_str("value")?,
+                            parquet_field_to_value(value)?,
+                        ),
+                    ]))?;
+                    anyhow::Ok(ConvexValue::Object(entry))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        )?,
+    };
+    Ok(value)
+}
+
+/// The exact value of a decimal with the given unscaled value and scale,
+/// without trailing zeros after the decimal point.
+fn decimal_string(unscaled: i128, scale: usize) -> String {
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let digits = format!("{:0>width$}", unscaled.unsigned_abs(), width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{sign}{integer}")
+    } else {
+        format!("{sign}{integer}.{fraction}")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_import_file<RT: Runtime>(
     application: &Application<RT>,
     identity: Identity,
     format: ImportFormat,
     mode: ImportMode,
+    upsert_key: Option<FieldPath>,
+    compression: Option<ImportCompression>,
+    dry_run: bool,
     body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
 ) -> anyhow::Result<DeveloperDocumentId> {
     if !identity.is_admin() {
         anyhow::bail!(ImportError::Unauthorized);
     }
     let object_key = application.upload_snapshot_import(body_stream).await?;
-    store_uploaded_import(application, identity, format, mode, object_key).await
+    store_uploaded_import(
+        application,
+        identity,
+        format,
+        mode,
+        upsert_key,
+        compression,
+        dry_run,
+        object_key,
+    )
+    .await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn store_uploaded_import<RT: Runtime>(
     application: &Application<RT>,
     identity: Identity,
     format: ImportFormat,
     mode: ImportMode,
+    upsert_key: Option<FieldPath>,
+    compression: Option<ImportCompression>,
+    dry_run: bool,
     object_key: ObjectKey,
 ) -> anyhow::Result<DeveloperDocumentId> {
+    if upsert_key.is_some() && mode != ImportMode::Upsert {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "UpsertKeyWithoutUpsert",
+            "An upsert key can only be used with the upsert import mode"
+        ));
+    }
+    if compression.is_some() && !matches!(format, ImportFormat::Csv(_) | ImportFormat::JsonLines(_))
+    {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "CompressionNotSupported",
+            "Only CSV and JSONL imports can be compressed"
+        ));
+    }
     let (_, id, _) = application
         .database
         .execute_with_overloaded_retries(
@@ -1270,7 +1993,14 @@ pub async fn store_uploaded_import<RT: Runtime>(
                 async {
                     let mut model = SnapshotImportModel::new(tx);
                     model
-                        .start_import(format.clone(), mode, object_key.clone())
+                        .start_import(
+                            format.clone(),
+                            mode,
+                            upsert_key.clone(),
+                            compression,
+                            dry_run,
+                            object_key.clone(),
+                        )
                         .await
                 }
                 .into()
@@ -1346,19 +2076,85 @@ pub async fn cancel_import<RT: Runtime>(
     Ok(())
 }
 
-fn wrap_import_err(e: anyhow::Error) -> anyhow::Error {
-    let e = e.wrap_error_message(|msg| format!("Hit an error while importing:\n{msg}"));
-    if let Some(import_err) = e.downcast_ref::<ImportError>() {
-        let error_metadata = import_err.error_metadata();
-        e.context(error_metadata)
-    } else {
-        e
+/// The most errors a dry-run import keeps in its report.
+const MAX_VALIDATION_ERRORS: usize = 100;
+
+fn add_validation_error(
+    report: &mut ImportValidationReport,
+    component_path: ComponentPath,
+    table_name: Option<TableName>,
+    row_number: Option<i64>,
+    message: String,
+) {
+    report.num_errors += 1;
+    if report.errors.len() < MAX_VALIDATION_ERRORS {
+        report.errors.push(ImportValidationError {
+            component_path,
+            table_name,
+            row_number,
+            message,
+        });
     }
 }
 
-async fn wait_for_import_worker<RT: Runtime>(
-    application: &Application<RT>,
-    identity: Identity,
+/// The message for an error caused by the uploaded file, or the error itself
+/// if it's not the file's fault.
+fn bad_request_message(e: anyhow::Error) -> anyhow::Result<String> {
+    let e = match e.downcast_ref::<ImportError>().map(ImportError::error_metadata) {
+        Some(error_metadata) => e.context(error_metadata),
+        None => e,
+    };
+    if !e.is_bad_request() {
+        return Err(e);
+    }
+    Ok(e.msg().to_string())
+}
+
+/// Check that an imported row would be inserted, returning why it wouldn't.
+fn validate_import_row(
+    table_mapping: &NamespacedTableMapping,
+    virtual_system_mapping: &VirtualSystemMapping,
+    table_name: &TableName,
+    mut generated_schema: Option<&mut GeneratedSchema<ProdConfigWithOptionalFields>>,
+    active_schema: Option<&DatabaseSchema>,
+    row_number: usize,
+    exported_value: JsonValue,
+) -> Result<(), String> {
+    let convex_value = GeneratedSchema::<ProdConfigWithOptionalFields>::apply(
+        &mut generated_schema,
+        exported_value,
+    )
+    .map_err(|e| ImportError::InvalidConvexValue(row_number, e).to_string())?;
+    let ConvexValue::Object(convex_object) = convex_value else {
+        return Err(ImportError::NotAnObject(row_number).to_string());
+    };
+    check_user_size(convex_object.size()).map_err(|e| e.msg().to_string())?;
+    if let Some(schema) = active_schema {
+        schema
+            .check_new_value(
+                &convex_object,
+                table_name.clone(),
+                table_mapping,
+                virtual_system_mapping,
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn wrap_import_err(e: anyhow::Error) -> anyhow::Error {
+    let e = e.wrap_error_message(|msg| format!("Hit an error while importing:\n{msg}"));
+    if let Some(import_err) = e.downcast_ref::<ImportError>() {
+        let error_metadata = import_err.error_metadata();
+        e.context(error_metadata)
+    } else {
+        e
+    }
+}
+
+async fn wait_for_import_worker<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
     import_id: DeveloperDocumentId,
 ) -> anyhow::Result<ParsedDocument<SnapshotImport>> {
     let snapshot_import = loop {
@@ -1384,7 +2180,8 @@ async fn wait_for_import_worker<RT: Runtime>(
             },
             ImportState::WaitingForConfirmation { .. }
             | ImportState::Completed { .. }
-            | ImportState::Failed(..) => {
+            | ImportState::Failed(..)
+            | ImportState::Validated { .. } => {
                 break snapshot_import;
             },
         }
@@ -1397,14 +2194,28 @@ pub async fn do_import<RT: Runtime>(
     identity: Identity,
     format: ImportFormat,
     mode: ImportMode,
+    upsert_key: Option<FieldPath>,
+    compression: Option<ImportCompression>,
     body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
 ) -> anyhow::Result<u64> {
-    let import_id =
-        upload_import_file(application, identity.clone(), format, mode, body_stream).await?;
+    let import_id = upload_import_file(
+        application,
+        identity.clone(),
+        format,
+        mode,
+        upsert_key,
+        compression,
+        false,
+        body_stream,
+    )
+    .await?;
 
     let snapshot_import = wait_for_import_worker(application, identity.clone(), import_id).await?;
     match &snapshot_import.state {
-        ImportState::Uploaded | ImportState::InProgress { .. } | ImportState::Completed { .. } => {
+        ImportState::Uploaded
+        | ImportState::InProgress { .. }
+        | ImportState::Completed { .. }
+        | ImportState::Validated { .. } => {
             anyhow::bail!("should be WaitingForConfirmation, is {snapshot_import:?}")
         },
         ImportState::WaitingForConfirmation { .. } => {},
@@ -1419,7 +2230,8 @@ pub async fn do_import<RT: Runtime>(
     match &snapshot_import.state {
         ImportState::Uploaded
         | ImportState::WaitingForConfirmation { .. }
-        | ImportState::InProgress { .. } => {
+        | ImportState::InProgress { .. }
+        | ImportState::Validated { .. } => {
             anyhow::bail!("should be done, is {snapshot_import:?}")
         },
         ImportState::Completed {
@@ -1432,6 +2244,39 @@ pub async fn do_import<RT: Runtime>(
     }
 }
 
+/// Upload an import with `dry_run` set and wait for the worker to validate
+/// it, returning the report without writing anything.
+pub async fn do_dry_run_import<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    format: ImportFormat,
+    mode: ImportMode,
+    upsert_key: Option<FieldPath>,
+    compression: Option<ImportCompression>,
+    body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
+) -> anyhow::Result<ImportValidationReport> {
+    let import_id = upload_import_file(
+        application,
+        identity.clone(),
+        format,
+        mode,
+        upsert_key,
+        compression,
+        true,
+        body_stream,
+    )
+    .await?;
+
+    let snapshot_import = wait_for_import_worker(application, identity, import_id).await?;
+    match snapshot_import.into_value().state {
+        ImportState::Validated { report } => Ok(report),
+        ImportState::Failed(e) => {
+            anyhow::bail!(ErrorMetadata::bad_request("ImportFailed", e))
+        },
+        state => anyhow::bail!("should be Validated, is {state:?}"),
+    }
+}
+
 /// Clears tables atomically.
 /// Returns number of documents deleted.
 /// This is implemented as an import of empty tables in Replace mode.
@@ -1458,6 +2303,7 @@ pub async fn clear_tables<RT: Runtime>(
         &application.file_storage,
         identity.clone(),
         ImportMode::Replace,
+        None,
         objects,
         usage.clone(),
         None,
@@ -1546,6 +2392,7 @@ async fn import_objects<RT: Runtime>(
     file_storage: &FileStorage<RT>,
     identity: Identity,
     mode: ImportMode,
+    upsert_key: Option<&FieldPath>,
     objects: Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>,
     usage: FunctionUsageTracker,
     import_id: Option<ResolvedDocumentId>,
@@ -1556,11 +2403,22 @@ async fn import_objects<RT: Runtime>(
     let mut table_mapping_for_import = TableMapping::new();
     let mut total_num_documents = 0;
 
+    let incremental = objects
+        .as_mut()
+        .try_next_if(|unit| matches!(unit, ImportUnit::Incremental))
+        .await?
+        .is_some();
+    if incremental {
+        ensure_incremental_import_mode(mode)?;
+    }
+
     while let Some(num_documents) = import_single_table(
         database,
         file_storage,
         &identity,
         mode,
+        upsert_key,
+        incremental,
         objects.as_mut(),
         &mut generated_schemas,
         &mut table_mapping_for_import,
@@ -1574,6 +2432,19 @@ async fn import_objects<RT: Runtime>(
     Ok((table_mapping_for_import, total_num_documents))
 }
 
+/// Incremental exports are applied on top of the existing tables, so they
+/// can't replace them.
+fn ensure_incremental_import_mode(mode: ImportMode) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        mode == ImportMode::Append,
+        ErrorMetadata::bad_request(
+            "IncrementalImportMode",
+            "Incremental exports must be imported with `npx convex import --append`"
+        )
+    );
+    Ok(())
+}
+
 /// The case where a schema can become invalid:
 /// 1. import is changing the table number of table "foo".
 /// 2. import does not touch table "bar".
@@ -1807,32 +2678,7 @@ async fn import_tables_table<RT: Runtime>(
         .await?
     {
         lineno += 1;
-        let exported_object = exported_value
-            .as_object()
-            .with_context(|| ImportError::NotAnObject(lineno))?;
-        let table_name = exported_object
-            .get("name")
-            .and_then(|name| name.as_str())
-            .with_context(|| {
-                ImportError::InvalidConvexValue(lineno, anyhow::anyhow!("table requires name"))
-            })?;
-        let table_name = table_name
-            .parse()
-            .map_err(|e| ImportError::InvalidName(table_name.to_string(), e))?;
-        let table_number = exported_object
-            .get("id")
-            .and_then(|id| id.as_f64())
-            .and_then(|id| TableNumber::try_from(id as u32).ok())
-            .with_context(|| {
-                ImportError::InvalidConvexValue(
-                    lineno,
-                    anyhow::anyhow!(
-                        "table requires id (received {:?})",
-                        exported_object.get("id")
-                    ),
-                )
-            })?;
-        import_tables.push((table_name, table_number));
+        import_tables.push(table_in_import(lineno, &exported_value)?);
     }
     let tables_in_import = import_tables
         .iter()
@@ -1860,6 +2706,39 @@ async fn import_tables_table<RT: Runtime>(
     Ok(table_mapping_for_import)
 }
 
+/// The name and number of a table listed in an import's `_tables`.
+fn table_in_import(
+    lineno: usize,
+    exported_value: &JsonValue,
+) -> anyhow::Result<(TableName, TableNumber)> {
+    let exported_object = exported_value
+        .as_object()
+        .with_context(|| ImportError::NotAnObject(lineno))?;
+    let table_name = exported_object
+        .get("name")
+        .and_then(|name| name.as_str())
+        .with_context(|| {
+            ImportError::InvalidConvexValue(lineno, anyhow::anyhow!("table requires name"))
+        })?;
+    let table_name = table_name
+        .parse()
+        .map_err(|e| ImportError::InvalidName(table_name.to_string(), e))?;
+    let table_number = exported_object
+        .get("id")
+        .and_then(|id| id.as_f64())
+        .and_then(|id| TableNumber::try_from(id as u32).ok())
+        .with_context(|| {
+            ImportError::InvalidConvexValue(
+                lineno,
+                anyhow::anyhow!(
+                    "table requires id (received {:?})",
+                    exported_object.get("id")
+                ),
+            )
+        })?;
+    Ok((table_name, table_number))
+}
+
 async fn import_storage_table<RT: Runtime>(
     database: &Database<RT>,
     file_storage: &FileStorage<RT>,
@@ -2107,6 +2986,8 @@ async fn import_single_table<RT: Runtime>(
     file_storage: &FileStorage<RT>,
     identity: &Identity,
     mode: ImportMode,
+    upsert_key: Option<&FieldPath>,
+    incremental: bool,
     mut objects: Pin<&mut Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>>,
     generated_schemas: &mut BTreeMap<
         (ComponentPath, TableName),
@@ -2236,6 +3117,20 @@ async fn import_single_table<RT: Runtime>(
         return Ok(Some(0));
     }
 
+    let upsert_key_index = match upsert_key {
+        Some(upsert_key) if mode == ImportMode::Upsert => Some(
+            upsert_key_index(
+                database,
+                identity,
+                table_id.tablet_id,
+                table_name,
+                upsert_key,
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+    let mut upsert_counts = UpsertCounts::default();
     let mut num_objects = 0;
 
     let mut tx = database.begin(identity.clone()).await?;
@@ -2268,16 +3163,31 @@ async fn import_single_table<RT: Runtime>(
         if objects_to_insert_size > *TRANSACTION_MAX_USER_WRITE_SIZE_BYTES / 2
             || objects_to_insert.len() > *TRANSACTION_MAX_NUM_USER_WRITES / 2
         {
-            insert_import_objects(
-                database,
-                identity,
-                objects_to_insert,
-                table_name,
-                table_id,
-                &table_mapping_for_schema,
-                usage.clone(),
-            )
-            .await?;
+            if mode == ImportMode::Upsert {
+                upsert_counts += upsert_import_objects(
+                    database,
+                    identity,
+                    objects_to_insert,
+                    table_name,
+                    table_id,
+                    upsert_key_index.as_ref(),
+                    &table_mapping_for_schema,
+                    usage.clone(),
+                )
+                .await?;
+            } else {
+                insert_import_objects(
+                    database,
+                    identity,
+                    objects_to_insert,
+                    table_name,
+                    table_id,
+                    &table_mapping_for_schema,
+                    incremental,
+                    usage.clone(),
+                )
+                .await?;
+            }
             objects_to_insert = Vec::new();
             objects_to_insert_size = 0;
             if let Some(import_id) = import_id {
@@ -2299,26 +3209,81 @@ async fn import_single_table<RT: Runtime>(
         num_objects += 1;
     }
 
-    insert_import_objects(
+    if mode == ImportMode::Upsert {
+        upsert_counts += upsert_import_objects(
+            database,
+            identity,
+            objects_to_insert,
+            table_name,
+            table_id,
+            upsert_key_index.as_ref(),
+            &table_mapping_for_schema,
+            usage.clone(),
+        )
+        .await?;
+    } else {
+        insert_import_objects(
+            database,
+            identity,
+            objects_to_insert,
+            table_name,
+            table_id,
+            &table_mapping_for_schema,
+            incremental,
+            usage.clone(),
+        )
+        .await?;
+    }
+
+    let mut ids_to_delete = vec![];
+    while let Some(ImportUnit::DeletedId(id)) = objects
+        .as_mut()
+        .try_next_if(|line| matches!(line, ImportUnit::DeletedId(_)))
+        .await?
+    {
+        ids_to_delete.push(id);
+        if ids_to_delete.len() > *TRANSACTION_MAX_NUM_USER_WRITES / 2 {
+            delete_import_objects(
+                database,
+                identity,
+                ids_to_delete,
+                table_name,
+                table_id,
+                usage.clone(),
+            )
+            .await?;
+            ids_to_delete = Vec::new();
+        }
+    }
+    delete_import_objects(
         database,
         identity,
-        objects_to_insert,
+        ids_to_delete,
         table_name,
         table_id,
-        &table_mapping_for_schema,
         usage,
     )
     .await?;
 
     if let Some(import_id) = import_id {
+        let message = if mode == ImportMode::Upsert {
+            format!(
+                "Upserted \"{table_name}\" ({} inserted, {} updated, {} unchanged)",
+                upsert_counts.inserted.separate_with_commas(),
+                upsert_counts.updated.separate_with_commas(),
+                upsert_counts.unchanged.separate_with_commas()
+            )
+        } else {
+            format!(
+                "Imported \"{table_name}\" ({} documents)",
+                num_objects.separate_with_commas()
+            )
+        };
         add_checkpoint_message(
             database,
             identity,
             import_id,
-            format!(
-                "Imported \"{table_name}\" ({} documents)",
-                num_objects.separate_with_commas()
-            ),
+            message,
             component_path,
             table_name,
             num_objects as i64,
@@ -2326,6 +3291,9 @@ async fn import_single_table<RT: Runtime>(
         .await?;
     }
 
+    if mode == ImportMode::Upsert {
+        return Ok(Some(upsert_counts.inserted + upsert_counts.updated));
+    }
     Ok(Some(num_objects))
 }
 
@@ -2336,12 +3304,53 @@ async fn insert_import_objects<RT: Runtime>(
     table_name: &TableName,
     table_id: TabletIdAndTableNumber,
     table_mapping_for_schema: &TableMapping,
+    replace_existing: bool,
     usage: FunctionUsageTracker,
 ) -> anyhow::Result<()> {
     if objects_to_insert.is_empty() {
         return Ok(());
     }
-    let object_ids: Vec<_> = objects_to_insert
+    ensure_unique_ids(table_name, &objects_to_insert)?;
+    database
+        .execute_with_overloaded_retries(
+            identity.clone(),
+            usage,
+            PauseClient::new(),
+            "snapshot_import_insert_objects",
+            |tx| {
+                async {
+                    for object_to_insert in objects_to_insert.clone() {
+                        if replace_existing {
+                            ImportFacingModel::new(tx)
+                                .upsert(
+                                    table_id,
+                                    table_name,
+                                    object_to_insert,
+                                    table_mapping_for_schema,
+                                )
+                                .await?;
+                        } else {
+                            ImportFacingModel::new(tx)
+                                .insert(
+                                    table_id,
+                                    table_name,
+                                    object_to_insert,
+                                    table_mapping_for_schema,
+                                )
+                                .await?;
+                        }
+                    }
+                    Ok(())
+                }
+                .into()
+            },
+        )
+        .await?;
+    Ok(())
+}
+
+fn ensure_unique_ids(table_name: &TableName, objects: &[ConvexObject]) -> anyhow::Result<()> {
+    let object_ids: Vec<_> = objects
         .iter()
         .filter_map(|object| object.get(&**ID_FIELD))
         .collect();
@@ -2352,22 +3361,214 @@ async fn insert_import_objects<RT: Runtime>(
             format!("Objects in table \"{table_name}\" have duplicate _id fields")
         ));
     }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct UpsertCounts {
+    inserted: u64,
+    updated: u64,
+    unchanged: u64,
+}
+
+impl std::ops::AddAssign for UpsertCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.unchanged += other.unchanged;
+    }
+}
+
+/// Finds an enabled index on the table whose first field is `upsert_key`, so
+/// rows can be matched to documents without scanning the table.
+async fn upsert_key_index<RT: Runtime>(
+    database: &Database<RT>,
+    identity: &Identity,
+    tablet_id: TabletId,
+    table_name: &TableName,
+    upsert_key: &FieldPath,
+) -> anyhow::Result<(TabletIndexName, IndexedFields)> {
+    let mut tx = database.begin(identity.clone()).await?;
+    let indexes = IndexModel::new(&mut tx)
+        .all_indexes_on_table(tablet_id)
+        .await?;
+    indexes
+        .into_iter()
+        .find_map(|index| match &index.config {
+            IndexConfig::Database {
+                developer_config, ..
+            } if index.config.is_enabled()
+                && developer_config.fields.first() == Some(upsert_key) =>
+            {
+                Some((index.name.clone(), developer_config.fields.clone()))
+            },
+            _ => None,
+        })
+        .with_context(|| {
+            ErrorMetadata::bad_request(
+                "UpsertKeyNotIndexed",
+                format!(
+                    "Upserting into \"{table_name}\" by {upsert_key} requires an index on \
+                     {upsert_key}"
+                ),
+            )
+        })
+}
+
+/// Patches the documents that match `objects` and inserts the rest. Objects
+/// are matched by `_id`, or by the first field of `upsert_key_index` if set.
+async fn upsert_import_objects<RT: Runtime>(
+    database: &Database<RT>,
+    identity: &Identity,
+    objects: Vec<ConvexObject>,
+    table_name: &TableName,
+    table_id: TabletIdAndTableNumber,
+    upsert_key_index: Option<&(TabletIndexName, IndexedFields)>,
+    table_mapping_for_schema: &TableMapping,
+    usage: FunctionUsageTracker,
+) -> anyhow::Result<UpsertCounts> {
+    if objects.is_empty() {
+        return Ok(UpsertCounts::default());
+    }
+    ensure_unique_ids(table_name, &objects)?;
+    let (_, counts, _) = database
+        .execute_with_overloaded_retries(
+            identity.clone(),
+            usage,
+            PauseClient::new(),
+            "snapshot_import_upsert_objects",
+            |tx| {
+                async {
+                    let mut counts = UpsertCounts::default();
+                    for object in objects.clone() {
+                        let existing_doc = match upsert_key_index {
+                            Some((index_name, fields)) => {
+                                let upsert_key = &fields[0];
+                                let value =
+                                    object.get_path(upsert_key).cloned().with_context(|| {
+                                        ErrorMetadata::bad_request(
+                                            "UpsertKeyMissing",
+                                            format!(
+                                                "Objects in table \"{table_name}\" are missing \
+                                                 the upsert key {upsert_key}"
+                                            ),
+                                        )
+                                    })?;
+                                let mut docs = ImportFacingModel::new(tx)
+                                    .get_by_indexed_value(
+                                        table_name,
+                                        index_name.clone(),
+                                        fields.clone(),
+                                        value,
+                                        2,
+                                    )
+                                    .await?;
+                                anyhow::ensure!(
+                                    docs.len() <= 1,
+                                    ErrorMetadata::bad_request(
+                                        "UpsertKeyNotUnique",
+                                        format!(
+                                            "Multiple documents in table \"{table_name}\" have \
+                                             the same {upsert_key}"
+                                        )
+                                    )
+                                );
+                                docs.pop()
+                            },
+                            None => match object.get(&**ID_FIELD) {
+                                Some(ConvexValue::String(s)) => {
+                                    let id = DeveloperDocumentId::decode(s).context(
+                                        ErrorMetadata::bad_request(
+                                            "InvalidId",
+                                            format!("invalid _id '{s}'"),
+                                        ),
+                                    )?;
+                                    // IDs from another table are rejected by the insert.
+                                    if id.table() == table_id.table_number {
+                                        tx.get(ResolvedDocumentId::new(table_id.tablet_id, id))
+                                            .await?
+                                    } else {
+                                        None
+                                    }
+                                },
+                                _ => None,
+                            },
+                        };
+                        match existing_doc {
+                            Some(existing_doc) => {
+                                let changed = ImportFacingModel::new(tx)
+                                    .patch(
+                                        table_name,
+                                        existing_doc,
+                                        object,
+                                        table_mapping_for_schema,
+                                    )
+                                    .await?;
+                                if changed {
+                                    counts.updated += 1;
+                                } else {
+                                    counts.unchanged += 1;
+                                }
+                            },
+                            None => {
+                                ImportFacingModel::new(tx)
+                                    .insert(table_id, table_name, object, table_mapping_for_schema)
+                                    .await?;
+                                counts.inserted += 1;
+                            },
+                        }
+                    }
+                    Ok(counts)
+                }
+                .into()
+            },
+        )
+        .await?;
+    Ok(counts)
+}
+
+async fn delete_import_objects<RT: Runtime>(
+    database: &Database<RT>,
+    identity: &Identity,
+    ids_to_delete: Vec<DeveloperDocumentId>,
+    table_name: &TableName,
+    table_id: TabletIdAndTableNumber,
+    usage: FunctionUsageTracker,
+) -> anyhow::Result<()> {
+    if ids_to_delete.is_empty() {
+        return Ok(());
+    }
     database
         .execute_with_overloaded_retries(
             identity.clone(),
             usage,
             PauseClient::new(),
-            "snapshot_import_insert_objects",
+            "snapshot_import_delete_objects",
             |tx| {
                 async {
-                    for object_to_insert in objects_to_insert.clone() {
-                        ImportFacingModel::new(tx)
-                            .insert(
-                                table_id,
-                                table_name,
-                                object_to_insert,
-                                table_mapping_for_schema,
+                    for id in ids_to_delete.clone() {
+                        anyhow::ensure!(
+                            id.table() == table_id.table_number,
+                            ErrorMetadata::bad_request(
+                                "ImportConflict",
+                                format!(
+                                    "_id {} cannot be deleted from '{table_name}' because its IDs \
+                                     have a different format",
+                                    id.encode()
+                                )
                             )
+                        );
+                        // Documents created and deleted since the base export
+                        // don't exist here.
+                        if tx
+                            .get(ResolvedDocumentId::new(table_id.tablet_id, id))
+                            .await?
+                            .is_none()
+                        {
+                            continue;
+                        }
+                        ImportFacingModel::new(tx)
+                            .delete(table_id, table_name, id)
                             .await?;
                     }
                     Ok(())
@@ -2434,7 +3635,7 @@ async fn prepare_table_for_import<RT: Runtime>(
         },
         None => {
             let tablet_id = match mode {
-                ImportMode::Append => existing_active_table_id,
+                ImportMode::Append | ImportMode::Upsert => existing_active_table_id,
                 ImportMode::RequireEmpty => {
                     if !TableModel::new(&mut tx)
                         .table_is_empty(component_id.into(), table_name)
@@ -2568,6 +3769,8 @@ async fn table_number_for_import(
         ImportUnit::NewTable(..) => None,
         ImportUnit::GeneratedSchema(..) => None,
         ImportUnit::StorageFileChunk(..) => None,
+        ImportUnit::Incremental => None,
+        ImportUnit::DeletedId(..) => None,
     }
 }
 
@@ -2597,7 +3800,9 @@ async fn remap_empty_string_by_schema<'a, RT: Runtime>(
             .map_ok(move |object| match object {
                 unit @ ImportUnit::NewTable(..)
                 | unit @ ImportUnit::GeneratedSchema(..)
-                | unit @ ImportUnit::StorageFileChunk(..) => unit,
+                | unit @ ImportUnit::StorageFileChunk(..)
+                | unit @ ImportUnit::Incremental
+                | unit @ ImportUnit::DeletedId(..) => unit,
                 ImportUnit::Object(mut object) => ImportUnit::Object({
                     remove_empty_string_optional_entries(&optional_fields, &mut object);
                     object
@@ -2640,6 +3845,15 @@ mod tests {
     };
 
     use anyhow::Context;
+    use async_compression::futures::bufread::{
+        GzipEncoder,
+        ZstdEncoder,
+    };
+    use async_zip::{
+        write::ZipFileWriter,
+        Compression,
+        ZipEntryBuilder,
+    };
     use bytes::Bytes;
     use common::{
         bootstrap_model::index::{
@@ -2672,6 +3886,7 @@ mod tests {
         IndexModel,
         ResolvedQuery,
         SchemaModel,
+        TableModel,
         UserFacingModel,
     };
     use errors::ErrorMetadataAnyhowExt;
@@ -2681,6 +3896,7 @@ mod tests {
             self,
             BoxStream,
         },
+        AsyncReadExt,
         FutureExt,
         StreamExt,
         TryStreamExt,
@@ -2690,8 +3906,23 @@ mod tests {
         Identity,
     };
     use maplit::btreemap;
-    use model::snapshot_imports::types::ImportState;
+    use model::snapshot_imports::types::{
+        ImportState,
+        ImportTableAction,
+        ImportValidationTable,
+    };
     use must_let::must_let;
+    use parquet::{
+        data_type::{
+            ByteArrayType,
+            Decimal,
+            DoubleType,
+            Int64Type,
+        },
+        file::writer::SerializedFileWriter,
+        record::Field as ParquetField,
+        schema::parser::parse_message_type,
+    };
     use runtime::testing::TestRuntime;
     use serde_json::{
         json,
@@ -2708,18 +3939,26 @@ mod tests {
     use value::{
         assert_obj,
         assert_val,
+        export::ValueFormat,
         id_v6::DeveloperDocumentId,
         ConvexObject,
         FieldName,
+        InternalId,
         TableName,
         TableNamespace,
+        TableNumber,
     };
 
     use super::{
+        decimal_string,
         do_import,
         import_objects,
+        parquet_field_to_value,
         parse_documents_jsonl_table_name,
         parse_objects,
+        perform_import,
+        ImportCompression,
+        ImportError,
         ImportFormat,
         ImportMode,
         ImportUnit,
@@ -2817,6 +4056,15 @@ mod tests {
         rt: RT,
         format: ImportFormat,
         v: &str,
+    ) -> anyhow::Result<Vec<JsonValue>> {
+        run_parse_bytes(rt, format, None, v.as_bytes()).await
+    }
+
+    async fn run_parse_bytes<RT: Runtime>(
+        rt: RT,
+        format: ImportFormat,
+        compression: Option<ImportCompression>,
+        v: &[u8],
     ) -> anyhow::Result<Vec<JsonValue>> {
         let storage_dir = tempfile::TempDir::new()?;
         let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::for_use_case(
@@ -2825,16 +4073,18 @@ mod tests {
             StorageUseCase::SnapshotImports,
         )?);
         let mut upload = storage.start_upload().await?;
-        upload.write(Bytes::copy_from_slice(v.as_bytes())).await?;
+        upload.write(Bytes::copy_from_slice(v)).await?;
         let object_key = upload.complete().await?;
         let stream = || storage.get_reader(&object_key);
-        parse_objects(format, stream)
+        parse_objects(format, compression, stream)
             .filter_map(|line| async move {
                 match line {
                     Ok(super::ImportUnit::Object(object)) => Some(Ok(object)),
                     Ok(super::ImportUnit::NewTable(..)) => None,
                     Ok(super::ImportUnit::GeneratedSchema(..)) => None,
                     Ok(super::ImportUnit::StorageFileChunk(..)) => None,
+                    Ok(super::ImportUnit::Incremental) => None,
+                    Ok(super::ImportUnit::DeletedId(..)) => None,
                     Err(e) => Some(Err(e)),
                 }
             })
@@ -2872,15 +4122,32 @@ a,b,c
     }
 
     #[convex_macro::test_runtime]
-    async fn test_duplicate_id(rt: TestRuntime) -> anyhow::Result<()> {
-        let app = Application::new_for_tests(&rt).await?;
-        let table_name = "table1";
-        let test_csv = r#"
-_id,value
-"jd7f2yq3tcc5h4ce9qhqdk0ach6hbmyb","hi"
-"jd7f2yq3tcc5h4ce9qhqdk0ach6hbmyb","there"
+    async fn test_csv_some_typed_headers(rt: TestRuntime) -> anyhow::Result<()> {
+        // Headers are only read as typed columns if they all have a type.
+        let test1 = r#"
+ratio:number,label
+0.5,half
 "#;
-        let err = run_csv_import(&app, table_name, test_csv)
+        let objects =
+            run_parse_objects(rt, ImportFormat::Csv("table".parse().unwrap()), test1).await?;
+        let expected = vec![json!({
+            "ratio:number": 0.5,
+            "label": "half",
+        })];
+        assert_eq!(objects, expected);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_duplicate_id(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        let test_csv = r#"
+_id,value
+"jd7f2yq3tcc5h4ce9qhqdk0ach6hbmyb","hi"
+"jd7f2yq3tcc5h4ce9qhqdk0ach6hbmyb","there"
+"#;
+        let err = run_csv_import(&app, table_name, test_csv)
             .await
             .unwrap_err();
         assert!(err.is_bad_request());
@@ -2911,6 +4178,166 @@ a,b,c,d
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_table_csv_export_roundtrip(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        let identity = new_admin_id();
+        {
+            let mut tx = app.begin(identity.clone()).await?;
+            let documents = vec![
+                assert_obj!(
+                    "name" => "alice",
+                    "address" => { "city" => "Paris", "zip" => 75001 },
+                    "visits" => 3,
+                    "avatar" => vec![0u8, 1, 2],
+                ),
+                assert_obj!(
+                    "name" => "",
+                    "score" => f64::NEG_INFINITY,
+                    "tags" => ["a", 1],
+                ),
+                assert_obj!(
+                    "address" => { "city" => "\"Lyon\"", "zip" => 69001 },
+                    "visits" => 1,
+                ),
+            ];
+            for document in documents {
+                UserFacingModel::new_root_for_test(&mut tx)
+                    .insert(table_name.parse()?, document)
+                    .await?;
+            }
+            app.commit_test(tx).await?;
+        }
+        let fields = vec![
+            "_id", "name", "address", "visits", "avatar", "score", "tags",
+        ];
+        let exported = load_fields_as_maps(&app, table_name, fields.clone()).await?;
+
+        let csv = app
+            .export_table_csv(&identity, table_name.parse()?, TableNamespace::test_user())
+            .await?;
+        do_import(
+            &app,
+            identity,
+            ImportFormat::Csv(table_name.parse()?),
+            ImportMode::Replace,
+            None,
+            None,
+            csv,
+        )
+        .await?;
+        let imported = load_fields_as_maps(&app, table_name, fields).await?;
+        assert_eq!(imported, exported);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_compressed_jsonl(rt: TestRuntime) -> anyhow::Result<()> {
+        let test1 = r#"{"a": 1, "b": "one"}
+{"a": 2, "b": "two"}
+"#;
+        let mut gzipped = vec![];
+        GzipEncoder::new(test1.as_bytes())
+            .read_to_end(&mut gzipped)
+            .await?;
+        let mut zstd = vec![];
+        ZstdEncoder::new(test1.as_bytes())
+            .read_to_end(&mut zstd)
+            .await?;
+        let expected = vec![json!({"a": 1, "b": "one"}), json!({"a": 2, "b": "two"})];
+        // Compression is detected from the file's contents...
+        let objects = run_parse_bytes(
+            rt.clone(),
+            ImportFormat::JsonLines("table".parse()?),
+            None,
+            &gzipped,
+        )
+        .await?;
+        assert_eq!(objects, expected);
+        // ...or declared on upload.
+        let objects = run_parse_bytes(
+            rt,
+            ImportFormat::JsonLines("table".parse()?),
+            Some(ImportCompression::Zstd),
+            &zstd,
+        )
+        .await?;
+        assert_eq!(objects, expected);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_compressed_csv(rt: TestRuntime) -> anyhow::Result<()> {
+        let test1 = r#"
+a,b
+1,one
+2,two
+"#;
+        let mut gzipped = vec![];
+        GzipEncoder::new(test1.as_bytes())
+            .read_to_end(&mut gzipped)
+            .await?;
+        let mut zstd = vec![];
+        ZstdEncoder::new(test1.as_bytes())
+            .read_to_end(&mut zstd)
+            .await?;
+        let expected = vec![json!({"a": 1., "b": "one"}), json!({"a": 2., "b": "two"})];
+        let objects = run_parse_bytes(
+            rt.clone(),
+            ImportFormat::Csv("table".parse()?),
+            None,
+            &gzipped,
+        )
+        .await?;
+        assert_eq!(objects, expected);
+        let objects = run_parse_bytes(
+            rt,
+            ImportFormat::Csv("table".parse()?),
+            Some(ImportCompression::Zstd),
+            &zstd,
+        )
+        .await?;
+        assert_eq!(objects, expected);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_parquet_zip_rejected(rt: TestRuntime) -> anyhow::Result<()> {
+        let zip = zip_bytes(vec![
+            ("_tables/documents.jsonl", String::new()),
+            ("messages/documents.parquet", String::new()),
+        ])
+        .await?;
+        let err = run_parse_bytes(rt, ImportFormat::Zip, None, &zip)
+            .await
+            .unwrap_err();
+        must_let!(let Some(ImportError::ParquetZip(filename)) = err.downcast_ref());
+        assert_eq!(filename, "messages/documents.parquet");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_decimal() -> anyhow::Result<()> {
+        let decimal = |unscaled, scale| {
+            parquet_field_to_value(&ParquetField::Decimal(Decimal::from_i64(
+                unscaled, 18, scale,
+            )))
+        };
+        assert_eq!(decimal(150, 2)?, assert_val!(1.5));
+        assert_eq!(decimal(-5, 1)?, assert_val!(-0.5));
+        assert_eq!(decimal(0, 3)?, assert_val!(0.));
+        assert_eq!(decimal(1, 7)?, assert_val!(0.0000001));
+        // Decimals that don't fit in a float64 aren't rounded.
+        assert!(decimal(123_456_789_012_345_678, 0).is_err());
+        assert!(decimal(123_456_789_012_345_678, 9).is_err());
+
+        assert_eq!(decimal_string(-1234, 2), "-12.34");
+        assert_eq!(decimal_string(100, 2), "1");
+        assert_eq!(decimal_string(5, 3), "0.005");
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     #[ignore]
     async fn import_huge_csv(rt: TestRuntime) -> anyhow::Result<()> {
@@ -3115,6 +4542,9 @@ a
             new_admin_id(),
             ImportFormat::Csv(table_name.parse()?),
             ImportMode::Replace,
+            None,
+            None,
+            false,
             stream_from_str(test_csv),
         )
         .await?;
@@ -3141,6 +4571,155 @@ Interrupting `npx convex import` will not cancel it."#
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn import_dry_run_reports_invalid_rows(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        let test_csv = r#"
+a
+1
+"string"
+2
+"#;
+
+        let schema = db_schema!(
+            table_name => DocumentSchema::Union(
+                vec![
+                    object_validator!(
+                        "a" => FieldValidator::optional_field_type(Validator::Float64),
+                    )
+                ]
+            )
+        );
+        activate_schema(&app, schema).await?;
+
+        let import_id = upload_import_file(
+            &app,
+            new_admin_id(),
+            ImportFormat::Csv(table_name.parse()?),
+            ImportMode::RequireEmpty,
+            None,
+            None,
+            true,
+            stream_from_str(test_csv),
+        )
+        .await?;
+
+        let snapshot_import = wait_for_import_worker(&app, new_admin_id(), import_id).await?;
+        must_let!(let ImportState::Validated { report } = snapshot_import.state.clone());
+        assert_eq!(
+            report.tables,
+            vec![ImportValidationTable {
+                component_path: ComponentPath::root(),
+                table_name: table_name.parse()?,
+                action: ImportTableAction::Create,
+                num_rows: 3,
+            }]
+        );
+        assert_eq!(report.num_errors, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row_number, Some(2));
+
+        // Nothing was written, and the dry run can't be performed.
+        let mut tx = app.begin(new_admin_id()).await?;
+        assert!(!TableModel::new(&mut tx)
+            .table_exists(TableNamespace::test_user(), &table_name.parse()?));
+        let err = perform_import(&app, new_admin_id(), import_id)
+            .await
+            .unwrap_err();
+        assert!(err.is_bad_request());
+
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn import_dry_run_zip_references_new_tables(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let schema = db_schema!(
+            "messages" => DocumentSchema::Union(
+                vec![
+                    object_validator!(
+                        "author" => FieldValidator::required_field_type(
+                            Validator::Id("users".parse()?)
+                        ),
+                    )
+                ]
+            )
+        );
+        activate_schema(&app, schema).await?;
+
+        let users_number = TableNumber::try_from(10001)?;
+        let user_id = DeveloperDocumentId::new(users_number, InternalId([1; 16])).encode();
+        let message_id =
+            DeveloperDocumentId::new(TableNumber::try_from(10002)?, InternalId([2; 16]));
+        let zip = zip_bytes(vec![
+            (
+                "_tables/documents.jsonl",
+                format!(
+                    "{}\n{}\n",
+                    json!({ "name": "users", "id": 10001 }),
+                    json!({ "name": "messages", "id": 10002 }),
+                ),
+            ),
+            (
+                "users/documents.jsonl",
+                format!("{}\n", json!({ "_id": user_id, "name": "sarah" })),
+            ),
+            (
+                "messages/documents.jsonl",
+                format!(
+                    "{}\n{}\n",
+                    json!({ "_id": message_id.encode(), "author": user_id }),
+                    json!({ "author": message_id.encode() }),
+                ),
+            ),
+        ])
+        .await?;
+
+        let import_id = upload_import_file(
+            &app,
+            new_admin_id(),
+            ImportFormat::Zip,
+            ImportMode::RequireEmpty,
+            None,
+            None,
+            true,
+            stream::iter(vec![anyhow::Ok(zip)]).boxed(),
+        )
+        .await?;
+
+        let snapshot_import = wait_for_import_worker(&app, new_admin_id(), import_id).await?;
+        must_let!(let ImportState::Validated { report } = snapshot_import.state.clone());
+        assert_eq!(
+            report.tables,
+            vec![
+                ImportValidationTable {
+                    component_path: ComponentPath::root(),
+                    table_name: "users".parse()?,
+                    action: ImportTableAction::Create,
+                    num_rows: 1,
+                },
+                ImportValidationTable {
+                    component_path: ComponentPath::root(),
+                    table_name: "messages".parse()?,
+                    action: ImportTableAction::Create,
+                    num_rows: 2,
+                },
+            ]
+        );
+        // Only the message whose author is another message is invalid.
+        assert_eq!(report.num_errors, 1);
+        assert_eq!(report.errors[0].table_name, Some("messages".parse()?));
+        assert_eq!(report.errors[0].row_number, Some(2));
+
+        let mut tx = app.begin(new_admin_id()).await?;
+        assert!(
+            !TableModel::new(&mut tx).table_exists(TableNamespace::test_user(), &"users".parse()?)
+        );
+
+        Ok(())
+    }
+
     // Hard to control timing in race test with background job moving state forward.
     #[convex_macro::test_runtime]
     async fn import_races_with_schema_update(rt: TestRuntime) -> anyhow::Result<()> {
@@ -3338,6 +4917,325 @@ a
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn import_upsert_by_indexed_field(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name: TableName = "table1".parse()?;
+        let identity = new_admin_id();
+        {
+            let mut tx = app.begin(identity.clone()).await?;
+            IndexModel::new(&mut tx)
+                .add_application_index(
+                    TableNamespace::test_user(),
+                    IndexMetadata::new_enabled(
+                        IndexName::new(table_name.clone(), "by_a".parse()?)?,
+                        vec!["a".parse()?].try_into()?,
+                    ),
+                )
+                .await?;
+            app.commit_test(tx).await?;
+        }
+        let test_csv = r#"
+a,b
+"k1","x"
+"k2","y"
+"#;
+        run_csv_import(&app, &table_name, test_csv).await?;
+
+        // k1 is unchanged, k2 is updated and k3 is inserted.
+        let test_csv = r#"
+a,b
+"k1","x"
+"k2","z"
+"k3","w"
+"#;
+        let num_written = do_import(
+            &app,
+            identity.clone(),
+            ImportFormat::Csv(table_name.clone()),
+            ImportMode::Upsert,
+            Some("a".parse()?),
+            None,
+            stream_from_str(test_csv),
+        )
+        .await?;
+        assert_eq!(num_written, 2);
+
+        let mut objects = load_fields_as_maps(&app, &table_name, vec!["a", "b"]).await?;
+        objects.sort_by(|x, y| x["a"].cmp(&y["a"]));
+        assert_eq!(
+            objects,
+            vec![
+                btreemap!("a" => assert_val!("k1"), "b" => assert_val!("x")),
+                btreemap!("a" => assert_val!("k2"), "b" => assert_val!("z")),
+                btreemap!("a" => assert_val!("k3"), "b" => assert_val!("w")),
+            ]
+        );
+
+        // Rows can only be matched on an indexed field.
+        let err = do_import(
+            &app,
+            identity,
+            ImportFormat::Csv(table_name.clone()),
+            ImportMode::Upsert,
+            Some("b".parse()?),
+            None,
+            stream_from_str(test_csv),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.is_bad_request());
+        assert!(err.msg().contains("requires an index"));
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn import_upsert_by_id(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let test_csv = r#"
+body
+"a"
+"b"
+"#;
+        run_csv_import(&app, "messages", test_csv).await?;
+        run_csv_import(&app, "other", test_csv).await?;
+        let mut ids = BTreeMap::new();
+        for table_name in ["messages", "other"] {
+            for object in load_fields_as_maps(&app, table_name, vec!["_id", "body"]).await? {
+                let (ConvexValue::String(id), ConvexValue::String(body)) =
+                    (&object["_id"], &object["body"])
+                else {
+                    anyhow::bail!("unexpected document {object:?}");
+                };
+                ids.insert(
+                    (table_name, body.to_string()),
+                    DeveloperDocumentId::decode(id)?,
+                );
+            }
+        }
+        let a_id = ids[&("messages", "a".to_string())];
+        let b_id = ids[&("messages", "b".to_string())];
+        // An ID in the table's format that isn't in the table.
+        let missing_id = DeveloperDocumentId::new(a_id.table(), InternalId([7; 16]));
+
+        // "a" is updated, "b" is unchanged, and the documents with a missing
+        // `_id` or none at all are inserted.
+        let test_jsonl = format!(
+            "{}\n{}\n{}\n{}\n",
+            json!({ "_id": a_id.encode(), "body": "a2" }),
+            json!({ "_id": b_id.encode(), "body": "b" }),
+            json!({ "_id": missing_id.encode(), "body": "c" }),
+            json!({ "body": "d" }),
+        );
+        let num_written = do_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::JsonLines("messages".parse()?),
+            ImportMode::Upsert,
+            None,
+            None,
+            stream_from_str(&test_jsonl),
+        )
+        .await?;
+        assert_eq!(num_written, 3);
+
+        let mut objects = load_fields_as_maps(&app, "messages", vec!["_id", "body"]).await?;
+        objects.sort_by(|x, y| x["body"].cmp(&y["body"]));
+        assert_eq!(objects.len(), 4);
+        let expected = [
+            (Some(a_id), "a2"),
+            (Some(b_id), "b"),
+            (Some(missing_id), "c"),
+            (None, "d"),
+        ];
+        for (object, (id, body)) in objects.iter().zip(expected) {
+            assert_eq!(object["body"], assert_val!(body));
+            if let Some(id) = id {
+                assert_eq!(object["_id"], assert_val!(id.encode()));
+            }
+        }
+
+        // IDs from another table can't be upserted.
+        let other_id = ids[&("other", "a".to_string())];
+        let test_jsonl = format!("{}\n", json!({ "_id": other_id.encode(), "body": "e" }));
+        let err = do_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::JsonLines("messages".parse()?),
+            ImportMode::Upsert,
+            None,
+            None,
+            stream_from_str(&test_jsonl),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.is_bad_request());
+        assert!(err.msg().contains("different format"), "{err:?}");
+        assert_eq!(
+            load_fields_as_maps(&app, "messages", vec!["body"])
+                .await?
+                .len(),
+            4
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn import_parquet(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let schema = parse_message_type(
+            "message schema {
+                REQUIRED BYTE_ARRAY name (UTF8);
+                REQUIRED INT64 count;
+                OPTIONAL DOUBLE score;
+                REQUIRED BYTE_ARRAY tags (JSON);
+            }",
+        )?;
+        let mut writer =
+            SerializedFileWriter::new(Vec::new(), Arc::new(schema), Default::default())?;
+        let mut row_group = writer.next_row_group()?;
+        let mut column = row_group.next_column()?.context("missing name")?;
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&["a".into(), "b".into()], None, None)?;
+        column.close()?;
+        let mut column = row_group.next_column()?.context("missing count")?;
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)?;
+        column.close()?;
+        let mut column = row_group.next_column()?.context("missing score")?;
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[0.5], Some(&[1, 0]), None)?;
+        column.close()?;
+        let mut column = row_group.next_column()?.context("missing tags")?;
+        column.typed::<ByteArrayType>().write_batch(
+            &[r#"["x"]"#.into(), r#"{"y": true}"#.into()],
+            None,
+            None,
+        )?;
+        column.close()?;
+        row_group.close()?;
+        let parquet_bytes = writer.into_inner()?;
+
+        let num_written = do_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::Parquet("items".parse()?),
+            ImportMode::Replace,
+            None,
+            None,
+            stream::iter(vec![anyhow::Ok(parquet_bytes.into())]).boxed(),
+        )
+        .await?;
+        assert_eq!(num_written, 2);
+
+        let mut objects =
+            load_fields_as_maps(&app, "items", vec!["name", "count", "score", "tags"]).await?;
+        objects.sort_by(|x, y| x["name"].cmp(&y["name"]));
+        assert_eq!(
+            objects,
+            vec![
+                btreemap!(
+                    "name" => assert_val!("a"),
+                    "count" => assert_val!(1),
+                    "score" => assert_val!(0.5),
+                    "tags" => assert_val!(["x"]),
+                ),
+                btreemap!(
+                    "name" => assert_val!("b"),
+                    "count" => assert_val!(2),
+                    "score" => assert_val!(null),
+                    "tags" => ConvexValue::Object(assert_obj!("y" => true)),
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn import_incremental_zip(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name: TableName = "messages".parse()?;
+        let test_csv = r#"
+body
+"a"
+"b"
+"c"
+"#;
+        run_csv_import(&app, "messages", test_csv).await?;
+        let mut exported = BTreeMap::new();
+        {
+            let mut tx = app.begin(new_admin_id()).await?;
+            let query = common::query::Query::full_table_scan(table_name.clone(), Order::Asc);
+            let mut query_stream = ResolvedQuery::new(&mut tx, TableNamespace::test_user(), query)?;
+            while let Some(doc) = query_stream.next(&mut tx, None).await? {
+                let json = doc.export(ValueFormat::ConvexCleanJSON);
+                let body = json["body"].as_str().context("missing body")?.to_string();
+                exported.insert(body, json);
+            }
+        }
+
+        // "a" changed and "c" was deleted since the base export.
+        let mut changed = exported["a"].clone();
+        changed["body"] = json!("changed");
+        let zip = zip_bytes(vec![
+            (
+                "incremental.json",
+                json!({ "baseSnapshotTs": "1" }).to_string(),
+            ),
+            ("messages/documents.jsonl", format!("{changed}\n")),
+            (
+                "messages/deleted.jsonl",
+                format!("{}\n", json!({ "_id": exported["c"]["_id"] })),
+            ),
+        ])
+        .await?;
+
+        // Incremental exports only apply on top of the existing tables.
+        let err = do_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::Zip,
+            ImportMode::Replace,
+            None,
+            None,
+            stream::iter(vec![anyhow::Ok(zip.clone())]).boxed(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.is_bad_request());
+
+        do_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::Zip,
+            ImportMode::Append,
+            None,
+            None,
+            stream::iter(vec![anyhow::Ok(zip)]).boxed(),
+        )
+        .await?;
+
+        let mut objects = load_fields_as_maps(&app, "messages", vec!["_id", "body"]).await?;
+        objects.sort_by(|x, y| x["body"].cmp(&y["body"]));
+        assert_eq!(
+            objects,
+            vec![
+                btreemap!(
+                    "_id" => ConvexValue::try_from(exported["b"]["_id"].clone())?,
+                    "body" => assert_val!("b"),
+                ),
+                btreemap!(
+                    "_id" => ConvexValue::try_from(exported["a"]["_id"].clone())?,
+                    "body" => assert_val!("changed"),
+                ),
+            ]
+        );
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_import_counts_bandwidth(rt: TestRuntime) -> anyhow::Result<()> {
         let app = Application::new_for_tests(&rt).await?;
@@ -3375,6 +5273,7 @@ a
             &app.file_storage,
             identity,
             ImportMode::Replace,
+            None,
             objects,
             usage.clone(),
             None,
@@ -3442,6 +5341,17 @@ a
         Ok(fields_list)
     }
 
+    async fn zip_bytes(entries: Vec<(&str, String)>) -> anyhow::Result<Bytes> {
+        let mut buf = vec![];
+        let mut writer = ZipFileWriter::new(&mut buf);
+        for (filename, contents) in entries {
+            let entry = ZipEntryBuilder::new(filename.to_string(), Compression::Stored).build();
+            writer.write_entry_whole(entry, contents.as_bytes()).await?;
+        }
+        writer.close().await?;
+        Ok(buf.into())
+    }
+
     fn new_admin_id() -> Identity {
         Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
             "test".to_string(),
@@ -3459,6 +5369,8 @@ a
             new_admin_id(),
             ImportFormat::Csv(table_name.parse()?),
             ImportMode::Replace,
+            None,
+            None,
             stream_from_str(input),
         )
         .await
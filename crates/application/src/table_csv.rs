@@ -0,0 +1,595 @@
+//! Column-mapped CSV export of a single table, for reading in spreadsheets.
+//!
+//! Columns come from the table's inferred shape. Nested objects are flattened
+//! into dotted columns like `address.city`, and each header ends with the
+//! column's type, like `address.city:string`, so that importing the file with
+//! `ImportFormat::Csv` restores the same documents:
+//!
+//! - `string` cells are the string itself, or a JSON string literal like `""`
+//!   if the string is empty or starts with `"`.
+//! - `number` cells are float64s in decimal, or `NaN`, `Infinity` or
+//!   `-Infinity`.
+//! - `boolean` cells are `true` or `false`.
+//! - `int64` cells are integers in decimal.
+//! - `bytes` cells are base64.
+//! - `json` cells hold fields with any other shape, like arrays, `null`s and
+//!   unions of several types, in the internal JSON encoding, which keeps int64s
+//!   and bytes distinct from numbers and strings.
+//!
+//! An empty cell is a missing field. A nested object is missing if all of its
+//! columns are empty.
+use std::{
+    collections::{
+        btree_map::Entry,
+        BTreeMap,
+        BTreeSet,
+    },
+    fmt,
+    mem,
+    str::FromStr,
+};
+
+use anyhow::Context;
+use bytes::Bytes;
+use common::{
+    document::{
+        ResolvedDocument,
+        CREATION_TIME_FIELD,
+        ID_FIELD,
+    },
+    knobs::TABLE_CSV_EXPORT_PAGE_SIZE,
+    runtime::Runtime,
+    types::TableName,
+};
+use database::IndexModel;
+use errors::ErrorMetadata;
+use futures::{
+    pin_mut,
+    stream::BoxStream,
+    Stream,
+    StreamExt,
+    TryStreamExt,
+};
+use futures_async_stream::try_stream;
+use keybroker::Identity;
+use serde_json::Value as JsonValue;
+use shape_inference::{
+    Shape,
+    ShapeConfig,
+    ShapeCounter,
+    ShapeEnum,
+};
+use sync_types::Timestamp;
+use value::{
+    ConvexObject,
+    ConvexValue,
+    FieldName,
+    Namespace,
+    TableNamespace,
+};
+
+use crate::Application;
+
+/// Rows are encoded and sent in batches of this many documents.
+const CSV_ROWS_PER_CHUNK: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CsvColumnType {
+    String,
+    Number,
+    Boolean,
+    Int64,
+    Bytes,
+    Json,
+}
+
+impl CsvColumnType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Int64 => "int64",
+            Self::Bytes => "bytes",
+            Self::Json => "json",
+        }
+    }
+
+    /// Unlike `ExportContext`, which can lean on per-document overrides, a
+    /// column's type alone has to decode every cell in it, so shapes without
+    /// a single scalar type fall back to `json`.
+    fn of_shape<C: ShapeConfig, S: ShapeCounter>(shape: &Shape<C, S>) -> Self {
+        match shape.variant() {
+            ShapeEnum::Int64 => Self::Int64,
+            ShapeEnum::NegativeInf
+            | ShapeEnum::PositiveInf
+            | ShapeEnum::NegativeZero
+            | ShapeEnum::NaN
+            | ShapeEnum::NormalFloat64
+            | ShapeEnum::Float64 => Self::Number,
+            ShapeEnum::Boolean => Self::Boolean,
+            ShapeEnum::StringLiteral(_)
+            | ShapeEnum::Id(_)
+            | ShapeEnum::FieldName
+            | ShapeEnum::String => Self::String,
+            ShapeEnum::Bytes => Self::Bytes,
+            ShapeEnum::Never
+            | ShapeEnum::Null
+            | ShapeEnum::Array(_)
+            | ShapeEnum::Set(_)
+            | ShapeEnum::Map(_)
+            | ShapeEnum::Object(_)
+            | ShapeEnum::Record(_)
+            | ShapeEnum::Union(_)
+            | ShapeEnum::Unknown => Self::Json,
+        }
+    }
+
+    fn encode(self, value: &ConvexValue) -> anyhow::Result<String> {
+        let cell = match (self, value) {
+            (Self::String, ConvexValue::String(s)) => {
+                // Quote strings that would otherwise read back as a missing
+                // field or as a JSON string literal.
+                if s.is_empty() || s.starts_with('"') {
+                    serde_json::to_string(&s[..])?
+                } else {
+                    String::from(s.clone())
+                }
+            },
+            (Self::Number, ConvexValue::Float64(f)) => {
+                if f.is_nan() {
+                    "NaN".to_string()
+                } else if *f == f64::INFINITY {
+                    "Infinity".to_string()
+                } else if *f == f64::NEG_INFINITY {
+                    "-Infinity".to_string()
+                } else {
+                    f.to_string()
+                }
+            },
+            (Self::Boolean, ConvexValue::Boolean(b)) => b.to_string(),
+            (Self::Int64, ConvexValue::Int64(i)) => i.to_string(),
+            (Self::Bytes, ConvexValue::Bytes(b)) => base64::encode(Vec::from(b.clone())),
+            (Self::Json, value) => serde_json::to_string(&JsonValue::from(value.clone()))?,
+            (column_type, value) => {
+                anyhow::bail!("Value {value} doesn't match column type {column_type}")
+            },
+        };
+        Ok(cell)
+    }
+
+    fn decode(self, cell: &str) -> anyhow::Result<ConvexValue> {
+        let value = match self {
+            Self::String => {
+                if cell.starts_with('"') {
+                    ConvexValue::try_from(serde_json::from_str::<String>(cell)?)?
+                } else {
+                    ConvexValue::try_from(cell.to_string())?
+                }
+            },
+            Self::Number => ConvexValue::from(cell.parse::<f64>()?),
+            Self::Boolean => match cell {
+                "true" => ConvexValue::from(true),
+                "false" => ConvexValue::from(false),
+                _ => anyhow::bail!("expected true or false"),
+            },
+            Self::Int64 => ConvexValue::from(cell.parse::<i64>()?),
+            Self::Bytes => ConvexValue::try_from(base64::decode(cell)?)?,
+            Self::Json => ConvexValue::try_from(serde_json::from_str::<JsonValue>(cell)?)?,
+        };
+        Ok(value)
+    }
+}
+
+impl fmt::Display for CsvColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for CsvColumnType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let column_type = match s {
+            "string" => Self::String,
+            "number" => Self::Number,
+            "boolean" => Self::Boolean,
+            "int64" => Self::Int64,
+            "bytes" => Self::Bytes,
+            "json" => Self::Json,
+            _ => anyhow::bail!("unknown column type {s}"),
+        };
+        Ok(column_type)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CsvColumn {
+    /// The path to the column's field, e.g. `["address", "city"]` for
+    /// `address.city`.
+    pub path: Vec<FieldName>,
+    pub column_type: CsvColumnType,
+}
+
+impl CsvColumn {
+    pub fn header(&self) -> String {
+        let path: Vec<&str> = self.path.iter().map(|field| &field[..]).collect();
+        format!("{}:{}", path.join("."), self.column_type)
+    }
+
+    /// Parse a header like `address.city:string`. Returns `None` for headers
+    /// without a column type.
+    fn parse_header(header: &str) -> Option<anyhow::Result<Self>> {
+        let (path, column_type) = header.rsplit_once(':')?;
+        let column_type = column_type.parse().ok()?;
+        let path = path
+            .split('.')
+            .map(|field| {
+                anyhow::ensure!(!field.is_empty(), "empty field name in {path}");
+                field.parse()
+            })
+            .collect::<anyhow::Result<_>>();
+        Some(path.map(|path| Self { path, column_type }))
+    }
+}
+
+/// Parse the headers of a CSV file. Returns `None` unless every header has a
+/// column type. Without column types, each header is a top-level field and
+/// cells are read as numbers or strings.
+pub fn parse_typed_csv_headers<'a>(
+    headers: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<Option<Vec<CsvColumn>>> {
+    let headers: Vec<_> = headers.collect();
+    let Some(columns) = headers
+        .iter()
+        .map(|header| CsvColumn::parse_header(header))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Ok(None);
+    };
+    let columns = headers
+        .into_iter()
+        .zip(columns)
+        .map(|(header, column)| column.with_context(|| format!("Invalid header {header}")))
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Some(columns))
+}
+
+/// Read a row of a CSV file with typed headers into a document.
+pub fn csv_row_to_object<'a>(
+    columns: &[CsvColumn],
+    cells: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<ConvexObject> {
+    let mut object = CsvObject::default();
+    for (column, cell) in columns.iter().zip(cells) {
+        if cell.is_empty() {
+            continue;
+        }
+        let value = column
+            .column_type
+            .decode(cell)
+            .with_context(|| format!("Invalid value for column {}", column.header()))?;
+        object.insert(&column.path, value)?;
+    }
+    object.try_into()
+}
+
+#[derive(Default)]
+struct CsvObject(BTreeMap<FieldName, CsvField>);
+
+enum CsvField {
+    Value(ConvexValue),
+    Object(CsvObject),
+}
+
+impl CsvObject {
+    /// Set the field at `path` to `value`, creating objects along the path.
+    fn insert(&mut self, path: &[FieldName], value: ConvexValue) -> anyhow::Result<()> {
+        let (field, rest) = path.split_first().context("Empty column path")?;
+        if rest.is_empty() {
+            match self.0.entry(field.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(CsvField::Value(value));
+                },
+                Entry::Occupied(_) => anyhow::bail!("Field {field} has more than one column"),
+            }
+            return Ok(());
+        }
+        match self
+            .0
+            .entry(field.clone())
+            .or_insert_with(|| CsvField::Object(CsvObject::default()))
+        {
+            CsvField::Object(object) => object.insert(rest, value),
+            CsvField::Value(_) => {
+                anyhow::bail!("Field {field} has both a column and nested columns")
+            },
+        }
+    }
+}
+
+impl TryFrom<CsvObject> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(object: CsvObject) -> anyhow::Result<Self> {
+        let fields: BTreeMap<FieldName, ConvexValue> = object
+            .0
+            .into_iter()
+            .map(|(field, value)| {
+                let value = match value {
+                    CsvField::Value(value) => value,
+                    CsvField::Object(object) => ConvexValue::Object(object.try_into()?),
+                };
+                anyhow::Ok((field, value))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        fields.try_into()
+    }
+}
+
+/// The columns of a table's CSV file. `_id` and `_creationTime` always come
+/// first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CsvTableSchema {
+    columns: Vec<CsvColumn>,
+}
+
+impl CsvTableSchema {
+    /// Derive columns from a table's inferred shape. Returns `None` unless the
+    /// shape is an object or a union of objects, since otherwise the table's
+    /// documents don't have a fixed set of fields.
+    pub fn from_shape<C: ShapeConfig, S: ShapeCounter>(shape: &Shape<C, S>) -> Option<Self> {
+        let objects = match shape.variant() {
+            ShapeEnum::Never => vec![],
+            ShapeEnum::Object(_) => vec![shape],
+            ShapeEnum::Union(union) => {
+                if !union
+                    .iter()
+                    .all(|option| matches!(option.variant(), ShapeEnum::Object(_)))
+                {
+                    return None;
+                }
+                union.iter().collect()
+            },
+            _ => return None,
+        };
+        let mut columns = vec![
+            CsvColumn {
+                path: vec![ID_FIELD.clone().into()],
+                column_type: CsvColumnType::String,
+            },
+            CsvColumn {
+                path: vec![CREATION_TIME_FIELD.clone().into()],
+                column_type: CsvColumnType::Number,
+            },
+        ];
+        add_object_columns(&[], &objects, &mut columns);
+        Some(Self { columns })
+    }
+
+    pub fn columns(&self) -> &[CsvColumn] {
+        &self.columns
+    }
+
+    pub fn headers(&self) -> Vec<String> {
+        self.columns.iter().map(|column| column.header()).collect()
+    }
+
+    /// Encode a document as a row with a cell for each column.
+    pub fn row(&self, document: &ConvexObject) -> anyhow::Result<Vec<String>> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let mut fields = column.path.iter();
+                let mut value = fields.next().and_then(|field| document.get(field));
+                for field in fields {
+                    value = match value {
+                        Some(ConvexValue::Object(object)) => object.get(field),
+                        _ => None,
+                    };
+                }
+                match value {
+                    Some(value) => column.column_type.encode(value),
+                    None => Ok(String::new()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Add columns for the fields of `objects`, which all have object shapes.
+/// Fields whose values are always objects are flattened into a column for each
+/// of their fields.
+fn add_object_columns<C: ShapeConfig, S: ShapeCounter>(
+    path: &[FieldName],
+    objects: &[&Shape<C, S>],
+    columns: &mut Vec<CsvColumn>,
+) {
+    let mut fields = BTreeMap::new();
+    for object in objects {
+        let ShapeEnum::Object(object) = object.variant() else {
+            continue;
+        };
+        for (field, field_shape) in object.iter() {
+            if path.is_empty() && field.is_system() {
+                continue;
+            }
+            let options: &mut Vec<_> = fields.entry(field).or_default();
+            match field_shape.value_shape.variant() {
+                ShapeEnum::Union(union) => options.extend(union.iter()),
+                ShapeEnum::Never => {},
+                _ => options.push(&field_shape.value_shape),
+            }
+        }
+    }
+    for (field, options) in fields {
+        let mut field_path = path.to_vec();
+        field_path.push(FieldName::from(field.clone()));
+        let num_columns = columns.len();
+        if !options.is_empty()
+            && options
+                .iter()
+                .all(|option| matches!(option.variant(), ShapeEnum::Object(_)))
+        {
+            add_object_columns(&field_path, &options, columns);
+        }
+        // Objects without any fields are written as JSON, so they aren't
+        // mistaken for missing objects.
+        if columns.len() == num_columns {
+            let column_types: BTreeSet<_> = options
+                .iter()
+                .map(|option| CsvColumnType::of_shape(option))
+                .collect();
+            let column_type = match column_types.len() {
+                1 => *column_types.first().expect("checked length"),
+                _ => CsvColumnType::Json,
+            };
+            columns.push(CsvColumn {
+                path: field_path,
+                column_type,
+            });
+        }
+    }
+}
+
+/// Stream `table_name` as a CSV file, as of the latest timestamp.
+pub async fn export_table_csv<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    namespace: TableNamespace,
+    table_name: TableName,
+) -> anyhow::Result<BoxStream<'static, anyhow::Result<Bytes>>> {
+    if !identity.is_admin() {
+        anyhow::bail!(ErrorMetadata::forbidden(
+            "InvalidCsvExport",
+            "Only an admin of the deployment can export tables"
+        ));
+    }
+    let database = &application.database;
+    let mut tx = database.begin(identity.clone()).await?;
+    let table_id = tx
+        .table_mapping()
+        .namespace(namespace)
+        .id_and_number_if_exists(&table_name)
+        .context(ErrorMetadata::bad_request(
+            "TableNotFound",
+            format!("Table {table_name} not found"),
+        ))?;
+    let by_id = IndexModel::new(&mut tx)
+        .by_id_index_metadata(table_id.tablet_id)
+        .await?
+        .id()
+        .internal_id();
+    let snapshot_ts = tx.begin_timestamp();
+    let table_summary = database
+        .snapshot(snapshot_ts)?
+        .table_summaries
+        .tablet_summary(&table_id.tablet_id);
+    let schema = CsvTableSchema::from_shape(table_summary.inferred_type()).context(
+        ErrorMetadata::bad_request(
+            "CsvExportUnsupportedTable",
+            format!(
+                "Documents in {table_name} don't share a fixed set of fields, so it can't be \
+                 exported as CSV. Try a JSONL export instead."
+            ),
+        ),
+    )?;
+    let documents = database
+        .table_iterator(snapshot_ts, *TABLE_CSV_EXPORT_PAGE_SIZE, None)
+        .stream_documents_in_table(table_id.tablet_id, by_id, None);
+    Ok(csv_chunks(schema, documents).boxed())
+}
+
+#[try_stream(ok = Bytes, error = anyhow::Error)]
+async fn csv_chunks(
+    schema: CsvTableSchema,
+    documents: impl Stream<Item = anyhow::Result<(ResolvedDocument, Timestamp)>> + Send + 'static,
+) {
+    yield write_csv_records(vec![schema.headers()]).await?;
+    pin_mut!(documents);
+    let mut rows = vec![];
+    while let Some((doc, _ts)) = documents.try_next().await? {
+        rows.push(schema.row(&doc.value().0)?);
+        if rows.len() >= CSV_ROWS_PER_CHUNK {
+            yield write_csv_records(mem::take(&mut rows)).await?;
+        }
+    }
+    if !rows.is_empty() {
+        yield write_csv_records(rows).await?;
+    }
+}
+
+async fn write_csv_records(records: Vec<Vec<String>>) -> anyhow::Result<Bytes> {
+    let mut buf = vec![];
+    {
+        let mut writer = csv_async::AsyncWriter::from_writer(&mut buf);
+        for record in records {
+            writer.write_record(&record).await?;
+        }
+        writer.flush().await?;
+    }
+    Ok(buf.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use shape_inference::{
+        CountedShape,
+        ProdConfigWithOptionalFields,
+    };
+    use value::assert_obj;
+
+    use super::{
+        csv_row_to_object,
+        parse_typed_csv_headers,
+        CsvTableSchema,
+    };
+
+    #[test]
+    fn test_csv_row_roundtrip() -> anyhow::Result<()> {
+        let documents = vec![
+            assert_obj!(
+                "name" => "alice",
+                "address" => { "city" => "Paris", "zip" => 75001 },
+                "visits" => 3,
+                "avatar" => vec![0u8, 1, 2],
+                "tags" => ["a", 1],
+            ),
+            assert_obj!(
+                "name" => "",
+                "score" => f64::NEG_INFINITY,
+                "tags" => null,
+            ),
+            assert_obj!(
+                "address" => { "city" => "\"Lyon\"", "zip" => 69001 },
+                "visits" => 1,
+            ),
+        ];
+        let mut shape = CountedShape::<ProdConfigWithOptionalFields>::empty();
+        for document in &documents {
+            shape = shape.insert(document);
+        }
+        let schema = CsvTableSchema::from_shape(&shape).unwrap();
+        let headers = schema.headers();
+        let columns = parse_typed_csv_headers(headers.iter().map(|header| &header[..]))?.unwrap();
+        assert_eq!(columns, schema.columns());
+        for document in documents {
+            let row = schema.row(&document)?;
+            let parsed = csv_row_to_object(&columns, row.iter().map(|cell| &cell[..]))?;
+            assert_eq!(parsed, document);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_untyped_csv_headers() -> anyhow::Result<()> {
+        assert_eq!(parse_typed_csv_headers(["a", "b.c"].into_iter())?, None);
+        assert_eq!(
+            parse_typed_csv_headers(["ratio:number", "label"].into_iter())?,
+            None
+        );
+        assert!(parse_typed_csv_headers(["a:string", ".b:string"].into_iter()).is_err());
+        Ok(())
+    }
+}
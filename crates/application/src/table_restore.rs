@@ -0,0 +1,433 @@
+//! Point-in-time restore of user tables from the document log.
+//!
+//! Restoring a table to a timestamp within document retention finds the
+//! documents that changed after the timestamp, and writes their revision at
+//! the timestamp as new revisions. Documents that didn't exist at the
+//! timestamp are deleted. The writes are split into batches, so a restore
+//! that fails partway through leaves the batches before it in place, and can
+//! be run again to finish.
+//!
+//! Documents deleted after the timestamp are reinserted with new IDs, because
+//! the IDs of deleted documents can't be reused. The new IDs are recorded in
+//! `_table_restore_documents`, so running the same restore again updates the
+//! reinserted documents instead of inserting them again.
+//!
+//! The document log is read a page at a time, so a restore holds at most a
+//! page of documents in memory.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    num::NonZeroU32,
+    ops::Bound,
+};
+
+use anyhow::Context;
+use common::{
+    bootstrap_model::tables::{
+        TableMetadata,
+        TableState,
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        ID_FIELD,
+    },
+    knobs::{
+        DEFAULT_DOCUMENTS_PAGE_SIZE,
+        TABLE_RESTORE_BATCH_SIZE,
+        TABLE_RESTORE_PAGES_PER_SECOND,
+    },
+    pause::PauseClient,
+    persistence::TimestampRange,
+    query::Order,
+    runtime::{
+        new_rate_limiter,
+        RateLimiter,
+        Runtime,
+    },
+    try_chunks::TryChunksExt,
+    types::TableName,
+};
+use database::{
+    ImportFacingModel,
+    Transaction,
+    UserFacingModel,
+};
+use errors::ErrorMetadata;
+use futures::{
+    pin_mut,
+    Stream,
+    TryStreamExt,
+};
+use governor::Quota;
+use keybroker::Identity;
+use model::table_restores::{
+    types::RestoredDocument,
+    TableRestoreModel,
+};
+use sync_types::Timestamp;
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    id_v6::DeveloperDocumentId,
+    ConvexObject,
+    FieldName,
+    InternalDocumentId,
+    ResolvedDocumentId,
+    TableNamespace,
+    TabletIdAndTableNumber,
+};
+
+use crate::Application;
+
+/// The number of documents a restore writes to a table, by kind of write.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct TableRestoreCounts {
+    pub inserted: u64,
+    pub updated: u64,
+    pub deleted: u64,
+}
+
+impl TableRestoreCounts {
+    fn add(&mut self, other: TableRestoreCounts) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.deleted += other.deleted;
+    }
+}
+
+/// Entries of a table's document log, in log order.
+type LogPage = Vec<(Timestamp, InternalDocumentId, Option<ResolvedDocument>)>;
+
+/// A table that was checked to be restorable to `ts`.
+struct TableRestorePlan {
+    table_name: TableName,
+    table_id: TabletIdAndTableNumber,
+    ts: Timestamp,
+    /// Changes to the table after `ts` and up to `end_ts` are undone.
+    end_ts: Timestamp,
+}
+
+fn count_write(
+    counts: &mut TableRestoreCounts,
+    latest: Option<&ResolvedDocument>,
+    target: Option<&ResolvedDocument>,
+) -> bool {
+    match (latest, target) {
+        (None, None) => return false,
+        (None, Some(_)) => counts.inserted += 1,
+        (Some(_), None) => counts.deleted += 1,
+        (Some(latest), Some(target)) => {
+            // A reinserted document has a different `_id` but the same
+            // `_creationTime`.
+            if latest.value().0.clone().filter_system_fields()
+                == target.value().0.clone().filter_system_fields()
+            {
+                return false;
+            }
+            counts.updated += 1;
+        },
+    }
+    true
+}
+
+/// Count the documents that restoring `table_names` to `ts` would insert,
+/// update, and delete, without writing anything.
+pub async fn preview_table_restore<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    namespace: TableNamespace,
+    table_names: Vec<TableName>,
+    ts: Timestamp,
+) -> anyhow::Result<BTreeMap<TableName, TableRestoreCounts>> {
+    let mut counts = BTreeMap::new();
+    for table_name in table_names {
+        let plan = plan_table_restore(application, identity, namespace, table_name, ts).await?;
+        let rate_limiter = restore_rate_limiter(application)?;
+        let mut table_counts = TableRestoreCounts::default();
+        let stream = log_pages(application, &plan, &rate_limiter)?;
+        pin_mut!(stream);
+        while let Some(page) = stream.try_next().await? {
+            let targets = targets_in_page(application, &plan, page).await?;
+            let mut tx = application.database.begin(identity.clone()).await?;
+            let page_counts = restore_documents(&mut tx, namespace, &plan, &targets, false).await?;
+            table_counts.add(page_counts);
+        }
+        counts.insert(plan.table_name.clone(), table_counts);
+    }
+    Ok(counts)
+}
+
+/// Restore `table_names` to their contents at `ts`, committing the changes in
+/// batches of `TABLE_RESTORE_BATCH_SIZE` documents. Returns the number of
+/// documents written to each table.
+pub async fn restore_tables<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    namespace: TableNamespace,
+    table_names: Vec<TableName>,
+    ts: Timestamp,
+) -> anyhow::Result<BTreeMap<TableName, TableRestoreCounts>> {
+    // Check every table before writing to any of them.
+    let mut plans = vec![];
+    for table_name in table_names {
+        plans.push(plan_table_restore(application, identity, namespace, table_name, ts).await?);
+    }
+    let mut counts = BTreeMap::new();
+    for plan in plans {
+        let table_counts = restore_table(application, identity, namespace, &plan).await?;
+        counts.insert(plan.table_name, table_counts);
+    }
+    Ok(counts)
+}
+
+async fn plan_table_restore<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    namespace: TableNamespace,
+    table_name: TableName,
+    ts: Timestamp,
+) -> anyhow::Result<TableRestorePlan> {
+    if !identity.is_admin() {
+        anyhow::bail!(ErrorMetadata::forbidden(
+            "InvalidTableRestore",
+            "Only an admin of the deployment can restore tables"
+        ));
+    }
+    anyhow::ensure!(
+        !table_name.is_system(),
+        ErrorMetadata::bad_request(
+            "InvalidTableRestore",
+            format!("Cannot restore system table {table_name}"),
+        )
+    );
+    let database = &application.database;
+    let now = database.now_ts_for_reads();
+    let min_document_snapshot_ts = database
+        .retention_validator()
+        .min_document_snapshot_ts()
+        .await?;
+    if ts < min_document_snapshot_ts || ts > *now {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "TableRestoreOutOfRetention",
+            format!(
+                "Cannot restore to {ts}. Tables can only be restored to a time between \
+                 {min_document_snapshot_ts} and {}",
+                *now
+            ),
+        ));
+    }
+
+    let (table_id, table_doc_id) = {
+        let mut tx = database.begin(identity.clone()).await?;
+        let table_id = tx
+            .table_mapping()
+            .namespace(namespace)
+            .id_and_number_if_exists(&table_name)
+            .context(ErrorMetadata::bad_request(
+                "TableNotFound",
+                format!("Table {table_name} not found"),
+            ))?;
+        let table_doc_id = tx
+            .bootstrap_tables()
+            .table_resolved_doc_id(table_id.tablet_id);
+        (table_id, InternalDocumentId::from(table_doc_id))
+    };
+    // If the table was created or replaced (e.g. by an import) after `ts`,
+    // its documents at `ts` aren't in its document log.
+    let table_metadata = database
+        .load_revisions_at_ts(BTreeSet::from([table_doc_id]), ts)
+        .await?
+        .remove(&table_doc_id)
+        .map(ParsedDocument::<TableMetadata>::try_from)
+        .transpose()?;
+    if !matches!(table_metadata, Some(metadata) if metadata.state == TableState::Active) {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "TableCreatedAfterRestoreTimestamp",
+            format!(
+                "Cannot restore {table_name} to {ts} because the table was created or replaced \
+                 after that"
+            ),
+        ));
+    }
+
+    Ok(TableRestorePlan {
+        table_name,
+        table_id,
+        ts,
+        end_ts: *now,
+    })
+}
+
+fn restore_rate_limiter<RT: Runtime>(
+    application: &Application<RT>,
+) -> anyhow::Result<RateLimiter<RT>> {
+    Ok(new_rate_limiter(
+        application.runtime.clone(),
+        Quota::per_second(
+            NonZeroU32::new(*DEFAULT_DOCUMENTS_PAGE_SIZE)
+                .and_then(|val| val.checked_mul(*TABLE_RESTORE_PAGES_PER_SECOND))
+                .context("Invalid row rate limit")?,
+        ),
+    ))
+}
+
+/// The table's document log after the restore timestamp, in pages.
+fn log_pages<'a, RT: Runtime>(
+    application: &'a Application<RT>,
+    plan: &TableRestorePlan,
+    rate_limiter: &'a RateLimiter<RT>,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<LogPage>> + 'a> {
+    let range = TimestampRange::new((Bound::Excluded(plan.ts), Bound::Included(plan.end_ts)))?;
+    Ok(application
+        .database
+        .load_documents_in_table(plan.table_id.tablet_id, range, Order::Asc, rate_limiter)
+        .try_chunks2(*DEFAULT_DOCUMENTS_PAGE_SIZE as usize))
+}
+
+/// The documents in a page of the log whose latest revision is in the page,
+/// with their revisions at the restore timestamp. Each changed document is in
+/// exactly one page.
+async fn targets_in_page<RT: Runtime>(
+    application: &Application<RT>,
+    plan: &TableRestorePlan,
+    page: LogPage,
+) -> anyhow::Result<Vec<(ResolvedDocumentId, Option<ResolvedDocument>)>> {
+    let database = &application.database;
+    // Later entries for a document replace earlier ones.
+    let page_revisions: BTreeMap<_, _> = page.into_iter().map(|(ts, id, _)| (id, ts)).collect();
+    let latest_revisions = database
+        .load_latest_revisions_at_ts(page_revisions.keys().copied().collect(), plan.end_ts)
+        .await?;
+    let ids: BTreeSet<_> = page_revisions
+        .into_iter()
+        .filter(|(id, ts)| {
+            latest_revisions
+                .get(id)
+                .is_some_and(|(latest_ts, _)| latest_ts == ts)
+        })
+        .map(|(id, _)| id)
+        .collect();
+    let mut revisions_at_ts = database.load_revisions_at_ts(ids.clone(), plan.ts).await?;
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let target = revisions_at_ts.remove(&id);
+            let id = ResolvedDocumentId::new(
+                id.table(),
+                DeveloperDocumentId::new(plan.table_id.table_number, id.internal_id()),
+            );
+            (id, target)
+        })
+        .collect())
+}
+
+/// The document's value without its `_id`, to write it to another ID.
+fn value_without_id(document: &ResolvedDocument) -> anyhow::Result<ConvexObject> {
+    let mut fields: BTreeMap<_, _> = document.value().0.clone().into();
+    fields.remove(&FieldName::from(ID_FIELD.clone()));
+    fields.try_into()
+}
+
+/// Write each document's revision at the restore timestamp, or only count the
+/// writes if `write` is false.
+async fn restore_documents<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    plan: &TableRestorePlan,
+    targets: &[(ResolvedDocumentId, Option<ResolvedDocument>)],
+    write: bool,
+) -> anyhow::Result<TableRestoreCounts> {
+    let tablet_id = plan.table_id.tablet_id;
+    let table_mapping = tx.table_mapping().clone();
+    let mut counts = TableRestoreCounts::default();
+    for (original_id, target) in targets {
+        let mut restore_model = TableRestoreModel::new(tx);
+        // Documents that an earlier run of this restore reinserted are
+        // restored along with the documents they were reinserted for.
+        if restore_model
+            .is_restored_document(tablet_id, plan.ts, original_id.developer_id)
+            .await?
+        {
+            continue;
+        }
+        let id = match restore_model
+            .restored_id(tablet_id, plan.ts, original_id.developer_id)
+            .await?
+        {
+            Some(restored_id) => ResolvedDocumentId::new(tablet_id, restored_id),
+            None => *original_id,
+        };
+        // Compare against the document now, in case it changed since the log
+        // was read.
+        let latest = tx.get(id).await?;
+        if !count_write(&mut counts, latest.as_ref(), target.as_ref()) || !write {
+            continue;
+        }
+        match (latest, target) {
+            (Some(_), None) => {
+                UserFacingModel::new(tx, namespace)
+                    .delete(id.into())
+                    .await?;
+            },
+            (None, Some(target)) => {
+                // Keep the `_creationTime` but not the `_id`.
+                let restored_id = ImportFacingModel::new(tx)
+                    .insert(
+                        plan.table_id,
+                        &plan.table_name,
+                        value_without_id(target)?,
+                        &table_mapping,
+                    )
+                    .await?;
+                TableRestoreModel::new(tx)
+                    .record_restored_document(RestoredDocument {
+                        table_id: tablet_id,
+                        restore_ts: plan.ts,
+                        original_id: original_id.developer_id,
+                        restored_id,
+                    })
+                    .await?;
+            },
+            (Some(_), Some(target)) => {
+                UserFacingModel::new(tx, namespace)
+                    .replace(id.into(), value_without_id(target)?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+    }
+    Ok(counts)
+}
+
+async fn restore_table<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    namespace: TableNamespace,
+    plan: &TableRestorePlan,
+) -> anyhow::Result<TableRestoreCounts> {
+    let table_name = &plan.table_name;
+    let rate_limiter = restore_rate_limiter(application)?;
+    let mut counts = TableRestoreCounts::default();
+    let stream = log_pages(application, plan, &rate_limiter)?;
+    pin_mut!(stream);
+    while let Some(page) = stream.try_next().await? {
+        let targets = targets_in_page(application, plan, page).await?;
+        for batch in targets.chunks(*TABLE_RESTORE_BATCH_SIZE) {
+            let (_, batch_counts, _) = application
+                .database
+                .execute_with_overloaded_retries(
+                    identity.clone(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "table_restore",
+                    |tx| restore_documents(tx, namespace, plan, batch, true).into(),
+                )
+                .await?;
+            counts.add(batch_counts);
+            tracing::info!("Restoring {table_name}: {counts:?}");
+        }
+    }
+    Ok(counts)
+}
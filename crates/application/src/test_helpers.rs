@@ -150,6 +150,7 @@ pub trait ApplicationTestExt<RT: Runtime> {
         job: CronJob,
         job_id: ResolvedDocumentId,
     ) -> anyhow::Result<()>;
+    async fn test_cron_job_executor_fail_interrupted_runs(&self) -> anyhow::Result<()>;
     fn validate_user_defined_index_fields(
         &self,
         fields: IndexedFields,
@@ -272,6 +273,7 @@ impl<RT: Runtime> ApplicationTestExt<RT> for Application<RT> {
                 kb.clone(),
                 Arc::new(NullAccessTokenAuth),
             )),
+            None,
         )
         .await?;
 
@@ -310,6 +312,17 @@ impl<RT: Runtime> ApplicationTestExt<RT> for Application<RT> {
         Ok(())
     }
 
+    async fn test_cron_job_executor_fail_interrupted_runs(&self) -> anyhow::Result<()> {
+        let test_executor = CronJobExecutor::new(
+            self.runtime.clone(),
+            DEV_INSTANCE_NAME.into(),
+            self.database.clone(),
+            self.runner.clone(),
+            self.function_log.clone(),
+        );
+        test_executor.fail_interrupted_concurrent_runs().await
+    }
+
     async fn load_udf_tests_modules(&self) -> anyhow::Result<()> {
         self.load_udf_tests_modules_inner(false).await
     }
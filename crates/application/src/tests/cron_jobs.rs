@@ -11,6 +11,7 @@ use common::{
         ComponentPath,
     },
     document::ParsedDocument,
+    knobs::CRON_MAX_CONCURRENT_RUNS,
     query::{
         IndexRange,
         IndexRangeExpression,
@@ -22,6 +23,7 @@ use common::{
 use database::{
     query::TableFilter,
     DeveloperQuery,
+    ResolvedQuery,
     TableModel,
     Transaction,
 };
@@ -36,6 +38,10 @@ use model::{
         types::{
             CronIdentifier,
             CronJob,
+            CronJobLog,
+            CronJobState,
+            CronJobStatus,
+            CronOverlapPolicy,
             CronSchedule,
             CronSpec,
         },
@@ -45,7 +51,10 @@ use model::{
     },
 };
 use runtime::testing::TestRuntime;
-use serde_json::Value as JsonValue;
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
 
 use crate::{
     test_helpers::{
@@ -80,6 +89,7 @@ async fn create_cron_job(
         udf_path: path.udf_path.clone(),
         udf_args: parse_udf_args(&path.udf_path, vec![JsonValue::Object(map)])?,
         cron_schedule: CronSchedule::Interval { seconds: 60 },
+        overlap_policy: CronOverlapPolicy::Skip,
     };
     let original_jobs = cron_model.list().await?;
     let name = test_cron_identifier();
@@ -87,6 +97,63 @@ async fn create_cron_job(
     Ok((original_jobs, cron_model))
 }
 
+// A cron that runs an action taking 150 seconds every 60 seconds, so each run
+// overlaps the next two.
+fn overlapping_cron_spec(overlap_policy: CronOverlapPolicy) -> anyhow::Result<CronSpec> {
+    let udf_path = "action:sleep".parse()?;
+    Ok(CronSpec {
+        udf_args: parse_udf_args(&udf_path, vec![json!({"ms": 150_000})])?,
+        udf_path,
+        cron_schedule: CronSchedule::Interval { seconds: 60 },
+        overlap_policy,
+    })
+}
+
+async fn create_overlapping_cron_job(
+    application: &Application<TestRuntime>,
+    overlap_policy: CronOverlapPolicy,
+) -> anyhow::Result<()> {
+    let mut tx = application.begin(Identity::system()).await?;
+    CronModel::new(&mut tx, ComponentId::test_user())
+        .create(
+            test_cron_identifier(),
+            overlapping_cron_spec(overlap_policy)?,
+        )
+        .await?;
+    application.commit_test(tx).await?;
+    Ok(())
+}
+
+async fn get_test_cron_job(
+    application: &Application<TestRuntime>,
+) -> anyhow::Result<ParsedDocument<CronJob>> {
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut jobs = CronModel::new(&mut tx, ComponentId::test_user())
+        .list()
+        .await?;
+    jobs.remove(&test_cron_identifier())
+        .ok_or_else(|| anyhow::anyhow!("Test cron job not found"))
+}
+
+async fn cron_logs(application: &Application<TestRuntime>) -> anyhow::Result<Vec<CronJobLog>> {
+    let mut tx = application.begin(Identity::system()).await?;
+    let query = Query::index_range(IndexRange {
+        index_name: CRON_JOB_LOGS_INDEX_BY_NAME_TS.clone(),
+        range: vec![IndexRangeExpression::Eq(
+            CRON_JOB_LOGS_NAME_FIELD.clone(),
+            common::types::MaybeValue(Some(test_cron_identifier().to_string().try_into()?)),
+        )],
+        order: Order::Asc,
+    });
+    let mut query = ResolvedQuery::new(&mut tx, ComponentId::test_user().into(), query)?;
+    let mut logs = vec![];
+    while let Some(doc) = query.next(&mut tx, None).await? {
+        let log: ParsedDocument<CronJobLog> = doc.try_into()?;
+        logs.push(log.into_value());
+    }
+    Ok(logs)
+}
+
 fn cron_log_query<RT: Runtime>(
     tx: &mut Transaction<RT>,
     component: ComponentId,
@@ -241,3 +308,163 @@ async fn test_cron_jobs_helper(rt: TestRuntime, backend_state: BackendState) ->
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_cron_overlap_skip(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    create_overlapping_cron_job(&application, CronOverlapPolicy::Skip).await?;
+    let first_run_ts = get_test_cron_job(&application).await?.next_ts;
+
+    // The first run finishes after 150 seconds, so the runs due at 60 and 120
+    // seconds are dropped and the next run is at 180 seconds.
+    rt.wait(Duration::from_secs(170)).await;
+    let logs = cron_logs(&application).await?;
+    assert_eq!(logs.len(), 2, "{logs:?}");
+    assert!(matches!(logs[0].status, CronJobStatus::Success(_)));
+    assert_eq!(logs[0].ts, first_run_ts);
+    assert_eq!(logs[1].status, CronJobStatus::Skipped { num_skipped: 2 });
+    assert_eq!(logs[1].ts, first_run_ts.add(Duration::from_secs(60))?);
+
+    let job = get_test_cron_job(&application).await?;
+    assert_eq!(job.state, CronJobState::Pending);
+    assert_eq!(job.next_ts, first_run_ts.add(Duration::from_secs(180))?);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_cron_overlap_queue_one(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    create_overlapping_cron_job(&application, CronOverlapPolicy::QueueOne).await?;
+    let first_run_ts = get_test_cron_job(&application).await?.next_ts;
+
+    // When the first run finishes, the run due at 60 seconds is dropped and the
+    // one due at 120 seconds starts right away.
+    rt.wait(Duration::from_secs(170)).await;
+    let logs = cron_logs(&application).await?;
+    assert_eq!(logs.len(), 2, "{logs:?}");
+    assert!(matches!(logs[0].status, CronJobStatus::Success(_)));
+    assert_eq!(logs[1].status, CronJobStatus::Skipped { num_skipped: 1 });
+    assert_eq!(logs[1].ts, first_run_ts.add(Duration::from_secs(60))?);
+
+    let job = get_test_cron_job(&application).await?;
+    assert_eq!(job.state, CronJobState::InProgress);
+    assert_eq!(job.next_ts, first_run_ts.add(Duration::from_secs(120))?);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_cron_overlap_allow_concurrent(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    create_overlapping_cron_job(&application, CronOverlapPolicy::AllowConcurrent).await?;
+    let first_run_ts = get_test_cron_job(&application).await?.next_ts;
+
+    // Every run starts on schedule. Only the first one has finished.
+    rt.wait(Duration::from_secs(170)).await;
+    let logs = cron_logs(&application).await?;
+    assert_eq!(logs.len(), 1, "{logs:?}");
+    assert!(matches!(logs[0].status, CronJobStatus::Success(_)));
+    assert_eq!(logs[0].ts, first_run_ts);
+
+    let job = get_test_cron_job(&application).await?;
+    assert_eq!(job.state, CronJobState::Pending);
+    assert_eq!(job.next_ts, first_run_ts.add(Duration::from_secs(180))?);
+    assert_eq!(
+        job.concurrent_runs,
+        vec![
+            first_run_ts.add(Duration::from_secs(60))?,
+            first_run_ts.add(Duration::from_secs(120))?,
+        ]
+    );
+
+    // The remaining runs are logged and untracked as they finish.
+    rt.wait(Duration::from_secs(110)).await;
+    let logs = cron_logs(&application).await?;
+    assert!(logs.len() >= 3, "{logs:?}");
+    assert!(logs[..3]
+        .iter()
+        .all(|log| matches!(log.status, CronJobStatus::Success(_))));
+    let job = get_test_cron_job(&application).await?;
+    assert!(!job
+        .concurrent_runs
+        .contains(&first_run_ts.add(Duration::from_secs(120))?));
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_cron_max_concurrent_runs(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut model = CronModel::new(&mut tx, ComponentId::test_user());
+    let cron_spec = overlapping_cron_spec(CronOverlapPolicy::AllowConcurrent)?;
+    model.create(test_cron_identifier(), cron_spec).await?;
+
+    // Simulate the most runs that can be in progress at once. Push the next run
+    // out so the executor doesn't start one of its own.
+    let (job_id, mut job) = model
+        .list()
+        .await?
+        .remove(&test_cron_identifier())
+        .unwrap()
+        .into_id_and_value();
+    job.concurrent_runs = vec![job.next_ts; *CRON_MAX_CONCURRENT_RUNS];
+    job.next_ts = job.next_ts.add(Duration::from_secs(3600))?;
+    model.update_job_state(job_id, job.clone()).await?;
+    application.commit_test(tx).await?;
+
+    // The next run is skipped instead of starting another copy of the action.
+    application
+        .test_one_off_cron_job_executor_run(job.clone(), job_id)
+        .await?;
+    let logs = cron_logs(&application).await?;
+    assert_eq!(logs.len(), 1, "{logs:?}");
+    assert_eq!(logs[0].status, CronJobStatus::Skipped { num_skipped: 1 });
+    assert_eq!(logs[0].ts, job.next_ts);
+    let updated_job = get_test_cron_job(&application).await?;
+    assert_eq!(updated_job.concurrent_runs, job.concurrent_runs);
+    assert_eq!(
+        updated_job.next_ts,
+        job.next_ts.add(Duration::from_secs(60))?
+    );
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_cron_interrupted_concurrent_runs(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut model = CronModel::new(&mut tx, ComponentId::test_user());
+    let cron_spec = overlapping_cron_spec(CronOverlapPolicy::AllowConcurrent)?;
+    model.create(test_cron_identifier(), cron_spec).await?;
+
+    // Simulate a run that was in progress when the backend stopped. Push the
+    // next run out so the executor doesn't start one of its own.
+    let (job_id, mut job) = model
+        .list()
+        .await?
+        .remove(&test_cron_identifier())
+        .unwrap()
+        .into_id_and_value();
+    let interrupted_ts = job.next_ts;
+    job.concurrent_runs = vec![interrupted_ts];
+    job.next_ts = interrupted_ts.add(Duration::from_secs(3600))?;
+    model.update_job_state(job_id, job).await?;
+    application.commit_test(tx).await?;
+
+    application
+        .test_cron_job_executor_fail_interrupted_runs()
+        .await?;
+    let logs = cron_logs(&application).await?;
+    assert_eq!(logs.len(), 1, "{logs:?}");
+    assert!(matches!(logs[0].status, CronJobStatus::Err(_)));
+    assert_eq!(logs[0].ts, interrupted_ts);
+    assert!(get_test_cron_job(&application)
+        .await?
+        .concurrent_runs
+        .is_empty());
+    Ok(())
+}
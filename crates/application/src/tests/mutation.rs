@@ -15,6 +15,7 @@ use common::{
 };
 use errors::ErrorMetadataAnyhowExt;
 use keybroker::Identity;
+use model::session_requests::types::MutationIdentifier;
 use runtime::testing::TestRuntime;
 use serde_json::{
     json,
@@ -29,6 +30,7 @@ use crate::{
 async fn insert_object(
     application: &Application<TestRuntime>,
     pause_client: PauseClient,
+    mutation_identifier: Option<MutationIdentifier>,
 ) -> anyhow::Result<JsonValue> {
     let obj = json!({"an": "object"});
     let result = application
@@ -40,7 +42,7 @@ async fn insert_object(
             }),
             vec![obj],
             Identity::system(),
-            None,
+            mutation_identifier,
             FunctionCaller::Action {
                 parent_scheduled_job: None,
             },
@@ -53,6 +55,7 @@ async fn insert_object(
 async fn insert_and_count(
     application: &Application<TestRuntime>,
     pause_client: PauseClient,
+    mutation_identifier: Option<MutationIdentifier>,
 ) -> anyhow::Result<usize> {
     let obj = json!({"an": "object"});
     let result = application
@@ -64,7 +67,7 @@ async fn insert_and_count(
             }),
             vec![obj],
             Identity::system(),
-            None,
+            mutation_identifier,
             FunctionCaller::Action {
                 parent_scheduled_job: None,
             },
@@ -80,7 +83,7 @@ async fn insert_and_count(
 async fn test_mutation(rt: TestRuntime) -> anyhow::Result<()> {
     let application = Application::new_for_tests(&rt).await?;
     application.load_udf_tests_modules().await?;
-    let result = insert_object(&application, PauseClient::new()).await?;
+    let result = insert_object(&application, PauseClient::new(), None).await?;
     assert_eq!(result["an"], "object");
     Ok(())
 }
@@ -91,7 +94,7 @@ async fn test_mutation_occ_fail(rt: TestRuntime) -> anyhow::Result<()> {
     application.load_udf_tests_modules().await?;
 
     let (mut pause, pause_client) = PauseController::new(["retry_mutation_loop_start"]);
-    let fut1 = insert_and_count(&application, pause_client);
+    let fut1 = insert_and_count(&application, pause_client, None);
     let fut2 = async {
         for i in 0..*UDF_EXECUTOR_OCC_MAX_RETRIES + 1 {
             let mut guard = pause
@@ -101,7 +104,7 @@ async fn test_mutation_occ_fail(rt: TestRuntime) -> anyhow::Result<()> {
 
             // Do an entire mutation while we're paused - to create an OCC conflict on
             // the original insertion.
-            let count = insert_and_count(&application, PauseClient::new()).await?;
+            let count = insert_and_count(&application, PauseClient::new(), None).await?;
             assert_eq!(count, i + 1);
 
             guard.unpause();
@@ -119,7 +122,7 @@ async fn test_mutation_occ_success(rt: TestRuntime) -> anyhow::Result<()> {
     application.load_udf_tests_modules().await?;
 
     let (mut pause, pause_client) = PauseController::new(["retry_mutation_loop_start"]);
-    let fut1 = insert_and_count(&application, pause_client);
+    let fut1 = insert_and_count(&application, pause_client, None);
     let fut2 = async {
         for i in 0..*UDF_EXECUTOR_OCC_MAX_RETRIES + 1 {
             let mut guard = pause
@@ -131,7 +134,7 @@ async fn test_mutation_occ_success(rt: TestRuntime) -> anyhow::Result<()> {
             if i < *UDF_EXECUTOR_OCC_MAX_RETRIES {
                 // Do an entire mutation while we're paused - to create an OCC conflict on
                 // the original insertion.
-                let count = insert_and_count(&application, PauseClient::new()).await?;
+                let count = insert_and_count(&application, PauseClient::new(), None).await?;
                 assert_eq!(count, i + 1);
             }
 
@@ -153,10 +156,10 @@ async fn test_multiple_inserts_dont_occ(rt: TestRuntime) -> anyhow::Result<()> {
     application.load_udf_tests_modules().await?;
 
     // Insert an object to create the table (otherwise it'll OCC on table creation).
-    insert_object(&application, PauseClient::new()).await?;
+    insert_object(&application, PauseClient::new(), None).await?;
 
     let (mut pause, pause_client) = PauseController::new(["retry_mutation_loop_start"]);
-    let fut1 = insert_object(&application, pause_client);
+    let fut1 = insert_object(&application, pause_client, None);
     let fut2 = async {
         let mut guard = pause
             .wait_for_blocked("retry_mutation_loop_start")
@@ -165,7 +168,7 @@ async fn test_multiple_inserts_dont_occ(rt: TestRuntime) -> anyhow::Result<()> {
 
         // Do several entire mutations while we're paused. Shouldn't OCC.
         for _ in 0..5 {
-            let result = insert_object(&application, PauseClient::new()).await?;
+            let result = insert_object(&application, PauseClient::new(), None).await?;
             assert_eq!(result["an"], "object");
         }
 
@@ -176,3 +179,45 @@ async fn test_multiple_inserts_dont_occ(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(result["an"], "object");
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_mutation_idempotency_key(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let key = || Some(MutationIdentifier::IdempotencyKey("retry-me".to_string()));
+    let count = insert_and_count(&application, PauseClient::new(), key()).await?;
+    assert_eq!(count, 1);
+
+    // Retrying with the same key returns the stored result without inserting.
+    let count = insert_and_count(&application, PauseClient::new(), key()).await?;
+    assert_eq!(count, 1);
+    let count = insert_and_count(&application, PauseClient::new(), None).await?;
+    assert_eq!(count, 2);
+
+    // The key is scoped to the function, so using it for another one runs it.
+    let result = insert_object(&application, PauseClient::new(), key()).await?;
+    assert_eq!(result["an"], "object");
+
+    // Retrying with the same key but different arguments is rejected.
+    let err = application
+        .mutation_udf(
+            RequestId::new(),
+            PublicFunctionPath::Component(CanonicalizedComponentFunctionPath {
+                component: ComponentPath::test_user(),
+                udf_path: "basic:insertAndCount".parse()?,
+            }),
+            vec![json!({"an": "other object"})],
+            Identity::system(),
+            key(),
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+            PauseClient::new(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "IdempotencyKeyArgsMismatch");
+    assert!(err.is_bad_request());
+    Ok(())
+}
@@ -6,6 +6,7 @@ use std::{
 use common::{
     components::{
         CanonicalizedComponentFunctionPath,
+        ComponentId,
         ComponentPath,
         PublicFunctionPath,
     },
@@ -20,26 +21,45 @@ use common::{
 };
 use database::{
     BootstrapComponentsModel,
+    IndexModel,
+    SystemMetadataModel,
     TableModel,
     Transaction,
 };
-use errors::ErrorMetadata;
-use isolate::parse_udf_args;
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
+use isolate::{
+    parse_udf_args,
+    ActionCallbacks,
+};
 use keybroker::Identity;
 use model::{
     backend_state::{
         types::BackendState,
         BackendStateModel,
     },
+    initialize_application_system_table,
+    initialize_application_system_tables,
     scheduled_jobs::{
         types::ScheduledJobState,
+        ScheduledJobsTable,
         SchedulerModel,
+        SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY,
+        SCHEDULED_JOBS_TABLE,
+        SCHEDULED_JOB_DEAD_LETTERS_TABLE,
     },
+    DEFAULT_TABLE_NUMBERS,
 };
 use runtime::testing::TestRuntime;
 use serde_json::Value as JsonValue;
-use sync_types::CanonicalizedUdfPath;
+use sync_types::{
+    CanonicalizedUdfPath,
+    Timestamp,
+};
 use value::{
+    DeveloperDocumentId,
     ResolvedDocumentId,
     TableNamespace,
 };
@@ -56,6 +76,7 @@ use crate::{
         OBJECTS_TABLE_COMPONENT,
     },
     Application,
+    CancelJobsProgress,
 };
 
 fn insert_object_path() -> CanonicalizedComponentFunctionPath {
@@ -155,6 +176,239 @@ async fn test_scheduled_jobs_canceled(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_scheduled_jobs_canceled_in_range(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let path = insert_object_path();
+    let (_, component) = BootstrapComponentsModel::new(&mut tx)
+        .component_path_to_ids(path.component.clone())
+        .await?;
+    let mut model = SchedulerModel::new(&mut tx, component.into());
+    let now = rt.unix_timestamp();
+    let mut job_ids = vec![];
+    for delay in [0, 60, 120] {
+        let job_id = model
+            .schedule(
+                path.clone(),
+                parse_udf_args(&path.udf_path, vec![JsonValue::Object(Default::default())])?,
+                now + Duration::from_secs(delay),
+                ExecutionContext::new_for_test(),
+            )
+            .await?;
+        job_ids.push(job_id);
+    }
+
+    // Only the job scheduled in [now + 30s, now + 90s) is canceled.
+    let start_ts = (now + Duration::from_secs(30))
+        .as_system_time()
+        .try_into()?;
+    let end_ts = (now + Duration::from_secs(90))
+        .as_system_time()
+        .try_into()?;
+    let (count, cursor) = model
+        .cancel_all_in_range(Some(path), Some(start_ts), Some(end_ts), 10)
+        .await?;
+    assert_eq!(count, 1);
+    let canceled_ts: Timestamp = (now + Duration::from_secs(60))
+        .as_system_time()
+        .try_into()?;
+    assert_eq!(cursor, Some(canceled_ts));
+    let mut states = vec![];
+    for job_id in job_ids {
+        states.push(model.check_status(job_id).await?.unwrap());
+    }
+    assert_eq!(
+        states,
+        vec![
+            ScheduledJobState::Pending,
+            ScheduledJobState::Canceled,
+            ScheduledJobState::Pending,
+        ]
+    );
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_cancel_jobs_in_range_progress(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let path = insert_object_path();
+    let (_, component) = BootstrapComponentsModel::new(&mut tx)
+        .component_path_to_ids(path.component.clone())
+        .await?;
+    let mut model = SchedulerModel::new(&mut tx, component.into());
+    let now = rt.unix_timestamp();
+    for delay in [60, 120] {
+        model
+            .schedule(
+                path.clone(),
+                parse_udf_args(&path.udf_path, vec![JsonValue::Object(Default::default())])?,
+                now + Duration::from_secs(delay),
+                ExecutionContext::new_for_test(),
+            )
+            .await?;
+    }
+    application.commit_test(tx).await?;
+
+    let progress = application
+        .cancel_jobs_in_range(
+            component,
+            Some(path.clone()),
+            None,
+            None,
+            Some(1),
+            Identity::system(),
+        )
+        .await?;
+    let last_ts: Timestamp = (now + Duration::from_secs(120))
+        .as_system_time()
+        .try_into()?;
+    assert_eq!(
+        progress,
+        CancelJobsProgress {
+            num_canceled: 2,
+            cursor: Some(last_ts),
+            is_done: true,
+        }
+    );
+
+    // Resuming from the cursor finds nothing left to cancel.
+    let progress = application
+        .cancel_jobs_in_range(
+            component,
+            Some(path),
+            progress.cursor,
+            None,
+            Some(1),
+            Identity::system(),
+        )
+        .await?;
+    assert_eq!(
+        progress,
+        CancelJobsProgress {
+            num_canceled: 0,
+            cursor: None,
+            is_done: true,
+        }
+    );
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_schedule_from_action_with_dedup_key(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let runner = application.runner();
+    let schedule = |dedup_key: &str| {
+        runner.schedule_job(
+            Identity::system(),
+            ComponentId::test_user(),
+            insert_object_path(),
+            vec![JsonValue::Object(Default::default())],
+            rt.unix_timestamp() + Duration::from_secs(60),
+            ExecutionContext::new_for_test(),
+            Some(dedup_key.to_string()),
+        )
+    };
+    // Retrying with the same key returns the job that was already enqueued.
+    let job_id = schedule("handoff-1").await?;
+    assert_eq!(schedule("handoff-1").await?, job_id);
+    assert_ne!(schedule("handoff-2").await?, job_id);
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let jobs = SchedulerModel::new(&mut tx, TableNamespace::test_user())
+        .list()
+        .await?;
+    assert_eq!(jobs.len(), 2);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_dedup_key_index_backfilled_for_existing_deployment(
+    rt: TestRuntime,
+) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    // Simulate a deployment created before dedup keys existed.
+    let namespace = TableNamespace::test_user();
+    let mut tx = application.begin(Identity::system()).await?;
+    let index = IndexModel::new(&mut tx)
+        .enabled_index_metadata(namespace, &SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY)?
+        .unwrap();
+    SystemMetadataModel::new_global(&mut tx)
+        .delete(index.id())
+        .await?;
+    application.commit_test(tx).await?;
+
+    // Startup adds the missing index as backfilling. Until it's enabled,
+    // scheduling with a dedup key fails with a retryable error.
+    let mut tx = application.begin(Identity::system()).await?;
+    initialize_application_system_table(
+        &mut tx,
+        &ScheduledJobsTable,
+        namespace,
+        &DEFAULT_TABLE_NUMBERS,
+    )
+    .await?;
+    let err = SchedulerModel::new(&mut tx, namespace)
+        .schedule_with_dedup_key(
+            insert_object_path(),
+            parse_udf_args(
+                &insert_object_path().udf_path,
+                vec![JsonValue::Object(Default::default())],
+            )?,
+            rt.unix_timestamp(),
+            ExecutionContext::new_for_test(),
+            "handoff-1".to_string(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "DedupKeyIndexBackfilling");
+    application.commit_test(tx).await?;
+
+    // The index worker backfills the index and enables it.
+    let mut enabled = false;
+    for _ in 0..100 {
+        let mut tx = application.begin(Identity::system()).await?;
+        if IndexModel::new(&mut tx)
+            .enabled_index_metadata(namespace, &SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY)?
+            .is_some()
+        {
+            enabled = true;
+            break;
+        }
+        rt.wait(Duration::from_millis(100)).await;
+    }
+    assert!(enabled);
+
+    let job_id = application
+        .runner()
+        .schedule_job(
+            Identity::system(),
+            ComponentId::test_user(),
+            insert_object_path(),
+            vec![JsonValue::Object(Default::default())],
+            rt.unix_timestamp() + Duration::from_secs(60),
+            ExecutionContext::new_for_test(),
+            Some("handoff-1".to_string()),
+        )
+        .await?;
+    let mut tx = application.begin(Identity::system()).await?;
+    let job = SchedulerModel::new(&mut tx, namespace)
+        .get_by_dedup_key("handoff-1")
+        .await?
+        .unwrap();
+    assert_eq!(DeveloperDocumentId::from(job.id()), job_id);
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_scheduled_jobs_race_condition(rt: TestRuntime) -> anyhow::Result<()> {
     let application = Application::new_for_tests(&rt).await?;
@@ -382,3 +636,164 @@ async fn test_scheduled_job_retry(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(state, ScheduledJobState::Success);
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_scheduled_job_dead_letter_replay(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    // Fail a job, which should move it to the dead-letter table.
+    let mut tx = application.begin(Identity::system()).await?;
+    let (job_id, mut model) = create_scheduled_job(&rt, &mut tx, insert_object_path()).await?;
+    model
+        .complete(job_id, ScheduledJobState::Failed("boom".to_string()))
+        .await?;
+    application.commit_test(tx).await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut model = SchedulerModel::new(&mut tx, TableNamespace::test_user());
+    let dead_letters = model.list_dead_letters(None, 10).await?;
+    assert_eq!(dead_letters.len(), 1);
+    let dead_letter = &dead_letters[0];
+    assert_eq!(dead_letter.job_id, job_id.into());
+    assert_eq!(dead_letter.error, "boom");
+    assert_eq!(dead_letter.replayed_job_id, None);
+    let dead_letter_id = dead_letter.id().into();
+
+    // Replay it with its original arguments.
+    let job_ids = application
+        .replay_scheduled_job_dead_letters(
+            Identity::system(),
+            ComponentId::test_user(),
+            vec![(dead_letter_id, None)],
+            ExecutionContext::new_for_test(),
+        )
+        .await?;
+    assert_eq!(job_ids.len(), 1);
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut model = SchedulerModel::new(&mut tx, TableNamespace::test_user());
+    let dead_letters = model.list_dead_letters(None, 10).await?;
+    assert_eq!(dead_letters[0].replayed_job_id, Some(job_ids[0]));
+    let replayed = model
+        .list()
+        .await?
+        .into_iter()
+        .find(|job| DeveloperDocumentId::from(job.id()) == job_ids[0])
+        .unwrap();
+    assert_eq!(replayed.udf_args, dead_letters[0].udf_args);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_scheduled_job_dead_letter_replay_args(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let (job_id, mut model) = create_scheduled_job(&rt, &mut tx, insert_object_path()).await?;
+    model
+        .complete(job_id, ScheduledJobState::Failed("boom".to_string()))
+        .await?;
+    let dead_letter_id = model.list_dead_letters(None, 10).await?[0].id().into();
+    application.commit_test(tx).await?;
+
+    // `insertObject` has no args validator, so replacement arguments must have
+    // the same fields as the original `{ key: "value" }`.
+    let path = insert_object_path();
+    let bad_args = parse_udf_args(
+        &path.udf_path,
+        vec![serde_json::json!({ "other": "value" })],
+    )?;
+    let err = application
+        .replay_scheduled_job_dead_letters(
+            Identity::system(),
+            ComponentId::test_user(),
+            vec![(dead_letter_id, Some(bad_args))],
+            ExecutionContext::new_for_test(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "InvalidDeadLetterArgs");
+
+    let fixed_args = parse_udf_args(&path.udf_path, vec![serde_json::json!({ "key": "fixed" })])?;
+    let job_ids = application
+        .replay_scheduled_job_dead_letters(
+            Identity::system(),
+            ComponentId::test_user(),
+            vec![(dead_letter_id, Some(fixed_args))],
+            ExecutionContext::new_for_test(),
+        )
+        .await?;
+    assert_eq!(job_ids.len(), 1);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_list_dead_letters_by_udf_path(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let other_path = CanonicalizedComponentFunctionPath {
+        component: ComponentPath::test_user(),
+        udf_path: CanonicalizedUdfPath::from_str("basic:insertAndCount").unwrap(),
+    };
+    for path in [insert_object_path(), other_path, insert_object_path()] {
+        let mut tx = application.begin(Identity::system()).await?;
+        let (job_id, mut model) = create_scheduled_job(&rt, &mut tx, path).await?;
+        model
+            .complete(job_id, ScheduledJobState::Failed("boom".to_string()))
+            .await?;
+        application.commit_test(tx).await?;
+    }
+
+    let all = application
+        .list_scheduled_job_dead_letters(Identity::system(), ComponentId::test_user(), None, 10)
+        .await?;
+    assert_eq!(all.len(), 3);
+    // Most recently failed first.
+    assert!(all.windows(2).all(|w| w[0].failed_ts >= w[1].failed_ts));
+
+    let by_path = application
+        .list_scheduled_job_dead_letters(
+            Identity::system(),
+            ComponentId::test_user(),
+            Some(insert_object_path().udf_path),
+            1,
+        )
+        .await?;
+    assert_eq!(by_path.len(), 1);
+    assert_eq!(by_path[0].id(), all[0].id());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_dead_letter_table_created_for_existing_components(
+    rt: TestRuntime,
+) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_component_tests_modules("basic").await?;
+
+    // Simulate a component created before dead letters existed.
+    let mut tx = application.begin(Identity::system()).await?;
+    let namespace = tx
+        .table_mapping()
+        .namespaces_for_name(&SCHEDULED_JOBS_TABLE)
+        .into_iter()
+        .find(|namespace| *namespace != TableNamespace::Global)
+        .unwrap();
+    TableModel::new(&mut tx)
+        .delete_table(namespace, SCHEDULED_JOB_DEAD_LETTERS_TABLE.clone())
+        .await?;
+    application.commit_test(tx).await?;
+
+    initialize_application_system_tables(&application.database).await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    assert!(TableModel::new(&mut tx).table_exists(namespace, &SCHEDULED_JOB_DEAD_LETTERS_TABLE));
+    assert!(SchedulerModel::new(&mut tx, namespace)
+        .list_dead_letters(None, 10)
+        .await?
+        .is_empty());
+    Ok(())
+}
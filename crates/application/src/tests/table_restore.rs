@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+
+use common::{
+    query::{
+        Order,
+        Query,
+    },
+    types::MemberId,
+};
+use database::{
+    ResolvedQuery,
+    UserFacingModel,
+};
+use errors::ErrorMetadataAnyhowExt;
+use keybroker::{
+    AdminIdentity,
+    Identity,
+};
+use runtime::testing::TestRuntime;
+use sync_types::Timestamp;
+use value::{
+    assert_obj,
+    ConvexValue,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    table_restore::TableRestoreCounts,
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+fn new_admin_id() -> Identity {
+    Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
+        "test".to_string(),
+        MemberId(1),
+    ))
+}
+
+async fn table_values(
+    application: &Application<TestRuntime>,
+    table_name: &TableName,
+) -> anyhow::Result<BTreeSet<ConvexValue>> {
+    let mut tx = application.begin(new_admin_id()).await?;
+    let query = Query::full_table_scan(table_name.clone(), Order::Asc);
+    let mut query_stream = ResolvedQuery::new(&mut tx, TableNamespace::test_user(), query)?;
+    let mut values = BTreeSet::new();
+    while let Some(doc) = query_stream.next(&mut tx, None).await? {
+        values.insert(ConvexValue::Object(
+            doc.into_value().0.filter_system_fields(),
+        ));
+    }
+    Ok(values)
+}
+
+#[convex_macro::test_runtime]
+async fn test_restore_table(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    let table_name: TableName = "messages".parse()?;
+
+    let mut tx = application.begin(new_admin_id()).await?;
+    let mut model = UserFacingModel::new_root_for_test(&mut tx);
+    let edited_id = model
+        .insert(table_name.clone(), assert_obj!("body" => "hello"))
+        .await?;
+    let deleted_id = model
+        .insert(table_name.clone(), assert_obj!("body" => "goodbye"))
+        .await?;
+    let restore_ts = application.commit_test(tx).await?;
+    let values_at_restore_ts = table_values(&application, &table_name).await?;
+
+    let mut tx = application.begin(new_admin_id()).await?;
+    let mut model = UserFacingModel::new_root_for_test(&mut tx);
+    model
+        .replace(edited_id, assert_obj!("body" => "edited"))
+        .await?;
+    model.delete(deleted_id).await?;
+    model
+        .insert(table_name.clone(), assert_obj!("body" => "new"))
+        .await?;
+    application.commit_test(tx).await?;
+
+    let expected_counts = TableRestoreCounts {
+        inserted: 1,
+        updated: 1,
+        deleted: 1,
+    };
+    let preview = application
+        .preview_table_restore(
+            &new_admin_id(),
+            vec![table_name.clone()],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await?;
+    assert_eq!(preview.get(&table_name), Some(&expected_counts));
+
+    let restored = application
+        .restore_tables(
+            &new_admin_id(),
+            vec![table_name.clone()],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await?;
+    assert_eq!(restored.get(&table_name), Some(&expected_counts));
+    assert_eq!(
+        table_values(&application, &table_name).await?,
+        values_at_restore_ts
+    );
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_restore_table_created_after_ts(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    let table_name: TableName = "messages".parse()?;
+    let tx = application.begin(new_admin_id()).await?;
+    let restore_ts: Timestamp = *tx.begin_timestamp();
+    drop(tx);
+
+    let mut tx = application.begin(new_admin_id()).await?;
+    UserFacingModel::new_root_for_test(&mut tx)
+        .insert(table_name.clone(), assert_obj!("body" => "hello"))
+        .await?;
+    application.commit_test(tx).await?;
+
+    let err = application
+        .restore_tables(
+            &new_admin_id(),
+            vec![table_name],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "TableCreatedAfterRestoreTimestamp");
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_restore_table_again(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    let table_name: TableName = "messages".parse()?;
+
+    let mut tx = application.begin(new_admin_id()).await?;
+    let deleted_id = UserFacingModel::new_root_for_test(&mut tx)
+        .insert(table_name.clone(), assert_obj!("body" => "goodbye"))
+        .await?;
+    let restore_ts = application.commit_test(tx).await?;
+    let values_at_restore_ts = table_values(&application, &table_name).await?;
+
+    let mut tx = application.begin(new_admin_id()).await?;
+    UserFacingModel::new_root_for_test(&mut tx)
+        .delete(deleted_id)
+        .await?;
+    application.commit_test(tx).await?;
+
+    let restored = application
+        .restore_tables(
+            &new_admin_id(),
+            vec![table_name.clone()],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await?;
+    assert_eq!(
+        restored.get(&table_name),
+        Some(&TableRestoreCounts {
+            inserted: 1,
+            updated: 0,
+            deleted: 0,
+        })
+    );
+
+    // Running the restore again finds the reinserted document instead of
+    // inserting another copy.
+    let preview = application
+        .preview_table_restore(
+            &new_admin_id(),
+            vec![table_name.clone()],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await?;
+    assert_eq!(
+        preview.get(&table_name),
+        Some(&TableRestoreCounts::default())
+    );
+    let restored = application
+        .restore_tables(
+            &new_admin_id(),
+            vec![table_name.clone()],
+            TableNamespace::test_user(),
+            restore_ts,
+        )
+        .await?;
+    assert_eq!(
+        restored.get(&table_name),
+        Some(&TableRestoreCounts::default())
+    );
+    assert_eq!(
+        table_values(&application, &table_name).await?,
+        values_at_restore_ts
+    );
+    Ok(())
+}
@@ -0,0 +1,695 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    fmt::Write,
+};
+
+use common::{
+    schemas::validator::{
+        FieldValidator,
+        LiteralValidator,
+        ObjectValidator,
+        Validator,
+    },
+    types::UdfType,
+};
+use model::modules::{
+    function_validators::{
+        ArgsValidator,
+        ReturnsValidator,
+    },
+    module_versions::Visibility,
+};
+use value::IdentifierFieldName;
+
+use crate::spec::{
+    FunctionSpec,
+    FunctionSpecs,
+};
+
+const DERIVES: &str = "#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]";
+
+/// Generate typed Rust bindings for every public query, mutation and action
+/// in `specs`.
+///
+/// Each function module becomes a nested `pub mod`, containing an async
+/// function per Convex function that calls it through a
+/// [`convex::ConvexClient`], along with structs and enums for its arguments
+/// and return value. The generated code depends on the `anyhow`, `convex` and
+/// `serde` crates, and on `serde_bytes` if any function takes or returns
+/// bytes.
+pub fn generate(specs: &FunctionSpecs) -> anyhow::Result<String> {
+    let mut root = Module::default();
+    for function in &specs.functions {
+        // HTTP actions can't be called from a client.
+        if function.udf_type == UdfType::HttpAction || function.visibility != Visibility::Public {
+            continue;
+        }
+        let (module_path, function_name) = split_identifier(&function.identifier)?;
+        let mut module = &mut root;
+        for segment in module_path.split('/') {
+            module = module.children.entry(snake_case(segment)).or_default();
+        }
+        module.add_function(function, &module_path, &function_name);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "// @generated by convex_codegen. Do not edit.")?;
+    if let Some(ref url) = specs.url {
+        writeln!(out, "// Generated from the functions of {url}.")?;
+    }
+    for (name, module) in &root.children {
+        out.push('\n');
+        out.push_str("#[allow(missing_docs, dead_code, clippy::all)]\n");
+        module.write(&mut out, name, 0);
+    }
+    Ok(out)
+}
+
+/// Split `dir/messages.js:list` into `dir/messages` and `list`.
+fn split_identifier(identifier: &str) -> anyhow::Result<(String, String)> {
+    let (module_path, function_name) = match identifier.rsplit_once(':') {
+        Some((module_path, function_name)) => (module_path, function_name),
+        None => (identifier, "default"),
+    };
+    let module_path = module_path.strip_suffix(".js").unwrap_or(module_path);
+    anyhow::ensure!(
+        !module_path.is_empty() && !function_name.is_empty(),
+        "Invalid function identifier {identifier}"
+    );
+    Ok((module_path.to_string(), function_name.to_string()))
+}
+
+type ObjectFields = BTreeMap<IdentifierFieldName, FieldValidator>;
+
+#[derive(Default)]
+struct Module {
+    children: BTreeMap<String, Module>,
+    /// Generated functions and types, each indented as if at the top level.
+    items: Vec<String>,
+    type_names: BTreeSet<String>,
+}
+
+impl Module {
+    fn add_function(&mut self, spec: &FunctionSpec, module_path: &str, function_name: &str) {
+        let path = format!("{module_path}:{function_name}");
+        let prefix = pascal_case(function_name);
+
+        let udf_type = spec.udf_type.to_lowercase_string();
+        let (args_param, args_expr) = match spec.args {
+            ArgsValidator::Unvalidated => (
+                Some("std::collections::BTreeMap<String, convex::Value>".to_string()),
+                None,
+            ),
+            ArgsValidator::Validated(ObjectValidator(ref fields)) if fields.is_empty() => {
+                (None, None)
+            },
+            ArgsValidator::Validated(ObjectValidator(ref fields)) => {
+                let name = self.unique_type_name(&format!("{prefix}Args"));
+                self.add_struct(&name, fields, Some(format!("Arguments to `{path}`.")));
+                (Some(name), Some("args"))
+            },
+        };
+        let return_type = match spec.returns {
+            ReturnsValidator::Unvalidated => "convex::Value".to_string(),
+            ReturnsValidator::Validated(ref returns) => {
+                self.rust_type(returns, &format!("{prefix}Return"))
+            },
+        };
+
+        let mut item = String::new();
+        let _ = writeln!(item, "/// Call the {udf_type} `{path}`.");
+        let _ = writeln!(item, "pub async fn {}(", ident(&snake_case(function_name)));
+        let _ = writeln!(item, "    client: &mut convex::ConvexClient,");
+        if let Some(ref args_param) = args_param {
+            let _ = writeln!(item, "    args: {args_param},");
+        }
+        let _ = writeln!(item, ") -> anyhow::Result<{return_type}> {{");
+        let args = match (args_param, args_expr) {
+            (None, _) => "std::collections::BTreeMap::new()",
+            (Some(_), None) => "args",
+            (Some(_), Some(args)) => {
+                let _ = writeln!(
+                    item,
+                    "    let convex::Value::Object({args}) = convex::to_value(&{args})? else {{"
+                );
+                let _ = writeln!(
+                    item,
+                    "        unreachable!(\"Structs serialize to objects\")"
+                );
+                let _ = writeln!(item, "    }};");
+                args
+            },
+        };
+        let _ = writeln!(item, "    client.{udf_type}_as(\"{path}\", {args}).await");
+        item.push_str("}\n");
+        self.items.push(item);
+    }
+
+    /// The Rust type for values matching `validator`, generating structs and
+    /// enums named after `hint` as needed.
+    fn rust_type(&mut self, validator: &Validator, hint: &str) -> String {
+        match validator {
+            Validator::Null => "()".to_string(),
+            Validator::Float64 => "f64".to_string(),
+            Validator::Int64 => "i64".to_string(),
+            Validator::Boolean => "bool".to_string(),
+            Validator::String | Validator::Id(_) => "String".to_string(),
+            Validator::Bytes => "serde_bytes::ByteBuf".to_string(),
+            // Sets and maps have no serde equivalent in `convex::Value`'s
+            // encoding, so they're left untyped along with `v.any()`.
+            Validator::Set(_) | Validator::Map(..) | Validator::Any => "convex::Value".to_string(),
+            Validator::Literal(literal) => match literal {
+                LiteralValidator::String(_) => "String".to_string(),
+                LiteralValidator::Boolean(_) => "bool".to_string(),
+                LiteralValidator::Float64(_) => "f64".to_string(),
+                LiteralValidator::Int64(_) => "i64".to_string(),
+            },
+            Validator::Array(value) => {
+                format!("Vec<{}>", self.rust_type(value, &format!("{hint}Item")))
+            },
+            Validator::Record(_, values) => format!(
+                "std::collections::BTreeMap<String, {}>",
+                self.rust_type(values, &format!("{hint}Value"))
+            ),
+            Validator::Object(ObjectValidator(fields)) => {
+                let name = self.unique_type_name(hint);
+                self.add_struct(&name, fields, None);
+                name
+            },
+            Validator::Union(members) => self.union_type(members, hint),
+        }
+    }
+
+    fn union_type(&mut self, members: &[Validator], hint: &str) -> String {
+        let non_null: Vec<_> = members
+            .iter()
+            .filter(|member| **member != Validator::Null)
+            .cloned()
+            .collect();
+        if non_null.is_empty() {
+            return "()".to_string();
+        }
+        if non_null.len() < members.len() {
+            let inner = match &non_null[..] {
+                [member] => self.rust_type(member, hint),
+                _ => self.union_type(&non_null, hint),
+            };
+            return format!("Option<{inner}>");
+        }
+        if let [member] = members {
+            return self.rust_type(member, hint);
+        }
+
+        let string_literals: Option<Vec<&str>> = members
+            .iter()
+            .map(|member| match member {
+                Validator::Literal(LiteralValidator::String(s)) => Some(&s[..]),
+                _ => None,
+            })
+            .collect();
+        if let Some(literals) = string_literals {
+            let name = self.unique_type_name(hint);
+            let mut variant_names = BTreeSet::new();
+            let mut item = format!("{DERIVES}\npub enum {name} {{\n");
+            for literal in literals {
+                let variant = unique(&mut variant_names, variant_name(literal));
+                let _ = writeln!(item, "    #[serde(rename = {literal:?})]");
+                let _ = writeln!(item, "    {variant},");
+            }
+            item.push_str("}\n");
+            self.items.push(item);
+            return name;
+        }
+
+        if let Some((tag, variants)) = discriminated_union(members) {
+            let name = self.unique_type_name(hint);
+            let mut variant_names = BTreeSet::new();
+            let mut item = format!("{DERIVES}\n#[serde(tag = {tag:?})]\npub enum {name} {{\n");
+            for (literal, fields) in variants {
+                let variant = unique(&mut variant_names, variant_name(&literal));
+                let struct_name = self.unique_type_name(&format!("{name}{variant}"));
+                self.add_struct(&struct_name, &fields, None);
+                let _ = writeln!(item, "    #[serde(rename = {literal:?})]");
+                let _ = writeln!(item, "    {variant}({struct_name}),");
+            }
+            item.push_str("}\n");
+            self.items.push(item);
+            return name;
+        }
+
+        "convex::Value".to_string()
+    }
+
+    fn add_struct(&mut self, name: &str, fields: &ObjectFields, doc: Option<String>) {
+        let mut field_names = BTreeSet::new();
+        let mut body = String::new();
+        for (field, validator) in fields {
+            let field = &field[..];
+            let mut field_type = self.rust_type(
+                validator.validator(),
+                &format!("{name}{}", pascal_case(field)),
+            );
+            let mut attributes = vec![];
+            let field_name = unique(&mut field_names, snake_case(field));
+            if field_name != field {
+                attributes.push(format!("rename = {field:?}"));
+            }
+            if validator.optional() {
+                if !field_type.starts_with("Option<") {
+                    field_type = format!("Option<{field_type}>");
+                }
+                attributes.push("default".to_string());
+                attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+            }
+            if !attributes.is_empty() {
+                let _ = writeln!(body, "    #[serde({})]", attributes.join(", "));
+            }
+            let _ = writeln!(body, "    pub {}: {field_type},", ident(&field_name));
+        }
+
+        let mut item = String::new();
+        if let Some(doc) = doc {
+            let _ = writeln!(item, "/// {doc}");
+        }
+        let _ = writeln!(item, "{DERIVES}");
+        if body.is_empty() {
+            let _ = writeln!(item, "pub struct {name} {{}}");
+        } else {
+            let _ = write!(item, "pub struct {name} {{\n{body}}}\n");
+        }
+        self.items.push(item);
+    }
+
+    fn unique_type_name(&mut self, hint: &str) -> String {
+        unique(&mut self.type_names, pascal_case(hint))
+    }
+
+    fn write(&self, out: &mut String, name: &str, depth: usize) {
+        let indent = "    ".repeat(depth);
+        let _ = writeln!(out, "{indent}pub mod {} {{", ident(name));
+        let mut first = true;
+        for (child_name, child) in &self.children {
+            if !first {
+                out.push('\n');
+            }
+            first = false;
+            child.write(out, child_name, depth + 1);
+        }
+        for item in &self.items {
+            if !first {
+                out.push('\n');
+            }
+            first = false;
+            for line in item.lines() {
+                if line.is_empty() {
+                    out.push('\n');
+                } else {
+                    let _ = writeln!(out, "{indent}    {line}");
+                }
+            }
+        }
+        let _ = writeln!(out, "{indent}}}");
+    }
+}
+
+/// If every member of a union is an object with a common string literal
+/// field, return that field and each member's literal and remaining fields.
+fn discriminated_union(members: &[Validator]) -> Option<(String, Vec<(String, ObjectFields)>)> {
+    let Validator::Object(ObjectValidator(first)) = members.first()? else {
+        return None;
+    };
+    'tags: for tag in first.keys() {
+        let mut variants = vec![];
+        let mut literals = BTreeSet::new();
+        for member in members {
+            let Validator::Object(ObjectValidator(fields)) = member else {
+                return None;
+            };
+            let Some(field) = fields.get(tag).filter(|field| !field.optional()) else {
+                continue 'tags;
+            };
+            let Validator::Literal(LiteralValidator::String(literal)) = field.validator() else {
+                continue 'tags;
+            };
+            let literal = literal.to_string();
+            if !literals.insert(literal.clone()) {
+                continue 'tags;
+            }
+            let mut fields = fields.clone();
+            fields.remove(tag);
+            variants.push((literal, fields));
+        }
+        return Some((tag.to_string(), variants));
+    }
+    None
+}
+
+fn unique(names: &mut BTreeSet<String>, name: String) -> String {
+    let mut candidate = name.clone();
+    let mut i = 2;
+    while names.contains(&candidate) {
+        candidate = format!("{name}{i}");
+        i += 1;
+    }
+    names.insert(candidate.clone());
+    candidate
+}
+
+fn words(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let chars: Vec<char> = s.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_ascii_uppercase()
+            && match prev {
+                Some(prev) if prev.is_ascii_lowercase() || prev.is_ascii_digit() => true,
+                // The end of an acronym, like the `P` in `HTTPServer`.
+                Some(prev) if prev.is_ascii_uppercase() => {
+                    matches!(next, Some(next) if next.is_ascii_lowercase())
+                },
+                _ => false,
+            };
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn snake_case(s: &str) -> String {
+    let name = words(s)
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match name.chars().next() {
+        None => "_".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    }
+}
+
+fn pascal_case(s: &str) -> String {
+    words(s)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn variant_name(literal: &str) -> String {
+    let name = pascal_case(literal);
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("V{name}"),
+    }
+}
+
+/// Escape `name` if it's a Rust keyword.
+fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let",
+        "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+        "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+        "virtual", "where", "while", "yield",
+    ];
+    match name {
+        // These can't be raw identifiers.
+        "self" | "Self" | "super" | "crate" | "_" => format!("{name}_"),
+        name if KEYWORDS.contains(&name) => format!("r#{name}"),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        object_validator,
+        schemas::validator::{
+            FieldValidator,
+            Validator,
+        },
+        types::UdfType,
+    };
+    use model::modules::{
+        function_validators::{
+            ArgsValidator,
+            ReturnsValidator,
+        },
+        module_versions::Visibility,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{
+        generate,
+        ident,
+        pascal_case,
+        snake_case,
+    };
+    use crate::spec::{
+        FunctionSpec,
+        FunctionSpecs,
+    };
+
+    fn field(field_type: serde_json::Value, optional: bool) -> serde_json::Value {
+        json!({ "fieldType": field_type, "optional": optional })
+    }
+
+    fn string_literal(value: &str) -> serde_json::Value {
+        json!({ "type": "literal", "value": value })
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(snake_case("sendMessage"), "send_message");
+        assert_eq!(snake_case("_creationTime"), "creation_time");
+        assert_eq!(snake_case("HTTPServer"), "http_server");
+        assert_eq!(snake_case("2fa"), "_2fa");
+        assert_eq!(pascal_case("send_message"), "SendMessage");
+        assert_eq!(pascal_case("in progress"), "InProgress");
+        assert_eq!(ident("type"), "r#type");
+        assert_eq!(ident("self"), "self_");
+        assert_eq!(ident("body"), "body");
+    }
+
+    #[test]
+    fn test_generate_functions() -> anyhow::Result<()> {
+        let specs = FunctionSpecs::from_json(json!([
+            {
+                "identifier": "messages.js:list",
+                "functionType": "Query",
+                "visibility": { "kind": "public" },
+                "args": { "type": "object", "value": {} },
+                "returns": {
+                    "type": "array",
+                    "value": {
+                        "type": "object",
+                        "value": {
+                            "_id": field(json!({ "type": "id", "tableName": "messages" }), false),
+                            "body": field(json!({ "type": "string" }), false),
+                        },
+                    },
+                },
+            },
+            {
+                "identifier": "messages.js:send",
+                "functionType": "Mutation",
+                "visibility": { "kind": "public" },
+                "args": {
+                    "type": "object",
+                    "value": {
+                        "body": field(json!({ "type": "string" }), false),
+                        "replyTo": field(json!({ "type": "id", "tableName": "messages" }), true),
+                    },
+                },
+                "returns": { "type": "null" },
+            },
+            {
+                "identifier": "messages.js:purge",
+                "functionType": "Mutation",
+                "visibility": { "kind": "internal" },
+            },
+            {
+                "identifier": "admin/jobs.js:default",
+                "functionType": "Action",
+                "visibility": { "kind": "public" },
+                "args": { "type": "any" },
+            },
+            { "functionType": "HttpAction", "method": "GET", "path": "/health" },
+        ]))?;
+        let expected = r#"// @generated by convex_codegen. Do not edit.
+
+#[allow(missing_docs, dead_code, clippy::all)]
+pub mod admin {
+    pub mod jobs {
+        /// Call the action `admin/jobs:default`.
+        pub async fn default(
+            client: &mut convex::ConvexClient,
+            args: std::collections::BTreeMap<String, convex::Value>,
+        ) -> anyhow::Result<convex::Value> {
+            client.action_as("admin/jobs:default", args).await
+        }
+    }
+}
+
+#[allow(missing_docs, dead_code, clippy::all)]
+pub mod messages {
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    pub struct ListReturnItem {
+        #[serde(rename = "_id")]
+        pub id: String,
+        pub body: String,
+    }
+
+    /// Call the query `messages:list`.
+    pub async fn list(
+        client: &mut convex::ConvexClient,
+    ) -> anyhow::Result<Vec<ListReturnItem>> {
+        client.query_as("messages:list", std::collections::BTreeMap::new()).await
+    }
+
+    /// Arguments to `messages:send`.
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    pub struct SendArgs {
+        pub body: String,
+        #[serde(rename = "replyTo", default, skip_serializing_if = "Option::is_none")]
+        pub reply_to: Option<String>,
+    }
+
+    /// Call the mutation `messages:send`.
+    pub async fn send(
+        client: &mut convex::ConvexClient,
+        args: SendArgs,
+    ) -> anyhow::Result<()> {
+        let convex::Value::Object(args) = convex::to_value(&args)? else {
+            unreachable!("Structs serialize to objects")
+        };
+        client.mutation_as("messages:send", args).await
+    }
+}
+"#;
+        assert_eq!(generate(&specs)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_unions() -> anyhow::Result<()> {
+        let specs = FunctionSpecs::from_json(json!([
+            {
+                "identifier": "tasks.js:get",
+                "functionType": "Query",
+                "visibility": { "kind": "public" },
+                "args": {
+                    "type": "object",
+                    "value": {
+                        "status": field(json!({
+                            "type": "union",
+                            "value": [string_literal("todo"), string_literal("in progress")],
+                        }), false),
+                    },
+                },
+                "returns": {
+                    "type": "union",
+                    "value": [
+                        { "type": "null" },
+                        {
+                            "type": "union",
+                            "value": [
+                                {
+                                    "type": "object",
+                                    "value": {
+                                        "kind": field(string_literal("text"), false),
+                                        "body": field(json!({ "type": "string" }), false),
+                                    },
+                                },
+                                {
+                                    "type": "object",
+                                    "value": {
+                                        "kind": field(string_literal("image"), false),
+                                        "data": field(json!({ "type": "bytes" }), false),
+                                    },
+                                },
+                            ],
+                        },
+                    ],
+                },
+            },
+        ]))?;
+        let generated = generate(&specs)?;
+        assert!(generated.contains(
+            r#"    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    pub enum GetArgsStatus {
+        #[serde(rename = "todo")]
+        Todo,
+        #[serde(rename = "in progress")]
+        InProgress,
+    }
+"#
+        ));
+        assert!(generated.contains(
+            r#"    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    #[serde(tag = "kind")]
+    pub enum GetReturn {
+        #[serde(rename = "text")]
+        Text(GetReturnText),
+        #[serde(rename = "image")]
+        Image(GetReturnImage),
+    }
+"#
+        ));
+        assert!(generated.contains("        pub data: serde_bytes::ByteBuf,\n"));
+        assert!(generated.contains(") -> anyhow::Result<Option<GetReturn>> {\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_from_validators() -> anyhow::Result<()> {
+        let specs = FunctionSpecs {
+            url: None,
+            functions: vec![FunctionSpec {
+                identifier: "counters.js:add".to_string(),
+                udf_type: UdfType::Mutation,
+                visibility: Visibility::Public,
+                args: ArgsValidator::Validated(object_validator!(
+                    "by" => FieldValidator::required_field_type(Validator::Int64),
+                    "tags" => FieldValidator::optional_field_type(
+                        Validator::Set(Box::new(Validator::String))
+                    ),
+                )),
+                returns: ReturnsValidator::Validated(Validator::Record(
+                    Box::new(Validator::String),
+                    Box::new(Validator::Float64),
+                )),
+            }],
+        };
+        let generated = generate(&specs)?;
+        assert!(generated.contains("        pub by: i64,\n"));
+        assert!(generated.contains("        pub tags: Option<convex::Value>,\n"));
+        assert!(
+            generated.contains(") -> anyhow::Result<std::collections::BTreeMap<String, f64>> {\n")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+//! # Convex Codegen
+//! Generates typed Rust bindings for the functions of a
+//! [Convex](https://convex.dev) deployment, so calling a function with the
+//! wrong arguments or misreading its result is a compile error rather than a
+//! runtime one.
+//!
+//! Bindings are generated from the argument and return validators the backend
+//! records for each public query, mutation and action when it analyzes your
+//! modules (see `model::modules::function_validators`), parsed exactly as the
+//! backend parses them. Functions without validators take and return
+//! [`convex::Value`]s.
+//!
+//! # Usage
+//! Save the function specs of your deployment with
+//! `npx convex function-spec --file`, or fetch them with
+//! [`fetch_function_specs`], and generate bindings from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() -> anyhow::Result<()> {
+//!     println!("cargo:rerun-if-changed=function_spec.json");
+//!     let out_dir = std::env::var("OUT_DIR")?;
+//!     convex_codegen::generate_from_file("function_spec.json", format!("{out_dir}/convex_api.rs"))
+//! }
+//! ```
+//!
+//! Then include them in your crate and call functions through a
+//! [`convex::ConvexClient`]:
+//!
+//! ```ignore
+//! mod api {
+//!     include!(concat!(env!("OUT_DIR"), "/convex_api.rs"));
+//! }
+//!
+//! let mut client = convex::ConvexClient::new(deployment_url).await?;
+//! let messages = api::messages::list(&mut client).await?;
+//! ```
+//!
+//! The `convex_codegen` binary does the same from the command line, writing
+//! the bindings to a file you check in.
+//!
+//! The generated code depends on the `anyhow`, `convex` and `serde` crates,
+//! and on `serde_bytes` if any function takes or returns bytes.
+
+#![warn(missing_docs)]
+#![warn(rustdoc::missing_crate_level_docs)]
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+};
+
+use anyhow::Context;
+use convex::{
+    ConvexHttpClient,
+    FunctionResult,
+};
+
+mod generate;
+mod spec;
+
+pub use generate::generate;
+pub use spec::{
+    FunctionSpec,
+    FunctionSpecs,
+};
+
+/// Read function specs from the JSON file at `input`, and write bindings for
+/// them to `output`. The output is only rewritten if it changed, to avoid
+/// needless rebuilds.
+pub fn generate_from_file(input: impl AsRef<Path>, output: impl AsRef<Path>) -> anyhow::Result<()> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let json =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let specs = FunctionSpecs::from_json(serde_json::from_str(&json)?)?;
+    write_if_changed(output, &generate(&specs)?)
+}
+
+/// Fetch the function specs of the deployment at `deployment_url`. Requires
+/// a deploy key, which you can get from the deployment settings page of the
+/// Convex dashboard.
+pub async fn fetch_function_specs(
+    deployment_url: &str,
+    deploy_key: String,
+) -> anyhow::Result<FunctionSpecs> {
+    let mut client = ConvexHttpClient::new(deployment_url)?;
+    client.set_admin_auth(deploy_key, None)?;
+    let result = client
+        .query("_system/cli/modules:apiSpec", BTreeMap::new())
+        .await?;
+    let functions = match result {
+        FunctionResult::Value(value) => serde_json::Value::from(value),
+        FunctionResult::ErrorMessage(message) => {
+            anyhow::bail!("Failed to fetch function specs: {message}")
+        },
+        FunctionResult::ConvexError(error) => {
+            anyhow::bail!("Failed to fetch function specs: {}", error.message)
+        },
+    };
+    let mut specs = FunctionSpecs::from_json(functions)?;
+    specs.url = Some(deployment_url.to_string());
+    Ok(specs)
+}
+
+/// Write `contents` to `path`, unless it already has those contents.
+fn write_if_changed(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if fs::read_to_string(path).ok().as_deref() == Some(contents) {
+        return Ok(());
+    }
+    fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,55 @@
+use std::{
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use clap::Parser;
+use convex_codegen::{
+    fetch_function_specs,
+    generate,
+    FunctionSpecs,
+};
+
+/// Generate typed Rust bindings for the public functions of a Convex
+/// deployment.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// A JSON file written by `npx convex function-spec --file`.
+    #[arg(long, conflicts_with = "url", required_unless_present = "url")]
+    spec: Option<PathBuf>,
+
+    /// The URL of the deployment to fetch function specs from.
+    #[arg(long, requires = "deploy_key")]
+    url: Option<String>,
+
+    /// The deploy key of the deployment at `--url`.
+    #[arg(long, env = "CONVEX_DEPLOY_KEY", hide_env_values = true)]
+    deploy_key: Option<String>,
+
+    /// Where to write the bindings. Defaults to stdout.
+    #[arg(long, short)]
+    out: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let specs = match (args.spec, args.url, args.deploy_key) {
+        (Some(path), ..) => {
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            FunctionSpecs::from_json(serde_json::from_str(&json)?)?
+        },
+        (None, Some(url), Some(deploy_key)) => fetch_function_specs(&url, deploy_key).await?,
+        _ => anyhow::bail!("Pass either --spec or --url and --deploy-key"),
+    };
+    let bindings = generate(&specs)?;
+    match args.out {
+        Some(path) => fs::write(&path, bindings)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{bindings}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use anyhow::Context;
+use common::types::UdfType;
+use model::modules::{
+    function_validators::{
+        ArgsValidator,
+        ReturnsValidator,
+    },
+    module_versions::{
+        AnalyzedFunction,
+        Visibility,
+    },
+};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+/// The functions of a deployment, as returned by `npx convex function-spec`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionSpecs {
+    /// The deployment the specs were read from, if known.
+    pub url: Option<String>,
+    /// Every query, mutation and action in the deployment.
+    pub functions: Vec<FunctionSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FunctionSpecsJson {
+    Functions(Vec<FunctionSpecJson>),
+    WithUrl {
+        url: Option<String>,
+        functions: Vec<FunctionSpecJson>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionSpecJson {
+    identifier: Option<String>,
+    function_type: String,
+    visibility: Option<Visibility>,
+    args: Option<JsonValue>,
+    returns: Option<JsonValue>,
+}
+
+impl FunctionSpecs {
+    /// Parse the output of `npx convex function-spec`, or the bare list of
+    /// functions returned by the deployment. The validators are parsed the
+    /// same way the backend parses them when analyzing modules.
+    pub fn from_json(json: JsonValue) -> anyhow::Result<Self> {
+        let (url, functions) =
+            match serde_json::from_value(json).context("Invalid function spec")? {
+                FunctionSpecsJson::Functions(functions) => (None, functions),
+                FunctionSpecsJson::WithUrl { url, functions } => (url, functions),
+            };
+        let mut specs = Self {
+            url,
+            functions: vec![],
+        };
+        for function in functions {
+            let udf_type: UdfType = function.function_type.parse()?;
+            // HTTP actions can't be called from a client, so no bindings are
+            // generated for them.
+            if udf_type == UdfType::HttpAction {
+                continue;
+            }
+            let identifier = function
+                .identifier
+                .context("Function spec is missing an identifier")?;
+            let args = match function.args {
+                Some(args) => ArgsValidator::try_from(args)
+                    .with_context(|| format!("Invalid args validator for {identifier}"))?,
+                None => ArgsValidator::Unvalidated,
+            };
+            let returns = match function.returns {
+                Some(returns) => ReturnsValidator::try_from(returns)
+                    .with_context(|| format!("Invalid returns validator for {identifier}"))?,
+                None => ReturnsValidator::Unvalidated,
+            };
+            specs.functions.push(FunctionSpec {
+                identifier,
+                udf_type,
+                visibility: function.visibility.unwrap_or(Visibility::Public),
+                args,
+                returns,
+            });
+        }
+        Ok(specs)
+    }
+}
+
+/// The signature of a query, mutation or action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionSpec {
+    /// The path of the function, e.g. `messages.js:list`.
+    pub identifier: String,
+    /// Whether the function is a query, mutation or action.
+    pub udf_type: UdfType,
+    /// Whether the function can be called from clients.
+    pub visibility: Visibility,
+    /// The validator of the function's arguments.
+    pub args: ArgsValidator,
+    /// The validator of the function's return value.
+    pub returns: ReturnsValidator,
+}
+
+impl FunctionSpec {
+    /// The spec of a function the backend analyzed in the module at
+    /// `module_path`, e.g. `messages.js`.
+    pub fn from_analyzed(module_path: &str, function: &AnalyzedFunction) -> Self {
+        Self {
+            identifier: format!("{module_path}:{}", function.name),
+            udf_type: function.udf_type,
+            visibility: function.visibility.clone().unwrap_or(Visibility::Public),
+            args: function.args.clone(),
+            returns: function.returns.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        object_validator,
+        schemas::validator::{
+            FieldValidator,
+            Validator,
+        },
+        types::UdfType,
+    };
+    use model::modules::{
+        function_validators::{
+            ArgsValidator,
+            ReturnsValidator,
+        },
+        module_versions::Visibility,
+    };
+    use serde_json::json;
+
+    use super::{
+        FunctionSpec,
+        FunctionSpecs,
+    };
+
+    #[test]
+    fn test_parse_function_specs() -> anyhow::Result<()> {
+        let functions = json!([
+            {
+                "identifier": "messages.js:send",
+                "functionType": "Mutation",
+                "visibility": { "kind": "public" },
+                "args": {
+                    "type": "object",
+                    "value": {
+                        "body": { "fieldType": { "type": "string" }, "optional": false },
+                    },
+                },
+                "returns": { "type": "id", "tableName": "messages" },
+            },
+            { "functionType": "HttpAction", "method": "GET", "path": "/health" },
+        ]);
+        let specs = FunctionSpecs::from_json(functions.clone())?;
+        assert_eq!(specs.url, None);
+        assert_eq!(
+            specs.functions,
+            vec![FunctionSpec {
+                identifier: "messages.js:send".to_string(),
+                udf_type: UdfType::Mutation,
+                visibility: Visibility::Public,
+                args: ArgsValidator::Validated(object_validator!(
+                    "body" => FieldValidator::required_field_type(Validator::String),
+                )),
+                returns: ReturnsValidator::Validated(Validator::Id("messages".parse()?)),
+            }]
+        );
+
+        let specs = FunctionSpecs::from_json(json!({
+            "url": "https://cool-music-123.convex.cloud",
+            "functions": functions,
+        }))?;
+        assert_eq!(
+            specs.url.as_deref(),
+            Some("https://cool-music-123.convex.cloud")
+        );
+        assert_eq!(specs.functions.len(), 1);
+
+        // Validators the backend would reject are rejected here too.
+        let invalid = json!([{
+            "identifier": "messages.js:send",
+            "functionType": "Mutation",
+            "args": { "type": "string" },
+        }]);
+        assert!(FunctionSpecs::from_json(invalid).is_err());
+        Ok(())
+    }
+}
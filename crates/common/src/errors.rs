@@ -27,6 +27,7 @@ use regex::Regex;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use sourcemap::SourceMap;
+use sync_types::QueryErrorCode;
 use url::Url;
 use value::{
     heap_size::{
@@ -488,6 +489,14 @@ impl JsError {
         }
     }
 
+    /// The code clients use to recover from this error when it fails a
+    /// query, if any.
+    pub fn query_error_code(&self) -> Option<QueryErrorCode> {
+        self.message
+            .contains(&format!("{INVALID_CURSOR_ERROR}: "))
+            .then_some(QueryErrorCode::InvalidCursor)
+    }
+
     pub fn convex_error(message: String, data: ConvexValue) -> Self {
         Self {
             message,
@@ -649,6 +658,10 @@ pub fn lease_lost_error() -> anyhow::Error {
 }
 
 pub const AUTH_ERROR: &str = "AuthError";
+/// Short message of the error from running a paginated query with a cursor
+/// from a different query. The error's message starts with it too, so it can
+/// be recognized after passing through JavaScript as a plain `Error`.
+pub const INVALID_CURSOR_ERROR: &str = "InvalidCursor";
 pub const TIMEOUT_ERROR_MESSAGE: &str = "Your request timed out.";
 
 #[cfg(test)]
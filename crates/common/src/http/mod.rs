@@ -1021,6 +1021,50 @@ where
     }
 }
 
+#[allow(clippy::declare_interior_mutable_const)]
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// The client-provided `Idempotency-Key` header, if there is one.
+pub struct ExtractIdempotencyKey(pub Option<String>);
+
+fn idempotency_key_from_req_parts(
+    parts: &axum::http::request::Parts,
+) -> anyhow::Result<Option<String>> {
+    let Some(header) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = header.to_str()?;
+    anyhow::ensure!(!key.is_empty(), "Idempotency key must not be empty");
+    anyhow::ensure!(
+        key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH,
+        "Idempotency key must be at most {MAX_IDEMPOTENCY_KEY_LENGTH} characters"
+    );
+    Ok(Some(key.to_string()))
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ExtractIdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpResponseError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let key = idempotency_key_from_req_parts(parts).map_err(|e| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidIdempotencyKey",
+                e.to_string(),
+            ))
+        })?;
+        Ok(Self(key))
+    }
+}
+
 async fn log_middleware(
     remote_addr: Option<axum::extract::ConnectInfo<SocketAddr>>,
     ExtractResolvedHost(resolved_host): ExtractResolvedHost,
@@ -1093,6 +1137,7 @@ pub async fn cli_cors() -> CorsLayer {
             REFERER,
             USER_AGENT,
             CONVEX_CLIENT_HEADER,
+            IDEMPOTENCY_KEY_HEADER,
         ])
         .allow_credentials(true)
         .allow_methods(vec![
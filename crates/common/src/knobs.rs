@@ -229,6 +229,11 @@ pub static TRANSACTION_MAX_NUM_SCHEDULED: LazyLock<usize> =
 pub static MAX_JOBS_CANCEL_BATCH: LazyLock<usize> =
     LazyLock::new(|| env_config("MAX_JOBS_CANCEL_BATCH", 1000));
 
+/// Maximum number of batches of scheduled jobs to cancel in a single
+/// `/cancel_jobs_in_range` request. Callers resume with the returned cursor.
+pub static MAX_JOBS_CANCEL_BATCHES_PER_REQUEST: LazyLock<usize> =
+    LazyLock::new(|| env_config("MAX_JOBS_CANCEL_BATCHES_PER_REQUEST", 10));
+
 /// Maximum size of the arguments to a scheduled function.
 pub static TRANSACTION_MAX_SCHEDULED_TOTAL_ARGUMENT_SIZE_BYTES: LazyLock<usize> =
     LazyLock::new(|| {
@@ -245,6 +250,12 @@ pub static TRANSACTION_MAX_SCHEDULED_TOTAL_ARGUMENT_SIZE_BYTES: LazyLock<usize>
 pub static SCHEDULED_JOB_EXECUTION_PARALLELISM: LazyLock<usize> =
     LazyLock::new(|| env_config("SCHEDULED_JOB_EXECUTION_PARALLELISM", 10));
 
+/// Most runs of a cron with the `allowConcurrent` overlap policy that can be
+/// in progress at once. Runs that come due while this many are still in
+/// progress are skipped.
+pub static CRON_MAX_CONCURRENT_RUNS: LazyLock<usize> =
+    LazyLock::new(|| env_config("CRON_MAX_CONCURRENT_RUNS", 10));
+
 /// Initial backoff in milliseconds on a system error from a scheduled job.
 pub static SCHEDULED_JOB_INITIAL_BACKOFF: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_millis(env_config("SCHEDULED_JOB_INITIAL_BACKOFF_MS", 10)));
@@ -283,10 +294,33 @@ pub static SCHEDULED_JOB_RETENTION: LazyLock<Duration> = LazyLock::new(|| {
     ))
 });
 
+/// How long dead letters for failed scheduled jobs are kept before getting
+/// garbage collected.
+pub static SCHEDULED_JOB_DEAD_LETTER_RETENTION: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "SCHEDULED_JOB_DEAD_LETTER_RETENTION",
+        60 * 60 * 24 * 30, // 30 days
+    ))
+});
+
 /// Maximum number of scheduled jobs to garbage collect in a single transaction
 pub static SCHEDULED_JOB_GARBAGE_COLLECTION_BATCH_SIZE: LazyLock<usize> =
     LazyLock::new(|| env_config("SCHEDULED_JOB_GARBAGE_COLLECTION_BATCH_SIZE", 1000));
 
+/// How long the result of a mutation run with an `Idempotency-Key` is kept.
+/// Retries with the same key within this window return the stored result.
+pub static IDEMPOTENCY_KEY_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "IDEMPOTENCY_KEY_TTL",
+        60 * 60 * 24, // 1 day
+    ))
+});
+
+/// Maximum number of expired idempotent requests to garbage collect in a
+/// single transaction.
+pub static IDEMPOTENT_REQUESTS_GARBAGE_COLLECTION_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("IDEMPOTENT_REQUESTS_GARBAGE_COLLECTION_BATCH_SIZE", 1000));
+
 /// Maximum number of syscalls that can run in a batch together when
 /// awaited in parallel. Higher values improve latency, while lower ones
 /// protect one isolate from hogging database connections.
@@ -590,6 +624,39 @@ pub static SEARCH_WORKER_PASSIVE_PAGES_PER_SECOND: LazyLock<NonZeroU32> = LazyLo
     )
 });
 
+/// Rate limit on pages of the document log read per second while building an
+/// incremental snapshot export. Each table's log is read up to three times,
+/// for its generated schema, its documents and its deleted IDs. The export
+/// fails with "Invalid row rate limit" if this times
+/// DEFAULT_DOCUMENTS_PAGE_SIZE doesn't fit in a u32.
+pub static INCREMENTAL_EXPORT_PAGES_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(|| {
+    env_config(
+        "INCREMENTAL_EXPORT_PAGES_PER_SECOND",
+        NonZeroU32::new(1000).unwrap(),
+    )
+});
+
+/// Pages of a table's document log that a point-in-time table restore, or its
+/// preview, reads per second to find the documents that changed since the
+/// restore timestamp. Lower it to take load off the database during a large
+/// restore; each page is DEFAULT_DOCUMENTS_PAGE_SIZE log entries.
+pub static TABLE_RESTORE_PAGES_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(|| {
+    env_config(
+        "TABLE_RESTORE_PAGES_PER_SECOND",
+        NonZeroU32::new(1000).unwrap(),
+    )
+});
+
+/// Maximum number of documents a point-in-time table restore writes in a
+/// single transaction.
+pub static TABLE_RESTORE_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("TABLE_RESTORE_BATCH_SIZE", 1000));
+
+/// Number of documents read from the table at a time while streaming a CSV
+/// export of a single table.
+pub static TABLE_CSV_EXPORT_PAGE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("TABLE_CSV_EXPORT_PAGE_SIZE", 1000));
+
 /// Default page size (in number of docuemnts) used when loading documents from
 /// the database for building a vector index.
 pub static VECTOR_INDEX_WORKER_PAGE_SIZE: LazyLock<usize> =
@@ -804,6 +871,17 @@ pub static MAX_CONCURRENT_ACTION_OPS: LazyLock<usize> =
 pub static SYNC_MAX_SEND_TRANSITION_COUNT: LazyLock<usize> =
     LazyLock::new(|| env_config("SYNC_MAX_SEND_TRANSITION_COUNT", 2));
 
+/// Query results larger than this many bytes are always sent in full, and
+/// aren't kept in memory to diff the query's next result against.
+pub static SYNC_QUERY_DIFF_MAX_VALUE_BYTES: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_QUERY_DIFF_MAX_VALUE_BYTES", 1 << 20));
+
+/// Maximum total size of the query results a sync connection keeps in memory
+/// to send diffs against. Once it's reached, new results are sent in full
+/// until queries holding earlier ones change or are removed.
+pub static SYNC_QUERY_DIFF_MAX_CONNECTION_BYTES: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_QUERY_DIFF_MAX_CONNECTION_BYTES", 8 << 20));
+
 /// Max Axiom sink attributes. This is a knob just in case a user actually hits
 /// the limit but has an Enterprise Axiom plan that lets them use more than the
 /// limit we've configured.
@@ -392,6 +392,28 @@ impl DatabaseSchema {
             })
     }
 
+    /// Checks a value that would be inserted into `table_name`, before it has
+    /// a document ID, e.g. when validating an import without writing it.
+    pub fn check_new_value(
+        &self,
+        value: &ConvexObject,
+        table_name: TableName,
+        table_mapping: &NamespacedTableMapping,
+        virtual_system_mapping: &VirtualSystemMapping,
+    ) -> Result<(), SchemaEnforcementError> {
+        if self.schema_validation
+            && let Some(document_schema) = self.schema_for_table(&table_name)
+        {
+            document_schema
+                .check_value(value, table_mapping, virtual_system_mapping)
+                .map_err(|validation_error| SchemaEnforcementError::Document {
+                    validation_error,
+                    table_name,
+                })?;
+        }
+        Ok(())
+    }
+
     fn contains_table_as_reference(&self, table_name: &TableName) -> Option<TableName> {
         for table_schema in self.tables.values() {
             if let Some(document_schema) = &table_schema.document_type {
@@ -1019,6 +1019,10 @@ impl FieldValidator {
         }
     }
 
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
+
     pub fn has_map_or_set(&self) -> bool {
         self.validator.has_map_or_set()
     }
@@ -0,0 +1,140 @@
+use convex_sync_types::ValueDiff;
+
+use crate::value::Value;
+
+/// Apply a diff from the server to the query result the client last received.
+/// Returns `false`, leaving `value` in an unspecified state, if the diff
+/// doesn't fit `value`.
+pub(super) fn apply_diff(value: &mut Value, diff: ValueDiff<Value>) -> bool {
+    match diff {
+        ValueDiff::Replace(new_value) => {
+            *value = new_value;
+            true
+        },
+        ValueDiff::Object { updated, removed } => {
+            let Value::Object(fields) = value else {
+                return false;
+            };
+            for field in removed {
+                if fields.remove(&field).is_none() {
+                    return false;
+                }
+            }
+            for (field, field_diff) in updated {
+                let applied = match (fields.get_mut(&field), field_diff) {
+                    (Some(field_value), field_diff) => apply_diff(field_value, field_diff),
+                    (None, ValueDiff::Replace(new_value)) => {
+                        fields.insert(field, new_value);
+                        true
+                    },
+                    (None, _) => false,
+                };
+                if !applied {
+                    return false;
+                }
+            }
+            true
+        },
+        ValueDiff::Array {
+            base_len,
+            start,
+            deleted,
+            inserted,
+        } => {
+            let Value::Array(items) = value else {
+                return false;
+            };
+            let (base_len, start, deleted) = (base_len as usize, start as usize, deleted as usize);
+            if items.len() != base_len || start + deleted > base_len {
+                return false;
+            }
+            let tail = items.split_off(start + deleted);
+            items.truncate(start);
+            items.extend(inserted);
+            items.extend(tail);
+            true
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convex_sync_types::ValueDiff;
+    use maplit::btreemap;
+
+    use super::apply_diff;
+    use crate::value::Value;
+
+    #[test]
+    fn test_apply_array_diff() {
+        let mut value = Value::Array(vec![1.into(), 2.into(), 3.into()]);
+        assert!(apply_diff(
+            &mut value,
+            ValueDiff::Array {
+                base_len: 3,
+                start: 1,
+                deleted: 1,
+                inserted: vec![4.into(), 5.into()],
+            }
+        ));
+        assert_eq!(
+            value,
+            Value::Array(vec![1.into(), 4.into(), 5.into(), 3.into()])
+        );
+
+        // The diff was computed against a list of a different length.
+        assert!(!apply_diff(
+            &mut value,
+            ValueDiff::Array {
+                base_len: 3,
+                start: 0,
+                deleted: 1,
+                inserted: vec![],
+            }
+        ));
+    }
+
+    #[test]
+    fn test_apply_object_diff() {
+        let mut value = Value::Object(btreemap! {
+            "count".into() => 1.into(),
+            "removed".into() => "x".into(),
+            "nested".into() => Value::Object(btreemap! { "a".into() => 1.into() }),
+        });
+        assert!(apply_diff(
+            &mut value,
+            ValueDiff::Object {
+                updated: btreemap! {
+                    "added".into() => ValueDiff::Replace("y".into()),
+                    "nested".into() => ValueDiff::Object {
+                        updated: btreemap! { "a".into() => ValueDiff::Replace(2.into()) },
+                        removed: vec![],
+                    },
+                },
+                removed: vec!["removed".into()],
+            }
+        ));
+        assert_eq!(
+            value,
+            Value::Object(btreemap! {
+                "count".into() => 1.into(),
+                "added".into() => "y".into(),
+                "nested".into() => Value::Object(btreemap! { "a".into() => 2.into() }),
+            })
+        );
+
+        // Diffing into a field that isn't an object.
+        assert!(!apply_diff(
+            &mut value,
+            ValueDiff::Object {
+                updated: btreemap! {
+                    "count".into() => ValueDiff::Object {
+                        updated: btreemap! {},
+                        removed: vec![],
+                    },
+                },
+                removed: vec![],
+            }
+        ));
+    }
+}
@@ -10,6 +10,7 @@ use std::{
         BTreeSet,
         VecDeque,
     },
+    mem,
 };
 
 use convex_sync_types::{
@@ -17,9 +18,11 @@ use convex_sync_types::{
     CanonicalizedUdfPath,
     ClientMessage,
     IdentityVersion,
+    QueryErrorCode,
     QueryId,
     QuerySetModification,
     QuerySetVersion,
+    SessionId,
     SessionRequestSeqNumber,
     StateModification,
     StateVersion,
@@ -51,6 +54,24 @@ pub use query_result::{
     FunctionResult,
     QueryResults,
 };
+mod optimistic_update;
+pub use optimistic_update::OptimisticLocalStore;
+use optimistic_update::OptimisticUpdate;
+mod outbox;
+pub use outbox::MutationOutbox;
+mod pagination;
+pub use pagination::{
+    PaginatedQuery,
+    PaginatedQueryResult,
+    PaginationStatus,
+};
+mod diff;
+use diff::apply_diff;
+mod query_group;
+pub use query_group::{
+    QueryGroup,
+    QueryGroupSnapshot,
+};
 
 use self::request_manager::RequestType;
 
@@ -262,6 +283,8 @@ impl LocalSyncState {
 struct RemoteQuerySet {
     version: StateVersion,
     remote_query_set: BTreeMap<QueryId, FunctionResult>,
+    /// The codes of failed queries whose errors the client can recover from.
+    error_codes: BTreeMap<QueryId, QueryErrorCode>,
 }
 
 impl RemoteQuerySet {
@@ -269,6 +292,7 @@ impl RemoteQuerySet {
         Self {
             version: StateVersion::initial(),
             remote_query_set: Default::default(),
+            error_codes: Default::default(),
         }
     }
 
@@ -303,6 +327,7 @@ impl RemoteQuerySet {
                     }
                     self.remote_query_set
                         .insert(query_id, FunctionResult::Value(value));
+                    self.error_codes.remove(&query_id);
                 },
                 StateModification::QueryFailed {
                     query_id,
@@ -310,6 +335,7 @@ impl RemoteQuerySet {
                     log_lines,
                     journal: _,
                     error_data,
+                    error_code,
                 } => {
                     for log_line in log_lines.0 {
                         convex_logs!("{}", log_line);
@@ -322,9 +348,34 @@ impl RemoteQuerySet {
                         None => FunctionResult::ErrorMessage(error_message),
                     };
                     self.remote_query_set.insert(query_id, function_result);
+                    match error_code {
+                        Some(code) => self.error_codes.insert(query_id, code),
+                        None => self.error_codes.remove(&query_id),
+                    };
                 },
                 StateModification::QueryRemoved { query_id } => {
                     self.remote_query_set.remove(&query_id);
+                    self.error_codes.remove(&query_id);
+                },
+                StateModification::QueryDiffed {
+                    query_id,
+                    diff,
+                    log_lines,
+                    journal: _,
+                } => {
+                    for log_line in log_lines.0 {
+                        convex_logs!("{}", log_line);
+                    }
+                    let Some(FunctionResult::Value(value)) =
+                        self.remote_query_set.get_mut(&query_id)
+                    else {
+                        tracing::error!("Received a diff for query {query_id:?} without a value");
+                        return Err(ReconnectProtocolReason::QueryDiffMismatch);
+                    };
+                    if !apply_diff(value, diff) {
+                        tracing::error!("Received a diff that doesn't apply to query {query_id:?}");
+                        return Err(ReconnectProtocolReason::QueryDiffMismatch);
+                    }
                 },
             }
         }
@@ -333,32 +384,59 @@ impl RemoteQuerySet {
     }
 }
 
-#[derive(Default, Debug)]
+/// The server's query results with the optimistic updates of in-flight
+/// mutations layered on top, in the order the mutations were sent.
+#[derive(Default)]
 struct OptimisticQueryResults {
     query_results: BTreeMap<QueryId, Query>,
+    optimistic_updates: Vec<(RequestId, OptimisticUpdate)>,
 }
 
 impl OptimisticQueryResults {
+    /// Replace the results with the server's, dropping the optimistic updates
+    /// of completed mutations and replaying the rest. Returns the queries whose
+    /// results changed, with `None` for queries that no longer have a result.
     fn ingest_query_results_from_server(
         &mut self,
+        query_set: &BTreeMap<QueryToken, LocalQuery>,
         server_query_results: BTreeMap<QueryId, Query>,
-        _optimistic_updates_to_drop: BTreeSet<RequestId>,
-    ) -> BTreeMap<QueryId, FunctionResult> {
-        // TODO: use optimistic_updates_to_drop
-        let old_query_results = self.query_results.clone();
-        self.query_results = server_query_results;
-        let mut changed_queries = BTreeMap::new();
-        for (query_id, query) in self.query_results.iter() {
-            let old_query = old_query_results.get(query_id);
-            if match old_query {
-                Some(old_query) => old_query.result != query.result,
-                None => true,
-            } {
-                let result = query.result.clone();
-                changed_queries.insert(*query_id, result);
-            }
+        optimistic_updates_to_drop: BTreeSet<RequestId>,
+    ) -> BTreeMap<QueryId, Option<FunctionResult>> {
+        self.optimistic_updates
+            .retain(|(request_id, _)| !optimistic_updates_to_drop.contains(request_id));
+        let old_query_results = mem::replace(&mut self.query_results, server_query_results);
+        let mut store = OptimisticLocalStore {
+            query_set,
+            query_results: &mut self.query_results,
+        };
+        for (_, update) in self.optimistic_updates.iter() {
+            update(&mut store);
         }
-        changed_queries
+        changed_queries(&old_query_results, &self.query_results)
+    }
+
+    /// Apply the optimistic update of a newly sent mutation. Returns the
+    /// queries whose results changed, as for
+    /// [`ingest_query_results_from_server`](Self::ingest_query_results_from_server).
+    fn apply_optimistic_update(
+        &mut self,
+        query_set: &BTreeMap<QueryToken, LocalQuery>,
+        request_id: RequestId,
+        update: OptimisticUpdate,
+    ) -> BTreeMap<QueryId, Option<FunctionResult>> {
+        let old_query_results = self.query_results.clone();
+        update(&mut OptimisticLocalStore {
+            query_set,
+            query_results: &mut self.query_results,
+        });
+        self.optimistic_updates.push((request_id, update));
+        changed_queries(&old_query_results, &self.query_results)
+    }
+
+    fn has_optimistic_update(&self, request_id: RequestId) -> bool {
+        self.optimistic_updates
+            .iter()
+            .any(|(update_request_id, _)| *update_request_id == request_id)
     }
 
     fn query_result(&self, query_id: QueryId) -> Option<FunctionResult> {
@@ -366,6 +444,28 @@ impl OptimisticQueryResults {
     }
 }
 
+fn changed_queries(
+    old_query_results: &BTreeMap<QueryId, Query>,
+    new_query_results: &BTreeMap<QueryId, Query>,
+) -> BTreeMap<QueryId, Option<FunctionResult>> {
+    let mut changed_queries = BTreeMap::new();
+    for (query_id, query) in new_query_results.iter() {
+        let old_query = old_query_results.get(query_id);
+        if match old_query {
+            Some(old_query) => old_query.result != query.result,
+            None => true,
+        } {
+            changed_queries.insert(*query_id, Some(query.result.clone()));
+        }
+    }
+    for query_id in old_query_results.keys() {
+        if !new_query_results.contains_key(query_id) {
+            changed_queries.insert(*query_id, None);
+        }
+    }
+    changed_queries
+}
+
 /// The synchronous state machine for the `ConvexClient`. It's recommended to
 /// use the higher level `ConvexClient` unless you are building a framework.
 ///
@@ -442,6 +542,7 @@ pub struct BaseConvexClient {
     next_request_id: SessionRequestSeqNumber,
     outgoing_message_queue: VecDeque<ClientMessage>,
     max_observed_timestamp: Option<Timestamp>,
+    outbox: Option<MutationOutbox>,
 }
 
 impl BaseConvexClient {
@@ -461,7 +562,95 @@ impl BaseConvexClient {
             next_request_id,
             outgoing_message_queue: VecDeque::new(),
             max_observed_timestamp: None,
+            outbox: None,
+        }
+    }
+
+    /// Construct a new [`BaseConvexClient`] that records mutations in
+    /// `outbox` until the server responds to them.
+    ///
+    /// Mutations left in the outbox by a previous process are added to the
+    /// outgoing message queue in their original order. Their results can't
+    /// be observed, since the receivers for them were lost with that process.
+    /// Connect with [`session_id`](Self::session_id()) so the server skips
+    /// any of them it already ran, and call
+    /// [`save_outbox`](Self::save_outbox()) before sending new mutations.
+    pub fn with_outbox(outbox: MutationOutbox) -> Self {
+        let mut client = Self::new();
+        client.next_request_id = outbox.next_request_id();
+        for message in outbox.pending() {
+            let ClientMessage::Mutation { request_id, .. } = *message else {
+                continue;
+            };
+            tracing::info!("Resending mutation with id {request_id} from outbox");
+            let _result = client.request_manager.track_request(
+                message,
+                RequestId::new(request_id),
+                RequestType::Mutation,
+            );
+            client.outgoing_message_queue.push_back(message.clone());
+        }
+        client.outbox = Some(outbox);
+        client
+    }
+
+    /// The session id to send in `ClientMessage::Connect`, if the client has
+    /// an outbox. It stays the same across reconnects and restarts so the
+    /// server can deduplicate resent mutations. Without an outbox, each
+    /// connection uses a new session.
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.outbox.as_ref().map(MutationOutbox::session_id)
+    }
+
+    /// Write mutations recorded since the last call to the outbox, if the
+    /// client has one. This must complete before the mutations are sent with
+    /// [`pop_next_message`](Self::pop_next_message()).
+    ///
+    /// Mutations that can't be saved fail, and are never sent. Returns the
+    /// new query results if that rolled back any optimistic updates.
+    pub async fn save_outbox(&mut self) -> Option<QueryResults> {
+        let (write, unsaved) = self.outbox.as_mut()?.take_write()?;
+        let Err(e) = write.write().await else {
+            return None;
+        };
+        tracing::error!("Failed to save mutations {unsaved:?} to outbox: {e:?}");
+        let outbox = self.outbox.as_mut()?;
+        for request_id in &unsaved {
+            outbox.remove(*request_id);
+        }
+        outbox.write_failed();
+        self.outgoing_message_queue.retain(|message| {
+            !matches!(
+                message,
+                ClientMessage::Mutation { request_id, .. } if unsaved.contains(request_id)
+            )
+        });
+        let mut rolled_back = BTreeSet::new();
+        for request_id in unsaved {
+            let request_id = RequestId::new(request_id);
+            let result =
+                FunctionResult::ErrorMessage(format!("Failed to save mutation to outbox: {e:#}"));
+            // The request was just tracked by `start_mutation`, so this can't
+            // fail.
+            let _ = self.request_manager.update_request(
+                &request_id,
+                RequestType::Mutation,
+                result,
+                None,
+            );
+            if self
+                .optimistic_query_results
+                .has_optimistic_update(request_id)
+            {
+                rolled_back.insert(request_id);
+            }
+        }
+        if rolled_back.is_empty() {
+            return None;
         }
+        let changed_queries = self.on_query_result_changes(rolled_back).ok()?;
+        self.update_latest_results(changed_queries);
+        Some(self.state.latest_results.clone())
     }
 
     /// Update state to be subscribed to a query and add subscription request to
@@ -506,6 +695,65 @@ impl BaseConvexClient {
         &mut self,
         udf_path: UdfPath,
         args: BTreeMap<String, Value>,
+    ) -> oneshot::Receiver<FunctionResult> {
+        self.start_mutation(udf_path, args, None)
+    }
+
+    /// Track mutation and add mutation request to the outgoing message queue,
+    /// applying `optimistic_update` to the local query results until the
+    /// mutation's effects are reflected in results from the server.
+    ///
+    /// The update runs immediately, so
+    /// [`latest_results`](Self::latest_results()) includes it as soon as
+    /// this returns. It reruns on top of the server's results after every
+    /// transition until the transition that includes the mutation arrives,
+    /// and is rolled back if the mutation fails. It may run many times, so
+    /// it must only read and write the [`OptimisticLocalStore`]
+    /// it is given.
+    ///
+    /// ```no_run
+    /// use convex::{
+    ///     base_client::BaseConvexClient,
+    ///     Value,
+    /// };
+    /// use maplit::btreemap;
+    ///
+    /// let mut base_client = BaseConvexClient::new();
+    /// let message: Value = "Let it be.".into();
+    /// let optimistic_message = message.clone();
+    /// let result_receiver = base_client.mutation_with_optimistic_update(
+    ///     "sendMessage".parse().unwrap(),
+    ///     btreemap! { "body".into() => message },
+    ///     move |store| {
+    ///         let Some(Value::Array(messages)) =
+    ///             store.get_query("listMessages".parse().unwrap(), btreemap! {})
+    ///         else {
+    ///             return;
+    ///         };
+    ///         let mut messages = messages.clone();
+    ///         messages.push(optimistic_message.clone());
+    ///         store.set_query(
+    ///             "listMessages".parse().unwrap(),
+    ///             btreemap! {},
+    ///             Some(Value::Array(messages)),
+    ///         );
+    ///     },
+    /// );
+    /// ```
+    pub fn mutation_with_optimistic_update(
+        &mut self,
+        udf_path: UdfPath,
+        args: BTreeMap<String, Value>,
+        optimistic_update: impl Fn(&mut OptimisticLocalStore<'_>) + Send + 'static,
+    ) -> oneshot::Receiver<FunctionResult> {
+        self.start_mutation(udf_path, args, Some(Box::new(optimistic_update)))
+    }
+
+    fn start_mutation(
+        &mut self,
+        udf_path: UdfPath,
+        args: BTreeMap<String, Value>,
+        optimistic_update: Option<OptimisticUpdate>,
     ) -> oneshot::Receiver<FunctionResult> {
         let request_id = self.next_request_id;
         self.next_request_id = request_id + 1;
@@ -516,6 +764,9 @@ impl BaseConvexClient {
             args: vec![Value::Object(args).into()],
             component_path: None,
         };
+        if let Some(ref mut outbox) = self.outbox {
+            outbox.push(request_id, &message);
+        }
 
         let result_receiver = self.request_manager.track_request(
             &message,
@@ -523,6 +774,14 @@ impl BaseConvexClient {
             RequestType::Mutation,
         );
         self.outgoing_message_queue.push_back(message);
+        if let Some(optimistic_update) = optimistic_update {
+            let changed_queries = self.optimistic_query_results.apply_optimistic_update(
+                &self.state.query_set,
+                RequestId::new(request_id),
+                optimistic_update,
+            );
+            self.update_latest_results(changed_queries);
+        }
         result_receiver
     }
 
@@ -561,6 +820,13 @@ impl BaseConvexClient {
         self.outgoing_message_queue.push_back(message);
     }
 
+    /// Replace the auth token without sending it, so that the next
+    /// [`resend_ongoing_queries_mutations`](Self::resend_ongoing_queries_mutations)
+    /// authenticates the new connection with it.
+    pub(crate) fn set_auth_for_reconnect(&mut self, token: AuthenticationToken) {
+        let _ = self.state.set_auth(token);
+    }
+
     /// Pop the next message from the outgoing message queue.
     ///
     /// Note that this does not *send* the message because the Internal client
@@ -578,12 +844,28 @@ impl BaseConvexClient {
         }
     }
 
+    /// Returns the number of mutations that have been sent but whose effects
+    /// aren't yet reflected in query results.
+    pub fn inflight_mutations(&self) -> usize {
+        self.request_manager.num_ongoing(RequestType::Mutation)
+    }
+
+    /// Returns the number of actions that have been sent but haven't
+    /// completed.
+    pub fn inflight_actions(&self) -> usize {
+        self.request_manager.num_ongoing(RequestType::Action)
+    }
+
     /// Returns the maximum timestamp observed by the client.
     pub fn max_observed_timestamp(&self) -> Option<Timestamp> {
         self.max_observed_timestamp
     }
 
     /// Given a message from a Server, update the base state accordingly.
+    ///
+    /// Returns the latest query results if they may have changed, either
+    /// because of a transition or because a failed mutation's optimistic update
+    /// was rolled back.
     pub fn receive_message(
         &mut self,
         message: ServerMessage,
@@ -595,10 +877,8 @@ impl BaseConvexClient {
                 let completed_requests = self
                     .request_manager
                     .remove_and_notify_completed(end_version.ts);
-                let changed_query_ids = self.on_query_result_changes(completed_requests)?;
-                for (id, result) in changed_query_ids {
-                    self.state.latest_results.results.insert(id, result);
-                }
+                let changed_queries = self.on_query_result_changes(completed_requests)?;
+                self.update_latest_results(changed_queries);
                 return Ok(Some(self.state.latest_results.clone()));
             },
             ServerMessage::MutationResponse {
@@ -614,13 +894,31 @@ impl BaseConvexClient {
                 if let Some(ts) = ts {
                     self.observe_timestamp(ts);
                 }
+                // The server has recorded the outcome, so the mutation doesn't
+                // need to be resent even if the process restarts.
+                if let Some(ref mut outbox) = self.outbox {
+                    outbox.remove(request_id);
+                }
                 let request_id = RequestId::new(request_id);
+                let failed = result.is_err();
                 self.request_manager.update_request(
                     &request_id,
                     RequestType::Mutation,
                     result.into(),
                     ts,
                 )?;
+                // A failed mutation won't appear in a transition, so roll back
+                // its optimistic update now.
+                if failed
+                    && self
+                        .optimistic_query_results
+                        .has_optimistic_update(request_id)
+                {
+                    let changed_queries =
+                        self.on_query_result_changes(BTreeSet::from([request_id]))?;
+                    self.update_latest_results(changed_queries);
+                    return Ok(Some(self.state.latest_results.clone()));
+                }
             },
             ServerMessage::AuthError {
                 error_message,
@@ -630,13 +928,13 @@ impl BaseConvexClient {
                     "AuthError: {error_message} for identity version {base_version:?}. Restarting \
                      protocol."
                 );
-                return Err(format!(
+                return Err(ReconnectProtocolReason::AuthError(format!(
                     "AuthError: {error_message} for identity version {base_version:?}"
-                ));
+                )));
             },
             ServerMessage::FatalError { error_message } => {
                 tracing::error!("FatalError: {error_message}. Restarting protocol.");
-                return Err(format!("FatalError: {error_message}"));
+                return Err(format!("FatalError: {error_message}").into());
             },
             ServerMessage::ActionResponse {
                 request_id,
@@ -666,6 +964,43 @@ impl BaseConvexClient {
         &self.state.latest_results
     }
 
+    /// Get the server's latest result for `subscriber_id`, without any
+    /// optimistic updates applied.
+    pub fn server_query_result(&self, subscriber_id: &SubscriberId) -> Option<&FunctionResult> {
+        if !self
+            .state
+            .latest_results
+            .subscribers
+            .contains(subscriber_id)
+        {
+            return None;
+        }
+        self.remote_query_set.remote_query_set.get(&subscriber_id.0)
+    }
+
+    /// Get the code of the server's latest error for `subscriber_id`, if the
+    /// query failed with an error the client can recover from.
+    pub(crate) fn server_query_error_code(
+        &self,
+        subscriber_id: &SubscriberId,
+    ) -> Option<QueryErrorCode> {
+        self.server_query_result(subscriber_id)?;
+        self.remote_query_set
+            .error_codes
+            .get(&subscriber_id.0)
+            .copied()
+    }
+
+    /// Returns the timestamp of the last transition received from the server
+    /// since connecting, which all results from
+    /// [`server_query_result`](Self::server_query_result()) are consistent at.
+    pub fn server_timestamp(&self) -> Option<Timestamp> {
+        if self.remote_query_set.version == StateVersion::initial() {
+            return None;
+        }
+        Some(self.remote_query_set.version.ts)
+    }
+
     /// Resend all subscribed queries and ongoing mutations. Should be used once
     /// the websocket closes and reconnects.
     pub fn resend_ongoing_queries_mutations(&mut self) {
@@ -683,7 +1018,7 @@ impl BaseConvexClient {
     fn on_query_result_changes(
         &mut self,
         completed_requests: BTreeSet<RequestId>,
-    ) -> Result<BTreeMap<QueryId, FunctionResult>, ReconnectProtocolReason> {
+    ) -> Result<BTreeMap<QueryId, Option<FunctionResult>>, ReconnectProtocolReason> {
         let remote_query_results = &self.remote_query_set.remote_query_set;
         let mut query_id_to_value = BTreeMap::new();
         for (query_id, result) in remote_query_results.iter() {
@@ -707,7 +1042,27 @@ impl BaseConvexClient {
         }
         Ok(self
             .optimistic_query_results
-            .ingest_query_results_from_server(query_id_to_value, completed_requests))
+            .ingest_query_results_from_server(
+                &self.state.query_set,
+                query_id_to_value,
+                completed_requests,
+            ))
+    }
+
+    fn update_latest_results(
+        &mut self,
+        changed_queries: BTreeMap<QueryId, Option<FunctionResult>>,
+    ) {
+        for (id, result) in changed_queries {
+            match result {
+                Some(result) => {
+                    self.state.latest_results.results.insert(id, result);
+                },
+                None => {
+                    self.state.latest_results.results.remove(&id);
+                },
+            }
+        }
     }
 
     fn local_query_result(&self, query_id: QueryId) -> Option<FunctionResult> {
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+
+use convex_sync_types::{
+    QueryId,
+    UdfPath,
+};
+
+use super::{
+    serialize_path_and_args,
+    FunctionResult,
+    LocalQuery,
+    Query,
+    QueryToken,
+};
+use crate::value::Value;
+
+/// A temporary, local update to query results within this client, passed to
+/// `BaseConvexClient::mutation_with_optimistic_update`.
+///
+/// The update is run once when the mutation is sent, and rerun on top of the
+/// server's results after every transition until the transition containing
+/// the mutation arrives. It is dropped immediately if the mutation fails. It
+/// may run many times, so it must be deterministic and only touch the store.
+pub(super) type OptimisticUpdate = Box<dyn Fn(&mut OptimisticLocalStore<'_>) + Send>;
+
+/// A view of the client's query results that an optimistic update can read and
+/// modify.
+///
+/// Only queries the client is subscribed to are visible. Changes to other
+/// queries are ignored.
+pub struct OptimisticLocalStore<'a> {
+    pub(super) query_set: &'a BTreeMap<QueryToken, LocalQuery>,
+    pub(super) query_results: &'a mut BTreeMap<QueryId, Query>,
+}
+
+impl<'a> OptimisticLocalStore<'a> {
+    /// Get the current value of the query `udf_path` called with `args`.
+    ///
+    /// Returns `None` if the client isn't subscribed to the query, its result
+    /// hasn't loaded yet, or it failed.
+    pub fn get_query(&self, udf_path: UdfPath, args: BTreeMap<String, Value>) -> Option<&Value> {
+        let query_id = self.query_id(udf_path, args)?;
+        match self.query_results.get(&query_id)?.result {
+            FunctionResult::Value(ref value) => Some(value),
+            FunctionResult::ErrorMessage(_) | FunctionResult::ConvexError(_) => None,
+        }
+    }
+
+    /// Get the arguments and current values of every subscribed query to
+    /// `udf_path`. Queries that haven't loaded or failed have a value of
+    /// `None`.
+    pub fn get_all_queries(
+        &self,
+        udf_path: UdfPath,
+    ) -> Vec<(BTreeMap<String, Value>, Option<Value>)> {
+        let udf_path = udf_path.canonicalize();
+        self.query_set
+            .values()
+            .filter(|local_query| local_query.canonicalized_udf_path == udf_path)
+            .map(|local_query| {
+                let value = match self.query_results.get(&local_query.id) {
+                    Some(Query {
+                        result: FunctionResult::Value(value),
+                        ..
+                    }) => Some(value.clone()),
+                    _ => None,
+                };
+                (local_query.args.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Set the value of the query `udf_path` called with `args`, or mark it as
+    /// loading if `value` is `None`. Does nothing if the client isn't
+    /// subscribed to the query.
+    pub fn set_query(
+        &mut self,
+        udf_path: UdfPath,
+        args: BTreeMap<String, Value>,
+        value: Option<Value>,
+    ) {
+        let query_token = serialize_path_and_args(udf_path, args);
+        let Some(local_query) = self.query_set.get(&query_token) else {
+            return;
+        };
+        match value {
+            Some(value) => {
+                self.query_results.insert(
+                    local_query.id,
+                    Query {
+                        result: FunctionResult::Value(value),
+                        _udf_path: local_query.canonicalized_udf_path.clone(),
+                        _args: local_query.args.clone(),
+                    },
+                );
+            },
+            None => {
+                self.query_results.remove(&local_query.id);
+            },
+        }
+    }
+
+    fn query_id(&self, udf_path: UdfPath, args: BTreeMap<String, Value>) -> Option<QueryId> {
+        let query_token = serialize_path_and_args(udf_path, args);
+        self.query_set
+            .get(&query_token)
+            .map(|local_query| local_query.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convex_sync_types::{
+        types::ErrorPayload,
+        LogLinesMessage,
+        QueryId,
+        StateModification,
+        StateVersion,
+    };
+    use maplit::btreemap;
+
+    use super::OptimisticLocalStore;
+    use crate::{
+        base_client::BaseConvexClient,
+        sync::ServerMessage,
+        FunctionResult,
+        Value,
+    };
+
+    fn transition(
+        start_version: StateVersion,
+        modifications: Vec<(QueryId, Value)>,
+    ) -> (ServerMessage, StateVersion) {
+        let end_version = StateVersion {
+            ts: start_version.ts.succ().expect("Succ failed"),
+            ..start_version
+        };
+        let modifications = modifications
+            .into_iter()
+            .map(|(query_id, value)| StateModification::QueryUpdated {
+                query_id,
+                value,
+                journal: None,
+                log_lines: LogLinesMessage(vec![]),
+            })
+            .collect();
+        (
+            ServerMessage::Transition {
+                start_version,
+                end_version,
+                modifications,
+            },
+            end_version,
+        )
+    }
+
+    fn messages(bodies: &[&str]) -> FunctionResult {
+        FunctionResult::Value(Value::Array(
+            bodies.iter().map(|body| (*body).into()).collect(),
+        ))
+    }
+
+    fn append_message(body: &'static str) -> impl Fn(&mut OptimisticLocalStore<'_>) + Send {
+        move |store| {
+            let Some(Value::Array(messages)) =
+                store.get_query("listMessages".parse().unwrap(), btreemap! {})
+            else {
+                return;
+            };
+            let mut messages = messages.clone();
+            messages.push(body.into());
+            store.set_query(
+                "listMessages".parse().unwrap(),
+                btreemap! {},
+                Some(Value::Array(messages)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_optimistic_update_layered_until_mutation_reflected() -> anyhow::Result<()> {
+        let mut client = BaseConvexClient::new();
+        let list = client.subscribe("listMessages".parse()?, btreemap! {});
+        let (message, version) = transition(
+            StateVersion::initial(),
+            vec![(list.query_id(), Value::Array(vec!["a".into()]))],
+        );
+        client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+
+        let _result = client.mutation_with_optimistic_update(
+            "sendMessage".parse()?,
+            btreemap! {},
+            append_message("b"),
+        );
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&messages(&["a", "b"]))
+        );
+
+        // Transitions that don't include the mutation are layered under it.
+        let (message, version) = transition(
+            version,
+            vec![(list.query_id(), Value::Array(vec!["a".into(), "c".into()]))],
+        );
+        client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&messages(&["a", "c", "b"]))
+        );
+
+        let mutation_ts = version.ts.succ()?;
+        client
+            .receive_message(ServerMessage::MutationResponse {
+                request_id: 0,
+                result: Ok(Value::Null),
+                ts: Some(mutation_ts),
+                log_lines: LogLinesMessage(vec![]),
+            })
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&messages(&["a", "c", "b"]))
+        );
+
+        // Once the transition includes the mutation, its update is dropped.
+        let (message, _version) = transition(
+            version,
+            vec![(
+                list.query_id(),
+                Value::Array(vec!["a".into(), "c".into(), "b".into()]),
+            )],
+        );
+        client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&messages(&["a", "c", "b"]))
+        );
+        assert!(client
+            .optimistic_query_results
+            .optimistic_updates
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimistic_update_rolled_back_on_failure() -> anyhow::Result<()> {
+        let mut client = BaseConvexClient::new();
+        let list = client.subscribe("listMessages".parse()?, btreemap! {});
+        let (message, _version) = transition(
+            StateVersion::initial(),
+            vec![(list.query_id(), Value::Array(vec!["a".into()]))],
+        );
+        client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+
+        let _result = client.mutation_with_optimistic_update(
+            "sendMessage".parse()?,
+            btreemap! {},
+            append_message("b"),
+        );
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&messages(&["a", "b"]))
+        );
+
+        let results = client
+            .receive_message(ServerMessage::MutationResponse {
+                request_id: 0,
+                result: Err(ErrorPayload::Message("Uncaught Error: oops".to_string())),
+                ts: None,
+                log_lines: LogLinesMessage(vec![]),
+            })
+            .map_err(anyhow::Error::msg)?;
+        assert!(results.is_some());
+        assert_eq!(client.latest_results().get(&list), Some(&messages(&["a"])));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_query_ignores_unsubscribed_queries() -> anyhow::Result<()> {
+        let mut client = BaseConvexClient::new();
+        let list = client.subscribe("listMessages".parse()?, btreemap! {});
+        let _result =
+            client.mutation_with_optimistic_update("sendMessage".parse()?, btreemap! {}, |store| {
+                store.set_query(
+                    "listMessages".parse().unwrap(),
+                    btreemap! {},
+                    Some(1.into()),
+                );
+                store.set_query(
+                    "countMessages".parse().unwrap(),
+                    btreemap! {},
+                    Some(1.into()),
+                );
+            });
+        assert_eq!(
+            client.latest_results().get(&list),
+            Some(&FunctionResult::Value(1.into()))
+        );
+        assert_eq!(client.latest_results().results.len(), 1);
+        Ok(())
+    }
+}
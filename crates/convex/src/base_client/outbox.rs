@@ -0,0 +1,323 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    mem,
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use convex_sync_types::{
+    ClientMessage,
+    SessionId,
+    SessionRequestSeqNumber,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+/// A file-backed queue of mutations that haven't been acknowledged by the
+/// server, so they survive a process restart.
+///
+/// Pass an outbox to
+/// [`BaseConvexClient::with_outbox`](super::BaseConvexClient::with_outbox) or
+/// [`ConvexClient::new_with_outbox`](crate::ConvexClient::new_with_outbox).
+/// Each mutation is written to the file before it's sent, and removed once
+/// the server responds to it. A mutation that can't be written fails instead
+/// of being sent. Mutations left over from a previous process are resent in
+/// order when the client connects.
+///
+/// The outbox also records the client's session id and the next request id.
+/// The server remembers which `(session id, request id)` pairs it has run,
+/// so a mutation that ran before the process stopped isn't run again when
+/// it's resent.
+///
+/// Only one client may use an outbox file at a time.
+pub struct MutationOutbox {
+    path: PathBuf,
+    session_id: SessionId,
+    next_request_id: SessionRequestSeqNumber,
+    mutations: BTreeMap<SessionRequestSeqNumber, ClientMessage>,
+    /// Whether the file is behind the in-memory state.
+    dirty: bool,
+    /// Mutations added since the file was last written.
+    unsaved: Vec<SessionRequestSeqNumber>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OutboxFile {
+    session_id: Uuid,
+    next_request_id: SessionRequestSeqNumber,
+    mutations: Vec<JsonValue>,
+}
+
+impl MutationOutbox {
+    /// Open the outbox at `path`, starting a new session if it doesn't exist.
+    /// The file is created when the first mutation is recorded.
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(Self {
+                    path,
+                    session_id: SessionId::new(Uuid::new_v4()),
+                    next_request_id: 0,
+                    mutations: BTreeMap::new(),
+                    dirty: false,
+                    unsaved: vec![],
+                });
+            },
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let file: OutboxFile = serde_json::from_slice(&contents)
+            .with_context(|| format!("Invalid mutation outbox {}", path.display()))?;
+        let mut mutations = BTreeMap::new();
+        for mutation in file.mutations {
+            let message = ClientMessage::try_from(mutation)?;
+            let ClientMessage::Mutation { request_id, .. } = message else {
+                anyhow::bail!("Invalid mutation outbox {}: {message:?}", path.display());
+            };
+            mutations.insert(request_id, message);
+        }
+        Ok(Self {
+            path,
+            session_id: SessionId::new(file.session_id),
+            next_request_id: file.next_request_id,
+            mutations,
+            dirty: false,
+            unsaved: vec![],
+        })
+    }
+
+    /// The session id to connect with, so the server can tell which resent
+    /// mutations it has already run.
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// The number of mutations waiting for a response from the server.
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    /// Whether every mutation has had a response from the server.
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+
+    pub(super) fn next_request_id(&self) -> SessionRequestSeqNumber {
+        self.next_request_id
+    }
+
+    /// Mutations waiting for a response, in the order they were made.
+    pub(super) fn pending(&self) -> impl Iterator<Item = &ClientMessage> {
+        self.mutations.values()
+    }
+
+    /// Record a mutation that must be saved before it's sent. Request ids
+    /// must never be reused within a session, so this also advances the next
+    /// request id.
+    pub(super) fn push(&mut self, request_id: SessionRequestSeqNumber, message: &ClientMessage) {
+        self.mutations.insert(request_id, message.clone());
+        self.next_request_id = self.next_request_id.max(request_id + 1);
+        self.unsaved.push(request_id);
+        self.dirty = true;
+    }
+
+    /// Remove a mutation once the server has responded to it, or once it
+    /// failed to be saved.
+    pub(super) fn remove(&mut self, request_id: SessionRequestSeqNumber) {
+        if self.mutations.remove(&request_id).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Snapshot the outbox for writing if it changed since the last write,
+    /// along with the mutations that aren't saved until the write succeeds.
+    /// If it fails, the caller must [`remove`](Self::remove) those mutations
+    /// and call [`write_failed`](Self::write_failed).
+    pub(super) fn take_write(&mut self) -> Option<(OutboxWrite, Vec<SessionRequestSeqNumber>)> {
+        if !mem::take(&mut self.dirty) {
+            return None;
+        }
+        let write = OutboxWrite {
+            path: self.path.clone(),
+            contents: self.serialize(),
+        };
+        Some((write, mem::take(&mut self.unsaved)))
+    }
+
+    /// Retry writing the outbox on the next [`take_write`](Self::take_write).
+    pub(super) fn write_failed(&mut self) {
+        self.dirty = true;
+    }
+
+    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        let file = OutboxFile {
+            session_id: self.session_id.into(),
+            next_request_id: self.next_request_id,
+            mutations: self
+                .mutations
+                .values()
+                .map(|message| JsonValue::try_from(message.clone()))
+                .collect::<anyhow::Result<_>>()?,
+        };
+        Ok(serde_json::to_vec(&file)?)
+    }
+}
+
+/// The contents of an outbox at some point, to be written to its file
+/// without blocking the client.
+pub(super) struct OutboxWrite {
+    path: PathBuf,
+    contents: anyhow::Result<Vec<u8>>,
+}
+
+impl OutboxWrite {
+    /// Atomically replace the outbox file with this snapshot.
+    pub(super) async fn write(self) -> anyhow::Result<()> {
+        let contents = self.contents?;
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        tokio::fs::write(&tmp_path, contents)
+            .await
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use convex_sync_types::{
+        ClientMessage,
+        LogLinesMessage,
+    };
+    use maplit::btreemap;
+    use uuid::Uuid;
+
+    use super::MutationOutbox;
+    use crate::{
+        base_client::BaseConvexClient,
+        sync::ServerMessage,
+        FunctionResult,
+        Value,
+    };
+
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("convex-outbox-{}.json", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn drain(client: &mut BaseConvexClient) -> Vec<ClientMessage> {
+        std::iter::from_fn(|| client.pop_next_message()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_outbox_replays_pending_mutations_after_restart() -> anyhow::Result<()> {
+        let path = TempPath::new();
+        let mut client = BaseConvexClient::with_outbox(MutationOutbox::open(&path.0)?);
+        let session_id = client.session_id();
+        assert!(session_id.is_some());
+        let _first = client.mutation(
+            "sendMessage".parse()?,
+            btreemap! {"body".into() => "a".into()},
+        );
+        let _second = client.mutation(
+            "sendMessage".parse()?,
+            btreemap! {"body".into() => "b".into()},
+        );
+        assert!(client.save_outbox().await.is_none());
+        let sent = drain(&mut client);
+        assert_eq!(sent.len(), 2);
+        client
+            .receive_message(ServerMessage::MutationResponse {
+                request_id: 0,
+                result: Ok(Value::Null),
+                ts: None,
+                log_lines: LogLinesMessage(vec![]),
+            })
+            .map_err(anyhow::Error::msg)?;
+        client.save_outbox().await;
+        drop(client);
+
+        // Only the mutation without a response is resent, with the same
+        // session and request id so the server can deduplicate it.
+        let outbox = MutationOutbox::open(&path.0)?;
+        assert_eq!(outbox.len(), 1);
+        let mut client = BaseConvexClient::with_outbox(outbox);
+        assert_eq!(client.session_id(), session_id);
+        assert_eq!(drain(&mut client), vec![sent[1].clone()]);
+
+        // New requests don't reuse ids from the previous process.
+        let _third = client.mutation("sendMessage".parse()?, btreemap! {});
+        let ClientMessage::Mutation { request_id, .. } = drain(&mut client)[0] else {
+            panic!("Expected a mutation");
+        };
+        assert_eq!(request_id, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_outbox_removes_failed_mutations() -> anyhow::Result<()> {
+        let path = TempPath::new();
+        let mut client = BaseConvexClient::with_outbox(MutationOutbox::open(&path.0)?);
+        let _result = client.mutation("sendMessage".parse()?, btreemap! {});
+        client.save_outbox().await;
+        client
+            .receive_message(ServerMessage::MutationResponse {
+                request_id: 0,
+                result: Err(convex_sync_types::types::ErrorPayload::Message(
+                    "Uncaught Error: oops".to_string(),
+                )),
+                ts: None,
+                log_lines: LogLinesMessage(vec![]),
+            })
+            .map_err(anyhow::Error::msg)?;
+        client.save_outbox().await;
+        assert!(MutationOutbox::open(&path.0)?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_outbox_write_failure_fails_mutation() -> anyhow::Result<()> {
+        // The outbox's directory doesn't exist, so it can't be written.
+        let path = TempPath::new();
+        let path = path.0.join("outbox.json");
+        let mut client = BaseConvexClient::with_outbox(MutationOutbox::open(&path)?);
+        let result = client.mutation("sendMessage".parse()?, btreemap! {});
+        client.save_outbox().await;
+
+        // The mutation is never sent, and fails instead.
+        assert_eq!(drain(&mut client), vec![]);
+        let FunctionResult::ErrorMessage(message) = result.await? else {
+            panic!("Expected the mutation to fail");
+        };
+        assert!(message.starts_with("Failed to save mutation to outbox"));
+        assert_eq!(client.inflight_mutations(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_id_requires_outbox() {
+        assert_eq!(BaseConvexClient::new().session_id(), None);
+    }
+}
@@ -0,0 +1,596 @@
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+};
+
+use convex_sync_types::{
+    QueryErrorCode,
+    UdfPath,
+};
+
+use super::{
+    BaseConvexClient,
+    FunctionResult,
+    QueryResults,
+    SubscriberId,
+};
+use crate::{
+    value::Value,
+    ConvexError,
+};
+
+/// Unique id for each pagination session, passed to the query in
+/// `paginationOpts.id`. It keeps the pages of separate sessions from sharing
+/// subscriptions, and lets a session restart from scratch after an error.
+static NEXT_PAGINATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The loading state of a [`PaginatedQuery`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PaginationStatus {
+    /// The first page hasn't loaded yet.
+    LoadingFirstPage,
+    /// All pages requested so far have loaded, and there are more to load.
+    CanLoadMore,
+    /// A page after the first is still loading.
+    LoadingMore,
+    /// All pages have loaded and there are no more results.
+    Exhausted,
+}
+
+/// The current results of a [`PaginatedQuery`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PaginatedQueryResult {
+    /// The items of all loaded pages, concatenated in order.
+    Results {
+        /// The loaded items.
+        results: Vec<Value>,
+        /// Whether more pages are loading or can be loaded.
+        status: PaginationStatus,
+    },
+    /// The error message of a page that failed to load.
+    ErrorMessage(String),
+    /// The application-level error of a page that failed to load.
+    ConvexError(ConvexError),
+}
+
+/// A single subscription covering the documents in `(cursor, end_cursor]`,
+/// or `num_items` documents after `cursor` if `end_cursor` is unset.
+struct PageQuery {
+    subscriber_id: SubscriberId,
+    cursor: Option<String>,
+    end_cursor: Option<String>,
+    num_items: usize,
+}
+
+struct Page {
+    query: PageQuery,
+    /// Subscriptions that cover the same range as `query` and replace it once
+    /// they all have results. Used to split pages that have grown too large
+    /// and to pin a page's end before loading the page after it.
+    replacement: Vec<PageQuery>,
+}
+
+struct PageResult {
+    page: Vec<Value>,
+    is_done: bool,
+    continue_cursor: String,
+    split_cursor: Option<String>,
+    page_status: Option<String>,
+}
+
+enum Outcome {
+    Result(PaginatedQueryResult),
+    Reset,
+}
+
+/// Reactively paginates through the results of a query that calls
+/// `.paginate(args.paginationOpts)`, by managing a set of page subscriptions
+/// in a [`BaseConvexClient`].
+///
+/// Call [`update`](Self::update) after every transition and every call to
+/// [`load_more`](Self::load_more), and flush the client's outgoing messages
+/// afterwards since updating may subscribe to new pages. Most users should use
+/// [`ConvexClient::subscribe_paginated`](crate::ConvexClient::subscribe_paginated)
+/// instead.
+pub struct PaginatedQuery {
+    udf_path: UdfPath,
+    args: BTreeMap<String, Value>,
+    initial_num_items: usize,
+    id: u64,
+    pages: Vec<Page>,
+}
+
+impl PaginatedQuery {
+    /// Subscribe to the first page of `udf_path` called with `args`, with
+    /// `initial_num_items` items. `args` shouldn't include `paginationOpts`.
+    pub fn new(
+        base_client: &mut BaseConvexClient,
+        udf_path: UdfPath,
+        args: BTreeMap<String, Value>,
+        initial_num_items: usize,
+    ) -> Self {
+        let mut query = Self {
+            udf_path,
+            args,
+            initial_num_items,
+            id: 0,
+            pages: vec![],
+        };
+        query.start(base_client);
+        query
+    }
+
+    /// Subscribe to the page after the last loaded page, with `num_items`
+    /// items. Returns `false` without doing anything if the last page is still
+    /// loading or there are no more pages.
+    pub fn load_more(&mut self, base_client: &mut BaseConvexClient, num_items: usize) -> bool {
+        let last = self.pages.last().expect("Paginated query has no pages");
+        if !last.replacement.is_empty() {
+            return false;
+        }
+        let Some(FunctionResult::Value(value)) =
+            base_client.latest_results().get(&last.query.subscriber_id)
+        else {
+            return false;
+        };
+        let Ok(result) = parse_page(value) else {
+            return false;
+        };
+        if result.is_done {
+            return false;
+        }
+        // Without a fixed end the last page grows and shrinks as documents
+        // change, so pin it to where it currently ends before the next page
+        // starts from there.
+        if last.query.end_cursor.is_none() {
+            let pinned = self.subscribe_page(
+                base_client,
+                last.query.cursor.clone(),
+                Some(result.continue_cursor.clone()),
+                last.query.num_items,
+            );
+            let last = self.pages.last_mut().expect("Paginated query has no pages");
+            last.replacement = vec![pinned];
+        }
+        let next = self.subscribe_page(base_client, Some(result.continue_cursor), None, num_items);
+        self.pages.push(Page {
+            query: next,
+            replacement: vec![],
+        });
+        true
+    }
+
+    /// Reconcile the page subscriptions with the client's latest results and
+    /// return the concatenated results.
+    pub fn update(&mut self, base_client: &mut BaseConvexClient) -> PaginatedQueryResult {
+        let results = base_client.latest_results().clone();
+
+        // Swap in replacement pages once all of them have loaded, so the
+        // results never go back to loading.
+        let mut pages = Vec::with_capacity(self.pages.len());
+        for page in mem::take(&mut self.pages) {
+            let replacement_loaded = !page.replacement.is_empty()
+                && page
+                    .replacement
+                    .iter()
+                    .all(|query| results.get(&query.subscriber_id).is_some());
+            if replacement_loaded {
+                base_client.unsubscribe(page.query.subscriber_id);
+                pages.extend(page.replacement.into_iter().map(|query| Page {
+                    query,
+                    replacement: vec![],
+                }));
+            } else {
+                pages.push(page);
+            }
+        }
+        self.pages = pages;
+
+        let mut splits = vec![];
+        let result = match self.collect_results(base_client, &results, &mut splits) {
+            Outcome::Result(result) => result,
+            Outcome::Reset => {
+                self.unsubscribe_all(base_client);
+                self.start(base_client);
+                return PaginatedQueryResult::Results {
+                    results: vec![],
+                    status: PaginationStatus::LoadingFirstPage,
+                };
+            },
+        };
+        for (i, split_cursor, end_cursor) in splits {
+            let query = &self.pages[i].query;
+            let (cursor, num_items) = (query.cursor.clone(), query.num_items);
+            let first =
+                self.subscribe_page(base_client, cursor, Some(split_cursor.clone()), num_items);
+            let second =
+                self.subscribe_page(base_client, Some(split_cursor), Some(end_cursor), num_items);
+            self.pages[i].replacement = vec![first, second];
+        }
+        result
+    }
+
+    /// Unsubscribe from all pages.
+    pub fn unsubscribe(mut self, base_client: &mut BaseConvexClient) {
+        self.unsubscribe_all(base_client);
+    }
+
+    fn start(&mut self, base_client: &mut BaseConvexClient) {
+        self.id = NEXT_PAGINATION_ID.fetch_add(1, Ordering::Relaxed);
+        let query = self.subscribe_page(base_client, None, None, self.initial_num_items);
+        self.pages = vec![Page {
+            query,
+            replacement: vec![],
+        }];
+    }
+
+    fn unsubscribe_all(&mut self, base_client: &mut BaseConvexClient) {
+        for page in mem::take(&mut self.pages) {
+            base_client.unsubscribe(page.query.subscriber_id);
+            for query in page.replacement {
+                base_client.unsubscribe(query.subscriber_id);
+            }
+        }
+    }
+
+    fn subscribe_page(
+        &self,
+        base_client: &mut BaseConvexClient,
+        cursor: Option<String>,
+        end_cursor: Option<String>,
+        num_items: usize,
+    ) -> PageQuery {
+        let mut pagination_opts = BTreeMap::new();
+        pagination_opts.insert("numItems".to_string(), Value::Float64(num_items as f64));
+        pagination_opts.insert("cursor".to_string(), cursor.clone().into());
+        if let Some(ref end_cursor) = end_cursor {
+            pagination_opts.insert("endCursor".to_string(), end_cursor.clone().into());
+        }
+        pagination_opts.insert("id".to_string(), Value::Float64(self.id as f64));
+        let mut args = self.args.clone();
+        args.insert("paginationOpts".to_string(), Value::Object(pagination_opts));
+        let subscriber_id = base_client.subscribe(self.udf_path.clone(), args);
+        PageQuery {
+            subscriber_id,
+            cursor,
+            end_cursor,
+            num_items,
+        }
+    }
+
+    fn collect_results(
+        &self,
+        base_client: &BaseConvexClient,
+        results: &QueryResults,
+        splits: &mut Vec<(usize, String, String)>,
+    ) -> Outcome {
+        let mut items = vec![];
+        let mut is_done = false;
+        for (i, page) in self.pages.iter().enumerate() {
+            let loading_status = if i == 0 {
+                PaginationStatus::LoadingFirstPage
+            } else {
+                PaginationStatus::LoadingMore
+            };
+            let value = match results.get(&page.query.subscriber_id) {
+                None => {
+                    return Outcome::Result(PaginatedQueryResult::Results {
+                        results: items,
+                        status: loading_status,
+                    })
+                },
+                Some(FunctionResult::Value(value)) => value,
+                // The query was data-dependent and changed underneath us, so
+                // our cursors are no longer valid. Start over.
+                Some(FunctionResult::ErrorMessage(message))
+                    if base_client.server_query_error_code(&page.query.subscriber_id)
+                        == Some(QueryErrorCode::InvalidCursor) =>
+                {
+                    tracing::warn!("Paginated query hit error, resetting pagination: {message}");
+                    return Outcome::Reset;
+                },
+                Some(FunctionResult::ErrorMessage(message)) => {
+                    return Outcome::Result(PaginatedQueryResult::ErrorMessage(message.clone()))
+                },
+                Some(FunctionResult::ConvexError(error)) => {
+                    return Outcome::Result(PaginatedQueryResult::ConvexError(error.clone()))
+                },
+            };
+            let result = match parse_page(value) {
+                Ok(result) => result,
+                Err(e) => {
+                    return Outcome::Result(PaginatedQueryResult::ErrorMessage(format!(
+                        "{} didn't return a pagination result: {e}",
+                        String::from(self.udf_path.clone().canonicalize())
+                    )))
+                },
+            };
+            let split_required = result.page_status.as_deref() == Some("SplitRequired");
+            if page.replacement.is_empty() {
+                if let Some(ref split_cursor) = result.split_cursor {
+                    let split_recommended = matches!(
+                        result.page_status.as_deref(),
+                        Some("SplitRecommended" | "SplitRequired")
+                    );
+                    if split_recommended || result.page.len() > self.initial_num_items * 2 {
+                        let end_cursor = page
+                            .query
+                            .end_cursor
+                            .clone()
+                            .unwrap_or_else(|| result.continue_cursor.clone());
+                        splits.push((i, split_cursor.clone(), end_cursor));
+                    }
+                }
+            }
+            if split_required {
+                // The server couldn't load the full page, so stop before it
+                // until it has been split.
+                return Outcome::Result(PaginatedQueryResult::Results {
+                    results: items,
+                    status: loading_status,
+                });
+            }
+            items.extend(result.page);
+            is_done = result.is_done;
+        }
+        let status = if is_done {
+            PaginationStatus::Exhausted
+        } else {
+            PaginationStatus::CanLoadMore
+        };
+        Outcome::Result(PaginatedQueryResult::Results {
+            results: items,
+            status,
+        })
+    }
+}
+
+fn parse_page(value: &Value) -> anyhow::Result<PageResult> {
+    let Value::Object(fields) = value else {
+        anyhow::bail!("expected an object");
+    };
+    let page = match fields.get("page") {
+        Some(Value::Array(page)) => page.clone(),
+        v => anyhow::bail!("invalid page field: {v:?}"),
+    };
+    let is_done = match fields.get("isDone") {
+        Some(Value::Boolean(is_done)) => *is_done,
+        v => anyhow::bail!("invalid isDone field: {v:?}"),
+    };
+    let continue_cursor = match fields.get("continueCursor") {
+        Some(Value::String(cursor)) => cursor.clone(),
+        v => anyhow::bail!("invalid continueCursor field: {v:?}"),
+    };
+    let split_cursor = match fields.get("splitCursor") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(cursor)) => Some(cursor.clone()),
+        v => anyhow::bail!("invalid splitCursor field: {v:?}"),
+    };
+    let page_status = match fields.get("pageStatus") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(status)) => Some(status.clone()),
+        v => anyhow::bail!("invalid pageStatus field: {v:?}"),
+    };
+    Ok(PageResult {
+        page,
+        is_done,
+        continue_cursor,
+        split_cursor,
+        page_status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use convex_sync_types::{
+        LogLinesMessage,
+        QueryErrorCode,
+        StateModification,
+        StateVersion,
+        Timestamp,
+    };
+    use maplit::btreemap;
+
+    use super::{
+        PaginatedQuery,
+        PaginatedQueryResult,
+        PaginationStatus,
+    };
+    use crate::{
+        base_client::{
+            BaseConvexClient,
+            SubscriberId,
+        },
+        sync::ServerMessage,
+        Value,
+    };
+
+    struct TestServer {
+        version: StateVersion,
+        ts: i32,
+    }
+
+    impl TestServer {
+        fn new() -> Self {
+            Self {
+                version: StateVersion::initial(),
+                ts: 0,
+            }
+        }
+
+        fn transition(
+            &mut self,
+            client: &mut BaseConvexClient,
+            modifications: Vec<StateModification<Value>>,
+        ) -> anyhow::Result<()> {
+            let start_version = self.version;
+            self.ts += 1;
+            self.version.ts = Timestamp::must(self.ts);
+            client
+                .receive_message(ServerMessage::Transition {
+                    start_version,
+                    end_version: self.version,
+                    modifications,
+                })
+                .map_err(anyhow::Error::msg)?;
+            Ok(())
+        }
+    }
+
+    fn page_updated(
+        subscriber_id: SubscriberId,
+        items: Vec<i64>,
+        is_done: bool,
+        continue_cursor: &str,
+        split_cursor: Option<&str>,
+    ) -> StateModification<Value> {
+        let mut fields = btreemap! {
+            "page".to_string() => Value::Array(items.into_iter().map(Value::from).collect()),
+            "isDone".to_string() => is_done.into(),
+            "continueCursor".to_string() => continue_cursor.into(),
+        };
+        if let Some(split_cursor) = split_cursor {
+            fields.insert("splitCursor".to_string(), split_cursor.into());
+            fields.insert("pageStatus".to_string(), "SplitRecommended".into());
+        }
+        StateModification::QueryUpdated {
+            query_id: subscriber_id.query_id(),
+            value: Value::Object(fields),
+            log_lines: LogLinesMessage(vec![]),
+            journal: None,
+        }
+    }
+
+    fn results(items: Vec<i64>, status: PaginationStatus) -> PaginatedQueryResult {
+        PaginatedQueryResult::Results {
+            results: items.into_iter().map(Value::from).collect(),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_page_split() -> anyhow::Result<()> {
+        let mut client = BaseConvexClient::new();
+        let mut server = TestServer::new();
+        let mut query = PaginatedQuery::new(&mut client, "messages:list".parse()?, btreemap! {}, 2);
+        assert_eq!(
+            query.update(&mut client),
+            results(vec![], PaginationStatus::LoadingFirstPage)
+        );
+
+        // The first page grew past its size, so the server recommends
+        // splitting it.
+        let first_page = query.pages[0].query.subscriber_id;
+        server.transition(
+            &mut client,
+            vec![page_updated(
+                first_page,
+                vec![1, 2, 3, 4, 5],
+                true,
+                "c5",
+                Some("c2"),
+            )],
+        )?;
+        assert_eq!(
+            query.update(&mut client),
+            results(vec![1, 2, 3, 4, 5], PaginationStatus::Exhausted)
+        );
+        let replacement = &query.pages[0].replacement;
+        assert_eq!(replacement.len(), 2);
+        assert_eq!(replacement[0].cursor, None);
+        assert_eq!(replacement[0].end_cursor.as_deref(), Some("c2"));
+        assert_eq!(replacement[1].cursor.as_deref(), Some("c2"));
+        assert_eq!(replacement[1].end_cursor.as_deref(), Some("c5"));
+        let (left, right) = (replacement[0].subscriber_id, replacement[1].subscriber_id);
+
+        // The old page stays in use until both halves have loaded.
+        server.transition(
+            &mut client,
+            vec![page_updated(left, vec![1, 2], false, "c2", None)],
+        )?;
+        assert_eq!(
+            query.update(&mut client),
+            results(vec![1, 2, 3, 4, 5], PaginationStatus::Exhausted)
+        );
+        assert_eq!(query.pages.len(), 1);
+
+        server.transition(
+            &mut client,
+            vec![page_updated(right, vec![3, 4, 5], true, "c5", None)],
+        )?;
+        assert_eq!(
+            query.update(&mut client),
+            results(vec![1, 2, 3, 4, 5], PaginationStatus::Exhausted)
+        );
+        let subscribers: Vec<_> = query
+            .pages
+            .iter()
+            .map(|page| page.query.subscriber_id)
+            .collect();
+        assert_eq!(subscribers, vec![left, right]);
+        assert!(query.pages.iter().all(|page| page.replacement.is_empty()));
+        assert!(client.latest_results().get(&first_page).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_on_invalid_cursor() -> anyhow::Result<()> {
+        let mut client = BaseConvexClient::new();
+        let mut server = TestServer::new();
+        let mut query = PaginatedQuery::new(&mut client, "messages:list".parse()?, btreemap! {}, 2);
+        let first_page = query.pages[0].query.subscriber_id;
+        server.transition(
+            &mut client,
+            vec![page_updated(first_page, vec![1, 2], false, "c2", None)],
+        )?;
+        assert!(query.load_more(&mut client, 2));
+        let second_page = query.pages[1].query.subscriber_id;
+        let id = query.id;
+
+        // Errors without a code are surfaced, even if their message mentions
+        // an invalid cursor.
+        server.transition(
+            &mut client,
+            vec![StateModification::QueryFailed {
+                query_id: second_page.query_id(),
+                error_message: "InvalidCursor: thrown by the function".to_string(),
+                log_lines: LogLinesMessage(vec![]),
+                journal: None,
+                error_data: None,
+                error_code: None,
+            }],
+        )?;
+        assert_eq!(
+            query.update(&mut client),
+            PaginatedQueryResult::ErrorMessage("InvalidCursor: thrown by the function".to_string())
+        );
+
+        server.transition(
+            &mut client,
+            vec![StateModification::QueryFailed {
+                query_id: second_page.query_id(),
+                error_message: "Uncaught Error: InvalidCursor: ...".to_string(),
+                log_lines: LogLinesMessage(vec![]),
+                journal: None,
+                error_data: None,
+                error_code: Some(QueryErrorCode::InvalidCursor),
+            }],
+        )?;
+        assert_eq!(
+            query.update(&mut client),
+            results(vec![], PaginationStatus::LoadingFirstPage)
+        );
+        assert_ne!(query.id, id);
+        assert_eq!(query.pages.len(), 1);
+        assert!(query.pages[0].replacement.is_empty());
+        assert_ne!(query.pages[0].query.subscriber_id, first_page);
+        assert_eq!(query.pages[0].query.cursor, None);
+        assert!(client.latest_results().get(&first_page).is_none());
+        assert!(client.latest_results().get(&second_page).is_none());
+        Ok(())
+    }
+}
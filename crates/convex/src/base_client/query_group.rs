@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use convex_sync_types::{
+    Timestamp,
+    UdfPath,
+};
+
+use super::{
+    BaseConvexClient,
+    FunctionResult,
+    SubscriberId,
+};
+use crate::value::Value;
+
+/// The results of every query in a [`QueryGroup`] at a single server
+/// timestamp.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueryGroupSnapshot {
+    /// The timestamp of the server transition that all of the results are
+    /// from.
+    pub ts: Timestamp,
+    /// The result of each query, keyed by the name it was given in the group.
+    pub results: BTreeMap<String, FunctionResult>,
+}
+
+impl QueryGroupSnapshot {
+    /// Get the result of the query named `name`.
+    pub fn get(&self, name: &str) -> Option<&FunctionResult> {
+        self.results.get(name)
+    }
+}
+
+/// A named group of query subscriptions in a [`BaseConvexClient`] whose
+/// results are read together, at the same server timestamp.
+///
+/// Results only come from the server, so optimistic updates are never
+/// included. Call [`update`](Self::update) after every transition. Most users
+/// should use
+/// [`ConvexClient::subscribe_group`](crate::ConvexClient::subscribe_group)
+/// instead.
+pub struct QueryGroup {
+    subscribers: BTreeMap<String, SubscriberId>,
+    last_snapshot: Option<QueryGroupSnapshot>,
+}
+
+impl QueryGroup {
+    /// Subscribe to each query in `queries`, keyed by the name its result will
+    /// have in each [`QueryGroupSnapshot`].
+    pub fn new(
+        base_client: &mut BaseConvexClient,
+        queries: BTreeMap<String, (UdfPath, BTreeMap<String, Value>)>,
+    ) -> Self {
+        let subscribers = queries
+            .into_iter()
+            .map(|(name, (udf_path, args))| (name, base_client.subscribe(udf_path, args)))
+            .collect();
+        Self {
+            subscribers,
+            last_snapshot: None,
+        }
+    }
+
+    /// Returns a new snapshot if the server has results for every query in
+    /// the group and any of them changed since the last snapshot returned.
+    pub fn update(&mut self, base_client: &BaseConvexClient) -> Option<QueryGroupSnapshot> {
+        let ts = base_client.server_timestamp()?;
+        let mut results = BTreeMap::new();
+        for (name, subscriber_id) in &self.subscribers {
+            let result = base_client.server_query_result(subscriber_id)?;
+            results.insert(name.clone(), result.clone());
+        }
+        if let Some(ref last_snapshot) = self.last_snapshot {
+            if last_snapshot.results == results {
+                return None;
+            }
+        }
+        let snapshot = QueryGroupSnapshot { ts, results };
+        self.last_snapshot = Some(snapshot.clone());
+        Some(snapshot)
+    }
+
+    /// Unsubscribe from every query in the group.
+    pub fn unsubscribe(self, base_client: &mut BaseConvexClient) {
+        for subscriber_id in self.subscribers.into_values() {
+            base_client.unsubscribe(subscriber_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convex_sync_types::{
+        LogLinesMessage,
+        QueryId,
+        StateModification,
+        StateVersion,
+        UdfPath,
+    };
+    use maplit::btreemap;
+
+    use super::QueryGroup;
+    use crate::{
+        base_client::BaseConvexClient,
+        sync::ServerMessage,
+        FunctionResult,
+        Value,
+    };
+
+    fn transition(
+        start_version: StateVersion,
+        modifications: Vec<(QueryId, Value)>,
+    ) -> (ServerMessage, StateVersion) {
+        let end_version = StateVersion {
+            ts: start_version.ts.succ().expect("Succ failed"),
+            ..start_version
+        };
+        let message = ServerMessage::Transition {
+            start_version,
+            end_version,
+            modifications: modifications
+                .into_iter()
+                .map(|(query_id, value)| StateModification::QueryUpdated {
+                    query_id,
+                    value,
+                    journal: None,
+                    log_lines: LogLinesMessage(vec![]),
+                })
+                .collect(),
+        };
+        (message, end_version)
+    }
+
+    #[test]
+    fn test_query_group_waits_for_all_results() -> anyhow::Result<()> {
+        let mut base_client = BaseConvexClient::new();
+        let udf_path: UdfPath = "getValue".parse()?;
+        let mut group = QueryGroup::new(
+            &mut base_client,
+            btreemap! {
+                "a".into() => (udf_path.clone(), btreemap! { "key".into() => "a".into() }),
+                "b".into() => (udf_path, btreemap! { "key".into() => "b".into() }),
+            },
+        );
+        let a = group.subscribers["a"].query_id();
+        let b = group.subscribers["b"].query_id();
+        assert_eq!(group.update(&base_client), None);
+
+        let (message, version) = transition(StateVersion::initial(), vec![(a, 1.into())]);
+        base_client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(group.update(&base_client), None);
+
+        let (message, version) = transition(version, vec![(b, 2.into())]);
+        base_client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        let snapshot = group
+            .update(&base_client)
+            .expect("group should be complete");
+        assert_eq!(snapshot.ts, version.ts);
+        assert_eq!(snapshot.get("a"), Some(&FunctionResult::Value(1.into())));
+        assert_eq!(snapshot.get("b"), Some(&FunctionResult::Value(2.into())));
+
+        // Transitions that don't change the group's results don't produce a
+        // snapshot.
+        let (message, version) = transition(version, vec![]);
+        base_client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(group.update(&base_client), None);
+
+        let (message, version) = transition(version, vec![(a, 3.into()), (b, 4.into())]);
+        base_client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        let snapshot = group.update(&base_client).expect("group should change");
+        assert_eq!(snapshot.ts, version.ts);
+        assert_eq!(snapshot.get("a"), Some(&FunctionResult::Value(3.into())));
+        assert_eq!(snapshot.get("b"), Some(&FunctionResult::Value(4.into())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_group_ignores_optimistic_updates() -> anyhow::Result<()> {
+        let mut base_client = BaseConvexClient::new();
+        let udf_path: UdfPath = "getValue".parse()?;
+        let mut group = QueryGroup::new(
+            &mut base_client,
+            btreemap! { "value".into() => (udf_path.clone(), btreemap! {}) },
+        );
+        let query_id = group.subscribers["value"].query_id();
+        let (message, _version) = transition(StateVersion::initial(), vec![(query_id, 1.into())]);
+        base_client
+            .receive_message(message)
+            .map_err(anyhow::Error::msg)?;
+        assert!(group.update(&base_client).is_some());
+
+        let _result = base_client.mutation_with_optimistic_update(
+            "setValue".parse()?,
+            btreemap! {},
+            move |store| store.set_query(udf_path.clone(), btreemap! {}, Some(2.into())),
+        );
+        assert_eq!(group.update(&base_client), None);
+        Ok(())
+    }
+}
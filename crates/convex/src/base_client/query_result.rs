@@ -6,9 +6,11 @@ use imbl::{
     OrdMap,
     OrdSet,
 };
+use serde::de::DeserializeOwned;
 
 use super::SubscriberId;
 use crate::{
+    from_value,
     ConvexError,
     Value,
 };
@@ -40,6 +42,21 @@ impl From<Result<Value, ErrorPayload<Value>>> for FunctionResult {
     }
 }
 
+impl FunctionResult {
+    /// Deserialize a successful result into `T` with [`from_value`].
+    ///
+    /// Error messages are returned as an [`anyhow::Error`], and
+    /// application errors as a [`ConvexError`] that can be recovered with
+    /// [`anyhow::Error::downcast`].
+    pub fn into_typed<T: DeserializeOwned>(self) -> anyhow::Result<T> {
+        match self {
+            FunctionResult::Value(value) => from_value(value),
+            FunctionResult::ErrorMessage(error) => Err(anyhow::anyhow!(error)),
+            FunctionResult::ConvexError(error) => Err(error.into()),
+        }
+    }
+}
+
 impl From<FunctionResult> for Result<Value, ErrorPayload<Value>> {
     fn from(result: FunctionResult) -> Self {
         match result {
@@ -85,10 +85,10 @@ impl RequestManager {
         ts: Option<Timestamp>,
     ) -> Result<(), ReconnectProtocolReason> {
         let Some((request, _)) = self.ongoing_requests.get_mut(request_id) else {
-            return Err("Invalid request id from server".to_string());
+            return Err("Invalid request id from server".into());
         };
         if request.typ != request_type {
-            return Err("Mismatched request type from server".to_string());
+            return Err("Mismatched request type from server".into());
         };
         let errored = matches!(value, FunctionResult::ErrorMessage(_));
         request.update_value(value);
@@ -153,6 +153,13 @@ impl RequestManager {
         rx
     }
 
+    pub fn num_ongoing(&self, request_type: RequestType) -> usize {
+        self.ongoing_requests
+            .values()
+            .filter(|(request, _)| request.typ == request_type)
+            .count()
+    }
+
     pub fn restart(&self) -> VecDeque<ClientMessage> {
         // Sort ongoing requests by timestamp
         let mut ordered_requests = Vec::from_iter(self.ongoing_requests.values());
@@ -0,0 +1,165 @@
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use convex_sync_types::{
+    backoff::Backoff,
+    AuthenticationToken,
+};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+/// How long before a token expires to fetch a new one.
+const REFRESH_TOKEN_LEEWAY: Duration = Duration::from_secs(10);
+/// Backoff between attempts to fetch a token after the fetcher fails.
+const INITIAL_FETCH_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_FETCH_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Fetches a new auth token, bypassing any cache if the argument is `true`.
+/// Returns `None` if the user is logged out.
+pub type AuthTokenFetcher =
+    Arc<dyn Fn(bool) -> BoxFuture<'static, anyhow::Result<Option<String>>> + Send + Sync>;
+
+/// Tracks the auth token fetcher set with
+/// [`ConvexClient::set_auth_callback`](super::ConvexClient::set_auth_callback)
+/// and when its token needs refreshing.
+pub struct AuthTokenManager {
+    fetcher: Option<AuthTokenFetcher>,
+    refresh_at: Option<Instant>,
+    /// Whether the next scheduled refresh should bypass the provider's cache,
+    /// because it retries a fetch for a token the server rejected.
+    force_next_refresh: bool,
+    backoff: Backoff,
+}
+
+impl Default for AuthTokenManager {
+    fn default() -> Self {
+        Self {
+            fetcher: None,
+            refresh_at: None,
+            force_next_refresh: false,
+            backoff: Backoff::new(INITIAL_FETCH_BACKOFF, MAX_FETCH_BACKOFF),
+        }
+    }
+}
+
+impl AuthTokenManager {
+    pub fn set_fetcher(&mut self, fetcher: Option<AuthTokenFetcher>) {
+        self.fetcher = fetcher;
+        self.refresh_at = None;
+        self.force_next_refresh = false;
+        self.backoff.reset();
+    }
+
+    pub fn has_fetcher(&self) -> bool {
+        self.fetcher.is_some()
+    }
+
+    /// When the current token should be refreshed, either because it expires
+    /// or because fetching it failed.
+    pub fn refresh_at(&self) -> Option<Instant> {
+        self.refresh_at
+    }
+
+    /// Fetch the token whose refresh was scheduled for
+    /// [`refresh_at`](Self::refresh_at).
+    pub async fn refresh_token(&mut self) -> Option<AuthenticationToken> {
+        self.fetch_token(self.force_next_refresh).await
+    }
+
+    /// Fetch a new token and schedule its refresh. Returns `None` if there's
+    /// no fetcher or fetching failed, in which case the current token should
+    /// be kept and the fetch is retried with backoff.
+    pub async fn fetch_token(&mut self, force_refresh: bool) -> Option<AuthenticationToken> {
+        let fetcher = self.fetcher.clone()?;
+        self.refresh_at = None;
+        self.force_next_refresh = false;
+        match fetcher(force_refresh).await {
+            Ok(Some(token)) => {
+                self.backoff.reset();
+                self.refresh_at = token_refresh_delay(&token).map(|delay| Instant::now() + delay);
+                Some(AuthenticationToken::User(token))
+            },
+            Ok(None) => {
+                self.backoff.reset();
+                Some(AuthenticationToken::None)
+            },
+            Err(e) => {
+                let delay = self.backoff.fail(&mut rand::thread_rng());
+                tracing::error!("Failed to fetch auth token: {e:?}. Retrying in {delay:?}");
+                self.refresh_at = Some(Instant::now() + delay);
+                self.force_next_refresh = force_refresh;
+                None
+            },
+        }
+    }
+}
+
+/// Wait until `refresh_at`, or forever if it's `None`.
+pub async fn wait_for_refresh(refresh_at: Option<Instant>) {
+    match refresh_at {
+        Some(refresh_at) => tokio::time::sleep_until(refresh_at).await,
+        None => futures::future::pending().await,
+    }
+}
+
+#[derive(Deserialize)]
+struct JwtClaims {
+    exp: Option<u64>,
+    iat: Option<u64>,
+}
+
+/// How long until a JWT should be refreshed, based on how long it was valid
+/// for when issued. Using the difference between `exp` and `iat` rather than
+/// `exp` alone avoids depending on the local clock. Returns `None` for tokens
+/// that aren't JWTs or don't expire.
+fn token_refresh_delay(token: &str) -> Option<Duration> {
+    let payload = token.split('.').nth(1)?;
+    let payload =
+        base64::decode_config(payload.trim_end_matches('='), base64::URL_SAFE_NO_PAD).ok()?;
+    let claims: JwtClaims = serde_json::from_slice(&payload).ok()?;
+    let validity = Duration::from_secs(claims.exp?.checked_sub(claims.iat?)?);
+    if validity <= REFRESH_TOKEN_LEEWAY {
+        tracing::warn!(
+            "Auth token is valid for {validity:?}, which is too short to refresh before it expires"
+        );
+        return None;
+    }
+    Some(validity - REFRESH_TOKEN_LEEWAY)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::token_refresh_delay;
+
+    pub fn fake_jwt(claims: serde_json::Value) -> String {
+        let encode = |value: serde_json::Value| {
+            base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+        };
+        format!(
+            "{}.{}.signature",
+            encode(json!({"alg": "RS256", "typ": "JWT"})),
+            encode(claims)
+        )
+    }
+
+    #[test]
+    fn test_token_refresh_delay() {
+        assert_eq!(
+            token_refresh_delay(&fake_jwt(json!({"iat": 1000, "exp": 4600}))),
+            Some(Duration::from_secs(3590))
+        );
+        assert_eq!(
+            token_refresh_delay(&fake_jwt(json!({"iat": 1000, "exp": 1005}))),
+            None
+        );
+        assert_eq!(token_refresh_delay(&fake_jwt(json!({"sub": "user"}))), None);
+        assert_eq!(token_refresh_delay("not a jwt"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use std::pin::Pin;
+
+use futures::{
+    task,
+    Stream,
+    StreamExt,
+};
+use tokio_stream::wrappers::WatchStream;
+
+#[cfg(doc)]
+use crate::ConvexClient;
+
+/// Whether a [`ConvexClient`] is connected to its deployment.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConnectionStatus {
+    /// The client hasn't connected yet.
+    #[default]
+    Connecting,
+    /// The client is connected.
+    Connected,
+    /// The connection was lost, and the client is backing off before
+    /// reconnecting. Subscriptions are kept, and requests are queued and
+    /// resent once the client reconnects.
+    Reconnecting,
+    /// Every handle to the client has been dropped, so it won't reconnect.
+    Closed,
+}
+
+/// A snapshot of the connection of a [`ConvexClient`] to its deployment.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConnectionState {
+    /// Whether the client is connected.
+    pub status: ConnectionStatus,
+    /// The number of times the client has connected, including reconnects.
+    pub connection_count: u32,
+    /// The number of times the connection has failed since the client was
+    /// last connected.
+    pub connection_retries: u32,
+    /// Why the connection last failed, if it has.
+    pub last_close_reason: Option<String>,
+    /// The number of mutations that have been sent but whose results aren't
+    /// yet reflected in query results.
+    pub inflight_mutations: usize,
+    /// The number of actions that have been sent but haven't completed.
+    pub inflight_actions: usize,
+}
+
+/// A stream of [`ConnectionState`]s, returned by
+/// [`ConvexClient::watch_connection_state`].
+///
+/// The first item is the current state, and a new item appears each time it
+/// changes. Intermediate states may be skipped if the stream isn't polled
+/// often enough. Once the client is dropped, the stream yields a final state
+/// with [`ConnectionStatus::Closed`] and ends.
+pub struct ConnectionStateSubscription {
+    watch: WatchStream<ConnectionState>,
+    last_state: ConnectionState,
+    closed: bool,
+}
+
+impl ConnectionStateSubscription {
+    pub(super) fn new(watch: WatchStream<ConnectionState>) -> Self {
+        Self {
+            watch,
+            last_state: ConnectionState::default(),
+            closed: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ConnectionStateSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionStateSubscription")
+            .field("last_state", &self.last_state)
+            .finish()
+    }
+}
+
+impl Stream for ConnectionStateSubscription {
+    type Item = ConnectionState;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> task::Poll<Option<Self::Item>> {
+        if self.closed {
+            return task::Poll::Ready(None);
+        }
+        match self.watch.poll_next_unpin(cx) {
+            task::Poll::Ready(Some(state)) => {
+                self.last_state = state.clone();
+                task::Poll::Ready(Some(state))
+            },
+            // The worker owning the sender has shut down.
+            task::Poll::Ready(None) => {
+                self.closed = true;
+                let mut state = self.last_state.clone();
+                state.status = ConnectionStatus::Closed;
+                task::Poll::Ready(Some(state))
+            },
+            task::Poll::Pending => task::Poll::Pending,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     collections::BTreeMap,
     convert::Infallible,
+    future::Future,
     sync::Arc,
 };
 
@@ -16,26 +17,42 @@ use futures::{
         mpsc,
         oneshot,
     },
+    FutureExt,
     SinkExt,
     StreamExt,
 };
+use serde::de::DeserializeOwned;
 use tokio::{
-    sync::broadcast,
+    sync::{
+        broadcast,
+        watch,
+    },
     task::JoinHandle,
 };
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{
+    BroadcastStream,
+    WatchStream,
+};
 use url::Url;
 
-use self::worker::AuthenticateRequest;
-#[cfg(doc)]
-use crate::SubscriberId;
+use self::{
+    auth::AuthTokenFetcher,
+    worker::AuthenticateRequest,
+};
 use crate::{
     base_client::{
         BaseConvexClient,
+        MutationOutbox,
         QueryResults,
     },
     client::{
+        connection_state::{
+            ConnectionState,
+            ConnectionStateSubscription,
+        },
         subscription::{
+            PaginatedSubscription,
+            QueryGroupSubscription,
             QuerySetSubscription,
             QuerySubscription,
         },
@@ -44,6 +61,8 @@ use crate::{
             ActionRequest,
             ClientRequest,
             MutationRequest,
+            SubscribeGroupRequest,
+            SubscribePaginatedRequest,
             SubscribeRequest,
         },
     },
@@ -54,7 +73,15 @@ use crate::{
     value::Value,
     FunctionResult,
 };
+#[cfg(doc)]
+use crate::{
+    PaginatedQueryResult,
+    QueryGroupSnapshot,
+    SubscriberId,
+};
 
+mod auth;
+pub mod connection_state;
 pub mod subscription;
 mod worker;
 
@@ -90,6 +117,7 @@ pub struct ConvexClient {
     listen_handle: Option<Arc<JoinHandle<Infallible>>>,
     request_sender: mpsc::UnboundedSender<ClientRequest>,
     watch_receiver: broadcast::Receiver<QueryResults>,
+    connection_state_receiver: watch::Receiver<ConnectionState>,
 }
 
 /// Clone the [`ConvexClient`], sharing the connection and outstanding
@@ -100,6 +128,7 @@ impl Clone for ConvexClient {
             listen_handle: self.listen_handle.clone(),
             request_sender: self.request_sender.clone(),
             watch_receiver: self.watch_receiver.resubscribe(),
+            connection_state_receiver: self.connection_state_receiver.clone(),
         }
     }
 }
@@ -130,6 +159,37 @@ impl ConvexClient {
     /// # }
     /// ```
     pub async fn new(deployment_url: &str) -> anyhow::Result<Self> {
+        Self::new_with_base_client(deployment_url, BaseConvexClient::new()).await
+    }
+
+    /// Constructs a new client for communicating with `deployment_url` that
+    /// records mutations in `outbox` until the server responds to them.
+    ///
+    /// Mutations made while the client is disconnected survive a restart of
+    /// the process, and are resent in order the next time a client is
+    /// created with the same outbox. See [`MutationOutbox`] for details.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, MutationOutbox};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let outbox = MutationOutbox::open("/var/lib/agent/convex-outbox.json")?;
+    /// let client =
+    ///     ConvexClient::new_with_outbox("https://cool-music-123.convex.cloud", outbox).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_with_outbox(
+        deployment_url: &str,
+        outbox: MutationOutbox,
+    ) -> anyhow::Result<Self> {
+        Self::new_with_base_client(deployment_url, BaseConvexClient::with_outbox(outbox)).await
+    }
+
+    async fn new_with_base_client(
+        deployment_url: &str,
+        base_client: BaseConvexClient,
+    ) -> anyhow::Result<Self> {
         let ws_url = deployment_to_ws_url(deployment_url.try_into()?)?;
 
         // Channels for the `listen` background thread
@@ -139,14 +199,17 @@ impl ConvexClient {
         // Listener for when each transaction completes
         let (watch_sender, watch_receiver) = broadcast::channel(1);
 
-        let base_client = BaseConvexClient::new();
+        let (connection_state_sender, connection_state_receiver) =
+            watch::channel(ConnectionState::default());
 
-        let protocol = WebSocketManager::open(ws_url, response_sender).await?;
+        let protocol =
+            WebSocketManager::open(ws_url, response_sender, base_client.session_id()).await?;
 
         let listen_handle = tokio::spawn(worker(
             response_receiver,
             request_receiver,
             watch_sender,
+            connection_state_sender,
             base_client,
             protocol,
         ));
@@ -154,6 +217,7 @@ impl ConvexClient {
             listen_handle: Some(Arc::new(listen_handle)),
             request_sender,
             watch_receiver,
+            connection_state_receiver,
         };
         Ok(client)
     }
@@ -200,6 +264,122 @@ impl ConvexClient {
         Ok(res)
     }
 
+    /// Subscribe to a paginated query `name` called with `args`, loading
+    /// `initial_num_items` items in the first page.
+    ///
+    /// The query must take a `paginationOpts` argument and return the result
+    /// of `.paginate(args.paginationOpts)`. `args` shouldn't include
+    /// `paginationOpts`; it is added for each page.
+    ///
+    /// Returns a [`PaginatedSubscription`] which implements [`Stream`]<
+    /// [`PaginatedQueryResult`]>. Each item has the items of all loaded pages,
+    /// and a new item appears whenever any page changes. Pages that grow too
+    /// large are split automatically.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, PaginatedQueryResult, PaginationStatus};
+    /// # use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// let mut sub = client
+    ///     .subscribe_paginated("listMessages", maplit::btreemap! {}, 10)
+    ///     .await?;
+    /// while let Some(result) = sub.next().await {
+    ///     if let PaginatedQueryResult::Results { results, status } = result {
+    ///         println!("{} messages", results.len());
+    ///         if status == PaginationStatus::CanLoadMore {
+    ///             sub.load_more(10);
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_paginated(
+        &mut self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+        initial_num_items: usize,
+    ) -> anyhow::Result<PaginatedSubscription> {
+        let (tx, rx) = oneshot::channel();
+
+        let udf_path = name.parse()?;
+        let request = SubscribePaginatedRequest {
+            udf_path,
+            args,
+            initial_num_items,
+        };
+
+        self.request_sender
+            .send(ClientRequest::SubscribePaginated(
+                request,
+                tx,
+                self.request_sender.clone(),
+            ))
+            .await?;
+
+        let res = rx.await?;
+        Ok(res)
+    }
+
+    /// Subscribe to a group of queries whose results are only delivered
+    /// together, at the same server timestamp.
+    ///
+    /// `queries` maps a name for each query to its function name and args.
+    /// Returns a [`QueryGroupSubscription`] which implements [`Stream`]<
+    /// [`QueryGroupSnapshot`]>. Each item has the result of every query in the
+    /// group, keyed by its name, and the timestamp they are all from. Unlike
+    /// [`ConvexClient::watch_all`], items never include a partial group or
+    /// optimistic updates.
+    ///
+    /// ```no_run
+    /// # use convex::ConvexClient;
+    /// # use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// let mut group = client
+    ///     .subscribe_group(maplit::btreemap! {
+    ///         "messages".into() => ("listMessages", maplit::btreemap! {}),
+    ///         "count".into() => ("countMessages", maplit::btreemap! {}),
+    ///     })
+    ///     .await?;
+    /// while let Some(snapshot) = group.next().await {
+    ///     println!(
+    ///         "At {:?}: {:?} {:?}",
+    ///         snapshot.ts,
+    ///         snapshot.get("messages"),
+    ///         snapshot.get("count"),
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_group(
+        &mut self,
+        queries: BTreeMap<String, (&str, BTreeMap<String, Value>)>,
+    ) -> anyhow::Result<QueryGroupSubscription> {
+        let (tx, rx) = oneshot::channel();
+
+        let queries = queries
+            .into_iter()
+            .map(|(key, (name, args))| Ok((key, (name.parse()?, args))))
+            .collect::<anyhow::Result<_>>()?;
+        let request = SubscribeGroupRequest { queries };
+
+        self.request_sender
+            .send(ClientRequest::SubscribeGroup(
+                request,
+                tx,
+                self.request_sender.clone(),
+            ))
+            .await?;
+
+        let res = rx.await?;
+        Ok(res)
+    }
+
     /// Make a oneshot request to a query `name` with `args`.
     ///
     /// Returns a [`FunctionResult`] representing the result of the query.
@@ -300,6 +480,54 @@ impl ConvexClient {
         Ok(res.await?)
     }
 
+    /// Run a query `name` with `args` like [`ConvexClient::query`], and
+    /// deserialize its result into `T` with [`from_value`](crate::from_value).
+    ///
+    /// ```no_run
+    /// # use convex::ConvexClient;
+    /// #[derive(serde::Deserialize)]
+    /// struct Message {
+    ///     #[serde(rename = "_id")]
+    ///     id: String,
+    ///     author: String,
+    ///     body: String,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// let messages: Vec<Message> = client.query_as("listMessages", maplit::btreemap!{}).await?;
+    /// # Ok(())
+    /// # }
+    pub async fn query_as<T: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<T> {
+        self.query(name, args).await?.into_typed()
+    }
+
+    /// Perform a mutation `name` with `args` like [`ConvexClient::mutation`],
+    /// and deserialize its result into `T` with
+    /// [`from_value`](crate::from_value).
+    pub async fn mutation_as<T: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<T> {
+        self.mutation(name, args).await?.into_typed()
+    }
+
+    /// Perform an action `name` with `args` like [`ConvexClient::action`], and
+    /// deserialize its result into `T` with [`from_value`](crate::from_value).
+    pub async fn action_as<T: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<T> {
+        self.action(name, args).await?.into_typed()
+    }
+
     /// Get a consistent view of the results of multiple queries (query set).
     ///
     /// Returns a [`QuerySetSubscription`] which
@@ -334,11 +562,46 @@ impl ConvexClient {
         QuerySetSubscription::new(BroadcastStream::new(self.watch_receiver.resubscribe()))
     }
 
+    /// Get the current state of the client's connection to the deployment.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection_state_receiver.borrow().clone()
+    }
+
+    /// Watch the state of the client's connection to the deployment, for
+    /// example to show when the client is offline or has mutations that
+    /// haven't been confirmed yet.
+    ///
+    /// Returns a [`ConnectionStateSubscription`] which implements
+    /// [`Stream`]<[`ConnectionState`]>, starting with the current state.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, ConnectionStatus};
+    /// # use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// let mut states = client.watch_connection_state();
+    /// while let Some(state) = states.next().await {
+    ///     if state.status == ConnectionStatus::Reconnecting {
+    ///         println!(
+    ///             "Offline: {:?}, {} mutations queued",
+    ///             state.last_close_reason, state.inflight_mutations
+    ///         );
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch_connection_state(&self) -> ConnectionStateSubscription {
+        ConnectionStateSubscription::new(WatchStream::new(self.connection_state_receiver.clone()))
+    }
+
     /// Set auth for use when calling Convex functions.
     ///
     /// Set it with a token that you get from your auth provider via their login
     /// flow. If `None` is passed as the token, then auth is unset (logging
-    /// out).
+    /// out). This replaces any callback set with
+    /// [`ConvexClient::set_auth_callback`].
     pub async fn set_auth(&mut self, token: Option<String>) {
         let req = AuthenticateRequest {
             token: match token {
@@ -352,6 +615,48 @@ impl ConvexClient {
             .expect("INTERNAL BUG: Worker has gone away");
     }
 
+    /// Set auth with a callback that fetches tokens from your auth provider, so
+    /// that the client can get a new token when the current one expires.
+    ///
+    /// `fetch_token` is called right away, shortly before each token it
+    /// returns expires (for JWTs with `iat` and `exp` claims), whenever the
+    /// client reconnects, and when the server rejects a token. Its argument
+    /// is `true` when the provider shouldn't return a cached token because
+    /// the server rejected it. It should return `None` if the user is logged
+    /// out. If it fails, the current token is kept and the call is retried
+    /// with backoff.
+    ///
+    /// Subscriptions are kept when the token changes, including when the
+    /// server rejects a token. The client doesn't handle other requests while
+    /// `fetch_token` is running.
+    ///
+    /// ```no_run
+    /// # use convex::ConvexClient;
+    /// # async fn fetch_token_from_provider(force_refresh: bool) -> anyhow::Result<Option<String>> {
+    /// #     unimplemented!()
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// client
+    ///     .set_auth_callback(|force_refresh| fetch_token_from_provider(force_refresh))
+    ///     .await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_auth_callback<F, Fut>(&mut self, fetch_token: F)
+    where
+        F: Fn(bool) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<Option<String>>> + Send + 'static,
+    {
+        let fetcher: AuthTokenFetcher =
+            Arc::new(move |force_refresh| fetch_token(force_refresh).boxed());
+        self.request_sender
+            .send(ClientRequest::SetAuthFetcher(fetcher))
+            .await
+            .expect("INTERNAL BUG: Worker has gone away");
+    }
+
     /// Set admin auth for use when calling Convex functions as a deployment
     /// admin. Not typically required.
     ///
@@ -390,8 +695,15 @@ fn deployment_to_ws_url(mut deployment_url: Url) -> anyhow::Result<Url> {
 #[cfg(test)]
 pub mod tests {
     use std::{
+        collections::BTreeMap,
         str::FromStr,
-        sync::Arc,
+        sync::{
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+            Arc,
+        },
         time::Duration,
     };
 
@@ -407,26 +719,43 @@ pub mod tests {
         StateVersion,
         UdfPath,
         UserIdentityAttributes,
+        ValueDiff,
     };
     use futures::{
         channel::mpsc,
         StreamExt,
     };
     use maplit::btreemap;
+    use parking_lot::Mutex;
     use pretty_assertions::assert_eq;
     use serde_json::json;
-    use tokio::sync::broadcast;
+    use tokio::sync::{
+        broadcast,
+        watch,
+    };
+    use uuid::Uuid;
 
     use super::ConvexClient;
     use crate::{
-        base_client::FunctionResult,
+        base_client::{
+            FunctionResult,
+            PaginatedQueryResult,
+            PaginationStatus,
+        },
         client::{
+            auth::tests::fake_jwt,
+            connection_state::{
+                ConnectionState,
+                ConnectionStatus,
+            },
             deployment_to_ws_url,
             worker::worker,
             BaseConvexClient,
+            MutationOutbox,
         },
         sync::{
             testing::TestProtocolManager,
+            ReconnectProtocolReason,
             ServerMessage,
             SyncProtocol,
         },
@@ -435,6 +764,12 @@ pub mod tests {
 
     impl ConvexClient {
         pub async fn with_test_protocol() -> anyhow::Result<(Self, TestProtocolManager)> {
+            Self::with_test_protocol_and_base_client(BaseConvexClient::new()).await
+        }
+
+        async fn with_test_protocol_and_base_client(
+            base_client: BaseConvexClient,
+        ) -> anyhow::Result<(Self, TestProtocolManager)> {
             let _ = tracing_subscriber::fmt()
                 .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
                 .try_init();
@@ -446,14 +781,21 @@ pub mod tests {
             // Listener for when each transaction completes
             let (watch_sender, watch_receiver) = broadcast::channel(1);
 
-            let test_protocol =
-                TestProtocolManager::open("ws://test.com".parse()?, response_sender).await?;
-            let base_client = BaseConvexClient::new();
+            let (connection_state_sender, connection_state_receiver) =
+                watch::channel(ConnectionState::default());
+
+            let test_protocol = TestProtocolManager::open(
+                "ws://test.com".parse()?,
+                response_sender,
+                base_client.session_id(),
+            )
+            .await?;
 
             let listen_handle = tokio::spawn(worker(
                 response_receiver,
                 request_receiver,
                 watch_sender,
+                connection_state_sender,
                 base_client,
                 test_protocol.clone(),
             ));
@@ -462,6 +804,7 @@ pub mod tests {
                 listen_handle: Some(Arc::new(listen_handle)),
                 request_sender,
                 watch_receiver,
+                connection_state_receiver,
             };
             Ok((client, test_protocol))
         }
@@ -545,6 +888,79 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_outbox_mutations_sent_on_start() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("convex-outbox-{}.json", Uuid::new_v4()));
+        let mut base_client = BaseConvexClient::with_outbox(MutationOutbox::open(&path)?);
+        let _result = base_client.mutation("incrementCounter".parse()?, btreemap! {});
+        base_client.save_outbox().await;
+        drop(base_client);
+
+        // After a restart, the saved mutation is sent without any other
+        // requests from the app.
+        let (_client, test_protocol) = ConvexClient::with_test_protocol_and_base_client(
+            BaseConvexClient::with_outbox(MutationOutbox::open(&path)?),
+        )
+        .await?;
+        test_protocol.wait_until_n_messages_sent(2).await;
+        let sent = test_protocol.take_sent().await;
+        std::fs::remove_file(&path)?;
+        assert_eq!(
+            sent[1],
+            ClientMessage::Mutation {
+                request_id: 0,
+                udf_path: UdfPath::from_str("incrementCounter")?,
+                args: vec![json!({})],
+                component_path: None,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connection_state() -> anyhow::Result<()> {
+        let (client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+        let mut states = client.watch_connection_state();
+        let mut state = states.next().await.unwrap();
+        if state.status == ConnectionStatus::Connecting {
+            state = states.next().await.unwrap();
+        }
+        assert_eq!(
+            state,
+            ConnectionState {
+                status: ConnectionStatus::Connected,
+                connection_count: 1,
+                ..Default::default()
+            }
+        );
+
+        let mut mutation_client = client.clone();
+        let res = tokio::spawn(async move {
+            mutation_client
+                .mutation("incrementCounter", btreemap! {})
+                .await
+        });
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(states.next().await.unwrap().inflight_mutations, 1);
+
+        // The mutation stays in flight until the transition including it.
+        let (mut_resp, transition) = fake_mutation_response(FunctionResult::Value(Value::Null));
+        test_protocol.fake_server_response(mut_resp).await?;
+        assert_eq!(client.connection_state().inflight_mutations, 1);
+        test_protocol.fake_server_response(transition).await?;
+        res.await??;
+        assert_eq!(states.next().await.unwrap().inflight_mutations, 0);
+
+        drop(client);
+        assert_eq!(
+            states.next().await.unwrap().status,
+            ConnectionStatus::Closed
+        );
+        assert_eq!(states.next().await, None);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_mutation_error() -> anyhow::Result<()> {
         let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
@@ -647,6 +1063,182 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_auth_callback() -> anyhow::Result<()> {
+        let (mut client, test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let tokens = vec![
+            fake_jwt(json!({"iat": 1000, "exp": 4600})),
+            fake_jwt(json!({"iat": 1000, "exp": 4600})),
+        ];
+        let num_fetches = Arc::new(AtomicUsize::new(0));
+        let fetches = num_fetches.clone();
+        let fetched_tokens = tokens.clone();
+        client
+            .set_auth_callback(move |force_refresh| {
+                assert!(!force_refresh);
+                let token = fetched_tokens[fetches.fetch_add(1, Ordering::SeqCst)].clone();
+                async move { Ok(Some(token)) }
+            })
+            .await;
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![ClientMessage::Authenticate {
+                base_version: 0,
+                token: AuthenticationToken::User(tokens[0].clone()),
+            }]
+        );
+
+        // The token is refreshed before it expires.
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![ClientMessage::Authenticate {
+                base_version: 1,
+                token: AuthenticationToken::User(tokens[1].clone()),
+            }]
+        );
+        assert_eq!(num_fetches.load(Ordering::SeqCst), 2);
+
+        // Setting a static token stops refreshing.
+        client.set_auth(None).await;
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![ClientMessage::Authenticate {
+                base_version: 2,
+                token: AuthenticationToken::None,
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_auth_error_refreshes_token() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let tokens = vec![
+            fake_jwt(json!({"iat": 1000, "exp": 4600, "n": 0})),
+            fake_jwt(json!({"iat": 1000, "exp": 4600, "n": 1})),
+        ];
+        let force_refreshes = Arc::new(Mutex::new(vec![]));
+        let fetches = force_refreshes.clone();
+        let fetched_tokens = tokens.clone();
+        client
+            .set_auth_callback(move |force_refresh| {
+                let mut fetches = fetches.lock();
+                let token = fetched_tokens[fetches.len()].clone();
+                fetches.push(force_refresh);
+                async move { Ok(Some(token)) }
+            })
+            .await;
+        test_protocol.wait_until_n_messages_sent(1).await;
+        test_protocol.take_sent().await;
+
+        // The server rejects the token, so the client reconnects with one
+        // fetched bypassing the provider's cache.
+        test_protocol
+            .fake_server_response(ServerMessage::AuthError {
+                error_message: "Token expired".to_string(),
+                base_version: Some(0),
+            })
+            .await?;
+        test_protocol.wait_until_n_messages_sent(2).await;
+        assert!(matches!(
+            &test_protocol.take_reconnect_reasons()[..],
+            [ReconnectProtocolReason::AuthError(_)]
+        ));
+        assert_eq!(*force_refreshes.lock(), vec![false, true]);
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![
+                ClientMessage::Authenticate {
+                    base_version: 0,
+                    token: AuthenticationToken::User(tokens[1].clone()),
+                },
+                ClientMessage::ModifyQuerySet {
+                    base_version: 0,
+                    new_version: 1,
+                    modifications: vec![],
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_diff_mismatch_reconnects() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        let _subscription = client.subscribe("getValue1", btreemap! {}).await?;
+        test_protocol.take_sent().await;
+
+        // The client has no value for the query to apply the diff to.
+        test_protocol
+            .fake_server_response(ServerMessage::Transition {
+                start_version: StateVersion::initial(),
+                end_version: StateVersion {
+                    ts: StateVersion::initial().ts.succ()?,
+                    ..StateVersion::initial()
+                },
+                modifications: vec![StateModification::QueryDiffed {
+                    query_id: QueryId::new(0),
+                    diff: ValueDiff::Object {
+                        updated: BTreeMap::new(),
+                        removed: vec!["count".to_string()],
+                    },
+                    log_lines: LogLinesMessage(vec![]),
+                    journal: None,
+                }],
+            })
+            .await?;
+
+        // The client reconnects with a typed reason, which makes the protocol
+        // stop asking for diffs, and resubscribes.
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert!(matches!(
+            &test_protocol.take_reconnect_reasons()[..],
+            [ReconnectProtocolReason::QueryDiffMismatch]
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_auth_callback_retries_failed_fetch() -> anyhow::Result<()> {
+        let (mut client, test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let token = fake_jwt(json!({"iat": 1000, "exp": 4600}));
+        let num_fetches = Arc::new(AtomicUsize::new(0));
+        let fetches = num_fetches.clone();
+        let fetched_token = token.clone();
+        client
+            .set_auth_callback(move |_| {
+                let result = match fetches.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(anyhow::anyhow!("Auth provider is down")),
+                    _ => Ok(Some(fetched_token.clone())),
+                };
+                async move { result }
+            })
+            .await;
+
+        // The failed fetch is retried after a backoff.
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(num_fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![ClientMessage::Authenticate {
+                base_version: 0,
+                token: AuthenticationToken::User(token),
+            }]
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_client_single_subscription() -> anyhow::Result<()> {
         let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
@@ -661,6 +1253,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -794,6 +1387,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -854,6 +1448,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -905,6 +1500,205 @@ pub mod tests {
         Ok(())
     }
 
+    fn added_queries(messages: Vec<ClientMessage>) -> Vec<(QueryId, serde_json::Value)> {
+        messages
+            .into_iter()
+            .filter_map(|message| match message {
+                ClientMessage::ModifyQuerySet { modifications, .. } => Some(modifications),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|modification| match modification {
+                QuerySetModification::Add(query) => {
+                    Some((query.query_id, query.args[0]["paginationOpts"].clone()))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn fake_page(items: Vec<Value>, is_done: bool, continue_cursor: &str) -> Value {
+        Value::Object(btreemap! {
+            "page".into() => Value::Array(items),
+            "isDone".into() => is_done.into(),
+            "continueCursor".into() => continue_cursor.into(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_paginated_subscription() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        let mut subscription = client
+            .subscribe_paginated("listMessages", btreemap! {}, 2)
+            .await?;
+        let added = added_queries(test_protocol.take_sent().await);
+        assert_eq!(added.len(), 1);
+        let (first_page, opts) = &added[0];
+        assert_eq!(opts["numItems"], json!(2.0));
+        assert_eq!(opts["cursor"], json!(null));
+        assert_eq!(
+            subscription.next().await,
+            Some(PaginatedQueryResult::Results {
+                results: vec![],
+                status: PaginationStatus::LoadingFirstPage,
+            })
+        );
+
+        let (transition, version) = fake_transition(
+            StateVersion::initial(),
+            vec![(
+                *first_page,
+                fake_page(vec![1.into(), 2.into()], false, "c1"),
+            )],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            subscription.next().await,
+            Some(PaginatedQueryResult::Results {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::CanLoadMore,
+            })
+        );
+
+        // Loading more pins the first page to end at its continue cursor and
+        // starts the next page there.
+        subscription.load_more(2);
+        assert_eq!(
+            subscription.next().await,
+            Some(PaginatedQueryResult::Results {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::LoadingMore,
+            })
+        );
+        test_protocol.wait_until_n_messages_sent(2).await;
+        let added = added_queries(test_protocol.take_sent().await);
+        assert_eq!(added.len(), 2);
+        let (pinned_page, opts) = &added[0];
+        assert_eq!(opts["cursor"], json!(null));
+        assert_eq!(opts["endCursor"], json!("c1"));
+        let (second_page, opts) = &added[1];
+        assert_eq!(opts["cursor"], json!("c1"));
+
+        let (transition, _version) = fake_transition(
+            version,
+            vec![
+                (
+                    *pinned_page,
+                    fake_page(vec![1.into(), 2.into()], false, "c1"),
+                ),
+                (*second_page, fake_page(vec![3.into()], true, "c2")),
+            ],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            subscription.next().await,
+            Some(PaginatedQueryResult::Results {
+                results: vec![1.into(), 2.into(), 3.into()],
+                status: PaginationStatus::Exhausted,
+            })
+        );
+        // The unpinned first page is replaced by the pinned one.
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![ClientMessage::ModifyQuerySet {
+                base_version: 3,
+                new_version: 4,
+                modifications: vec![QuerySetModification::Remove {
+                    query_id: *first_page
+                }],
+            }]
+        );
+
+        drop(subscription);
+        test_protocol.wait_until_n_messages_sent(2).await;
+        assert_eq!(test_protocol.take_sent().await.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_group_subscription() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        let mut group = client
+            .subscribe_group(btreemap! {
+                "messages".into() => ("listMessages", btreemap! {}),
+                "count".into() => ("countMessages", btreemap! {}),
+            })
+            .await?;
+        let added: Vec<_> = test_protocol
+            .take_sent()
+            .await
+            .into_iter()
+            .filter_map(|message| match message {
+                ClientMessage::ModifyQuerySet { modifications, .. } => Some(modifications),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|modification| match modification {
+                QuerySetModification::Add(query) => Some((query.udf_path, query.query_id)),
+                _ => None,
+            })
+            .collect();
+        let query_id = |name: &str| {
+            added
+                .iter()
+                .find(|(udf_path, _)| *udf_path == UdfPath::from_str(name).unwrap())
+                .map(|(_, query_id)| *query_id)
+                .unwrap()
+        };
+        let (messages, count) = (query_id("listMessages"), query_id("countMessages"));
+
+        // A transition with only some of the group's results isn't delivered.
+        let (transition, version) =
+            fake_transition(StateVersion::initial(), vec![(messages, 1.into())]);
+        test_protocol.fake_server_response(transition).await?;
+        let (transition, version) = fake_transition(version, vec![(count, 1.into())]);
+        test_protocol.fake_server_response(transition).await?;
+        let snapshot = group.next().await.expect("group closed");
+        assert_eq!(snapshot.ts, version.ts);
+        assert_eq!(
+            snapshot.get("messages"),
+            Some(&FunctionResult::Value(1.into()))
+        );
+        assert_eq!(
+            snapshot.get("count"),
+            Some(&FunctionResult::Value(1.into()))
+        );
+
+        let (transition, version) =
+            fake_transition(version, vec![(messages, 2.into()), (count, 2.into())]);
+        test_protocol.fake_server_response(transition).await?;
+        let snapshot = group.next().await.expect("group closed");
+        assert_eq!(snapshot.ts, version.ts);
+        assert_eq!(
+            snapshot.get("messages"),
+            Some(&FunctionResult::Value(2.into()))
+        );
+        assert_eq!(
+            snapshot.get("count"),
+            Some(&FunctionResult::Value(2.into()))
+        );
+
+        drop(group);
+        test_protocol.wait_until_n_messages_sent(2).await;
+        assert_eq!(
+            test_protocol.take_sent().await,
+            vec![
+                ClientMessage::ModifyQuerySet {
+                    base_version: 2,
+                    new_version: 3,
+                    modifications: vec![QuerySetModification::Remove { query_id: count }],
+                },
+                ClientMessage::ModifyQuerySet {
+                    base_version: 3,
+                    new_version: 4,
+                    modifications: vec![QuerySetModification::Remove { query_id: messages }],
+                },
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_deployment_url() -> anyhow::Result<()> {
         assert_eq!(
@@ -4,7 +4,10 @@ use std::{
 };
 
 use futures::{
-    channel::mpsc::UnboundedSender,
+    channel::mpsc::{
+        UnboundedReceiver,
+        UnboundedSender,
+    },
     task,
     Stream,
     StreamExt,
@@ -17,11 +20,18 @@ use tokio_stream::wrappers::{
 use crate::{
     base_client::{
         FunctionResult,
+        PaginatedQueryResult,
+        QueryGroupSnapshot,
         QueryResults,
         SubscriberId,
     },
     client::worker::{
         ClientRequest,
+        LoadMoreRequest,
+        PaginationId,
+        QueryGroupId,
+        UnsubscribeGroupRequest,
+        UnsubscribePaginatedRequest,
         UnsubscribeRequest,
     },
 };
@@ -147,3 +157,123 @@ impl Stream for QuerySetSubscription {
         }
     }
 }
+
+/// A subscription to a named group of queries, returned by
+/// [`ConvexClient::subscribe_group`].
+///
+/// [`QueryGroupSubscription`] implements [`Stream`]<[`QueryGroupSnapshot`]>.
+/// Each item has a result for every query in the group, all from the same
+/// server timestamp. Nothing appears until every query has a result, and a new
+/// item appears whenever any of them changes.
+///
+/// All queries in the group are unsubscribed when this is dropped.
+pub struct QueryGroupSubscription {
+    pub(super) group_id: QueryGroupId,
+    pub(super) request_sender: UnboundedSender<ClientRequest>,
+    pub(super) results: UnboundedReceiver<QueryGroupSnapshot>,
+}
+impl std::fmt::Debug for QueryGroupSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryGroupSubscription")
+            .field("group_id", &self.group_id)
+            .finish()
+    }
+}
+impl Drop for QueryGroupSubscription {
+    fn drop(&mut self) {
+        let _ = self
+            .request_sender
+            .unbounded_send(ClientRequest::UnsubscribeGroup(UnsubscribeGroupRequest {
+                group_id: self.group_id,
+            }));
+    }
+}
+impl Stream for QueryGroupSubscription {
+    type Item = QueryGroupSnapshot;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> task::Poll<Option<Self::Item>> {
+        self.results.poll_next_unpin(cx)
+    }
+}
+
+/// A subscription to a paginated query, returned by
+/// [`ConvexClient::subscribe_paginated`].
+///
+/// [`PaginatedSubscription`] implements [`Stream`]<[`PaginatedQueryResult`]>.
+/// Each item contains the items of all loaded pages, concatenated, and is
+/// updated whenever any page changes. Call
+/// [`load_more`](PaginatedSubscription::load_more) to add another page.
+///
+/// All page subscriptions are unsubscribed when this is dropped.
+pub struct PaginatedSubscription {
+    pub(super) load_more_handle: LoadMoreHandle,
+    pub(super) results: UnboundedReceiver<PaginatedQueryResult>,
+}
+impl PaginatedSubscription {
+    /// Load another page with `num_items` items. Does nothing if a page is
+    /// still loading or there are no more pages, so it is safe to call
+    /// repeatedly.
+    pub fn load_more(&self, num_items: usize) {
+        self.load_more_handle.load_more(num_items);
+    }
+
+    /// Returns a handle that can load more pages while the stream is being
+    /// polled elsewhere.
+    pub fn load_more_handle(&self) -> LoadMoreHandle {
+        self.load_more_handle.clone()
+    }
+}
+impl std::fmt::Debug for PaginatedSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PaginatedSubscription")
+            .field("pagination_id", &self.load_more_handle.pagination_id)
+            .finish()
+    }
+}
+impl Drop for PaginatedSubscription {
+    fn drop(&mut self) {
+        let _ = self.load_more_handle.request_sender.unbounded_send(
+            ClientRequest::UnsubscribePaginated(UnsubscribePaginatedRequest {
+                pagination_id: self.load_more_handle.pagination_id,
+            }),
+        );
+    }
+}
+impl Stream for PaginatedSubscription {
+    type Item = PaginatedQueryResult;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> task::Poll<Option<Self::Item>> {
+        self.results.poll_next_unpin(cx)
+    }
+}
+
+/// Loads more pages of a [`PaginatedSubscription`].
+#[derive(Clone)]
+pub struct LoadMoreHandle {
+    pub(super) pagination_id: PaginationId,
+    pub(super) request_sender: UnboundedSender<ClientRequest>,
+}
+impl std::fmt::Debug for LoadMoreHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadMoreHandle")
+            .field("pagination_id", &self.pagination_id)
+            .finish()
+    }
+}
+impl LoadMoreHandle {
+    /// See [`PaginatedSubscription::load_more`].
+    pub fn load_more(&self, num_items: usize) {
+        let _ = self
+            .request_sender
+            .unbounded_send(ClientRequest::LoadMore(LoadMoreRequest {
+                pagination_id: self.pagination_id,
+                num_items,
+            }));
+    }
+}
@@ -18,15 +18,36 @@ use futures::{
     FutureExt,
     StreamExt,
 };
-use tokio::sync::broadcast;
+use tokio::sync::{
+    broadcast,
+    watch,
+};
 use tokio_stream::wrappers::BroadcastStream;
 
 use crate::{
     base_client::{
         BaseConvexClient,
+        PaginatedQuery,
+        PaginatedQueryResult,
+        QueryGroup,
+        QueryGroupSnapshot,
         SubscriberId,
     },
     client::{
+        auth::{
+            wait_for_refresh,
+            AuthTokenFetcher,
+            AuthTokenManager,
+        },
+        connection_state::{
+            ConnectionState,
+            ConnectionStatus,
+        },
+        subscription::{
+            LoadMoreHandle,
+            PaginatedSubscription,
+            QueryGroupSubscription,
+        },
         QueryResults,
         QuerySubscription,
     },
@@ -58,7 +79,21 @@ pub enum ClientRequest {
         mpsc::UnboundedSender<ClientRequest>,
     ),
     Unsubscribe(UnsubscribeRequest),
+    SubscribePaginated(
+        SubscribePaginatedRequest,
+        oneshot::Sender<PaginatedSubscription>,
+        mpsc::UnboundedSender<ClientRequest>,
+    ),
+    LoadMore(LoadMoreRequest),
+    UnsubscribePaginated(UnsubscribePaginatedRequest),
+    SubscribeGroup(
+        SubscribeGroupRequest,
+        oneshot::Sender<QueryGroupSubscription>,
+        mpsc::UnboundedSender<ClientRequest>,
+    ),
+    UnsubscribeGroup(UnsubscribeGroupRequest),
     Authenticate(AuthenticateRequest),
+    SetAuthFetcher(AuthTokenFetcher),
 }
 
 pub struct MutationRequest {
@@ -76,6 +111,39 @@ pub struct SubscribeRequest {
     pub args: BTreeMap<String, Value>,
 }
 
+pub struct SubscribePaginatedRequest {
+    pub udf_path: UdfPath,
+    pub args: BTreeMap<String, Value>,
+    pub initial_num_items: usize,
+}
+
+/// Identifies a [`PaginatedSubscription`] within the worker.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct PaginationId(usize);
+
+pub struct LoadMoreRequest {
+    pub pagination_id: PaginationId,
+    pub num_items: usize,
+}
+
+#[derive(Debug)]
+pub struct UnsubscribePaginatedRequest {
+    pub pagination_id: PaginationId,
+}
+
+pub struct SubscribeGroupRequest {
+    pub queries: BTreeMap<String, (UdfPath, BTreeMap<String, Value>)>,
+}
+
+/// Identifies a [`QueryGroupSubscription`] within the worker.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub struct QueryGroupId(usize);
+
+#[derive(Debug)]
+pub struct UnsubscribeGroupRequest {
+    pub group_id: QueryGroupId,
+}
+
 pub struct AuthenticateRequest {
     pub token: AuthenticationToken,
 }
@@ -90,30 +158,56 @@ pub async fn worker<T: SyncProtocol>(
 
     mut client_request_receiver: mpsc::UnboundedReceiver<ClientRequest>,
     mut watch_sender: broadcast::Sender<QueryResults>,
+    connection_state: watch::Sender<ConnectionState>,
     mut base_client: BaseConvexClient,
     mut protocol_manager: T,
 ) -> Infallible {
     let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+    let mut paginated_queries = PaginatedQueries::default();
+    let mut query_groups = QueryGroups::default();
+    let mut auth = AuthTokenManager::default();
+    // Send mutations restored from the outbox without waiting for another
+    // request.
+    flush_messages(&mut base_client, &mut protocol_manager).await;
     loop {
         let e = loop {
-            match _worker_once(
+            let result = _worker_once(
                 &mut protocol_response_receiver,
                 &mut client_request_receiver,
                 &mut watch_sender,
+                &connection_state,
                 &mut base_client,
+                &mut paginated_queries,
+                &mut query_groups,
+                &mut auth,
                 &mut protocol_manager,
             )
-            .await
-            {
+            .await;
+            update_inflight_requests(&connection_state, &base_client);
+            match result {
                 Ok(()) => backoff.reset(),
                 Err(e) => break e,
             }
         };
+        connection_state.send_modify(|state| {
+            state.status = ConnectionStatus::Reconnecting;
+            state.connection_retries += 1;
+            state.last_close_reason = Some(e.to_string());
+        });
 
         let delay = backoff.fail(&mut rand::thread_rng());
         tracing::error!(
-            "Convex Client Worker failed: {e:?}. Backing off for {delay:?} and retrying."
+            "Convex Client Worker failed: {e}. Backing off for {delay:?} and retrying."
         );
+        // Fetch a token to authenticate the new connection with, since the
+        // current one may have expired while disconnected. If the server
+        // rejected it, bypass the provider's cache.
+        if auth.has_fetcher() {
+            let force_refresh = matches!(e, ReconnectProtocolReason::AuthError(_));
+            if let Some(token) = auth.fetch_token(force_refresh).await {
+                base_client.set_auth_for_reconnect(token);
+            }
+        }
         // Tell the sync protocol to reconnect followed by an immediate resend of
         // ongoing queries/mutations. It's important these happen together to
         // ensure mutation ordering.
@@ -129,25 +223,46 @@ pub async fn worker<T: SyncProtocol>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn _worker_once<T: SyncProtocol>(
     protocol_response_receiver: &mut mpsc::Receiver<ProtocolResponse>,
 
     client_request_receiver: &mut mpsc::UnboundedReceiver<ClientRequest>,
     watch_sender: &mut broadcast::Sender<QueryResults>,
+    connection_state: &watch::Sender<ConnectionState>,
     base_client: &mut BaseConvexClient,
+    paginated_queries: &mut PaginatedQueries,
+    query_groups: &mut QueryGroups,
+    auth: &mut AuthTokenManager,
     protocol_manager: &mut T,
 ) -> Result<(), ReconnectProtocolReason> {
+    let refresh_auth_at = auth.refresh_at();
     select_biased! {
         protocol_response = protocol_response_receiver.next().fuse() => {
             match protocol_response {
                 Some(ProtocolResponse::ServerMessage(msg)) => {
-                    if let Some(subscriber_id_to_latest_value) = base_client.receive_message(msg)? {
+                    let results = base_client.receive_message(msg)?;
+                    // Drop mutations the server responded to from the outbox.
+                    base_client.save_outbox().await;
+                    if let Some(subscriber_id_to_latest_value) = results {
                         // Notify watchers of the new consistent query results at new timestamp
                         let _ = watch_sender.send(subscriber_id_to_latest_value);
+                        // Updating paginated queries may subscribe to new pages.
+                        paginated_queries.update(base_client);
+                        query_groups.update(base_client);
+                        flush_messages(base_client, protocol_manager).await;
                     }
                 },
-                Some(ProtocolResponse::Failure) => {
-                    return Err("ProtocolFailure".into());
+                Some(ProtocolResponse::Connected) => {
+                    connection_state.send_modify(|state| {
+                        state.status = ConnectionStatus::Connected;
+                        state.connection_count += 1;
+                        state.connection_retries = 0;
+                    });
+                    flush_messages(base_client, protocol_manager).await;
+                },
+                Some(ProtocolResponse::Failure(reason)) => {
+                    return Err(reason);
                 },
                 None => {},
             }
@@ -179,6 +294,11 @@ async fn _worker_once<T: SyncProtocol>(
                     } = mutation;
                     let result_receiver = base_client
                         .mutation(udf_path, args);
+                    // The mutation must be saved before it's sent. If it can't
+                    // be, it fails and any optimistic update is rolled back.
+                    if let Some(results) = base_client.save_outbox().await {
+                        let _ = watch_sender.send(results);
+                    }
                     flush_messages(base_client, protocol_manager).await;
                     let _ = tx.send(result_receiver);
                 },
@@ -197,16 +317,219 @@ async fn _worker_once<T: SyncProtocol>(
                     base_client.unsubscribe(subscriber_id);
                     flush_messages(base_client, protocol_manager).await;
                 },
+                ClientRequest::SubscribePaginated(request, tx, request_sender) => {
+                    let SubscribePaginatedRequest {
+                        udf_path,
+                        args,
+                        initial_num_items,
+                    } = request;
+                    let (pagination_id, results) = paginated_queries.subscribe(
+                        base_client,
+                        udf_path,
+                        args,
+                        initial_num_items,
+                    );
+                    paginated_queries.update(base_client);
+                    flush_messages(base_client, protocol_manager).await;
+                    let subscription = PaginatedSubscription {
+                        load_more_handle: LoadMoreHandle {
+                            pagination_id,
+                            request_sender,
+                        },
+                        results,
+                    };
+                    let _ = tx.send(subscription);
+                },
+                ClientRequest::LoadMore(load_more) => {
+                    let LoadMoreRequest {
+                        pagination_id,
+                        num_items,
+                    } = load_more;
+                    if paginated_queries.load_more(base_client, pagination_id, num_items) {
+                        paginated_queries.update(base_client);
+                        flush_messages(base_client, protocol_manager).await;
+                    }
+                },
+                ClientRequest::UnsubscribePaginated(unsubscribe) => {
+                    let UnsubscribePaginatedRequest {pagination_id} = unsubscribe;
+                    paginated_queries.unsubscribe(base_client, pagination_id);
+                    flush_messages(base_client, protocol_manager).await;
+                },
+                ClientRequest::SubscribeGroup(request, tx, request_sender) => {
+                    let SubscribeGroupRequest { queries } = request;
+                    let (group_id, results) = query_groups.subscribe(base_client, queries);
+                    // Other subscriptions may already have results for every
+                    // query in the group.
+                    query_groups.update(base_client);
+                    flush_messages(base_client, protocol_manager).await;
+                    let subscription = QueryGroupSubscription {
+                        group_id,
+                        request_sender,
+                        results,
+                    };
+                    let _ = tx.send(subscription);
+                },
+                ClientRequest::UnsubscribeGroup(unsubscribe) => {
+                    let UnsubscribeGroupRequest { group_id } = unsubscribe;
+                    query_groups.unsubscribe(base_client, group_id);
+                    flush_messages(base_client, protocol_manager).await;
+                },
                 ClientRequest::Authenticate(authenticate) => {
+                    auth.set_fetcher(None);
                     base_client.set_auth(authenticate.token);
                     flush_messages(base_client, protocol_manager).await;
                 },
+                ClientRequest::SetAuthFetcher(fetcher) => {
+                    auth.set_fetcher(Some(fetcher));
+                    if let Some(token) = auth.fetch_token(false).await {
+                        base_client.set_auth(token);
+                        flush_messages(base_client, protocol_manager).await;
+                    }
+                },
+            }
+        }
+        _ = wait_for_refresh(refresh_auth_at).fuse() => {
+            // The token is about to expire, or fetching it failed.
+            if let Some(token) = auth.refresh_token().await {
+                base_client.set_auth(token);
+                flush_messages(base_client, protocol_manager).await;
             }
         }
     }
     Ok(())
 }
 
+struct PaginatedQueryState {
+    query: PaginatedQuery,
+    sender: mpsc::UnboundedSender<PaginatedQueryResult>,
+    last_result: Option<PaginatedQueryResult>,
+}
+
+/// The paginated queries subscribed to through [`PaginatedSubscription`]s.
+#[derive(Default)]
+struct PaginatedQueries {
+    next_id: usize,
+    queries: BTreeMap<PaginationId, PaginatedQueryState>,
+}
+
+impl PaginatedQueries {
+    fn subscribe(
+        &mut self,
+        base_client: &mut BaseConvexClient,
+        udf_path: UdfPath,
+        args: BTreeMap<String, Value>,
+        initial_num_items: usize,
+    ) -> (PaginationId, mpsc::UnboundedReceiver<PaginatedQueryResult>) {
+        let pagination_id = PaginationId(self.next_id);
+        self.next_id += 1;
+        let (sender, receiver) = mpsc::unbounded();
+        let query = PaginatedQuery::new(base_client, udf_path, args, initial_num_items);
+        self.queries.insert(
+            pagination_id,
+            PaginatedQueryState {
+                query,
+                sender,
+                last_result: None,
+            },
+        );
+        (pagination_id, receiver)
+    }
+
+    fn load_more(
+        &mut self,
+        base_client: &mut BaseConvexClient,
+        pagination_id: PaginationId,
+        num_items: usize,
+    ) -> bool {
+        match self.queries.get_mut(&pagination_id) {
+            Some(state) => state.query.load_more(base_client, num_items),
+            None => false,
+        }
+    }
+
+    fn unsubscribe(&mut self, base_client: &mut BaseConvexClient, pagination_id: PaginationId) {
+        if let Some(state) = self.queries.remove(&pagination_id) {
+            state.query.unsubscribe(base_client);
+        }
+    }
+
+    /// Send each paginated query's results to its subscription if they have
+    /// changed.
+    fn update(&mut self, base_client: &mut BaseConvexClient) {
+        for state in self.queries.values_mut() {
+            let result = state.query.update(base_client);
+            if state.last_result.as_ref() != Some(&result) {
+                let _ = state.sender.unbounded_send(result.clone());
+                state.last_result = Some(result);
+            }
+        }
+    }
+}
+
+struct QueryGroupState {
+    group: QueryGroup,
+    sender: mpsc::UnboundedSender<QueryGroupSnapshot>,
+}
+
+/// The query groups subscribed to through [`QueryGroupSubscription`]s.
+#[derive(Default)]
+struct QueryGroups {
+    next_id: usize,
+    groups: BTreeMap<QueryGroupId, QueryGroupState>,
+}
+
+impl QueryGroups {
+    fn subscribe(
+        &mut self,
+        base_client: &mut BaseConvexClient,
+        queries: BTreeMap<String, (UdfPath, BTreeMap<String, Value>)>,
+    ) -> (QueryGroupId, mpsc::UnboundedReceiver<QueryGroupSnapshot>) {
+        let group_id = QueryGroupId(self.next_id);
+        self.next_id += 1;
+        let (sender, receiver) = mpsc::unbounded();
+        let group = QueryGroup::new(base_client, queries);
+        self.groups
+            .insert(group_id, QueryGroupState { group, sender });
+        (group_id, receiver)
+    }
+
+    fn unsubscribe(&mut self, base_client: &mut BaseConvexClient, group_id: QueryGroupId) {
+        if let Some(state) = self.groups.remove(&group_id) {
+            state.group.unsubscribe(base_client);
+        }
+    }
+
+    /// Send each group's results to its subscription if all of them have
+    /// loaded and any have changed.
+    fn update(&mut self, base_client: &BaseConvexClient) {
+        for state in self.groups.values_mut() {
+            if let Some(snapshot) = state.group.update(base_client) {
+                let _ = state.sender.unbounded_send(snapshot);
+            }
+        }
+    }
+}
+
+/// Publish the number of in-flight mutations and actions to the connection
+/// state, notifying watchers only if it changed.
+fn update_inflight_requests(
+    connection_state: &watch::Sender<ConnectionState>,
+    base_client: &BaseConvexClient,
+) {
+    let inflight_mutations = base_client.inflight_mutations();
+    let inflight_actions = base_client.inflight_actions();
+    connection_state.send_if_modified(|state| {
+        if state.inflight_mutations == inflight_mutations
+            && state.inflight_actions == inflight_actions
+        {
+            return false;
+        }
+        state.inflight_mutations = inflight_mutations;
+        state.inflight_actions = inflight_actions;
+        true
+    });
+}
+
 /// Flush all messages to the protocol
 async fn flush_messages<P: SyncProtocol>(base_client: &mut BaseConvexClient, protocol: &mut P) {
     while let Some(modification) = base_client.pop_next_message() {
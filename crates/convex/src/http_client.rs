@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use convex_sync_types::UserIdentityAttributes;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use url::Url;
+
+use crate::{
+    convex_logs,
+    value::Value,
+    ConvexError,
+    FunctionResult,
+};
+
+const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
+
+/// A stateless client for calling Convex functions over HTTP.
+///
+/// Unlike [`ConvexClient`](crate::ConvexClient), this client doesn't open a
+/// websocket, so it can't subscribe to queries. Each call is a single HTTP
+/// request, which makes it a better fit for CLI tools and serverless jobs that
+/// only make a few calls.
+///
+/// Log lines from function calls are emitted with the `convex_logs` tracing
+/// target, as they are for [`ConvexClient`](crate::ConvexClient).
+///
+/// ```no_run
+/// # use convex::ConvexHttpClient;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let client = ConvexHttpClient::new("https://cool-music-123.convex.cloud")?;
+/// let result = client
+///     .mutation(
+///         "sendMessage",
+///         maplit::btreemap! {
+///             "body".into() => "Let it be.".into(),
+///             "author".into() => "The Beatles".into(),
+///         },
+///     )
+///     .await?;
+/// println!("{result:?}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConvexHttpClient {
+    http_client: reqwest::Client,
+    deployment_url: Url,
+    /// The value of the `Authorization` header, if any.
+    authorization: Option<String>,
+}
+
+/// An opaque timestamp to run queries at with
+/// [`ConvexHttpClient::query_at_ts`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QueryTimestamp(String);
+
+#[derive(Serialize)]
+struct UdfPostRequest<'a> {
+    path: &'a str,
+    args: JsonValue,
+    format: &'static str,
+}
+
+#[derive(Serialize)]
+struct UdfPostWithTsRequest<'a> {
+    path: &'a str,
+    args: JsonValue,
+    ts: &'a QueryTimestamp,
+    format: &'static str,
+}
+
+#[derive(Serialize)]
+struct QueryBatchRequest<'a> {
+    queries: Vec<UdfPostRequest<'a>>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "status")]
+#[serde(rename_all = "camelCase")]
+enum UdfResponse {
+    #[serde(rename_all = "camelCase")]
+    Success {
+        value: JsonValue,
+        #[serde(default)]
+        log_lines: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error {
+        error_message: String,
+        error_data: Option<JsonValue>,
+        #[serde(default)]
+        log_lines: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct QueryBatchResponse {
+    results: Vec<UdfResponse>,
+}
+
+#[derive(Deserialize)]
+struct QueryTimestampResponse {
+    ts: QueryTimestamp,
+}
+
+impl ConvexHttpClient {
+    /// Constructs a new client for communicating with `deployment_url`. No
+    /// requests are made until a function is called.
+    pub fn new(deployment_url: &str) -> anyhow::Result<Self> {
+        let deployment_url: Url = deployment_url.try_into()?;
+        match deployment_url.scheme() {
+            "http" | "https" => (),
+            scheme => anyhow::bail!("Unknown scheme {scheme}. Expected http or https."),
+        }
+        let version = VERSION.unwrap_or("unknown");
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            "Convex-Client",
+            format!("rust-{version}")
+                .try_into()
+                .context("Bad version")?,
+        );
+        let http_client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+        Ok(Self {
+            http_client,
+            deployment_url,
+            authorization: None,
+        })
+    }
+
+    /// Run the query `name` with `args` and return its result.
+    pub async fn query(
+        &self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<FunctionResult> {
+        self.call("api/query", &udf_request(name, args)).await
+    }
+
+    /// Perform the mutation `name` with `args` and return its result.
+    pub async fn mutation(
+        &self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<FunctionResult> {
+        self.call("api/mutation", &udf_request(name, args)).await
+    }
+
+    /// Perform the action `name` with `args` and return its result.
+    pub async fn action(
+        &self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+    ) -> anyhow::Result<FunctionResult> {
+        self.call("api/action", &udf_request(name, args)).await
+    }
+
+    /// Get the timestamp of the latest data in the deployment, for use with
+    /// [`ConvexHttpClient::query_at_ts`].
+    pub async fn query_ts(&self) -> anyhow::Result<QueryTimestamp> {
+        let response: QueryTimestampResponse = self.post("api/query_ts", &()).await?;
+        Ok(response.ts)
+    }
+
+    /// Run the query `name` with `args` at a timestamp returned by
+    /// [`ConvexHttpClient::query_ts`]. Queries run at the same timestamp see a
+    /// consistent snapshot of the database.
+    pub async fn query_at_ts(
+        &self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+        ts: &QueryTimestamp,
+    ) -> anyhow::Result<FunctionResult> {
+        let request = UdfPostWithTsRequest {
+            path: name,
+            args: Value::Object(args).into(),
+            ts,
+            format: "convex_encoded_json",
+        };
+        self.call("api/query_at_ts", &request).await
+    }
+
+    /// Run several queries at the same timestamp, returning their results in
+    /// the same order as `queries`.
+    ///
+    /// ```no_run
+    /// # use convex::ConvexHttpClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let client = ConvexHttpClient::new("https://cool-music-123.convex.cloud")?;
+    /// let results = client
+    ///     .query_batch(vec![
+    ///         ("listMessages", maplit::btreemap! {}),
+    ///         ("countMessages", maplit::btreemap! {}),
+    ///     ])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn query_batch(
+        &self,
+        queries: Vec<(&str, BTreeMap<String, Value>)>,
+    ) -> anyhow::Result<Vec<FunctionResult>> {
+        let request = QueryBatchRequest {
+            queries: queries
+                .into_iter()
+                .map(|(name, args)| udf_request(name, args))
+                .collect(),
+        };
+        let response: QueryBatchResponse = self.post("api/query_batch", &request).await?;
+        response.results.into_iter().map(function_result).collect()
+    }
+
+    /// Set auth for use when calling Convex functions.
+    ///
+    /// Set it with a token that you get from your auth provider via their login
+    /// flow. If `None` is passed as the token, then auth is unset (logging
+    /// out).
+    pub fn set_auth(&mut self, token: Option<String>) {
+        self.authorization = token.map(|token| format!("Bearer {token}"));
+    }
+
+    /// Set admin auth for use when calling Convex functions as a deployment
+    /// admin. Not typically required.
+    ///
+    /// You can get a deploy_key from the Convex dashboard's deployment settings
+    /// page. Deployment admins can act as users as part of their
+    /// development flow to see how a function would act.
+    #[doc(hidden)]
+    pub fn set_admin_auth(
+        &mut self,
+        deploy_key: String,
+        acting_as: Option<UserIdentityAttributes>,
+    ) -> anyhow::Result<()> {
+        self.authorization = Some(match acting_as {
+            None => format!("Convex {deploy_key}"),
+            Some(attributes) => {
+                let attributes: JsonValue = attributes.try_into()?;
+                let acting_as = base64::encode(serde_json::to_vec(&attributes)?);
+                format!("Convex {deploy_key}:{acting_as}")
+            },
+        });
+        Ok(())
+    }
+
+    async fn call<T: Serialize>(&self, path: &str, request: &T) -> anyhow::Result<FunctionResult> {
+        let response: UdfResponse = self.post(path, request).await?;
+        function_result(response)
+    }
+
+    async fn post<T: Serialize, R: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        request: &T,
+    ) -> anyhow::Result<R> {
+        let url = self.deployment_url.join(path)?;
+        let mut builder = self.http_client.post(url.clone()).json(request);
+        if let Some(ref authorization) = self.authorization {
+            builder = builder.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        let response = builder
+            .send()
+            .await
+            .with_context(|| format!("Request to {url} failed"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Request to {url} failed with {status}: {body}");
+        }
+        Ok(response.json().await?)
+    }
+}
+
+fn udf_request(name: &str, args: BTreeMap<String, Value>) -> UdfPostRequest<'_> {
+    UdfPostRequest {
+        path: name,
+        args: Value::Object(args).into(),
+        format: "convex_encoded_json",
+    }
+}
+
+fn function_result(response: UdfResponse) -> anyhow::Result<FunctionResult> {
+    let result = match response {
+        UdfResponse::Success { value, log_lines } => {
+            for log_line in log_lines {
+                convex_logs!("{}", log_line);
+            }
+            FunctionResult::Value(value.try_into()?)
+        },
+        UdfResponse::Error {
+            error_message,
+            error_data,
+            log_lines,
+        } => {
+            for log_line in log_lines {
+                convex_logs!("{}", log_line);
+            }
+            match error_data {
+                Some(data) => FunctionResult::ConvexError(ConvexError {
+                    message: error_message,
+                    data: data.try_into()?,
+                }),
+                None => FunctionResult::ErrorMessage(error_message),
+            }
+        },
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        function_result,
+        ConvexHttpClient,
+        UdfResponse,
+    };
+    use crate::{
+        ConvexError,
+        FunctionResult,
+        Value,
+    };
+
+    #[test]
+    fn test_new_requires_http_url() {
+        assert!(ConvexHttpClient::new("https://cool-music-123.convex.cloud").is_ok());
+        assert!(ConvexHttpClient::new("http://127.0.0.1:3210").is_ok());
+        assert!(ConvexHttpClient::new("wss://cool-music-123.convex.cloud").is_err());
+    }
+
+    #[test]
+    fn test_udf_response_to_function_result() -> anyhow::Result<()> {
+        let success: UdfResponse = serde_json::from_value(json!({
+            "status": "success",
+            "value": { "$integer": "AQAAAAAAAAA=" },
+            "logLines": ["[LOG] 'hello'"],
+        }))?;
+        assert_eq!(
+            function_result(success)?,
+            FunctionResult::Value(Value::Int64(1))
+        );
+
+        let error: UdfResponse = serde_json::from_value(json!({
+            "status": "error",
+            "errorMessage": "Uncaught Error: oops",
+        }))?;
+        assert_eq!(
+            function_result(error)?,
+            FunctionResult::ErrorMessage("Uncaught Error: oops".to_string())
+        );
+
+        let convex_error: UdfResponse = serde_json::from_value(json!({
+            "status": "error",
+            "errorMessage": "Uncaught ConvexError: taken",
+            "errorData": "taken",
+        }))?;
+        assert_eq!(
+            function_result(convex_error)?,
+            FunctionResult::ConvexError(ConvexError {
+                message: "Uncaught ConvexError: taken".to_string(),
+                data: Value::String("taken".to_string()),
+            })
+        );
+        Ok(())
+    }
+}
@@ -31,6 +31,11 @@
 //! }
 //! ```
 //!
+//! ## One-off calls over HTTP
+//! For CLI tools and serverless jobs that only make a few calls, the
+//! [`ConvexHttpClient`] calls functions over HTTP without opening a websocket.
+//! It can't subscribe to queries.
+//!
 //! ## Extending client for other programming languages or frameworks.
 //! To extend Convex into non-[`tokio`] frameworks,
 //! you can use the [`base_client::BaseConvexClient`] to build something similar
@@ -45,23 +50,45 @@ mod value;
 #[cfg(any(test, feature = "testing"))]
 pub use value::export::roundtrip::ExportContext;
 pub use value::{
+    serde::{
+        from_value,
+        to_value,
+    },
     ConvexError,
     Value,
 };
 
 mod client;
 pub use client::{
+    connection_state::{
+        ConnectionState,
+        ConnectionStateSubscription,
+        ConnectionStatus,
+    },
     subscription::{
+        LoadMoreHandle,
+        PaginatedSubscription,
+        QueryGroupSubscription,
         QuerySetSubscription,
         QuerySubscription,
     },
     ConvexClient,
 };
 
+mod http_client;
+pub use http_client::{
+    ConvexHttpClient,
+    QueryTimestamp,
+};
+
 pub mod base_client;
 #[doc(inline)]
 pub use base_client::{
     FunctionResult,
+    MutationOutbox,
+    PaginatedQueryResult,
+    PaginationStatus,
+    QueryGroupSnapshot,
     QueryResults,
     SubscriberId,
 };
@@ -1,6 +1,9 @@
+use std::fmt;
+
 use async_trait::async_trait;
 use convex_sync_types::{
     ClientMessage,
+    SessionId,
     Timestamp,
 };
 use futures::channel::mpsc;
@@ -20,20 +23,64 @@ pub struct ReconnectRequest {
     pub max_observed_timestamp: Option<Timestamp>,
 }
 
-pub type ReconnectProtocolReason = String;
+/// Why the client has to reconnect. Its message is sent to the server as the
+/// close reason of the next connection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReconnectProtocolReason {
+    /// The server rejected the auth token.
+    AuthError(String),
+    /// A diff-encoded query update didn't apply to the client's copy of the
+    /// query result.
+    QueryDiffMismatch,
+    /// Any other protocol failure.
+    Other(String),
+}
+
+impl fmt::Display for ReconnectProtocolReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconnectProtocolReason::AuthError(message)
+            | ReconnectProtocolReason::Other(message) => {
+                write!(f, "{message}")
+            },
+            ReconnectProtocolReason::QueryDiffMismatch => write!(f, "QueryDiffMismatch"),
+        }
+    }
+}
+
+impl From<&str> for ReconnectProtocolReason {
+    fn from(message: &str) -> Self {
+        ReconnectProtocolReason::Other(message.to_string())
+    }
+}
+
+impl From<String> for ReconnectProtocolReason {
+    fn from(message: String) -> Self {
+        ReconnectProtocolReason::Other(message)
+    }
+}
 
 pub type ServerMessage = convex_sync_types::ServerMessage<Value>;
 
 #[derive(Debug)]
 pub enum ProtocolResponse {
     ServerMessage(ServerMessage),
-    Failure,
+    /// The connection was (re)established and the `Connect` message sent.
+    Connected,
+    /// The connection failed for the given reason.
+    Failure(ReconnectProtocolReason),
 }
 
 #[async_trait]
 pub trait SyncProtocol: Send + Sized {
-    async fn open(ws_url: Url, on_response: mpsc::Sender<ProtocolResponse>)
-        -> anyhow::Result<Self>;
+    /// Open a connection. If `session_id` is set, every connection uses it so
+    /// the server can deduplicate resent mutations; otherwise each connection
+    /// starts a new session.
+    async fn open(
+        ws_url: Url,
+        on_response: mpsc::Sender<ProtocolResponse>,
+        session_id: Option<SessionId>,
+    ) -> anyhow::Result<Self>;
     async fn send(&mut self, message: ClientMessage) -> anyhow::Result<()>;
     async fn reconnect(&mut self, request: ReconnectRequest);
 }
@@ -15,11 +15,11 @@ use futures::{
 };
 use parking_lot::Mutex;
 use url::Url;
-use uuid::Uuid;
 
 use super::ReconnectRequest;
 use crate::sync::{
     ProtocolResponse,
+    ReconnectProtocolReason,
     ServerMessage,
     SyncProtocol,
 };
@@ -28,6 +28,7 @@ use crate::sync::{
 struct TestProtocolInner {
     closed: bool,
     sent_messages: Vec<ClientMessage>,
+    reconnect_reasons: Vec<ReconnectProtocolReason>,
 }
 #[derive(Debug, Clone)]
 pub struct TestProtocolManager {
@@ -56,6 +57,10 @@ impl TestProtocolManager {
     pub async fn take_sent(&self) -> Vec<ClientMessage> {
         std::mem::take(&mut self.inner.lock().sent_messages)
     }
+
+    pub fn take_reconnect_reasons(&self) -> Vec<ReconnectProtocolReason> {
+        std::mem::take(&mut self.inner.lock().reconnect_reasons)
+    }
 }
 
 #[async_trait]
@@ -63,26 +68,32 @@ impl SyncProtocol for TestProtocolManager {
     async fn open(
         _ws_url: Url,
         response_sender: mpsc::Sender<ProtocolResponse>,
+        session_id: Option<SessionId>,
     ) -> anyhow::Result<Self> {
         let mut test_protocol = TestProtocolManager {
             inner: Arc::new(Mutex::new(TestProtocolInner {
                 closed: false,
                 sent_messages: vec![],
+                reconnect_reasons: vec![],
             })),
             response_sender,
         };
 
-        let session_id = Uuid::nil();
         let connection_count = 0;
 
         test_protocol
             .send(ClientMessage::Connect {
-                session_id: SessionId::new(session_id),
+                session_id: session_id.unwrap_or(SessionId::nil()),
                 connection_count,
                 last_close_reason: "InitialConnect".to_string(),
                 max_observed_timestamp: None,
+                query_diffs: true,
             })
             .await?;
+        test_protocol
+            .response_sender
+            .send(ProtocolResponse::Connected)
+            .await?;
 
         Ok(test_protocol)
     }
@@ -97,6 +108,6 @@ impl SyncProtocol for TestProtocolManager {
     }
 
     async fn reconnect(&mut self, request: ReconnectRequest) {
-        panic!("Test reconnected {request:?}");
+        self.inner.lock().reconnect_reasons.push(request.reason);
     }
 }
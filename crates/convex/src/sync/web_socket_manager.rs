@@ -49,6 +49,7 @@ use uuid::Uuid;
 
 use crate::sync::{
     ProtocolResponse,
+    ReconnectProtocolReason,
     ReconnectRequest,
     ServerMessage,
     SyncProtocol,
@@ -71,11 +72,15 @@ struct WebSocketInternal {
 }
 struct WebSocketWorker {
     ws_url: Url,
+    session_id: Option<SessionId>,
     on_response: mpsc::Sender<ProtocolResponse>,
     internal_receiver: mpsc::UnboundedReceiver<WebSocketRequest>,
     ping_ticker: Interval,
     connection_count: u32,
     backoff: Backoff,
+    /// Whether to ask the server for diff-encoded query updates. Turned off
+    /// for the rest of the session if a diff ever fails to apply.
+    query_diffs: bool,
 }
 
 pub struct WebSocketManager {
@@ -93,10 +98,15 @@ impl SyncProtocol for WebSocketManager {
     async fn open(
         ws_url: Url,
         on_response: mpsc::Sender<ProtocolResponse>,
+        session_id: Option<SessionId>,
     ) -> anyhow::Result<Self> {
         let (internal_sender, internal_receiver) = mpsc::unbounded();
-        let worker_handle =
-            tokio::spawn(WebSocketWorker::run(ws_url, on_response, internal_receiver));
+        let worker_handle = tokio::spawn(WebSocketWorker::run(
+            ws_url,
+            on_response,
+            internal_receiver,
+            session_id,
+        ));
 
         Ok(WebSocketManager {
             internal_sender,
@@ -131,17 +141,20 @@ impl WebSocketWorker {
         ws_url: Url,
         on_response: mpsc::Sender<ProtocolResponse>,
         internal_receiver: mpsc::UnboundedReceiver<WebSocketRequest>,
+        session_id: Option<SessionId>,
     ) -> Infallible {
         let ping_ticker = tokio::time::interval(Self::HEARTBEAT_INTERVAL);
         let backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
 
         let mut worker = Self {
             ws_url,
+            session_id,
             on_response,
             internal_receiver,
             ping_ticker,
             connection_count: 0,
             backoff,
+            query_diffs: true,
         };
 
         let mut last_close_reason = "InitialConnect".to_string();
@@ -151,7 +164,8 @@ impl WebSocketWorker {
                 Ok(reconnect) => {
                     // WS worker exited cleanly because it got a request to reconnect
                     tracing::debug!("Reconnecting websocket due to {}", reconnect.reason);
-                    last_close_reason = reconnect.reason;
+                    worker.on_reconnect(&reconnect.reason);
+                    last_close_reason = reconnect.reason.to_string();
                     max_observed_timestamp = reconnect.max_observed_timestamp;
                     continue;
                 },
@@ -168,7 +182,10 @@ impl WebSocketWorker {
             // The worker will send a Reconnect message and the new query set all together.
             // Drain the input request queue until we get that reconnect message - which
             // will be followed by the refreshed query set.
-            let _ = worker.on_response.send(ProtocolResponse::Failure).await;
+            let _ = worker
+                .on_response
+                .send(ProtocolResponse::Failure(last_close_reason.clone().into()))
+                .await;
             tracing::debug!("Waiting for base client to acknowledge reconnect");
             loop {
                 let request = worker.internal_receiver.next().await;
@@ -177,6 +194,7 @@ impl WebSocketWorker {
                 // observed. This is fine since it will never cause errors. Will can fix this
                 // when we restructure the wider protocol to be a single routine.
                 if let Some(WebSocketRequest::Reconnect(reconnect)) = request {
+                    worker.on_reconnect(&reconnect.reason);
                     max_observed_timestamp = reconnect.max_observed_timestamp;
                     break;
                 }
@@ -189,6 +207,14 @@ impl WebSocketWorker {
         }
     }
 
+    /// Stop asking for query diffs once one fails to apply, since the server
+    /// would keep diffing against a value the client doesn't have.
+    fn on_reconnect(&mut self, reason: &ReconnectProtocolReason) {
+        if matches!(reason, ReconnectProtocolReason::QueryDiffMismatch) {
+            self.query_diffs = false;
+        }
+    }
+
     async fn work(
         &mut self,
         last_close_reason: String,
@@ -202,12 +228,15 @@ impl WebSocketWorker {
         tracing::debug!("trying to {verb} to {}", self.ws_url);
         let mut internal = WebSocketInternal::new(
             self.ws_url.clone(),
+            self.session_id,
             self.connection_count,
             last_close_reason,
             max_seen_transition,
+            self.query_diffs,
         )
         .await?;
         tracing::debug!("completed websocket {verb} to {}", self.ws_url);
+        let _ = self.on_response.send(ProtocolResponse::Connected).await;
 
         loop {
             select_biased! {
@@ -276,9 +305,11 @@ fn deprecation_message(headers: &HeaderMap) -> Option<String> {
 impl WebSocketInternal {
     async fn new(
         ws_url: Url,
+        session_id: Option<SessionId>,
         connection_count: u32,
         last_close_reason: String,
         max_observed_timestamp: Option<Timestamp>,
+        query_diffs: bool,
     ) -> anyhow::Result<WebSocketInternal> {
         let mut request = (&ws_url).into_client_request().context("Bad WS Url")?;
         let version = VERSION.unwrap_or("unknown");
@@ -310,13 +341,16 @@ impl WebSocketInternal {
             last_server_response,
         };
 
-        // Send an initial connect message on the new websocket
-        let session_id = Uuid::new_v4();
+        // Send an initial connect message on the new websocket. With an
+        // outbox, the session id is the same for every connection so the
+        // server can deduplicate resent mutations.
+        let session_id = session_id.unwrap_or_else(|| SessionId::new(Uuid::new_v4()));
         let message = ClientMessage::Connect {
-            session_id: SessionId::new(session_id),
+            session_id,
             connection_count,
             last_close_reason,
             max_observed_timestamp,
+            query_diffs,
         };
         let msg = Message::Text(
             serde_json::Value::try_from(message)
@@ -335,3 +369,37 @@ impl WebSocketInternal {
             .context("WebsocketClosedOnSend")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc;
+
+    use super::{
+        Backoff,
+        WebSocketWorker,
+        INITIAL_BACKOFF,
+        MAX_BACKOFF,
+    };
+    use crate::sync::ReconnectProtocolReason;
+
+    #[tokio::test]
+    async fn test_query_diff_mismatch_disables_query_diffs() -> anyhow::Result<()> {
+        let (on_response, _response_receiver) = mpsc::channel(1);
+        let (_internal_sender, internal_receiver) = mpsc::unbounded();
+        let mut worker = WebSocketWorker {
+            ws_url: "ws://test.com".parse()?,
+            session_id: None,
+            on_response,
+            internal_receiver,
+            ping_ticker: tokio::time::interval(WebSocketWorker::HEARTBEAT_INTERVAL),
+            connection_count: 0,
+            backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
+            query_diffs: true,
+        };
+        worker.on_reconnect(&ReconnectProtocolReason::Other("Timeout".to_string()));
+        assert!(worker.query_diffs);
+        worker.on_reconnect(&ReconnectProtocolReason::QueryDiffMismatch);
+        assert!(!worker.query_diffs);
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 
 pub mod export;
 mod json;
+pub mod serde;
 mod sorting;
 use thiserror::Error;
 
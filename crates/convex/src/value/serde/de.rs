@@ -0,0 +1,586 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{
+        self,
+        Display,
+    },
+    num::TryFromIntError,
+};
+
+use serde::de::{
+    value::StringDeserializer,
+    DeserializeOwned,
+    DeserializeSeed,
+    Error as SerdeError,
+    IntoDeserializer,
+    MapAccess,
+    SeqAccess,
+    Visitor,
+};
+
+use crate::value::Value;
+
+/// An error deserializing a [`Value`]. This is public because it is the
+/// `Error` type of `Value`'s [`serde::Deserializer`] implementation.
+#[derive(thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Invalid type: received {received}, expected {expected}")]
+    InvalidType {
+        expected: &'static str,
+        received: &'static str,
+    },
+
+    #[error("Value::Int64 was out of range: {0:?}.")]
+    IntegerOutofRange(#[from] TryFromIntError),
+
+    #[error("Value::Float64 {0} isn't an integer in range for an i64.")]
+    NonIntegralFloat(f64),
+
+    #[error("f32s aren't supported, use an f64 instead.")]
+    Float32Unsupported,
+
+    #[error("chars aren't supported, use a string instead.")]
+    CharUnsupported,
+
+    #[error(
+        "Only unit enum variants can be deserialized directly, use #[serde(tag = \"type\")] \
+         instead."
+    )]
+    EnumUnsupported,
+
+    #[error("Items remaining after deserialization")]
+    ItemsRemaining,
+
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl SerdeError for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "Null",
+        Value::Int64(_) => "Int64",
+        Value::Float64(_) => "Float64",
+        Value::Boolean(_) => "Boolean",
+        Value::String(_) => "String",
+        Value::Bytes(_) => "Bytes",
+        Value::Array(_) => "Array",
+        Value::Object(_) => "Object",
+    }
+}
+
+fn invalid_type(expected: &'static str, received: &Value) -> Error {
+    Error::InvalidType {
+        expected,
+        received: type_name(received),
+    }
+}
+
+/// Integers can come from a [`Value::Int64`], or from a [`Value::Float64`]
+/// holding a whole number, since that's what a TypeScript `number` is.
+fn integer(value: Value) -> Result<i64, Error> {
+    match value {
+        Value::Int64(n) => Ok(n),
+        // `i64::MAX as f64` rounds up to 2^63, which is out of range.
+        Value::Float64(n) if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 => {
+            Ok(n as i64)
+        },
+        Value::Float64(n) => Err(Error::NonIntegralFloat(n)),
+        v => Err(invalid_type("Int64", &v)),
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Value {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Int64(n) => visitor.visit_i64(n),
+            Value::Float64(n) => visitor.visit_f64(n),
+            Value::Boolean(b) => visitor.visit_bool(b),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Bytes(b) => visitor.visit_byte_buf(b),
+            Value::Array(v) => visit_array(v, visitor),
+            Value::Object(v) => visit_object(v, visitor),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i8(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i16(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i32(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(integer(self)?)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u8(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u16(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u32(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(integer(self)?.try_into()?)
+    }
+
+    fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error::Float32Unsupported)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Float64(n) => visitor.visit_f64(n),
+            v => Err(invalid_type("Float64", &v)),
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => {
+                let variant: StringDeserializer<Error> = s.into_deserializer();
+                visitor.visit_enum(variant)
+            },
+            _ => Err(Error::EnumUnsupported),
+        }
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Boolean(b) => visitor.visit_bool(b),
+            v => Err(invalid_type("Boolean", &v)),
+        }
+    }
+
+    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error::CharUnsupported)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_string(s),
+            v => Err(invalid_type("String", &v)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(b) => visitor.visit_byte_buf(b),
+            v => Err(invalid_type("Bytes", &v)),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            v => Err(invalid_type("Null", &v)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Array(v) => visit_array(v, visitor),
+            v => Err(invalid_type("Array", &v)),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Object(v) => visit_object(v, visitor),
+            v => Err(invalid_type("Object", &v)),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        drop(self);
+        visitor.visit_unit()
+    }
+}
+
+fn visit_array<'de, V>(array: Vec<Value>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let mut deserializer = SeqDeserializer {
+        iter: array.into_iter(),
+    };
+    let seq = visitor.visit_seq(&mut deserializer)?;
+    if deserializer.iter.len() != 0 {
+        return Err(Error::ItemsRemaining);
+    }
+    Ok(seq)
+}
+
+struct SeqDeserializer {
+    iter: std::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+fn visit_object<'de, V>(object: BTreeMap<String, Value>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let mut deserializer = MapDeserializer {
+        iter: object.into_iter(),
+        value: None,
+    };
+    let map = visitor.visit_map(&mut deserializer)?;
+    if deserializer.iter.len() != 0 {
+        return Err(Error::ItemsRemaining);
+    }
+    Ok(map)
+}
+
+struct MapDeserializer {
+    iter: <BTreeMap<String, Value> as IntoIterator>::IntoIter,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key_de: StringDeserializer<Error> = key.into_deserializer();
+                Ok(Some(seed.deserialize(key_de)?))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializes any self-describing input into a [`Value`], so it can be
+/// embedded in `Deserialize` types passed to [`from_value`].
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Convex value")
+    }
+
+    fn visit_unit<E: SerdeError>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: SerdeError>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: SerdeError>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(b))
+    }
+
+    fn visit_i64<E: SerdeError>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Int64(n))
+    }
+
+    fn visit_u64<E: SerdeError>(self, n: u64) -> Result<Value, E> {
+        let n = i64::try_from(n).map_err(E::custom)?;
+        Ok(Value::Int64(n))
+    }
+
+    fn visit_f64<E: SerdeError>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::Float64(n))
+    }
+
+    fn visit_str<E: SerdeError>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E: SerdeError>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_bytes<E: SerdeError>(self, b: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(b.to_vec()))
+    }
+
+    fn visit_byte_buf<E: SerdeError>(self, b: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(b))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut fields = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            fields.insert(key, value);
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+/// Convert a [`Value`] into a `T: Deserialize`.
+///
+/// This is the inverse of [`to_value`](crate::to_value). Float fields must be
+/// [`Value::Float64`]. Integer fields accept a [`Value::Int64`], or a
+/// [`Value::Float64`] holding a whole number, since a TypeScript `number` is
+/// always a [`Value::Float64`].
+///
+/// Documents returned from queries include the `_id` and `_creationTime`
+/// system fields, which can be renamed onto regular struct fields.
+///
+/// ```
+/// # use convex::Value;
+/// #[derive(serde::Deserialize)]
+/// struct Message {
+///     #[serde(rename = "_id")]
+///     id: String,
+///     #[serde(rename = "_creationTime")]
+///     creation_time: f64,
+///     body: String,
+/// }
+///
+/// let value = Value::Object(maplit::btreemap! {
+///     "_id".into() => "j97a3ffj0yvxrjz1sx8cgwb1h96snbsh".into(),
+///     "_creationTime".into() => 1700000000000.0.into(),
+///     "body".into() => "Let it be.".into(),
+/// });
+/// let message: Message = convex::from_value(value)?;
+/// assert_eq!(message.body, "Let it be.");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn from_value<T: DeserializeOwned>(value: Value) -> anyhow::Result<T> {
+    Ok(T::deserialize(value)?)
+}
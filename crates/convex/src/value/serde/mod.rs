@@ -0,0 +1,151 @@
+//! Conversions between [`Value`](crate::Value) and types that implement
+//! [`serde::Serialize`] and [`serde::Deserialize`].
+
+mod de;
+mod ser;
+
+pub use de::from_value;
+pub use ser::to_value;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use maplit::btreemap;
+    use serde::{
+        Deserialize,
+        Serialize,
+    };
+
+    use super::{
+        from_value,
+        to_value,
+    };
+    use crate::Value;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    enum Status {
+        Draft,
+        Sent,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct MessageId(String);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Message {
+        #[serde(rename = "_id")]
+        id: MessageId,
+        #[serde(rename = "_creationTime")]
+        creation_time: f64,
+        body: String,
+        likes: u32,
+        #[serde(with = "serde_bytes")]
+        attachment: Vec<u8>,
+        status: Status,
+        reply_to: Option<MessageId>,
+        tags: Vec<String>,
+    }
+
+    fn message() -> Message {
+        Message {
+            id: MessageId("j97a3ffj0yvxrjz1sx8cgwb1h96snbsh".to_string()),
+            creation_time: 1700000000000.5,
+            body: "Let it be.".to_string(),
+            likes: 3,
+            attachment: vec![0, 1, 255],
+            status: Status::Sent,
+            reply_to: None,
+            tags: vec!["music".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_to_value() -> anyhow::Result<()> {
+        let expected = Value::Object(btreemap! {
+            "_id".to_string() => "j97a3ffj0yvxrjz1sx8cgwb1h96snbsh".into(),
+            "_creationTime".to_string() => Value::Float64(1700000000000.5),
+            "body".to_string() => "Let it be.".into(),
+            "likes".to_string() => Value::Int64(3),
+            "attachment".to_string() => Value::Bytes(vec![0, 1, 255]),
+            "status".to_string() => "sent".into(),
+            "replyTo".to_string() => Value::Null,
+            "tags".to_string() => Value::Array(vec!["music".into()]),
+        });
+        assert_eq!(to_value(&message())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip() -> anyhow::Result<()> {
+        let message = message();
+        let roundtripped: Message = from_value(to_value(&message)?)?;
+        assert_eq!(roundtripped, message);
+
+        let map = btreemap! { "a".to_string() => 1i64, "b".to_string() => i64::MIN };
+        let roundtripped: BTreeMap<String, i64> = from_value(to_value(&map)?)?;
+        assert_eq!(roundtripped, map);
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_fields_roundtrip() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Event {
+            name: String,
+            payload: Value,
+        }
+        let event = Event {
+            name: "created".to_string(),
+            payload: Value::Object(btreemap! {
+                "count".to_string() => Value::Int64(2),
+                "ratio".to_string() => Value::Float64(0.5),
+                "data".to_string() => Value::Bytes(vec![1, 2]),
+                "tags".to_string() => Value::Array(vec![Value::Null, true.into()]),
+            }),
+        };
+        let roundtripped: Event = from_value(to_value(&event)?)?;
+        assert_eq!(roundtripped, event);
+        Ok(())
+    }
+
+    #[test]
+    fn test_integers_and_floats_are_distinct() -> anyhow::Result<()> {
+        assert_eq!(to_value(&5u8)?, Value::Int64(5));
+        assert_eq!(to_value(&5.0f64)?, Value::Float64(5.0));
+        assert!(to_value(&u64::MAX).is_err());
+        assert!(from_value::<f64>(Value::Int64(5)).is_err());
+        assert!(from_value::<u8>(Value::Int64(256)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_integers_from_whole_floats() -> anyhow::Result<()> {
+        // TypeScript numbers arrive as Float64 even when they're whole.
+        assert_eq!(from_value::<i64>(Value::Float64(5.0))?, 5);
+        assert_eq!(from_value::<i32>(Value::Float64(-3.0))?, -3);
+        assert_eq!(
+            from_value::<u64>(Value::Float64(1e15))?,
+            1_000_000_000_000_000
+        );
+        assert!(from_value::<i32>(Value::Float64(5.5)).is_err());
+        assert!(from_value::<u8>(Value::Float64(256.0)).is_err());
+        assert!(from_value::<u64>(Value::Float64(-1.0)).is_err());
+        assert!(from_value::<i64>(Value::Float64(f64::NAN)).is_err());
+        assert!(from_value::<i64>(Value::Float64(9.3e18)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_enum_variants() {
+        #[derive(Serialize)]
+        enum Shape {
+            Circle { radius: f64 },
+        }
+        assert!(to_value(&Shape::Circle { radius: 1.0 }).is_err());
+        assert!(from_value::<Status>(Value::Object(BTreeMap::new())).is_err());
+        assert!(from_value::<Status>("archived".into()).is_err());
+    }
+}
@@ -0,0 +1,618 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{
+        self,
+        Display,
+    },
+    num::TryFromIntError,
+};
+
+use serde::{
+    ser::{
+        Error as SerdeError,
+        Impossible,
+    },
+    Serialize,
+};
+
+use crate::value::Value;
+
+#[derive(thiserror::Error)]
+enum Error {
+    #[error("Integer isn't in range for Value::Int64: {0:?}.")]
+    IntegerOutofRange(#[from] TryFromIntError),
+
+    #[error("f32s aren't supported, use an f64 instead.")]
+    Float32Unsupported,
+
+    #[error("chars aren't supported, use a string instead.")]
+    CharUnsupported,
+
+    #[error("Object keys must be strings, received {0}.")]
+    InvalidKey(&'static str),
+
+    #[error(
+        "Struct enum variants unsupported. Set #[serde(tag = \"type\")] to serialize as a regular \
+         object."
+    )]
+    StructVariantsUnsupported,
+
+    #[error(
+        "Newtype enum variants unsupported. Set #[serde(tag = \"type\")] to serialize as a \
+         regular object."
+    )]
+    NewtypeVariantsUnsupported,
+
+    #[error(
+        "Tuple enum variants unsupported. Set #[serde(tag = \"type\")] to serialize as a regular \
+         object."
+    )]
+    TupleVariantsUnsupported,
+
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl SerdeError for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+struct Serializer;
+
+impl serde::Serializer for Serializer {
+    type Error = Error;
+    type Ok = Value;
+    type SerializeMap = SerializeObject;
+    type SerializeSeq = SerializeVec;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = Impossible<Value, Error>;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = Impossible<Value, Error>;
+
+    #[inline]
+    fn serialize_bool(self, value: bool) -> Result<Value> {
+        Ok(Value::Boolean(value))
+    }
+
+    #[inline]
+    fn serialize_i8(self, value: i8) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    #[inline]
+    fn serialize_i16(self, value: i16) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    #[inline]
+    fn serialize_i32(self, value: i32) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value> {
+        Ok(Value::Int64(value))
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Value> {
+        Ok(Value::Int64(value.try_into()?))
+    }
+
+    #[inline]
+    fn serialize_u8(self, value: u8) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    #[inline]
+    fn serialize_u16(self, value: u16) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    #[inline]
+    fn serialize_u32(self, value: u32) -> Result<Value> {
+        Ok(Value::Int64(value as i64))
+    }
+
+    #[inline]
+    fn serialize_u64(self, value: u64) -> Result<Value> {
+        Ok(Value::Int64(value.try_into()?))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value> {
+        Ok(Value::Int64(value.try_into()?))
+    }
+
+    #[inline]
+    fn serialize_f32(self, _float: f32) -> Result<Value> {
+        // We don't serialize `f32` so we don't have to worry about roundtripping from
+        // f32 to f64 to f32.
+        Err(Error::Float32Unsupported)
+    }
+
+    #[inline]
+    fn serialize_f64(self, float: f64) -> Result<Value> {
+        Ok(Value::Float64(float))
+    }
+
+    #[inline]
+    fn serialize_char(self, _value: char) -> Result<Value> {
+        Err(Error::CharUnsupported)
+    }
+
+    #[inline]
+    fn serialize_str(self, value: &str) -> Result<Value> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value> {
+        Ok(Value::Bytes(value.to_vec()))
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    /// Unit variants serialize as their name, which matches a union of string
+    /// literals on the TypeScript side.
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    /// Newtype structs are transparent, so a wrapper like `struct
+    /// MessageId(String)` serializes as the string it contains.
+    #[inline]
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::NewtypeVariantsUnsupported)
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::TupleVariantsUnsupported)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeObject {
+            fields: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(SerializeObject {
+            fields: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::StructVariantsUnsupported)
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Display,
+    {
+        Ok(Value::String(value.to_string()))
+    }
+}
+
+struct SerializeVec {
+    vec: Vec<Value>,
+}
+
+impl serde::ser::SerializeSeq for SerializeVec {
+    type Error = Error;
+    type Ok = Value;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.vec))
+    }
+}
+
+impl serde::ser::SerializeTuple for SerializeVec {
+    type Error = Error;
+    type Ok = Value;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.vec))
+    }
+}
+
+impl serde::ser::SerializeTupleStruct for SerializeVec {
+    type Error = Error;
+    type Ok = Value;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.vec))
+    }
+}
+
+struct SerializeObject {
+    fields: BTreeMap<String, Value>,
+    next_key: Option<String>,
+}
+
+impl serde::ser::SerializeMap for SerializeObject {
+    type Error = Error;
+    type Ok = Value;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        assert!(
+            self.next_key.is_none(),
+            "serialize_key called twice without serialize_value"
+        );
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self
+            .next_key
+            .take()
+            .expect("serialize_value called without preceding serialize_key");
+        self.fields.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(self.fields))
+    }
+}
+
+impl serde::ser::SerializeStruct for SerializeObject {
+    type Error = Error;
+    type Ok = Value;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        field: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.fields
+            .insert(field.to_string(), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(self.fields))
+    }
+}
+
+/// Serializes map keys, which must be strings. Field names are validated by the
+/// server, so system fields like `_id` pass through unchanged.
+struct KeySerializer;
+
+impl serde::Serializer for KeySerializer {
+    type Error = Error;
+    type Ok = String;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+
+    #[inline]
+    fn serialize_str(self, value: &str) -> Result<String> {
+        Ok(value.to_string())
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Display,
+    {
+        Ok(value.to_string())
+    }
+
+    fn serialize_bool(self, _value: bool) -> Result<String> {
+        Err(Error::InvalidKey("bool"))
+    }
+
+    fn serialize_i8(self, _value: i8) -> Result<String> {
+        Err(Error::InvalidKey("i8"))
+    }
+
+    fn serialize_i16(self, _value: i16) -> Result<String> {
+        Err(Error::InvalidKey("i16"))
+    }
+
+    fn serialize_i32(self, _value: i32) -> Result<String> {
+        Err(Error::InvalidKey("i32"))
+    }
+
+    fn serialize_i64(self, _value: i64) -> Result<String> {
+        Err(Error::InvalidKey("i64"))
+    }
+
+    fn serialize_u8(self, _value: u8) -> Result<String> {
+        Err(Error::InvalidKey("u8"))
+    }
+
+    fn serialize_u16(self, _value: u16) -> Result<String> {
+        Err(Error::InvalidKey("u16"))
+    }
+
+    fn serialize_u32(self, _value: u32) -> Result<String> {
+        Err(Error::InvalidKey("u32"))
+    }
+
+    fn serialize_u64(self, _value: u64) -> Result<String> {
+        Err(Error::InvalidKey("u64"))
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<String> {
+        Err(Error::InvalidKey("f32"))
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<String> {
+        Err(Error::InvalidKey("f64"))
+    }
+
+    fn serialize_char(self, _value: char) -> Result<String> {
+        Err(Error::InvalidKey("char"))
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<String> {
+        Err(Error::InvalidKey("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Error::InvalidKey("None"))
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::InvalidKey("Some"))
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(Error::InvalidKey("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(Error::InvalidKey("unit struct"))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::InvalidKey("newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::InvalidKey("seq"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::InvalidKey("tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::InvalidKey("tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::InvalidKey("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::InvalidKey("map"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::InvalidKey("struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::InvalidKey("struct variant"))
+    }
+}
+
+/// Serializes a [`Value`] as the serde data model type it holds, so it can
+/// be embedded in `Serialize` types passed to [`to_value`].
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Int64(n) => serializer.serialize_i64(*n),
+            Value::Float64(n) => serializer.serialize_f64(*n),
+            Value::Boolean(b) => serializer.serialize_bool(*b),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Bytes(b) => serializer.serialize_bytes(b),
+            Value::Array(v) => serializer.collect_seq(v),
+            Value::Object(v) => serializer.collect_map(v),
+        }
+    }
+}
+
+/// Convert a `T: Serialize` into a [`Value`].
+///
+/// Integers become [`Value::Int64`] (`v.int64()` in a validator) and floats
+/// become [`Value::Float64`] (`v.number()`). Use `serde_bytes` to serialize a
+/// `Vec<u8>` as [`Value::Bytes`]; otherwise it is serialized as an array of
+/// integers.
+///
+/// ```
+/// # use convex::Value;
+/// #[derive(serde::Serialize)]
+/// struct Message {
+///     author: String,
+///     body: String,
+///     likes: i64,
+/// }
+///
+/// let message = Message {
+///     author: "The Beatles".into(),
+///     body: "Let it be.".into(),
+///     likes: 3,
+/// };
+/// let Value::Object(fields) = convex::to_value(&message)? else {
+///     unreachable!()
+/// };
+/// assert_eq!(fields["likes"], Value::Int64(3));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn to_value<T: ?Sized + Serialize>(value: &T) -> anyhow::Result<Value> {
+    Ok(value.serialize(Serializer)?)
+}
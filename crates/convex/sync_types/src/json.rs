@@ -24,6 +24,7 @@ use crate::{
     IdentityVersion,
     LogLinesMessage,
     Query,
+    QueryErrorCode,
     QueryId,
     QuerySetModification,
     SerializedQueryJournal,
@@ -34,6 +35,7 @@ use crate::{
     Timestamp,
     UserIdentifier,
     UserIdentityAttributes,
+    ValueDiff,
 };
 
 /// We implement custom deserialize and serialize to deliver u64s to
@@ -173,6 +175,10 @@ enum ClientMessageJson {
         #[serde(default)]
         #[serde(skip_serializing_if = "Option::is_none")]
         max_observed_timestamp: Option<String>,
+
+        #[serde(default)]
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        query_diffs: bool,
     },
     #[serde(rename_all = "camelCase")]
     ModifyQuerySet {
@@ -219,11 +225,13 @@ impl TryFrom<ClientMessage> for JsonValue {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                query_diffs,
             } => ClientMessageJson::Connect {
                 session_id: format!("{}", session_id.as_hyphenated()),
                 connection_count,
                 last_close_reason: Some(last_close_reason),
                 max_observed_timestamp: max_observed_timestamp.map(|ts| u64_to_string(ts.into())),
+                query_diffs,
             },
             ClientMessage::ModifyQuerySet {
                 base_version,
@@ -303,6 +311,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                query_diffs,
             } => ClientMessage::Connect {
                 session_id: session_id.parse()?,
                 connection_count,
@@ -312,6 +321,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                     .transpose()?
                     .map(Timestamp::try_from)
                     .transpose()?,
+                query_diffs,
             },
             ClientMessageJson::ModifyQuerySet {
                 base_version,
@@ -431,6 +441,7 @@ impl<V: Into<JsonValue>> From<StateModification<V>> for JsonValue {
                 log_lines,
                 journal,
                 error_data,
+                error_code,
             } => {
                 let mut response = json!({
                     "type": "QueryFailed",
@@ -443,12 +454,27 @@ impl<V: Into<JsonValue>> From<StateModification<V>> for JsonValue {
                 if let Some(error_data) = error_data {
                     response["errorData"] = error_data.into();
                 }
+                if let Some(error_code) = error_code {
+                    response["errorCode"] = json!(error_code);
+                }
                 response
             },
             StateModification::QueryRemoved { query_id } => json!({
                 "type": "QueryRemoved",
                 "queryId": query_id,
             }),
+            StateModification::QueryDiffed {
+                query_id,
+                diff,
+                log_lines,
+                journal,
+            } => json!({
+                "type": "QueryDiffed",
+                "queryId": query_id,
+                "diff": JsonValue::from(diff),
+                "logLines": log_lines,
+                "journal": journal
+            }),
         }
     }
 }
@@ -476,9 +502,18 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 journal: SerializedQueryJournal,
                 #[serde(default, deserialize_with = "deserialize_some")]
                 error_data: Option<JsonValue>,
+                #[serde(default)]
+                error_code: Option<JsonValue>,
             },
             #[serde(rename_all = "camelCase")]
             QueryRemoved { query_id: QueryId },
+            #[serde(rename_all = "camelCase")]
+            QueryDiffed {
+                query_id: QueryId,
+                diff: JsonValue,
+                log_lines: LogLinesMessage,
+                journal: SerializedQueryJournal,
+            },
         }
         let s: StateModificationJson = serde_json::from_value(value)?;
         let result = match s {
@@ -499,6 +534,7 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 log_lines,
                 journal,
                 error_data,
+                error_code,
             } => StateModification::QueryFailed {
                 query_id,
                 error_message,
@@ -507,10 +543,113 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 error_data: error_data
                     .map(|error_data| error_data.try_into())
                     .transpose()?,
+                // Codes this client doesn't know about are treated like any
+                // other error, so newer servers can add codes.
+                error_code: error_code
+                    .and_then(|code| serde_json::from_value::<QueryErrorCode>(code).ok()),
             },
             StateModificationJson::QueryRemoved { query_id } => {
                 StateModification::QueryRemoved { query_id }
             },
+            StateModificationJson::QueryDiffed {
+                query_id,
+                diff,
+                log_lines,
+                journal,
+            } => StateModification::QueryDiffed {
+                query_id,
+                diff: diff.try_into()?,
+                log_lines,
+                journal,
+            },
+        };
+        Ok(result)
+    }
+}
+
+impl<V: Into<JsonValue>> From<ValueDiff<V>> for JsonValue {
+    fn from(diff: ValueDiff<V>) -> Self {
+        match diff {
+            ValueDiff::Replace(value) => {
+                let jv: JsonValue = value.into();
+                json!({
+                    "type": "Replace",
+                    "value": jv,
+                })
+            },
+            ValueDiff::Object { updated, removed } => {
+                let updated: serde_json::Map<String, JsonValue> = updated
+                    .into_iter()
+                    .map(|(field, diff)| (field, JsonValue::from(diff)))
+                    .collect();
+                json!({
+                    "type": "Object",
+                    "updated": updated,
+                    "removed": removed,
+                })
+            },
+            ValueDiff::Array {
+                base_len,
+                start,
+                deleted,
+                inserted,
+            } => json!({
+                "type": "Array",
+                "baseLen": base_len,
+                "start": start,
+                "deleted": deleted,
+                "inserted": inserted.into_iter().map(Into::into).collect::<Vec<JsonValue>>(),
+            }),
+        }
+    }
+}
+
+impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for ValueDiff<V> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        #[derive(Deserialize)]
+        #[serde(tag = "type")]
+        enum ValueDiffJson {
+            Replace {
+                value: JsonValue,
+            },
+            Object {
+                updated: BTreeMap<String, JsonValue>,
+                removed: Vec<String>,
+            },
+            #[serde(rename_all = "camelCase")]
+            Array {
+                base_len: u32,
+                start: u32,
+                deleted: u32,
+                inserted: Vec<JsonValue>,
+            },
+        }
+        let d: ValueDiffJson = serde_json::from_value(value)?;
+        let result = match d {
+            ValueDiffJson::Replace { value } => ValueDiff::Replace(value.try_into()?),
+            ValueDiffJson::Object { updated, removed } => ValueDiff::Object {
+                updated: updated
+                    .into_iter()
+                    .map(|(field, diff)| Ok((field, diff.try_into()?)))
+                    .collect::<anyhow::Result<_>>()?,
+                removed,
+            },
+            ValueDiffJson::Array {
+                base_len,
+                start,
+                deleted,
+                inserted,
+            } => ValueDiff::Array {
+                base_len,
+                start,
+                deleted,
+                inserted: inserted
+                    .into_iter()
+                    .map(V::try_from)
+                    .collect::<anyhow::Result<_>>()?,
+            },
         };
         Ok(result)
     }
@@ -971,6 +1110,25 @@ mod tests {
         assert_roundtrips::<JsonValue, ClientMessage>(old_user_auth_message);
     }
 
+    #[test]
+    fn connect_without_query_diffs_backwards_compatibility() {
+        let old_connect_message = json!({
+            "type": "Connect",
+            "sessionId": "00000000-0000-0000-0000-000000000000",
+            "connectionCount": 0,
+            "lastCloseReason": "InitialConnect",
+        });
+        assert_roundtrips::<JsonValue, ClientMessage>(old_connect_message.clone());
+        let message = ClientMessage::try_from(old_connect_message).unwrap();
+        assert!(matches!(
+            message,
+            ClientMessage::Connect {
+                query_diffs: false,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn user_identity_attributes_deserialize_token_identifier_given() {
         let serialized = "{\"tokenIdentifier\":\"fake_identifier\"}";
@@ -1042,6 +1200,7 @@ mod tests {
                 log_lines: crate::LogLinesMessage(vec![]),
                 journal: None,
                 error_data: Some(TestValue(JsonValue::Null)),
+                error_code: None,
             }],
         });
     }
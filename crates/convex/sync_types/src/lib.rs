@@ -25,6 +25,7 @@ pub use crate::{
         IdentityVersion,
         LogLinesMessage,
         Query,
+        QueryErrorCode,
         QueryId,
         QuerySetModification,
         QuerySetVersion,
@@ -36,6 +37,7 @@ pub use crate::{
         StateVersion,
         UserIdentifier,
         UserIdentityAttributes,
+        ValueDiff,
     },
     udf_path::{
         CanonicalizedUdfPath,
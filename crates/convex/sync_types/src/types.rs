@@ -108,6 +108,9 @@ pub enum ClientMessage {
         connection_count: u32,
         last_close_reason: String,
         max_observed_timestamp: Option<Timestamp>,
+        /// Whether the client can apply `StateModification::QueryDiffed`. The
+        /// server only sends diffs to clients that opt in.
+        query_diffs: bool,
     },
     ModifyQuerySet {
         base_version: QuerySetVersion,
@@ -260,7 +263,7 @@ pub type SerializedQueryJournal = Option<String>;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
-pub enum StateModification<V> {
+pub enum StateModification<V: 'static> {
     QueryUpdated {
         query_id: QueryId,
         value: V,
@@ -273,10 +276,82 @@ pub enum StateModification<V> {
         log_lines: LogLinesMessage,
         journal: SerializedQueryJournal,
         error_data: Option<V>,
+        error_code: Option<QueryErrorCode>,
     },
     QueryRemoved {
         query_id: QueryId,
     },
+    /// The query has a new value, sent as a diff against the last value sent
+    /// for the query on this connection.
+    QueryDiffed {
+        query_id: QueryId,
+        #[cfg_attr(
+            any(test, feature = "testing"),
+            proptest(strategy = "value_diff_strategy::<V>()")
+        )]
+        diff: ValueDiff<V>,
+        log_lines: LogLinesMessage,
+        journal: SerializedQueryJournal,
+    },
+}
+
+/// A machine-readable reason for a failed query that clients handle
+/// specially, sent alongside the error message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum QueryErrorCode {
+    /// A paginated query was called with a cursor that doesn't belong to it,
+    /// usually because the query changed. Clients should restart pagination
+    /// from the first page.
+    InvalidCursor,
+}
+
+/// A structural change from one value to another.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValueDiff<V> {
+    /// The new value, in full.
+    Replace(V),
+    /// Changes to some fields of an object. Fields that aren't mentioned keep
+    /// their previous values.
+    Object {
+        updated: BTreeMap<String, ValueDiff<V>>,
+        removed: Vec<String>,
+    },
+    /// Replace `deleted` elements starting at index `start` of an array of
+    /// `base_len` elements with `inserted`.
+    Array {
+        base_len: u32,
+        start: u32,
+        deleted: u32,
+        inserted: Vec<V>,
+    },
+}
+
+#[cfg(any(test, feature = "testing"))]
+fn value_diff_strategy<V: Arbitrary + 'static>() -> impl Strategy<Value = ValueDiff<V>> {
+    let leaf = prop_oneof![
+        any::<V>().prop_map(ValueDiff::Replace),
+        (
+            any::<u32>(),
+            any::<u32>(),
+            any::<u32>(),
+            prop::collection::vec(any::<V>(), 0..3),
+        )
+            .prop_map(|(base_len, start, deleted, inserted)| ValueDiff::Array {
+                base_len,
+                start,
+                deleted,
+                inserted,
+            }),
+    ];
+    // Object diffs nest a few levels deep.
+    leaf.prop_recursive(3, 16, 3, |inner| {
+        (
+            prop::collection::btree_map(any::<String>(), inner, 0..3),
+            prop::collection::vec(any::<String>(), 0..2),
+        )
+            .prop_map(|(updated, removed)| ValueDiff::Object { updated, removed })
+    })
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
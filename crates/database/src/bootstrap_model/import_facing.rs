@@ -1,16 +1,26 @@
 use anyhow::Context;
 use common::{
+    bootstrap_model::index::database_index::IndexedFields,
     document::{
         CreationTime,
         ResolvedDocument,
         CREATION_TIME_FIELD,
         ID_FIELD,
     },
+    interval::{
+        BinaryKey,
+        Interval,
+    },
+    query::Order,
     runtime::Runtime,
+    types::TabletIndexName,
 };
 use errors::ErrorMetadata;
+use indexing::backend_in_memory_indexes::RangeRequest;
+use maplit::btreemap;
 use value::{
     check_user_size,
+    values_to_bytes,
     ConvexObject,
     ConvexValue,
     DeveloperDocumentId,
@@ -24,6 +34,7 @@ use value::{
 
 use crate::{
     defaults::bootstrap_system_tables,
+    query::IndexRangeResponse,
     SchemaModel,
     Transaction,
 };
@@ -204,6 +215,106 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         Ok(id.into())
     }
 
+    /// Reads up to `max_size` documents whose first indexed field in
+    /// `index_name` equals `value`. This reads the tablet's index directly, so
+    /// unlike a query it works on tables that are hidden while being imported.
+    pub async fn get_by_indexed_value(
+        &mut self,
+        table_name: &TableName,
+        index_name: TabletIndexName,
+        fields: IndexedFields,
+        value: ConvexValue,
+        max_size: usize,
+    ) -> anyhow::Result<Vec<ResolvedDocument>> {
+        if !(self.tx.identity.is_admin() || self.tx.identity.is_system()) {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "UnauthorizedImport",
+                "Import requires admin auth"
+            ));
+        }
+        let printable_index_name = index_name.clone().map_table(&|_| Ok(table_name.clone()))?;
+        let interval = Interval::prefix(BinaryKey::from(values_to_bytes(&[Some(value)])));
+        let range_request = RangeRequest {
+            index_name: index_name.clone(),
+            printable_index_name,
+            interval: interval.clone(),
+            order: Order::Asc,
+            max_size,
+        };
+        let mut results = self
+            .tx
+            .index
+            .range_batch(&mut self.tx.reads, btreemap! { 0 => range_request })
+            .await;
+        self.tx
+            .reads
+            .record_indexed_directly(index_name, fields, interval)?;
+        let IndexRangeResponse { page, .. } = results.remove(&0).context("expected result")??;
+        let mut documents = Vec::with_capacity(page.len());
+        for (_, document, _) in page {
+            self.tx.record_read_document(&document, table_name)?;
+            documents.push(document);
+        }
+        Ok(documents)
+    }
+
+    /// Patches an existing document with the user fields of `value` as part of
+    /// an upsert import. Returns whether the document changed.
+    #[convex_macro::instrument_future]
+    pub async fn patch(
+        &mut self,
+        table_name: &TableName,
+        existing_doc: ResolvedDocument,
+        value: ConvexObject,
+        table_mapping_for_schema: &TableMapping,
+    ) -> anyhow::Result<bool> {
+        if self
+            .tx
+            .virtual_system_mapping()
+            .is_virtual_table(table_name)
+        {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "ReadOnlyTable",
+                format!("{table_name} is a read-only table"),
+            ));
+        }
+        anyhow::ensure!(
+            bootstrap_system_tables()
+                .iter()
+                .all(|t| t.table_name() != table_name),
+            "Cannot import into bootstrap system table {table_name}"
+        );
+        if !(self.tx.identity.is_admin() || self.tx.identity.is_system()) {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "UnauthorizedImport",
+                "Import requires admin auth"
+            ));
+        }
+
+        // The existing document keeps its `_id` and `_creationTime`.
+        let existing_value = existing_doc.value().0.clone();
+        let new_value = existing_value
+            .clone()
+            .shallow_merge(value.filter_system_fields())?;
+        if new_value == existing_value {
+            return Ok(false);
+        }
+        if !table_name.is_system() {
+            check_user_size(new_value.size())?;
+        }
+        self.tx.retention_validator.fail_if_falling_behind()?;
+        let id = existing_doc.id();
+        let namespace = self.tx.table_mapping().tablet_namespace(id.tablet_id)?;
+        let document = existing_doc.replace_value(new_value)?;
+        SchemaModel::new(self.tx, namespace)
+            .enforce_with_table_mapping(&document, &table_mapping_for_schema.namespace(namespace))
+            .await?;
+        self.tx
+            .apply_validated_write(id, Some(existing_doc), Some(document))?;
+
+        Ok(true)
+    }
+
     pub async fn delete(
         &mut self,
         table_id: TabletIdAndTableNumber,
@@ -137,6 +137,7 @@ use usage_tracking::{
 use value::{
     heap_size::HeapSize,
     id_v6::DeveloperDocumentId,
+    InternalDocumentId,
     Size,
     TableNamespace,
     TableNumber,
@@ -927,6 +928,48 @@ impl<RT: Runtime> Database<RT> {
             .boxed()
     }
 
+    /// Load the revisions of `ids` that were visible at `ts`, skipping
+    /// documents that didn't exist at `ts`. Unlike `table_iterator`, this
+    /// reads the document log, so `ts` must be within document retention.
+    pub async fn load_revisions_at_ts(
+        &self,
+        ids: BTreeSet<InternalDocumentId>,
+        ts: Timestamp,
+    ) -> anyhow::Result<BTreeMap<InternalDocumentId, ResolvedDocument>> {
+        Ok(self
+            .load_latest_revisions_at_ts(ids, ts)
+            .await?
+            .into_iter()
+            .filter_map(|(id, (_, revision))| Some((id, revision?)))
+            .collect())
+    }
+
+    /// Load the timestamp and value of the latest revision of each of `ids`
+    /// at `ts`, with `None` for revisions that deleted the document. IDs
+    /// without any revision at `ts` are omitted.
+    pub async fn load_latest_revisions_at_ts(
+        &self,
+        ids: BTreeSet<InternalDocumentId>,
+        ts: Timestamp,
+    ) -> anyhow::Result<BTreeMap<InternalDocumentId, (Timestamp, Option<ResolvedDocument>)>> {
+        // Revisions earlier than `ts.succ()` are the ones visible at `ts`.
+        let ts_succ = ts.succ()?;
+        let retention_validator = self.retention_validator();
+        let repeatable_persistence = RepeatablePersistence::new(
+            self.reader.clone(),
+            self.now_ts_for_reads(),
+            retention_validator.clone(),
+        );
+        let revisions = repeatable_persistence
+            .previous_revisions(ids.into_iter().map(|id| (id, ts_succ)).collect())
+            .await?;
+        retention_validator.validate_document_snapshot(ts).await?;
+        Ok(revisions
+            .into_iter()
+            .map(|((id, _), revision)| (id, revision))
+            .collect())
+    }
+
     /// Allows iterating over tables at any repeatable timestamp,
     /// even if it's outside of retention.
     /// TableIterator will have to walk all documents between snapshot_ts
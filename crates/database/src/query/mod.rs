@@ -15,6 +15,7 @@ use common::{
         DeveloperDocument,
         ResolvedDocument,
     },
+    errors::INVALID_CURSOR_ERROR,
     index::IndexKeyBytes,
     interval::Interval,
     query::{
@@ -755,7 +756,9 @@ fn query_scanned_too_much_data(num_bytes: usize) -> ErrorMetadata {
 }
 
 pub fn invalid_cursor() -> ErrorMetadata {
-    let message = "InvalidCursor: Tried to run a query starting from a cursor, but it looks like \
-                   this cursor is from a different query.";
-    ErrorMetadata::bad_request("InvalidCursor", message)
+    let message = format!(
+        "{INVALID_CURSOR_ERROR}: Tried to run a query starting from a cursor, but it looks like \
+         this cursor is from a different query."
+    );
+    ErrorMetadata::bad_request(INVALID_CURSOR_ERROR, message)
 }
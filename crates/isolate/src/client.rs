@@ -354,6 +354,7 @@ pub trait ActionCallbacks: Send + Sync {
         udf_args: Vec<JsonValue>,
         scheduled_ts: UnixTimestamp,
         context: ExecutionContext,
+        dedup_key: Option<String>,
     ) -> anyhow::Result<DeveloperDocumentId>;
 
     async fn cancel_job(
@@ -231,6 +231,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
             function_handle: Option<String>,
             ts: f64,
             args: UdfArgsJson,
+            dedup_key: Option<String>,
         }
 
         let ScheduleArgs {
@@ -239,6 +240,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
             function_handle,
             ts,
             args,
+            dedup_key,
         }: ScheduleArgs = with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
         let path = match function_handle {
             Some(h) => {
@@ -264,6 +266,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 args.into_arg_vec(),
                 scheduled_ts,
                 self.context.clone(),
+                dedup_key,
             )
             .await?;
 
@@ -825,6 +825,7 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
             function_handle: Option<String>,
             ts: f64,
             args: UdfArgsJson,
+            dedup_key: Option<String>,
         }
 
         let ScheduleArgs {
@@ -833,6 +834,7 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
             function_handle,
             ts,
             args,
+            dedup_key,
         }: ScheduleArgs = with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
 
         let path = match function_handle {
@@ -872,7 +874,7 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
         let context = provider.context().clone();
         let tx = provider.tx()?;
         let virtual_id = VirtualSchedulerModel::new(tx, scheduling_component.into())
-            .schedule(path, udf_args, scheduled_ts, context)
+            .schedule(path, udf_args, scheduled_ts, context, dedup_key)
             .await?;
 
         Ok(JsonValue::from(virtual_id))
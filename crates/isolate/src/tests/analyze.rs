@@ -17,6 +17,7 @@ use model::{
     config::types::ModuleConfig,
     cron_jobs::types::{
         CronIdentifier,
+        CronOverlapPolicy,
         CronSchedule,
         CronSpec,
     },
@@ -173,15 +174,18 @@ async fn test_analyze_module(rt: TestRuntime) -> anyhow::Result<()> {
         CronIdentifier::from_str("weekly re-engagement email")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args.clone(),
-            cron_schedule: CronSchedule::Weekly { day_of_week: 2, hour_utc: 17, minute_utc: 30 }},
+            cron_schedule: CronSchedule::Weekly { day_of_week: 2, hour_utc: 17, minute_utc: 30 },
+            overlap_policy: CronOverlapPolicy::Skip },
         CronIdentifier::from_str("add one every hour")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args.clone(),
-            cron_schedule: CronSchedule::Interval{ seconds: 3600 * 24 * 7 } },
+            cron_schedule: CronSchedule::Interval{ seconds: 3600 * 24 * 7 },
+            overlap_policy: CronOverlapPolicy::Skip },
         CronIdentifier::from_str("clear presence data")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args,
-            cron_schedule: CronSchedule::Interval{ seconds: 300} },
+            cron_schedule: CronSchedule::Interval{ seconds: 300},
+            overlap_policy: CronOverlapPolicy::Skip },
         ).into()),
     );
 
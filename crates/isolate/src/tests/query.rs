@@ -25,6 +25,7 @@ use keybroker::Identity;
 use must_let::must_let;
 use pretty_assertions::assert_eq;
 use runtime::testing::TestRuntime;
+use sync_types::QueryErrorCode;
 use value::{
     assert_val,
     id_v6::DeveloperDocumentId,
@@ -568,6 +569,7 @@ async fn test_invalid_cursor_error(rt: TestRuntime) -> anyhow::Result<()> {
             )
             .await?;
         assert_contains(&e, "InvalidCursor");
+        assert_eq!(e.query_error_code(), Some(QueryErrorCode::InvalidCursor));
         Ok(())
     }).await
 }
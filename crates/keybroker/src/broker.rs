@@ -101,6 +101,7 @@ const ADMIN_KEY_VERSION: u8 = 1;
 const CURSOR_VERSION: u8 = 7;
 const STORE_FILE_AUTHZ_VERSION: u8 = 1;
 const QUERY_JOURNAL_VERSION: u8 = 7;
+const EXPORT_KEY_VERSION: u8 = 1;
 
 // Max delay from transaction start time -> key being issued that is tolerable.
 const MAX_TS_DELAY: Duration = Duration::from_secs(15);
@@ -964,6 +965,18 @@ impl KeyBroker {
         }
     }
 
+    /// Encrypts the key a snapshot export is encrypted with, so it can be
+    /// stored until the export runs.
+    pub fn encrypt_export_key(&self, key: Vec<u8>) -> String {
+        self.encryptor.encode_proto(EXPORT_KEY_VERSION, key)
+    }
+
+    pub fn decrypt_export_key(&self, encrypted_key: &str) -> anyhow::Result<Vec<u8>> {
+        self.encryptor
+            .decode_proto(EXPORT_KEY_VERSION, encrypted_key)
+            .context("Couldn't decrypt export key")
+    }
+
     pub fn issue_action_token(&self) -> ActionCallbackToken {
         let now = SystemTime::now();
         let since_epoch = now
@@ -1,12 +1,19 @@
 use std::{
+    collections::BTreeSet,
     fmt,
     path::PathBuf,
+    time::Duration,
 };
 
+use application::backup_scheduler::BackupPolicy;
 use clap::Parser;
-use common::types::{
-    ConvexOrigin,
-    ConvexSite,
+use common::{
+    components::ComponentPath,
+    types::{
+        ConvexOrigin,
+        ConvexSite,
+        TableName,
+    },
 };
 use keybroker::{
     InstanceSecret,
@@ -15,6 +22,7 @@ use keybroker::{
     DEV_SECRET,
 };
 use metrics::SERVER_VERSION_STR;
+use model::exports::types::ExportFilter;
 use url::Url;
 
 #[derive(Parser, Clone)]
@@ -54,6 +62,43 @@ pub struct LocalConfig {
     /// Which directory should local storage use
     #[clap(long, default_value = "convex_local_storage")]
     local_storage: String,
+
+    /// Back up the deployment as a zip export every this many hours. Backups
+    /// aren't scheduled if unset.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    backup_interval_hours: Option<u64>,
+
+    /// Include file storage in scheduled backups
+    #[clap(long, requires = "backup_interval_hours")]
+    backup_include_storage: bool,
+
+    /// Comma-separated tables to include in scheduled backups. All tables are
+    /// backed up if unset.
+    #[clap(long, value_delimiter = ',', requires = "backup_interval_hours")]
+    backup_include_tables: Option<Vec<TableName>>,
+
+    /// Comma-separated tables to leave out of scheduled backups
+    #[clap(long, value_delimiter = ',', requires = "backup_interval_hours")]
+    backup_exclude_tables: Vec<TableName>,
+
+    /// Comma-separated component paths to include in scheduled backups. All
+    /// components are backed up if unset.
+    #[clap(long, value_delimiter = ',', requires = "backup_interval_hours")]
+    backup_include_components: Option<Vec<ComponentPath>>,
+
+    /// Comma-separated component paths to leave out of scheduled backups
+    #[clap(long, value_delimiter = ',', requires = "backup_interval_hours")]
+    backup_exclude_components: Vec<ComponentPath>,
+
+    /// Keep the latest scheduled backup from each of this many most recent
+    /// days
+    #[clap(long, default_value = "7")]
+    backup_daily_copies: usize,
+
+    /// Keep the latest scheduled backup from each of this many most recent
+    /// weeks
+    #[clap(long, default_value = "4")]
+    backup_weekly_copies: usize,
 }
 
 impl fmt::Debug for LocalConfig {
@@ -112,6 +157,24 @@ impl LocalConfig {
         self.local_storage.clone().into()
     }
 
+    pub fn backup_policy(&self) -> Option<BackupPolicy> {
+        self.backup_interval_hours.map(|hours| BackupPolicy {
+            interval: Duration::from_secs(hours * 60 * 60),
+            include_storage: self.backup_include_storage,
+            filter: ExportFilter {
+                include_components: self
+                    .backup_include_components
+                    .clone()
+                    .map(BTreeSet::from_iter),
+                exclude_components: self.backup_exclude_components.iter().cloned().collect(),
+                include_tables: self.backup_include_tables.clone().map(BTreeSet::from_iter),
+                exclude_tables: self.backup_exclude_tables.iter().cloned().collect(),
+            },
+            daily_copies: self.backup_daily_copies,
+            weekly_copies: self.backup_weekly_copies,
+        })
+    }
+
     #[cfg(test)]
     pub fn new_for_test() -> anyhow::Result<Self> {
         use anyhow::Context;
@@ -1,9 +1,83 @@
+use anyhow::Context;
+use application::snapshot_encryption::{
+    SnapshotDecryptionKey,
+    SnapshotEncryptionKey,
+};
 use axum_extra::headers::{
     Header,
     HeaderName,
     HeaderValue,
 };
-use http::header::CONTENT_DISPOSITION;
+use errors::ErrorMetadata;
+use http::{
+    header::CONTENT_DISPOSITION,
+    HeaderMap,
+};
+
+/// The key to encrypt a snapshot export with, or to decrypt a snapshot import
+/// with: `Passphrase <passphrase>`, `PublicKey <base64>` (exports only) or
+/// `SecretKey <base64>` (imports only).
+pub const CONVEX_SNAPSHOT_KEY_HEADER: HeaderName = HeaderName::from_static("convex-snapshot-key");
+
+fn snapshot_key_from_headers(headers: &HeaderMap) -> anyhow::Result<Option<(&str, &str)>> {
+    let Some(value) = headers.get(CONVEX_SNAPSHOT_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let value = value.to_str().context(ErrorMetadata::bad_request(
+        "InvalidSnapshotKey",
+        format!("{CONVEX_SNAPSHOT_KEY_HEADER} header is not valid UTF-8"),
+    ))?;
+    let (kind, key) = value.split_once(' ').context(ErrorMetadata::bad_request(
+        "InvalidSnapshotKey",
+        format!("{CONVEX_SNAPSHOT_KEY_HEADER} header must be \"<kind> <key>\""),
+    ))?;
+    Ok(Some((kind, key.trim())))
+}
+
+fn decode_snapshot_key(kind: &str, key: &str) -> anyhow::Result<Vec<u8>> {
+    base64::decode(key).context(ErrorMetadata::bad_request(
+        "InvalidSnapshotKey",
+        format!("{kind} in {CONVEX_SNAPSHOT_KEY_HEADER} header is not valid base64"),
+    ))
+}
+
+pub fn snapshot_encryption_key_from_headers(
+    headers: &HeaderMap,
+) -> anyhow::Result<Option<SnapshotEncryptionKey>> {
+    let key = match snapshot_key_from_headers(headers)? {
+        None => return Ok(None),
+        Some(("Passphrase", passphrase)) => {
+            SnapshotEncryptionKey::Passphrase(passphrase.to_string())
+        },
+        Some((kind @ "PublicKey", key)) => {
+            SnapshotEncryptionKey::PublicKey(decode_snapshot_key(kind, key)?)
+        },
+        Some((kind, _)) => anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidSnapshotKey",
+            format!("Exports can't be encrypted with a {kind} key"),
+        )),
+    };
+    Ok(Some(key))
+}
+
+pub fn snapshot_decryption_key_from_headers(
+    headers: &HeaderMap,
+) -> anyhow::Result<Option<SnapshotDecryptionKey>> {
+    let key = match snapshot_key_from_headers(headers)? {
+        None => return Ok(None),
+        Some(("Passphrase", passphrase)) => {
+            SnapshotDecryptionKey::Passphrase(passphrase.to_string())
+        },
+        Some((kind @ "SecretKey", key)) => {
+            SnapshotDecryptionKey::SecretKey(decode_snapshot_key(kind, key)?)
+        },
+        Some((kind, _)) => anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidSnapshotKey",
+            format!("Imports can't be decrypted with a {kind} key"),
+        )),
+    };
+    Ok(Some(key))
+}
 
 // Takes filename
 pub struct ContentDispositionAttachment(pub String);
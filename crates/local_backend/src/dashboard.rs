@@ -1,4 +1,16 @@
-use application::valid_identifier::ValidIdentifier;
+use std::{
+    collections::BTreeMap,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use anyhow::Context;
+use application::{
+    table_restore::TableRestoreCounts,
+    valid_identifier::ValidIdentifier,
+};
 use axum::{
     debug_handler,
     extract::State,
@@ -19,12 +31,14 @@ use common::{
     },
 };
 use database::IndexModel;
+use errors::ErrorMetadata;
 use http::StatusCode;
 use model::virtual_system_mapping;
 use serde::{
     Deserialize,
     Serialize,
 };
+use sync_types::Timestamp;
 use value::{
     TableName,
     TableNamespace,
@@ -104,6 +118,107 @@ pub async fn delete_tables(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreTablesArgs {
+    table_names: Vec<String>,
+    component_id: Option<String>,
+    /// The time to restore the tables to, in milliseconds since the Unix
+    /// epoch. Must be within the deployment's document retention.
+    timestamp_ms: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreTablesResponse {
+    tables: BTreeMap<String, TableRestoreCountsResponse>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TableRestoreCountsResponse {
+    inserted: u64,
+    updated: u64,
+    deleted: u64,
+}
+
+impl From<BTreeMap<TableName, TableRestoreCounts>> for RestoreTablesResponse {
+    fn from(counts: BTreeMap<TableName, TableRestoreCounts>) -> Self {
+        Self {
+            tables: counts
+                .into_iter()
+                .map(|(table_name, counts)| {
+                    (
+                        table_name.to_string(),
+                        TableRestoreCountsResponse {
+                            inserted: counts.inserted,
+                            updated: counts.updated,
+                            deleted: counts.deleted,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+fn parse_restore_tables_args(
+    RestoreTablesArgs {
+        table_names,
+        component_id,
+        timestamp_ms,
+    }: RestoreTablesArgs,
+) -> anyhow::Result<(Vec<TableName>, TableNamespace, Timestamp)> {
+    let table_names = table_names
+        .into_iter()
+        .map(|t| Ok(t.parse::<ValidIdentifier<TableName>>()?.0))
+        .collect::<anyhow::Result<_>>()?;
+    let table_namespace = TableNamespace::from(ComponentId::deserialize_from_string(
+        component_id.as_deref(),
+    )?);
+    let ts = Timestamp::try_from(SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp_ms))
+        .context(ErrorMetadata::bad_request(
+            "InvalidRestoreTimestamp",
+            "RestoreTables requires a time in milliseconds since the Unix epoch",
+        ))?;
+    Ok((table_names, table_namespace, ts))
+}
+
+/// Count the documents that restoring tables to an earlier time would insert,
+/// update, and delete, without writing anything.
+#[debug_handler]
+pub async fn preview_restore_tables(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<RestoreTablesArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member(&identity)?;
+    let (table_names, table_namespace, ts) = parse_restore_tables_args(args)?;
+    let counts = st
+        .application
+        .preview_table_restore(&identity, table_names, table_namespace, ts)
+        .await?;
+    Ok(Json(RestoreTablesResponse::from(counts)))
+}
+
+/// Restore tables to their contents at an earlier time. Documents are written
+/// in batches, so if this fails partway through, the batches written before
+/// the failure stay written, and restoring again finishes the restore.
+#[debug_handler]
+pub async fn restore_tables(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<RestoreTablesArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+    let (table_names, table_namespace, ts) = parse_restore_tables_args(args)?;
+    let counts = st
+        .application
+        .restore_tables(&identity, table_names, table_namespace, ts)
+        .await?;
+    Ok(Json(RestoreTablesResponse::from(counts)))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetIndexesArgs {
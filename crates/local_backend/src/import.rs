@@ -1,10 +1,14 @@
 use std::str::FromStr;
 
 use anyhow::Context;
-use application::snapshot_import::{
-    self,
-    do_import,
-    upload_import_file,
+use application::{
+    snapshot_encryption::decrypt_stream,
+    snapshot_import::{
+        self,
+        do_dry_run_import,
+        do_import,
+        upload_import_file,
+    },
 };
 use axum::{
     body::Body,
@@ -24,9 +28,18 @@ use futures::{
     StreamExt,
     TryStreamExt,
 };
+use http::{
+    header::{
+        CONTENT_ENCODING,
+        CONTENT_TYPE,
+    },
+    HeaderMap,
+};
 use model::snapshot_imports::types::{
+    ImportCompression,
     ImportFormat,
     ImportMode,
+    SerializedImportValidationReport,
 };
 use serde::{
     Deserialize,
@@ -38,12 +51,14 @@ use storage::{
 };
 use value::{
     id_v6::DeveloperDocumentId,
+    FieldPath,
     TableName,
 };
 
 use crate::{
     admin::must_be_admin_with_write_access,
     authentication::ExtractIdentity,
+    custom_headers::snapshot_decryption_key_from_headers,
     LocalAppState,
 };
 
@@ -54,6 +69,15 @@ pub struct ImportQueryArgs {
     format: ImportFormatArg,
     #[serde(default)]
     mode: ImportMode,
+    /// The field to match rows on when `mode` is upsert. Defaults to `_id`.
+    upsert_key: Option<String>,
+    /// Compression of a CSV or JSONL file. If absent, it's taken from the
+    /// request's headers or detected from the file's contents.
+    compression: Option<ImportCompression>,
+    /// Validate the upload and report what importing it would do, without
+    /// writing anything. `/import` waits for the report and returns it.
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[derive(Deserialize)]
@@ -79,11 +103,15 @@ enum ImportFormatArg {
     JsonLines,
     JsonArray,
     Zip,
+    Parquet,
 }
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ImportResponse {
     num_written: u64,
+    /// The validation report of a dry run, which writes nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<SerializedImportValidationReport>,
 }
 
 fn parse_format_arg(
@@ -119,10 +147,45 @@ fn parse_format_arg(
         ImportFormatArg::JsonLines => ImportFormat::JsonLines(table_name.context(
             ErrorMetadata::bad_request("InvalidName", "JSONL import requires table name"),
         )?),
+        ImportFormatArg::Parquet => ImportFormat::Parquet(table_name.context(
+            ErrorMetadata::bad_request("InvalidName", "Parquet import requires table name"),
+        )?),
     };
     Ok(inner_format)
 }
 
+fn parse_upsert_key_arg(upsert_key: Option<String>) -> anyhow::Result<Option<FieldPath>> {
+    upsert_key
+        .map(|upsert_key| {
+            upsert_key.parse().map_err(|e| {
+                ErrorMetadata::bad_request(
+                    "InvalidUpsertKey",
+                    format!("invalid upsert key {upsert_key}: {e}"),
+                )
+                .into()
+            })
+        })
+        .transpose()
+}
+
+/// Compression declared by a streamed upload's `Content-Type` or
+/// `Content-Encoding` header.
+fn compression_from_headers(headers: &HeaderMap) -> Option<ImportCompression> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let content_encoding = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok());
+    match (content_type, content_encoding) {
+        (Some("application/gzip" | "application/x-gzip"), _) | (_, Some("gzip")) => {
+            Some(ImportCompression::Gzip)
+        },
+        (Some("application/zstd"), _) | (_, Some("zstd")) => Some(ImportCompression::Zstd),
+        _ => None,
+    }
+}
+
 pub async fn import(
     State(st): State<LocalAppState>,
     ExtractIdentity(identity): ExtractIdentity,
@@ -130,17 +193,54 @@ pub async fn import(
         table_name,
         format,
         mode,
+        upsert_key,
+        compression,
+        dry_run,
     }): Query<ImportQueryArgs>,
+    headers: HeaderMap,
     stream: Body,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
     let format = parse_format_arg(table_name, format)?;
-    let body_stream = stream
+    let upsert_key = parse_upsert_key_arg(upsert_key)?;
+    let compression = compression.or_else(|| compression_from_headers(&headers));
+    let mut body_stream = stream
         .into_data_stream()
         .map_err(anyhow::Error::from)
         .boxed();
-    let num_written = do_import(&st.application, identity, format, mode, body_stream).await?;
-    Ok(Json(ImportResponse { num_written }))
+    if let Some(decryption_key) = snapshot_decryption_key_from_headers(&headers)? {
+        body_stream = decrypt_stream(decryption_key, body_stream).boxed();
+    }
+    if dry_run {
+        let report = do_dry_run_import(
+            &st.application,
+            identity,
+            format,
+            mode,
+            upsert_key,
+            compression,
+            body_stream,
+        )
+        .await?;
+        return Ok(Json(ImportResponse {
+            num_written: 0,
+            report: Some(report.try_into()?),
+        }));
+    }
+    let num_written = do_import(
+        &st.application,
+        identity,
+        format,
+        mode,
+        upsert_key,
+        compression,
+        body_stream,
+    )
+    .await?;
+    Ok(Json(ImportResponse {
+        num_written,
+        report: None,
+    }))
 }
 
 #[derive(Serialize)]
@@ -198,12 +298,16 @@ pub async fn import_upload_part(
 pub async fn import_finish_upload(
     State(st): State<LocalAppState>,
     ExtractIdentity(identity): ExtractIdentity,
+    headers: HeaderMap,
     Json(ImportFinishUploadArgs {
         import:
             ImportQueryArgs {
                 table_name,
                 format,
                 mode,
+                upsert_key,
+                compression,
+                dry_run,
             },
         upload_token,
         part_tokens,
@@ -211,17 +315,23 @@ pub async fn import_finish_upload(
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
     let format = parse_format_arg(table_name, format)?;
+    let upsert_key = parse_upsert_key_arg(upsert_key)?;
+    let decryption_key = snapshot_decryption_key_from_headers(&headers)?;
     let import_id = st
         .application
         .import_finish_upload(
             identity,
             format,
             mode,
+            upsert_key,
+            compression,
+            dry_run,
             ClientDrivenUploadToken(upload_token),
             part_tokens
                 .into_iter()
                 .map(ClientDrivenUploadPartToken)
                 .collect(),
+            decryption_key,
         )
         .await?;
     Ok(Json(PrepareImportResponse {
@@ -242,17 +352,35 @@ pub async fn prepare_import(
         table_name,
         format,
         mode,
+        upsert_key,
+        compression,
+        dry_run,
     }): Query<ImportQueryArgs>,
+    headers: HeaderMap,
     stream: Body,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
     let format = parse_format_arg(table_name, format)?;
-    let body_stream = stream
+    let upsert_key = parse_upsert_key_arg(upsert_key)?;
+    let compression = compression.or_else(|| compression_from_headers(&headers));
+    let mut body_stream = stream
         .into_data_stream()
         .map_err(anyhow::Error::from)
         .boxed();
-    let import_id =
-        upload_import_file(&st.application, identity, format, mode, body_stream).await?;
+    if let Some(decryption_key) = snapshot_decryption_key_from_headers(&headers)? {
+        body_stream = decrypt_stream(decryption_key, body_stream).boxed();
+    }
+    let import_id = upload_import_file(
+        &st.application,
+        identity,
+        format,
+        mode,
+        upsert_key,
+        compression,
+        dry_run,
+        body_stream,
+    )
+    .await?;
     Ok(Json(PrepareImportResponse {
         import_id: import_id.encode(),
     }))
@@ -296,3 +424,64 @@ pub async fn cancel_import(
     snapshot_import::cancel_import(&st.application, identity, import_id).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        header::{
+            CONTENT_ENCODING,
+            CONTENT_TYPE,
+        },
+        HeaderMap,
+        HeaderValue,
+    };
+    use model::snapshot_imports::types::ImportCompression;
+
+    use super::compression_from_headers;
+
+    fn headers(
+        content_type: Option<&'static str>,
+        content_encoding: Option<&'static str>,
+    ) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        if let Some(content_encoding) = content_encoding {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static(content_encoding));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_compression_from_headers() {
+        assert_eq!(
+            compression_from_headers(&headers(Some("application/gzip"), None)),
+            Some(ImportCompression::Gzip)
+        );
+        assert_eq!(
+            compression_from_headers(&headers(Some("application/x-gzip"), None)),
+            Some(ImportCompression::Gzip)
+        );
+        assert_eq!(
+            compression_from_headers(&headers(Some("text/csv"), Some("gzip"))),
+            Some(ImportCompression::Gzip)
+        );
+        assert_eq!(
+            compression_from_headers(&headers(Some("application/zstd"), None)),
+            Some(ImportCompression::Zstd)
+        );
+        assert_eq!(
+            compression_from_headers(&headers(None, Some("zstd"))),
+            Some(ImportCompression::Zstd)
+        );
+        // Uncompressed uploads, and compressions that aren't supported, are
+        // left to be detected from the file's contents.
+        assert_eq!(
+            compression_from_headers(&headers(Some("text/csv"), None)),
+            None
+        );
+        assert_eq!(compression_from_headers(&headers(None, Some("br"))), None);
+        assert_eq!(compression_from_headers(&HeaderMap::new()), None);
+    }
+}
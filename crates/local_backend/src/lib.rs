@@ -266,6 +266,7 @@ pub async fn make_app(
             key_broker.clone(),
             Arc::new(NullAccessTokenAuth),
         )),
+        config.backup_policy(),
     )
     .await?;
 
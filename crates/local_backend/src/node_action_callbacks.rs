@@ -235,6 +235,7 @@ pub struct ScheduleJobRequest {
     udf_path: String,
     udf_args: UdfArgsJson,
     scheduled_ts: f64,
+    dedup_key: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -269,6 +270,7 @@ pub async fn schedule_job(
             udf_args,
             scheduled_ts,
             context,
+            req.dedup_key,
         )
         .await?;
     Ok(Json(ScheduleJobResponse {
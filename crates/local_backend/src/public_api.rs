@@ -21,6 +21,7 @@ use common::{
             Query,
         },
         ExtractClientVersion,
+        ExtractIdempotencyKey,
         ExtractRequestId,
         ExtractResolvedHost,
         HttpResponseError,
@@ -30,6 +31,7 @@ use common::{
 };
 use errors::ErrorMetadata;
 use isolate::UdfArgsJson;
+use model::session_requests::types::MutationIdentifier;
 use serde::{
     Deserialize,
     Serialize,
@@ -535,6 +537,7 @@ pub async fn public_mutation_post(
     ExtractRequestId(request_id): ExtractRequestId,
     ExtractAuthenticationToken(auth_token): ExtractAuthenticationToken,
     ExtractClientVersion(client_version): ExtractClientVersion,
+    ExtractIdempotencyKey(idempotency_key): ExtractIdempotencyKey,
     Json(req): Json<UdfPostRequest>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let export_path = parse_export_path(&req.path)?;
@@ -555,7 +558,7 @@ pub async fn public_mutation_post(
             export_path,
             req.args.into_arg_vec(),
             FunctionCaller::HttpApi(client_version.clone()),
-            None,
+            idempotency_key.map(MutationIdentifier::IdempotencyKey),
         )
         .await?;
     let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
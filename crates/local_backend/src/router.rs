@@ -50,6 +50,8 @@ use crate::{
         delete_tables,
         get_indexes,
         get_source_code,
+        preview_restore_tables,
+        restore_tables,
         shapes2,
     },
     deploy_config::{
@@ -100,14 +102,20 @@ use crate::{
     scheduling::{
         cancel_all_jobs,
         cancel_job,
+        cancel_jobs_in_range,
+        list_dead_letters,
+        replay_dead_letters,
     },
     schema::{
         prepare_schema,
         schema_state,
     },
     snapshot_export::{
+        delete_backup,
+        export_table_csv,
         get_export,
         get_zip_export,
+        list_backups,
         request_export,
         request_zip_export,
     },
@@ -133,12 +141,17 @@ pub async fn router(st: LocalAppState) -> Router {
         // Scheduled jobs routes
         .route("/cancel_all_jobs", post(cancel_all_jobs))
         .route("/cancel_job", post(cancel_job))
+        .route("/cancel_jobs_in_range", post(cancel_jobs_in_range))
+        .route("/list_dead_letters", get(list_dead_letters))
+        .route("/replay_dead_letters", post(replay_dead_letters))
         // Environment variable routes
         .route("/update_environment_variables", post(update_environment_variables))
         // Administrative routes for the dashboard
         .route("/shapes2", get(shapes2))
         .route("/get_indexes", get(get_indexes))
         .route("/delete_tables", post(delete_tables))
+        .route("/preview_restore_tables", post(preview_restore_tables))
+        .route("/restore_tables", post(restore_tables))
         .route("/get_source_code", get(get_source_code))
         // Metrics routes
         .route("/app_metrics/stream_udf_execution", get(stream_udf_execution))
@@ -174,7 +187,13 @@ pub async fn router(st: LocalAppState) -> Router {
         .route("/request", post(request_export))
         .route("/:snapshot_ts/:table_name", get(get_export))
         .route("/request/zip", post(request_zip_export))
-        .route("/zip/:snapshot_ts", get(get_zip_export));
+        .route("/zip/:snapshot_ts", get(get_zip_export))
+        .route("/csv/:table_name", get(export_table_csv))
+        .route("/backups", get(list_backups))
+        .route(
+            "/backups/:snapshot_ts",
+            get(get_zip_export).delete(delete_backup),
+        );
 
     let api_routes = Router::new()
         .merge(cli_routes)
@@ -1,3 +1,8 @@
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
 use anyhow::Context;
 use axum::{
     debug_handler,
@@ -10,10 +15,18 @@ use common::{
         ComponentId,
         ComponentPath,
     },
+    execution_context::ExecutionContext,
     http::{
-        extract::Json,
+        extract::{
+            Json,
+            Query,
+        },
+        ExtractClientVersion,
+        ExtractRequestId,
         HttpResponseError,
     },
+    knobs::MAX_JOBS_CANCEL_BATCHES_PER_REQUEST,
+    types::FunctionCaller,
 };
 use errors::ErrorMetadata;
 use http::StatusCode;
@@ -25,10 +38,20 @@ use serde::{
     Deserialize,
     Serialize,
 };
-use value::TableNamespace;
+use serde_json::Value as JsonValue;
+use sync_types::Timestamp;
+use value::{
+    ConvexArray,
+    ConvexValue,
+    DeveloperDocumentId,
+    TableNamespace,
+};
 
 use crate::{
-    admin::must_be_admin_member_with_write_access,
+    admin::{
+        must_be_admin_member,
+        must_be_admin_member_with_write_access,
+    },
     authentication::ExtractIdentity,
     parse::parse_document_id,
     LocalAppState,
@@ -80,6 +103,133 @@ pub async fn cancel_all_jobs(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelJobsInRangeRequest {
+    /// component_id is the current component in which we will cancel jobs.
+    pub component_id: Option<String>,
+    /// component_path and udf_path are an optional filter for the function that
+    /// is scheduled, as in `CancelAllJobsRequest`.
+    pub component_path: Option<String>,
+    pub udf_path: Option<String>,
+    /// Only cancel jobs scheduled to run at or after this time, in milliseconds
+    /// since the Unix epoch.
+    pub start_time_ms: Option<u64>,
+    /// Only cancel jobs scheduled to run before this time, in milliseconds
+    /// since the Unix epoch.
+    pub end_time_ms: Option<u64>,
+    /// Resume a previous request that returned `isDone: false`. Takes the
+    /// place of `start_time_ms`.
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelJobsInRangeResponse {
+    /// Number of jobs canceled by this request.
+    pub num_canceled: usize,
+    /// Pass this back as `cursor` to cancel the rest of the jobs if `is_done`
+    /// is false.
+    pub cursor: Option<String>,
+    pub is_done: bool,
+}
+
+fn parse_time_range(
+    start_time_ms: Option<u64>,
+    end_time_ms: Option<u64>,
+) -> anyhow::Result<(Option<Timestamp>, Option<Timestamp>)> {
+    let parse = |time_ms: Option<u64>| {
+        time_ms
+            .map(|ms| Timestamp::try_from(SystemTime::UNIX_EPOCH + Duration::from_millis(ms)))
+            .transpose()
+            .context(ErrorMetadata::bad_request(
+                "InvalidScheduledTime",
+                "CancelJobsInRange requires times in milliseconds since the Unix epoch",
+            ))
+    };
+    let start_ts = parse(start_time_ms)?;
+    let end_ts = parse(end_time_ms)?;
+    if let (Some(start_ts), Some(end_ts)) = (start_ts, end_ts) {
+        anyhow::ensure!(
+            start_ts <= end_ts,
+            ErrorMetadata::bad_request(
+                "InvalidScheduledTimeRange",
+                "CancelJobsInRange requires startTimeMs to be at most endTimeMs",
+            )
+        );
+    }
+    Ok((start_ts, end_ts))
+}
+
+fn parse_cursor(cursor: String) -> anyhow::Result<Timestamp> {
+    let invalid_cursor = || {
+        ErrorMetadata::bad_request(
+            "InvalidCursor",
+            "CancelJobsInRange requires a cursor returned by a previous request",
+        )
+    };
+    let ts: i64 = cursor.parse().context(invalid_cursor())?;
+    Timestamp::try_from(ts).context(invalid_cursor())
+}
+
+/// Cancel pending scheduled jobs matching a function and a window of scheduled
+/// times. Jobs are canceled in batches in order of their scheduled time, and a
+/// request stops after `MAX_JOBS_CANCEL_BATCHES_PER_REQUEST` batches. Callers
+/// poll by repeating the request with the returned cursor until `isDone`.
+/// Batches that finished before a failure stay canceled.
+#[debug_handler]
+pub async fn cancel_jobs_in_range(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CancelJobsInRangeRequest {
+        component_id,
+        component_path,
+        udf_path,
+        start_time_ms,
+        end_time_ms,
+        cursor,
+    }): Json<CancelJobsInRangeRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+
+    let udf_path = udf_path
+        .map(|p| p.parse())
+        .transpose()
+        .context(ErrorMetadata::bad_request(
+            "InvalidUdfPath",
+            "CancelJobsInRange requires an optional canonicalized UdfPath",
+        ))?;
+    let (mut start_ts, end_ts) = parse_time_range(start_time_ms, end_time_ms)?;
+    if let Some(cursor) = cursor {
+        start_ts = Some(parse_cursor(cursor)?);
+    }
+    let component_id = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let path = match udf_path {
+        None => None,
+        Some(udf_path) => Some(CanonicalizedComponentFunctionPath {
+            component: ComponentPath::deserialize(component_path.as_deref())?,
+            udf_path,
+        }),
+    };
+    let progress = st
+        .application
+        .cancel_jobs_in_range(
+            component_id,
+            path,
+            start_ts,
+            end_ts,
+            Some(*MAX_JOBS_CANCEL_BATCHES_PER_REQUEST),
+            identity,
+        )
+        .await?;
+
+    Ok(Json(CancelJobsInRangeResponse {
+        num_canceled: progress.num_canceled,
+        cursor: progress.cursor.map(|ts| i64::from(ts).to_string()),
+        is_done: progress.is_done,
+    }))
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CancelJobRequest {
@@ -113,3 +263,166 @@ pub async fn cancel_job(
 
     Ok(StatusCode::OK)
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayDeadLettersRequest {
+    pub component_id: Option<String>,
+    pub dead_letters: Vec<DeadLetterReplay>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterReplay {
+    /// The id of a document in `_scheduled_job_dead_letters`.
+    pub id: String,
+    /// Arguments to run the job with instead of the ones it failed with.
+    pub args: Option<JsonValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayDeadLettersResponse {
+    /// Ids of the newly scheduled jobs, in the same order as the request.
+    pub job_ids: Vec<String>,
+}
+
+#[debug_handler]
+pub async fn replay_dead_letters(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    ExtractRequestId(request_id): ExtractRequestId,
+    ExtractClientVersion(client_version): ExtractClientVersion,
+    Json(ReplayDeadLettersRequest {
+        component_id,
+        dead_letters,
+    }): Json<ReplayDeadLettersRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+
+    let component_id = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let replays = dead_letters
+        .into_iter()
+        .map(|DeadLetterReplay { id, args }| {
+            let id = DeveloperDocumentId::decode(&id).context(ErrorMetadata::bad_request(
+                "InvalidDeadLetterId",
+                format!("Invalid dead letter id: {id}"),
+            ))?;
+            let args = args
+                .map(|args| -> anyhow::Result<_> {
+                    ConvexArray::try_from(vec![ConvexValue::try_from(args)?])
+                })
+                .transpose()
+                .context(ErrorMetadata::bad_request(
+                    "InvalidDeadLetterArgs",
+                    "Replayed job arguments must be a Convex object",
+                ))?;
+            anyhow::Ok((id, args))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let context = ExecutionContext::new(request_id, &FunctionCaller::HttpApi(client_version));
+    let job_ids = st
+        .application
+        .replay_scheduled_job_dead_letters(identity, component_id, replays, context)
+        .await?;
+
+    Ok(Json(ReplayDeadLettersResponse {
+        job_ids: job_ids.into_iter().map(|id| id.to_string()).collect(),
+    }))
+}
+
+/// Upper bound on the number of dead letters returned by one
+/// `list_dead_letters` request.
+const MAX_DEAD_LETTERS_PER_REQUEST: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeadLettersArgs {
+    pub component_id: Option<String>,
+    /// Only list dead letters for this function.
+    pub udf_path: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterResponse {
+    pub id: String,
+    pub job_id: String,
+    pub component: String,
+    pub udf_path: String,
+    pub args: JsonValue,
+    pub error: String,
+    /// Times in milliseconds since the Unix epoch.
+    pub original_scheduled_time_ms: u64,
+    pub failed_time_ms: u64,
+    pub replayed_job_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeadLettersResponse {
+    pub dead_letters: Vec<DeadLetterResponse>,
+}
+
+/// List dead-lettered scheduled jobs, most recently failed first, so they can
+/// be inspected and passed to `replay_dead_letters`.
+#[debug_handler]
+pub async fn list_dead_letters(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListDeadLettersArgs {
+        component_id,
+        udf_path,
+        limit,
+    }): Query<ListDeadLettersArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member(&identity)?;
+
+    let udf_path = udf_path
+        .map(|p| p.parse())
+        .transpose()
+        .context(ErrorMetadata::bad_request(
+            "InvalidUdfPath",
+            "ListDeadLetters requires an optional canonicalized UdfPath",
+        ))?;
+    let component_id = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let limit = limit
+        .unwrap_or(MAX_DEAD_LETTERS_PER_REQUEST)
+        .min(MAX_DEAD_LETTERS_PER_REQUEST);
+    let dead_letters = st
+        .application
+        .list_scheduled_job_dead_letters(identity, component_id, udf_path, limit)
+        .await?;
+    let dead_letters = dead_letters
+        .into_iter()
+        .map(|dead_letter| {
+            let (id, dead_letter) = dead_letter.into_id_and_value();
+            // Show the single argument object, which is what replays take.
+            let args = match &dead_letter.udf_args[..] {
+                [arg] => JsonValue::from(arg.clone()),
+                _ => JsonValue::from(dead_letter.udf_args.clone()),
+            };
+            Ok(DeadLetterResponse {
+                id: id.developer_id.to_string(),
+                job_id: dead_letter.job_id.to_string(),
+                component: String::from(dead_letter.path.component),
+                udf_path: dead_letter.path.udf_path.to_string(),
+                args,
+                error: dead_letter.error,
+                original_scheduled_time_ms: timestamp_to_ms(dead_letter.original_scheduled_ts)?,
+                failed_time_ms: timestamp_to_ms(dead_letter.failed_ts)?,
+                replayed_job_id: dead_letter.replayed_job_id.map(|id| id.to_string()),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Json(ListDeadLettersResponse { dead_letters }))
+}
+
+fn timestamp_to_ms(ts: Timestamp) -> anyhow::Result<u64> {
+    Ok(SystemTime::from(ts)
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_millis()
+        .try_into()?)
+}
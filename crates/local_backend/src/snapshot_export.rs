@@ -1,6 +1,11 @@
-use std::time::Duration;
+use std::{
+    collections::BTreeSet,
+    str::FromStr,
+    time::Duration,
+};
 
 use anyhow::Context;
+use application::valid_identifier::ValidIdentifier;
 use axum::{
     body::Body,
     debug_handler,
@@ -14,36 +19,107 @@ use axum_extra::{
     },
     TypedHeader,
 };
-use common::http::{
-    extract::{
-        Path,
-        Query,
+use common::{
+    components::ComponentId,
+    http::{
+        extract::{
+            Json,
+            Path,
+            Query,
+        },
+        HttpResponseError,
     },
-    HttpResponseError,
 };
 use errors::ErrorMetadata;
-use http::StatusCode;
-use serde::Deserialize;
+use http::{
+    header::CONTENT_TYPE,
+    HeaderMap,
+    StatusCode,
+};
+use model::exports::types::{
+    Export,
+    ExportFilter,
+    ExportFormat,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use storage::StorageGetStream;
 use sync_types::Timestamp;
+use value::{
+    TableName,
+    TableNamespace,
+};
 
 use crate::{
-    admin::must_be_admin_with_write_access,
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
     authentication::ExtractIdentity,
-    custom_headers::ContentDispositionAttachment,
+    custom_headers::{
+        snapshot_encryption_key_from_headers,
+        ContentDispositionAttachment,
+    },
     LocalAppState,
 };
 
 // Export GETs are immutable. Browser can cache for a long time.
 const MAX_CACHE_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
 
+/// Comma-separated lists of the tables and component paths to include in or
+/// exclude from an export. Everything is exported by default.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFilterArgs {
+    include_tables: Option<String>,
+    exclude_tables: Option<String>,
+    include_components: Option<String>,
+    exclude_components: Option<String>,
+}
+
+fn parse_list<T: FromStr<Err = anyhow::Error> + Ord>(
+    list: Option<String>,
+) -> anyhow::Result<Option<BTreeSet<T>>> {
+    list.map(|list| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                name.parse().context(ErrorMetadata::bad_request(
+                    "InvalidExportFilter",
+                    format!("Invalid table name or component path in export filter: {name}"),
+                ))
+            })
+            .collect()
+    })
+    .transpose()
+}
+
+impl TryFrom<ExportFilterArgs> for ExportFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(args: ExportFilterArgs) -> anyhow::Result<Self> {
+        Ok(ExportFilter {
+            include_components: parse_list(args.include_components)?,
+            exclude_components: parse_list(args.exclude_components)?.unwrap_or_default(),
+            include_tables: parse_list(args.include_tables)?,
+            exclude_tables: parse_list(args.exclude_tables)?.unwrap_or_default(),
+        })
+    }
+}
+
 pub async fn request_export(
     State(st): State<LocalAppState>,
     ExtractIdentity(identity): ExtractIdentity,
+    Query(filter): Query<ExportFilterArgs>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
+    let encryption_key = snapshot_encryption_key_from_headers(&headers)?;
     st.application
-        .request_export(identity, false, false)
+        .request_export(identity, None, filter.try_into()?, encryption_key)
         .await?;
     Ok(StatusCode::OK)
 }
@@ -53,17 +129,65 @@ pub async fn request_export(
 pub struct RequestZipExport {
     #[serde(default)]
     include_storage: bool,
+    #[serde(default)]
+    format: ZipExportFormat,
+    /// Only export changes since the completed export with this snapshot
+    /// timestamp.
+    base_snapshot_ts: Option<String>,
+}
+
+/// The format of each table's documents inside the zip.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ZipExportFormat {
+    #[default]
+    Jsonl,
+    Parquet,
 }
 
 #[minitrace::trace]
 pub async fn request_zip_export(
     State(st): State<LocalAppState>,
     ExtractIdentity(identity): ExtractIdentity,
-    Query(RequestZipExport { include_storage }): Query<RequestZipExport>,
+    Query(RequestZipExport {
+        include_storage,
+        format,
+        base_snapshot_ts,
+    }): Query<RequestZipExport>,
+    Query(filter): Query<ExportFilterArgs>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
+    let format = match (format, base_snapshot_ts) {
+        (ZipExportFormat::Jsonl, None) => ExportFormat::Zip { include_storage },
+        (ZipExportFormat::Parquet, None) => ExportFormat::Parquet { include_storage },
+        (ZipExportFormat::Jsonl, Some(base_snapshot_ts)) => {
+            if include_storage {
+                return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+                    "IncrementalExportWithStorage",
+                    "Incremental exports can't include file storage.",
+                ))
+                .into());
+            }
+            let base_ts = base_snapshot_ts
+                .parse()
+                .context(ErrorMetadata::bad_request(
+                    "BadSnapshotTimestamp",
+                    "Snapshot timestamp did not parse to a timestamp.",
+                ))?;
+            ExportFormat::Incremental { base_ts }
+        },
+        (ZipExportFormat::Parquet, Some(_)) => {
+            return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+                "IncrementalExportFormat",
+                "Incremental exports are only available in the JSONL format.",
+            ))
+            .into());
+        },
+    };
+    let encryption_key = snapshot_encryption_key_from_headers(&headers)?;
     st.application
-        .request_export(identity, true, include_storage)
+        .request_export(identity, Some(format), filter.try_into()?, encryption_key)
         .await?;
     Ok(StatusCode::OK)
 }
@@ -166,3 +290,274 @@ pub async fn get_zip_export(
         Body::from_stream(stream),
     ))
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupResponse {
+    snapshot_ts: String,
+    complete_ts: String,
+    /// `zip`, `parquet` or `incremental`.
+    format: &'static str,
+    include_storage: bool,
+    /// The snapshot an incremental backup contains the changes since.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_snapshot_ts: Option<String>,
+}
+
+impl TryFrom<Export> for BackupResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(export: Export) -> anyhow::Result<Self> {
+        let Export::Completed {
+            start_ts,
+            complete_ts,
+            format,
+            ..
+        } = &export
+        else {
+            anyhow::bail!("Backup {export} isn't a completed export");
+        };
+        let (format_name, include_storage, base_snapshot_ts) = match *format {
+            ExportFormat::Zip { include_storage } => ("zip", include_storage, None),
+            ExportFormat::Parquet { include_storage } => ("parquet", include_storage, None),
+            ExportFormat::Incremental { base_ts } => {
+                ("incremental", false, Some(base_ts.to_string()))
+            },
+            ExportFormat::InternalJson | ExportFormat::CleanJsonl => {
+                anyhow::bail!("Backup {export} isn't a zip export")
+            },
+        };
+        Ok(Self {
+            snapshot_ts: start_ts.to_string(),
+            complete_ts: complete_ts.to_string(),
+            format: format_name,
+            include_storage,
+            base_snapshot_ts,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListBackupsResponse {
+    /// Newest first. Download each from `/api/export/backups/{snapshotTs}`.
+    backups: Vec<BackupResponse>,
+}
+
+#[debug_handler]
+pub async fn list_backups(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let backups = st
+        .application
+        .list_backups(identity)
+        .await?
+        .into_iter()
+        .map(BackupResponse::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListBackupsResponse { backups }))
+}
+
+#[debug_handler]
+pub async fn delete_backup(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(ZipExportRequest { snapshot_ts }): Path<ZipExportRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let ts: Timestamp = snapshot_ts.parse().context(ErrorMetadata::bad_request(
+        "BadSnapshotTimestamp",
+        "Snapshot timestamp did not parse to a timestamp.",
+    ))?;
+    st.application.delete_backup(identity, ts).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct TableCsvExportRequest {
+    table_name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableCsvExportArgs {
+    component_id: Option<String>,
+}
+
+/// Downloads a single table as a CSV file, with nested objects flattened into
+/// dotted columns. The file can be imported back with `npx convex import`.
+#[debug_handler]
+pub async fn export_table_csv(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(TableCsvExportRequest { table_name }): Path<TableCsvExportRequest>,
+    Query(TableCsvExportArgs { component_id }): Query<TableCsvExportArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let table_name = table_name.parse::<ValidIdentifier<TableName>>()?.0;
+    let table_namespace = TableNamespace::from(ComponentId::deserialize_from_string(
+        component_id.as_deref(),
+    )?);
+    let stream = st
+        .application
+        .export_table_csv(&identity, table_name.clone(), table_namespace)
+        .await?;
+    Ok((
+        [(CONTENT_TYPE, "text/csv; charset=utf-8")],
+        TypedHeader(ContentDispositionAttachment(format!("{table_name}.csv"))),
+        Body::from_stream(stream),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_extra::headers::authorization::Credentials;
+    use common::types::{
+        MemberId,
+        ObjectKey,
+    };
+    use database::SystemMetadataModel;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use keybroker::Identity;
+    use model::exports::{
+        types::{
+            Export,
+            ExportFilter,
+            ExportFormat,
+            ExportObjectKeys,
+            ExportRequestor,
+        },
+        EXPORTS_TABLE,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+    use sync_types::Timestamp;
+
+    use crate::test_helpers::{
+        setup_backend_for_test,
+        TestLocalBackend,
+    };
+
+    async fn insert_backup(
+        backend: &TestLocalBackend,
+        format: ExportFormat,
+        snapshot_ts: Timestamp,
+    ) -> anyhow::Result<()> {
+        let export = Export::requested(
+            format,
+            ExportRequestor::ScheduledBackup,
+            ExportFilter::default(),
+            None,
+        )
+        .in_progress(snapshot_ts)?
+        .completed(
+            snapshot_ts,
+            snapshot_ts,
+            ExportObjectKeys::Zip(ObjectKey::try_from(format!("backup-{snapshot_ts}"))?),
+        )?;
+        let mut tx = backend.st.application.begin(Identity::system()).await?;
+        SystemMetadataModel::new_global(&mut tx)
+            .insert(&EXPORTS_TABLE, export.try_into()?)
+            .await?;
+        backend.st.application.commit_test(tx).await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_list_and_delete_backups(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        insert_backup(
+            &backend,
+            ExportFormat::Zip {
+                include_storage: true,
+            },
+            Timestamp::must(1000),
+        )
+        .await?;
+        insert_backup(
+            &backend,
+            ExportFormat::Parquet {
+                include_storage: false,
+            },
+            Timestamp::must(2000),
+        )
+        .await?;
+
+        // Listing backups only needs read access.
+        let read_only_header = backend
+            .st
+            .application
+            .key_broker()
+            .issue_read_only_admin_key(MemberId(2))
+            .as_header()?;
+        let req = Request::builder()
+            .uri("/api/export/backups")
+            .method("GET")
+            .header("Authorization", read_only_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        let backups: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(
+            backups,
+            json!({
+                "backups": [
+                    {
+                        "snapshotTs": "2000",
+                        "completeTs": "2000",
+                        "format": "parquet",
+                        "includeStorage": false,
+                    },
+                    {
+                        "snapshotTs": "1000",
+                        "completeTs": "1000",
+                        "format": "zip",
+                        "includeStorage": true,
+                    },
+                ],
+            })
+        );
+
+        // Deleting them needs write access.
+        let req = Request::builder()
+            .uri("/api/export/backups/1000")
+            .method("DELETE")
+            .header("Authorization", read_only_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "ReadOnlyAdminKey")
+            .await?;
+        let req = Request::builder()
+            .uri("/api/export/backups/1000")
+            .method("DELETE")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        let req = Request::builder()
+            .uri("/api/export/backups")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        let backups: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(backups["backups"].as_array().map(Vec::len), Some(1));
+        assert_eq!(backups["backups"][0]["snapshotTs"], "2000");
+
+        // The deleted backup can't be deleted again.
+        let req = Request::builder()
+            .uri("/api/export/backups/1000")
+            .method("DELETE")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "BackupNotFound")
+            .await?;
+        Ok(())
+    }
+}
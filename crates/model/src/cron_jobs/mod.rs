@@ -203,6 +203,7 @@ impl<'a, RT: Runtime> CronModel<'a, RT> {
             cron_spec,
             state: CronJobState::Pending,
             prev_ts: None,
+            concurrent_runs: vec![],
         };
         SystemMetadataModel::new(self.tx, self.component.into())
             .insert(&CRON_JOBS_TABLE, cron.try_into()?)
@@ -58,6 +58,9 @@ pub struct CronJob {
     pub state: CronJobState,
     pub prev_ts: Option<Timestamp>,
     pub next_ts: Timestamp,
+    // Scheduled times of runs started under
+    // `CronOverlapPolicy::AllowConcurrent` that haven't finished yet.
+    pub concurrent_runs: Vec<Timestamp>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -68,6 +71,8 @@ struct SerializedCronJob {
     state: CronJobState,
     prev_ts: Option<i64>,
     next_ts: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    concurrent_runs: Vec<i64>,
 }
 
 impl TryFrom<CronJob> for SerializedCronJob {
@@ -80,6 +85,11 @@ impl TryFrom<CronJob> for SerializedCronJob {
             state: job.state,
             prev_ts: job.prev_ts.map(|ts| ts.into()),
             next_ts: job.next_ts.into(),
+            concurrent_runs: job
+                .concurrent_runs
+                .into_iter()
+                .map(|ts| ts.into())
+                .collect(),
         })
     }
 }
@@ -94,6 +104,11 @@ impl TryFrom<SerializedCronJob> for CronJob {
             state: value.state,
             prev_ts: value.prev_ts.map(|ts| ts.try_into()).transpose()?,
             next_ts: value.next_ts.try_into()?,
+            concurrent_runs: value
+                .concurrent_runs
+                .into_iter()
+                .map(|ts| ts.try_into())
+                .collect::<anyhow::Result<_>>()?,
         })
     }
 }
@@ -172,6 +187,7 @@ pub struct CronSpec {
     )]
     pub udf_args: ConvexArray,
     pub cron_schedule: CronSchedule,
+    pub overlap_policy: CronOverlapPolicy,
 }
 
 impl HeapSize for CronSpec {
@@ -187,6 +203,10 @@ pub struct SerializedCronSpec {
     #[serde(with = "serde_bytes")]
     udf_args: Option<Vec<u8>>,
     cron_schedule: SerializedCronSchedule,
+    // Omitted for the default policy so crons pushed before overlap policies
+    // existed are unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overlap_policy: Option<CronOverlapPolicy>,
 }
 
 impl TryFrom<CronSpec> for SerializedCronSpec {
@@ -201,6 +221,8 @@ impl TryFrom<CronSpec> for SerializedCronSpec {
             udf_path: String::from(spec.udf_path),
             udf_args: Some(udf_args_bytes),
             cron_schedule: spec.cron_schedule.try_into()?,
+            overlap_policy: (spec.overlap_policy != CronOverlapPolicy::default())
+                .then_some(spec.overlap_policy),
         })
     }
 }
@@ -222,6 +244,7 @@ impl TryFrom<SerializedCronSpec> for CronSpec {
             udf_path,
             udf_args,
             cron_schedule,
+            overlap_policy: value.overlap_policy.unwrap_or_default(),
         })
     }
 }
@@ -297,6 +320,14 @@ impl TryFrom<JsonValue> for CronSpec {
             Cron { cron: String },
         }
 
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        enum CronOverlapPolicyJson {
+            Skip,
+            QueueOne,
+            AllowConcurrent,
+        }
+
         // The JavaScript object produced by crons.export() uses different names:
         // name -> udf_path, schedule -> cron_schedule, args -> udf_args
         #[derive(Deserialize)]
@@ -305,6 +336,7 @@ impl TryFrom<JsonValue> for CronSpec {
             name: String,
             args: JsonValue,
             schedule: ScheduleJson,
+            overlap_policy: Option<CronOverlapPolicyJson>,
         }
         let j: CronSpecJson = serde_json::from_value(value.clone())
             .with_context(|| CronValidationError::InvalidJson)?;
@@ -442,6 +474,11 @@ impl TryFrom<JsonValue> for CronSpec {
             udf_path: udf_path_canonicalized,
             udf_args: ConvexArray::try_from(j.args)?,
             cron_schedule: schedule,
+            overlap_policy: match j.overlap_policy {
+                None | Some(CronOverlapPolicyJson::Skip) => CronOverlapPolicy::Skip,
+                Some(CronOverlapPolicyJson::QueueOne) => CronOverlapPolicy::QueueOne,
+                Some(CronOverlapPolicyJson::AllowConcurrent) => CronOverlapPolicy::AllowConcurrent,
+            },
         })
     }
 }
@@ -456,6 +493,23 @@ pub enum CronJobState {
     InProgress,
 }
 
+// What to do when a cron comes due while its previous run is still in
+// progress. Only actions can overlap since mutations finish before the cron is
+// rescheduled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum CronOverlapPolicy {
+    // Drop every run that comes due while the previous run is in progress.
+    #[default]
+    Skip,
+    // Run once as soon as the previous run finishes if any runs came due in
+    // the meantime, dropping all but the latest of them.
+    QueueOne,
+    // Start every run on schedule, even if previous runs haven't finished.
+    AllowConcurrent,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum CronSchedule {
@@ -836,6 +890,9 @@ pub enum CronJobStatus {
     Success(CronJobResult),
     Err(String),
     Canceled { num_canceled: i64 },
+    // Runs dropped by `CronOverlapPolicy` because a previous run was still in
+    // progress.
+    Skipped { num_skipped: i64 },
 }
 
 impl TryFrom<CronJobStatus> for ConvexObject {
@@ -855,6 +912,9 @@ impl TryFrom<CronJobStatus> for ConvexObject {
             CronJobStatus::Canceled { num_canceled } => {
                 obj!("type" => "canceled", "num_canceled" => num_canceled)
             },
+            CronJobStatus::Skipped { num_skipped } => {
+                obj!("type" => "skipped", "num_skipped" => num_skipped)
+            },
         }
     }
 }
@@ -903,6 +963,16 @@ impl TryFrom<ConvexObject> for CronJobStatus {
                 };
                 Ok(CronJobStatus::Canceled { num_canceled })
             },
+            "skipped" => {
+                let num_skipped = match fields.remove("num_skipped") {
+                    Some(ConvexValue::Int64(i)) => i,
+                    _ => anyhow::bail!(
+                        "Missing or invalid `num_skipped` field for CronJobStatus: {:?}",
+                        fields
+                    ),
+                };
+                Ok(CronJobStatus::Skipped { num_skipped })
+            },
             _ => anyhow::bail!("Invalid CronJobStatus `type`: {}", status_t),
         };
     }
@@ -1040,6 +1110,8 @@ mod tests {
         CronJobLogLines,
         CronJobResult,
         CronJobStatus,
+        CronOverlapPolicy,
+        CronSpec,
     };
 
     proptest! {
@@ -1100,4 +1172,33 @@ mod tests {
         );
         assert_roundtrips::<_, CronJob>(cron_job_obj);
     }
+
+    #[test]
+    fn test_cron_spec_overlap_policy_from_json() -> anyhow::Result<()> {
+        let spec_json = |overlap_policy: Option<&str>| {
+            let mut spec = serde_json::json!({
+                "name": "crons.js:sync",
+                "args": [{}],
+                "schedule": {"type": "interval", "hours": 1},
+            });
+            if let Some(overlap_policy) = overlap_policy {
+                spec["overlapPolicy"] = overlap_policy.into();
+            }
+            spec
+        };
+        let policy =
+            |json: serde_json::Value| CronSpec::try_from(json).map(|spec| spec.overlap_policy);
+        assert_eq!(policy(spec_json(None))?, CronOverlapPolicy::Skip);
+        assert_eq!(policy(spec_json(Some("skip")))?, CronOverlapPolicy::Skip);
+        assert_eq!(
+            policy(spec_json(Some("queueOne")))?,
+            CronOverlapPolicy::QueueOne
+        );
+        assert_eq!(
+            policy(spec_json(Some("allowConcurrent")))?,
+            CronOverlapPolicy::AllowConcurrent
+        );
+        assert!(policy(spec_json(Some("sometimes"))).is_err());
+        Ok(())
+    }
 }
@@ -1,11 +1,16 @@
 use std::{
-    collections::BTreeMap,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
     fmt,
     fmt::Display,
+    str::FromStr,
 };
 
 use anyhow::Context;
 use common::{
+    components::ComponentPath,
     obj,
     types::{
         ObjectKey,
@@ -27,12 +32,18 @@ const EXPORT_RETENTION: u64 = 14 * 24 * 60 * 60 * 1000000000; // 14 days
 pub enum Export {
     Requested {
         format: ExportFormat,
+        requestor: ExportRequestor,
+        filter: ExportFilter,
+        encryption: Option<ExportEncryption>,
     },
     InProgress {
         /// Timestamp when the first attempt
         /// at the Export started.
         start_ts: Timestamp,
         format: ExportFormat,
+        requestor: ExportRequestor,
+        filter: ExportFilter,
+        encryption: Option<ExportEncryption>,
     },
     Completed {
         /// Timestamp for the successful (final) attempt at Export.
@@ -45,6 +56,11 @@ pub enum Export {
         object_keys: ExportObjectKeys,
         /// Format of the export
         format: ExportFormat,
+        requestor: ExportRequestor,
+        filter: ExportFilter,
+        /// Whether the zip was encrypted. The key isn't kept once the export
+        /// completes.
+        encrypted: bool,
     },
     Failed {
         /// Timestamp for the failed (final) attempt at Export.
@@ -52,18 +68,267 @@ pub enum Export {
         /// Timestamp when the Export failed
         failed_ts: Timestamp,
         format: ExportFormat,
+        requestor: ExportRequestor,
+        filter: ExportFilter,
     },
 }
 
 impl Export {
     pub fn format(&self) -> ExportFormat {
         match self {
-            Export::Requested { format }
+            Export::Requested { format, .. }
             | Export::InProgress { format, .. }
             | Export::Completed { format, .. }
             | Export::Failed { format, .. } => *format,
         }
     }
+
+    pub fn requestor(&self) -> ExportRequestor {
+        match self {
+            Export::Requested { requestor, .. }
+            | Export::InProgress { requestor, .. }
+            | Export::Completed { requestor, .. }
+            | Export::Failed { requestor, .. } => *requestor,
+        }
+    }
+
+    pub fn filter(&self) -> &ExportFilter {
+        match self {
+            Export::Requested { filter, .. }
+            | Export::InProgress { filter, .. }
+            | Export::Completed { filter, .. }
+            | Export::Failed { filter, .. } => filter,
+        }
+    }
+
+    /// How to encrypt the export's zip, until the export finishes.
+    pub fn encryption(&self) -> Option<&ExportEncryption> {
+        match self {
+            Export::Requested { encryption, .. } | Export::InProgress { encryption, .. } => {
+                encryption.as_ref()
+            },
+            Export::Completed { .. } | Export::Failed { .. } => None,
+        }
+    }
+}
+
+/// How an export's zip is encrypted. The key the zip is encrypted with is
+/// never stored in the clear: for a public key, the export worker generates
+/// it and seals it to the public key, and for a passphrase, the key derived
+/// from it is stored encrypted with the instance secret.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum ExportEncryption {
+    /// A Curve25519 public key, as used by libsodium's `crypto_box`.
+    PublicKey(Vec<u8>),
+    Passphrase {
+        /// The header written before the encrypted zip, with the salt and
+        /// limits to derive the key from the passphrase.
+        key_header: Vec<u8>,
+        /// The derived key, encrypted with the instance secret.
+        encrypted_key: String,
+    },
+}
+
+impl fmt::Debug for ExportEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PublicKey(public_key) => f.debug_tuple("PublicKey").field(public_key).finish(),
+            Self::Passphrase { key_header, .. } => f
+                .debug_struct("Passphrase")
+                .field("key_header", key_header)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl TryFrom<ExportEncryption> for ConvexValue {
+    type Error = anyhow::Error;
+
+    fn try_from(encryption: ExportEncryption) -> anyhow::Result<Self> {
+        let value = match encryption {
+            ExportEncryption::PublicKey(public_key) => val!({
+                "type" => "public_key",
+                "public_key" => public_key,
+            }),
+            ExportEncryption::Passphrase {
+                key_header,
+                encrypted_key,
+            } => val!({
+                "type" => "passphrase",
+                "key_header" => key_header,
+                "encrypted_key" => encrypted_key,
+            }),
+        };
+        Ok(value)
+    }
+}
+
+impl TryFrom<Option<&ConvexValue>> for ExportEncryption {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Option<&ConvexValue>) -> anyhow::Result<Self> {
+        let Some(ConvexValue::Object(o)) = value else {
+            anyhow::bail!("invalid export encryption {value:?}");
+        };
+        let encryption = match o.get("type") {
+            Some(ConvexValue::String(s)) if &s[..] == "public_key" => match o.get("public_key") {
+                Some(ConvexValue::Bytes(public_key)) => Self::PublicKey(public_key.clone().into()),
+                _ => anyhow::bail!("invalid export encryption {o:?}"),
+            },
+            Some(ConvexValue::String(s)) if &s[..] == "passphrase" => {
+                match (o.get("key_header"), o.get("encrypted_key")) {
+                    (
+                        Some(ConvexValue::Bytes(key_header)),
+                        Some(ConvexValue::String(encrypted_key)),
+                    ) => Self::Passphrase {
+                        key_header: key_header.clone().into(),
+                        encrypted_key: encrypted_key.clone().into(),
+                    },
+                    _ => anyhow::bail!("invalid export encryption {o:?}"),
+                }
+            },
+            _ => anyhow::bail!("invalid export encryption {o:?}"),
+        };
+        Ok(encryption)
+    }
+}
+
+/// What asked for an export. Scheduled backups are deleted by the backup
+/// scheduler according to its retention policy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum ExportRequestor {
+    SnapshotExport,
+    ScheduledBackup,
+}
+
+impl ExportRequestor {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::SnapshotExport => "snapshot_export",
+            Self::ScheduledBackup => "scheduled_backup",
+        }
+    }
+}
+
+impl TryFrom<Option<&ConvexValue>> for ExportRequestor {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Option<&ConvexValue>) -> anyhow::Result<Self> {
+        let requestor = match value {
+            // Exports from before backups were scheduled were all requested
+            // from the snapshot export page.
+            None => Self::SnapshotExport,
+            Some(ConvexValue::String(s)) => match &s[..] {
+                "snapshot_export" => Self::SnapshotExport,
+                "scheduled_backup" => Self::ScheduledBackup,
+                _ => anyhow::bail!("invalid requestor {s}"),
+            },
+            Some(v) => anyhow::bail!("invalid requestor {v:?}"),
+        };
+        Ok(requestor)
+    }
+}
+
+/// Which user tables an export includes. The default filter includes every
+/// table of every component.
+///
+/// A component path also matches the components nested inside it, and table
+/// names match tables with that name in any included component.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ExportFilter {
+    /// If set, only tables in these components are exported.
+    pub include_components: Option<BTreeSet<ComponentPath>>,
+    pub exclude_components: BTreeSet<ComponentPath>,
+    /// If set, only tables with these names are exported.
+    pub include_tables: Option<BTreeSet<TableName>>,
+    pub exclude_tables: BTreeSet<TableName>,
+}
+
+impl ExportFilter {
+    pub fn includes_component(&self, component_path: &ComponentPath) -> bool {
+        let matches = |paths: &BTreeSet<ComponentPath>| {
+            paths.iter().any(|path| component_path.starts_with(path))
+        };
+        self.include_components.as_ref().map_or(true, matches) && !matches(&self.exclude_components)
+    }
+
+    pub fn includes_table(&self, component_path: &ComponentPath, table_name: &TableName) -> bool {
+        self.includes_component(component_path)
+            && self
+                .include_tables
+                .as_ref()
+                .map_or(true, |tables| tables.contains(table_name))
+            && !self.exclude_tables.contains(table_name)
+    }
+}
+
+fn names_to_value(names: impl IntoIterator<Item = String>) -> anyhow::Result<ConvexValue> {
+    let names: Vec<_> = names.into_iter().map(ConvexValue::try_from).try_collect()?;
+    Ok(ConvexValue::Array(names.try_into()?))
+}
+
+fn names_from_value<T: FromStr<Err = anyhow::Error> + Ord>(
+    value: Option<&ConvexValue>,
+) -> anyhow::Result<Option<BTreeSet<T>>> {
+    match value {
+        None | Some(ConvexValue::Null) => Ok(None),
+        Some(ConvexValue::Array(names)) => names
+            .iter()
+            .map(|name| match name {
+                ConvexValue::String(name) => name.parse(),
+                _ => anyhow::bail!("invalid export filter name {name:?}"),
+            })
+            .try_collect::<BTreeSet<_>>()
+            .map(Some),
+        Some(v) => anyhow::bail!("invalid export filter names {v:?}"),
+    }
+}
+
+impl TryFrom<ExportFilter> for ConvexValue {
+    type Error = anyhow::Error;
+
+    fn try_from(filter: ExportFilter) -> anyhow::Result<Self> {
+        let component_names =
+            |paths: BTreeSet<ComponentPath>| names_to_value(paths.into_iter().map(String::from));
+        let table_names =
+            |tables: BTreeSet<TableName>| names_to_value(tables.iter().map(|t| t.to_string()));
+        let include_components = match filter.include_components {
+            Some(paths) => component_names(paths)?,
+            None => ConvexValue::Null,
+        };
+        let include_tables = match filter.include_tables {
+            Some(tables) => table_names(tables)?,
+            None => ConvexValue::Null,
+        };
+        Ok(val!({
+            "include_components" => include_components,
+            "exclude_components" => component_names(filter.exclude_components)?,
+            "include_tables" => include_tables,
+            "exclude_tables" => table_names(filter.exclude_tables)?,
+        }))
+    }
+}
+
+impl TryFrom<Option<&ConvexValue>> for ExportFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Option<&ConvexValue>) -> anyhow::Result<Self> {
+        match value {
+            // Exports from before filters existed include every table.
+            None => Ok(Self::default()),
+            Some(ConvexValue::Object(o)) => Ok(Self {
+                include_components: names_from_value(o.get("include_components"))?,
+                exclude_components: names_from_value(o.get("exclude_components"))?
+                    .unwrap_or_default(),
+                include_tables: names_from_value(o.get("include_tables"))?,
+                exclude_tables: names_from_value(o.get("exclude_tables"))?.unwrap_or_default(),
+            }),
+            Some(v) => anyhow::bail!("invalid export filter {v:?}"),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -75,18 +340,42 @@ pub enum ExportFormat {
     CleanJsonl,
     /// zip file containing a CleanJsonl for each table, and sidecar type info.
     Zip { include_storage: bool },
+    /// zip file containing a Parquet file for each table, and sidecar type
+    /// info.
+    Parquet { include_storage: bool },
+    /// zip file containing the documents changed and deleted in each table
+    /// since the completed export whose snapshot was at `base_ts`.
+    Incremental { base_ts: Timestamp },
 }
 
 impl Export {
-    pub fn requested(format: ExportFormat) -> Self {
-        Self::Requested { format }
+    pub fn requested(
+        format: ExportFormat,
+        requestor: ExportRequestor,
+        filter: ExportFilter,
+        encryption: Option<ExportEncryption>,
+    ) -> Self {
+        Self::Requested {
+            format,
+            requestor,
+            filter,
+            encryption,
+        }
     }
 
     pub fn in_progress(self, ts: Timestamp) -> anyhow::Result<Export> {
         match self {
-            Self::Requested { format } => Ok(Self::InProgress {
+            Self::Requested {
+                format,
+                requestor,
+                filter,
+                encryption,
+            } => Ok(Self::InProgress {
                 start_ts: ts,
                 format,
+                requestor,
+                filter,
+                encryption,
             }),
             Self::Completed { .. } | Self::InProgress { .. } | Self::Failed { .. } => Err(
                 anyhow::anyhow!("Can only begin an export that is requested"),
@@ -102,7 +391,13 @@ impl Export {
     ) -> anyhow::Result<Export> {
         let expiration_ts = Into::<u64>::into(complete_ts) + EXPORT_RETENTION;
         match self {
-            Self::InProgress { format, .. } => {
+            Self::InProgress {
+                format,
+                requestor,
+                filter,
+                encryption,
+                ..
+            } => {
                 anyhow::ensure!(snapshot_ts <= complete_ts);
                 Ok(Self::Completed {
                     start_ts: snapshot_ts,
@@ -110,20 +405,33 @@ impl Export {
                     expiration_ts,
                     object_keys,
                     format,
+                    requestor,
+                    filter,
+                    encrypted: encryption.is_some(),
                 })
             },
-            Self::Requested { format: _ }
+            Self::Requested {
+                format: _,
+                requestor: _,
+                filter: _,
+                encryption: _,
+            }
             | Self::Completed {
                 start_ts: _,
                 complete_ts: _,
                 expiration_ts: _,
                 object_keys: _,
                 format: _,
+                requestor: _,
+                filter: _,
+                encrypted: _,
             }
             | Self::Failed {
                 start_ts: _,
                 failed_ts: _,
                 format: _,
+                requestor: _,
+                filter: _,
             } => Err(anyhow::anyhow!(
                 "Can only complete an export that is in_progress"
             )),
@@ -132,26 +440,43 @@ impl Export {
 
     pub fn failed(self, snapshot_ts: Timestamp, failed_ts: Timestamp) -> anyhow::Result<Export> {
         match self {
-            Self::InProgress { format, .. } => {
+            Self::InProgress {
+                format,
+                requestor,
+                filter,
+                ..
+            } => {
                 anyhow::ensure!(snapshot_ts <= failed_ts);
                 Ok(Self::Failed {
                     start_ts: snapshot_ts,
                     failed_ts,
                     format,
+                    requestor,
+                    filter,
                 })
             },
-            Self::Requested { format: _ }
+            Self::Requested {
+                format: _,
+                requestor: _,
+                filter: _,
+                encryption: _,
+            }
             | Self::Completed {
                 start_ts: _,
                 complete_ts: _,
                 expiration_ts: _,
                 object_keys: _,
                 format: _,
+                requestor: _,
+                filter: _,
+                encrypted: _,
             }
             | Self::Failed {
                 start_ts: _,
                 failed_ts: _,
                 format: _,
+                requestor: _,
+                filter: _,
             } => Err(anyhow::anyhow!(
                 "Can only fail an export that is in_progress"
             )),
@@ -162,10 +487,18 @@ impl Export {
 impl Display for Export {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Requested { format: _ } => write!(f, "requested"),
+            Self::Requested {
+                format: _,
+                requestor: _,
+                filter: _,
+                encryption: _,
+            } => write!(f, "requested"),
             Self::InProgress {
                 start_ts: _,
                 format: _,
+                requestor: _,
+                filter: _,
+                encryption: _,
             } => write!(f, "in_progress"),
             Self::Completed {
                 start_ts: _,
@@ -173,16 +506,28 @@ impl Display for Export {
                 expiration_ts: _,
                 object_keys: _,
                 format: _,
+                requestor: _,
+                filter: _,
+                encrypted: _,
             } => write!(f, "completed"),
             Self::Failed {
                 start_ts: _,
                 failed_ts: _,
                 format: _,
+                requestor: _,
+                filter: _,
             } => write!(f, "failed"),
         }
     }
 }
 
+fn encryption_to_value(encryption: Option<ExportEncryption>) -> anyhow::Result<ConvexValue> {
+    match encryption {
+        Some(encryption) => encryption.try_into(),
+        None => Ok(ConvexValue::Null),
+    }
+}
+
 impl TryFrom<Export> for ConvexObject {
     type Error = anyhow::Error;
 
@@ -194,6 +539,9 @@ impl TryFrom<Export> for ConvexObject {
                 expiration_ts,
                 object_keys,
                 format,
+                requestor,
+                filter,
+                encrypted,
             } => {
                 let mut o = btreemap! {
                     "start_ts".parse()? => val!(i64::from(start_ts)),
@@ -201,6 +549,9 @@ impl TryFrom<Export> for ConvexObject {
                     "expiration_ts".parse()? => val!(expiration_ts as i64),
                     "state".parse()? => val!("completed"),
                     "format".parse()? => val!(format),
+                    "requestor".parse()? => val!(requestor.as_str()),
+                    "filter".parse()? => val!(filter),
+                    "encrypted".parse()? => val!(encrypted),
                 };
                 match object_keys {
                     ExportObjectKeys::ByTable(tables) => o.insert("tables".parse()?, {
@@ -217,27 +568,48 @@ impl TryFrom<Export> for ConvexObject {
                 };
                 ConvexObject::try_from(o)
             },
-            Export::Requested { format } => obj!(
+            Export::Requested {
+                format,
+                requestor,
+                filter,
+                encryption,
+            } => obj!(
                 "state" => "requested",
                 "format" => format,
+                "requestor" => requestor.as_str(),
+                "filter" => filter,
+                "encryption" => encryption_to_value(encryption)?,
             ),
-            Export::InProgress { start_ts, format } => {
+            Export::InProgress {
+                start_ts,
+                format,
+                requestor,
+                filter,
+                encryption,
+            } => {
                 obj!(
                     "state" => "in_progress",
                     "start_ts" => i64::from(start_ts),
                     "format" => format,
+                    "requestor" => requestor.as_str(),
+                    "filter" => filter,
+                    "encryption" => encryption_to_value(encryption)?,
                 )
             },
             Export::Failed {
                 start_ts,
                 failed_ts,
                 format,
+                requestor,
+                filter,
             } => {
                 obj!(
                     "state" => "failed",
                     "start_ts" => i64::from(start_ts),
                     "failed_ts" => i64::from(failed_ts),
                     "format" => format,
+                    "requestor" => requestor.as_str(),
+                    "filter" => filter,
                 )
             },
         }
@@ -252,9 +624,20 @@ impl TryFrom<ConvexObject> for Export {
             Some(format) => ExportFormat::try_from(format.clone())?,
             _ => anyhow::bail!("invalid format: {:?}", o),
         };
+        let requestor = ExportRequestor::try_from(o.get("requestor"))?;
+        let filter = ExportFilter::try_from(o.get("filter"))?;
+        let encryption = match o.get("encryption") {
+            None | Some(ConvexValue::Null) => None,
+            value => Some(ExportEncryption::try_from(value)?),
+        };
         match o.get("state") {
             Some(ConvexValue::String(s)) => match &s[..] {
-                "requested" => Ok(Export::Requested { format }),
+                "requested" => Ok(Export::Requested {
+                    format,
+                    requestor,
+                    filter,
+                    encryption,
+                }),
                 "in_progress" => {
                     if let Some(start_ts_value) = o.get("start_ts")
                         && let ConvexValue::Int64(start_ts) = start_ts_value
@@ -262,6 +645,9 @@ impl TryFrom<ConvexObject> for Export {
                         Ok(Export::InProgress {
                             start_ts: (*start_ts).try_into()?,
                             format,
+                            requestor,
+                            filter,
+                            encryption,
                         })
                     } else {
                         Err(anyhow::anyhow!("No start_ts found for in_progress export."))
@@ -313,12 +699,21 @@ impl TryFrom<ConvexObject> for Export {
                         },
                         _ => anyhow::bail!("invalid object keys: {:?}", o),
                     };
+                    // Exports from before encryption existed weren't encrypted.
+                    let encrypted = match o.get("encrypted") {
+                        None => false,
+                        Some(ConvexValue::Boolean(encrypted)) => *encrypted,
+                        _ => anyhow::bail!("invalid encrypted: {:?}", o),
+                    };
                     Ok(Export::Completed {
                         expiration_ts,
                         start_ts,
                         complete_ts,
                         object_keys,
                         format,
+                        requestor,
+                        filter,
+                        encrypted,
                     })
                 },
                 "failed" => {
@@ -334,6 +729,8 @@ impl TryFrom<ConvexObject> for Export {
                         start_ts,
                         failed_ts,
                         format,
+                        requestor,
+                        filter,
                     })
                 },
                 _ => Err(anyhow::anyhow!("Invalid export state {s}")),
@@ -353,6 +750,12 @@ impl TryFrom<ExportFormat> for ConvexValue {
             ExportFormat::Zip { include_storage } => {
                 val!({"format" => "zip", "include_storage" => include_storage})
             },
+            ExportFormat::Parquet { include_storage } => {
+                val!({"format" => "parquet", "include_storage" => include_storage})
+            },
+            ExportFormat::Incremental { base_ts } => {
+                val!({"format" => "incremental", "base_ts" => i64::from(base_ts)})
+            },
         };
         Ok(v)
     }
@@ -379,6 +782,18 @@ impl TryFrom<ConvexValue> for ExportFormat {
                         },
                         _ => anyhow::bail!("invalid format {value:?}"),
                     },
+                    "parquet" => match o.get("include_storage") {
+                        Some(ConvexValue::Boolean(include_storage)) => Self::Parquet {
+                            include_storage: *include_storage,
+                        },
+                        _ => anyhow::bail!("invalid format {value:?}"),
+                    },
+                    "incremental" => match o.get("base_ts") {
+                        Some(ConvexValue::Int64(base_ts)) => Self::Incremental {
+                            base_ts: (*base_ts).try_into()?,
+                        },
+                        _ => anyhow::bail!("invalid format {value:?}"),
+                    },
                     _ => anyhow::bail!("invalid format {value:?}"),
                 },
                 _ => anyhow::bail!("invalid format {value:?}"),
@@ -0,0 +1,241 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::{
+        IndexName,
+        WriteTimestamp,
+    },
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use keybroker::Identity;
+use sync_types::Timestamp;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+pub mod types;
+
+use types::{
+    IdempotencyKeyScope,
+    IdempotentRequestRecord,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+/// Table name for mutations run through the HTTP API with an
+/// `Idempotency-Key` header. Unlike `_session_requests`, records expire and are
+/// garbage collected.
+pub static IDEMPOTENT_REQUESTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_idempotent_requests"
+        .parse()
+        .expect("Invalid built-in idempotent requests table")
+});
+
+static KEY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "key".parse().expect("Invalid built-in field"));
+static IDENTITY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "identity".parse().expect("Invalid built-in field"));
+static COMPONENT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "component".parse().expect("Invalid built-in field"));
+static UDF_PATH_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "udfPath".parse().expect("Invalid built-in field"));
+
+pub static EXPIRES_TS_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "expiresTs".parse().expect("Invalid built-in field"));
+
+pub static IDEMPOTENT_REQUESTS_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&IDEMPOTENT_REQUESTS_TABLE, "by_key"));
+
+pub static IDEMPOTENT_REQUESTS_INDEX_BY_EXPIRES_TS: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&IDEMPOTENT_REQUESTS_TABLE, "by_expires_ts"));
+
+pub struct IdempotentRequestsTable;
+impl SystemTable for IdempotentRequestsTable {
+    fn table_name(&self) -> &'static TableName {
+        &IDEMPOTENT_REQUESTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: IDEMPOTENT_REQUESTS_INDEX.clone(),
+                fields: vec![
+                    KEY_FIELD.clone(),
+                    IDENTITY_FIELD.clone(),
+                    COMPONENT_FIELD.clone(),
+                    UDF_PATH_FIELD.clone(),
+                ]
+                .try_into()
+                .unwrap(),
+            },
+            // Used to garbage collect expired records.
+            SystemIndex {
+                name: IDEMPOTENT_REQUESTS_INDEX_BY_EXPIRES_TS.clone(),
+                fields: vec![EXPIRES_TS_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<IdempotentRequestRecord>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct IdempotentRequestModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> IdempotentRequestModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Returns the record for `scope` along with the timestamp it was
+    /// committed at, if there is one. The record may have expired.
+    pub async fn get_idempotent_request(
+        &mut self,
+        scope: &IdempotencyKeyScope,
+        identity: Identity,
+    ) -> anyhow::Result<Option<(Timestamp, ParsedDocument<IdempotentRequestRecord>)>> {
+        // We only expect this function to be called by the framework as part
+        // of a mutation UDF. We require passing in a system identity to confirm
+        // that the caller isn't letting a user call this directly.
+        if !identity.is_system() {
+            anyhow::bail!(unauthorized_error("get_idempotent_request"))
+        }
+        // Scanning the index includes it in our read set, so a concurrent
+        // mutation with the same key will conflict and see this record on retry.
+        let Some((doc, ts)) = self.query_by_scope(scope).await? else {
+            return Ok(None);
+        };
+        let WriteTimestamp::Committed(ts) = ts else {
+            anyhow::bail!(
+                "Wrote an idempotent request record in the same transaction as the get? Not \
+                 supported."
+            );
+        };
+        Ok(Some((ts, doc.try_into()?)))
+    }
+
+    /// Records the outcome of a mutation, replacing any expired record with the
+    /// same scope.
+    pub async fn record_idempotent_request(
+        &mut self,
+        record: IdempotentRequestRecord,
+        identity: Identity,
+    ) -> anyhow::Result<()> {
+        if !identity.is_system() {
+            anyhow::bail!(unauthorized_error("record_idempotent_request"))
+        }
+        match self.query_by_scope(&record.scope).await? {
+            Some((existing, _)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), record.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert_metadata(&IDEMPOTENT_REQUESTS_TABLE, record.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Deletes up to `limit` records that expired before `now`. Returns the
+    /// number of records deleted.
+    pub async fn delete_expired(&mut self, now: Timestamp, limit: usize) -> anyhow::Result<usize> {
+        let query = Query::index_range(IndexRange {
+            index_name: IDEMPOTENT_REQUESTS_INDEX_BY_EXPIRES_TS.clone(),
+            range: vec![IndexRangeExpression::Lt(
+                EXPIRES_TS_FIELD.clone(),
+                ConvexValue::from(i64::from(now)).into(),
+            )],
+            order: Order::Asc,
+        })
+        .limit(limit);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut expired = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            expired.push(doc.id());
+        }
+        for id in expired.iter() {
+            SystemMetadataModel::new_global(self.tx).delete(*id).await?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Returns when the next record expires, if there are any records.
+    pub async fn next_expiration(&mut self) -> anyhow::Result<Option<Timestamp>> {
+        let query = Query::index_range(IndexRange {
+            index_name: IDEMPOTENT_REQUESTS_INDEX_BY_EXPIRES_TS.clone(),
+            range: vec![],
+            order: Order::Asc,
+        })
+        .limit(1);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let Some(doc) = query_stream.next(self.tx, None).await? else {
+            return Ok(None);
+        };
+        let record: ParsedDocument<IdempotentRequestRecord> = doc.try_into()?;
+        Ok(Some(record.expires_ts))
+    }
+
+    async fn query_by_scope(
+        &mut self,
+        scope: &IdempotencyKeyScope,
+    ) -> anyhow::Result<Option<(ResolvedDocument, WriteTimestamp)>> {
+        let query = Query::index_range(IndexRange {
+            index_name: IDEMPOTENT_REQUESTS_INDEX.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    KEY_FIELD.clone(),
+                    ConvexValue::try_from(scope.key.as_str())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    IDENTITY_FIELD.clone(),
+                    ConvexValue::try_from(scope.identity.to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    COMPONENT_FIELD.clone(),
+                    ConvexValue::try_from(scope.component.as_str())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    UDF_PATH_FIELD.clone(),
+                    ConvexValue::try_from(scope.udf_path.as_str())?.into(),
+                ),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let result = query_stream.next_with_ts(self.tx, None).await?;
+        anyhow::ensure!(
+            query_stream.next(self.tx, Some(1)).await?.is_none(),
+            "Expected at most one idempotent request record."
+        );
+        Ok(result)
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use common::{
+    components::{
+        ComponentPath,
+        PublicFunctionPath,
+    },
+    identity::InertIdentity,
+    obj,
+    sha256::Sha256,
+    value::ConvexValue,
+};
+use sync_types::Timestamp;
+use value::{
+    json_serialize,
+    ConvexArray,
+    ConvexObject,
+};
+
+use crate::session_requests::types::SessionRequestOutcome;
+
+/// What an `Idempotency-Key` refers to. The same key sent by a different
+/// identity or for a different function is a different request.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct IdempotencyKeyScope {
+    pub key: String,
+    /// Non-permission-granting representation of the identity input to the
+    /// mutation.
+    pub identity: InertIdentity,
+    /// The component path, or component id for functions called by id.
+    pub component: String,
+    pub udf_path: String,
+}
+
+impl IdempotencyKeyScope {
+    pub fn new(key: String, identity: InertIdentity, path: &PublicFunctionPath) -> Self {
+        let component = match path {
+            PublicFunctionPath::RootExport(_) => String::from(ComponentPath::root()),
+            PublicFunctionPath::Component(path) => String::from(path.component.clone()),
+            PublicFunctionPath::ResolvedComponent(path) => {
+                path.component.serialize_to_string().unwrap_or_default()
+            },
+        };
+        Self {
+            key,
+            identity,
+            component,
+            udf_path: path.udf_path().to_string(),
+        }
+    }
+}
+
+/// The committed result of a mutation that was run with an
+/// `Idempotency-Key`.
+///
+/// While the record hasn't expired, retries with the same key return the
+/// stored outcome instead of executing the mutation again.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct IdempotentRequestRecord {
+    pub scope: IdempotencyKeyScope,
+    /// Hex SHA-256 of the mutation's arguments. Retrying with the same key but
+    /// different arguments is an error.
+    pub args_hash: String,
+
+    pub outcome: SessionRequestOutcome,
+
+    /// After this timestamp the key may be reused, and the record is garbage
+    /// collected.
+    pub expires_ts: Timestamp,
+}
+
+impl IdempotentRequestRecord {
+    pub fn hash_args(args: &ConvexArray) -> anyhow::Result<String> {
+        Ok(Sha256::hash(json_serialize(args.clone())?.as_bytes()).as_hex())
+    }
+}
+
+impl TryFrom<IdempotentRequestRecord> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(record: IdempotentRequestRecord) -> anyhow::Result<Self> {
+        obj!(
+            "key" => record.scope.key,
+            "identity" => record.scope.identity.to_string(),
+            "component" => record.scope.component,
+            "udfPath" => record.scope.udf_path,
+            "argsHash" => record.args_hash,
+            "outcome" => ConvexValue::Object(record.outcome.try_into()?),
+            "expiresTs" => i64::from(record.expires_ts),
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for IdempotentRequestRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(object: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields: BTreeMap<_, _> = object.into();
+
+        let key = match fields.remove("key") {
+            Some(ConvexValue::String(s)) => s.to_string(),
+            v => anyhow::bail!("Invalid key field for IdempotentRequest: {:?}", v),
+        };
+        let identity: InertIdentity = match fields.remove("identity") {
+            Some(ConvexValue::String(s)) => s.to_string().parse()?,
+            v => anyhow::bail!("Invalid identity field for IdempotentRequest: {:?}", v),
+        };
+        let component = match fields.remove("component") {
+            Some(ConvexValue::String(s)) => s.to_string(),
+            v => anyhow::bail!("Invalid component field for IdempotentRequest: {:?}", v),
+        };
+        let udf_path = match fields.remove("udfPath") {
+            Some(ConvexValue::String(s)) => s.to_string(),
+            v => anyhow::bail!("Invalid udfPath field for IdempotentRequest: {:?}", v),
+        };
+        let args_hash = match fields.remove("argsHash") {
+            Some(ConvexValue::String(s)) => s.to_string(),
+            v => anyhow::bail!("Invalid argsHash field for IdempotentRequest: {:?}", v),
+        };
+        let outcome: SessionRequestOutcome = match fields.remove("outcome") {
+            Some(ConvexValue::Object(o)) => o.try_into()?,
+            v => anyhow::bail!("Invalid outcome field for IdempotentRequest: {:?}", v),
+        };
+        let expires_ts: Timestamp = match fields.remove("expiresTs") {
+            Some(ConvexValue::Int64(ts)) => ts.try_into()?,
+            v => anyhow::bail!("Invalid expiresTs field for IdempotentRequest: {:?}", v),
+        };
+
+        Ok(IdempotentRequestRecord {
+            scope: IdempotencyKeyScope {
+                key,
+                identity,
+                component,
+                udf_path,
+            },
+            args_hash,
+            outcome,
+            expires_ts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::testing::assert_roundtrips;
+    use proptest::prelude::*;
+    use value::ConvexObject;
+
+    use super::IdempotentRequestRecord;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+        #[test]
+        fn test_idempotent_request_roundtrips(v in any::<IdempotentRequestRecord>()) {
+            assert_roundtrips::<IdempotentRequestRecord, ConvexObject>(v);
+        }
+    }
+}
@@ -88,11 +88,16 @@ use crate::{
     exports::ExportsTable,
     external_packages::ExternalPackagesTable,
     file_storage::FileStorageTable,
+    idempotent_requests::IdempotentRequestsTable,
     modules::ModulesTable,
-    scheduled_jobs::ScheduledJobsTable,
+    scheduled_jobs::{
+        ScheduledJobDeadLettersTable,
+        ScheduledJobsTable,
+    },
     session_requests::SessionRequestsTable,
     snapshot_imports::SnapshotImportsTable,
     source_packages::SourcePackagesTable,
+    table_restores::TableRestoreDocumentsTable,
     udf_config::UdfConfigTable,
 };
 
@@ -106,11 +111,13 @@ pub mod environment_variables;
 pub mod exports;
 pub mod external_packages;
 pub mod file_storage;
+pub mod idempotent_requests;
 pub mod modules;
 pub mod scheduled_jobs;
 pub mod session_requests;
 pub mod snapshot_imports;
 pub mod source_packages;
+pub mod table_restores;
 pub mod udf_config;
 
 #[cfg(any(test, feature = "testing"))]
@@ -145,9 +152,12 @@ enum DefaultTableNumber {
     ComponentDefinitionsTable = 31,
     ComponentsTable = 32,
     FunctionHandlesTable = 33,
+    ScheduledJobDeadLetters = 34,
+    IdempotentRequests = 35,
+    TableRestoreDocuments = 36,
     // Keep this number and your user name up to date. The number makes it easy to know
     // what to use next. The username on the same line detects merge conflicts
-    // Next Number - 34 - sujayakar
+    // Next Number - 37 - sujayakar
 }
 
 impl From<DefaultTableNumber> for TableNumber {
@@ -184,6 +194,9 @@ impl From<DefaultTableNumber> for &'static dyn SystemTable {
             DefaultTableNumber::ComponentDefinitionsTable => &ComponentDefinitionsTable,
             DefaultTableNumber::ComponentsTable => &ComponentsTable,
             DefaultTableNumber::FunctionHandlesTable => &FunctionHandlesTable,
+            DefaultTableNumber::ScheduledJobDeadLetters => &ScheduledJobDeadLettersTable,
+            DefaultTableNumber::IdempotentRequests => &IdempotentRequestsTable,
+            DefaultTableNumber::TableRestoreDocuments => &TableRestoreDocumentsTable,
         }
     }
 }
@@ -227,6 +240,21 @@ pub async fn initialize_application_system_tables<RT: Runtime>(
             }
         }
     }
+    // Components created before a component system table was added don't have
+    // it yet. Every component namespace has `_scheduled_jobs`, so use it to
+    // find them and create whatever tables or indexes are missing.
+    let component_namespaces = tx
+        .table_mapping()
+        .namespaces_for_name(ScheduledJobsTable.table_name());
+    for namespace in component_namespaces {
+        if namespace == TableNamespace::Global {
+            continue;
+        }
+        for table in component_system_tables() {
+            initialize_application_system_table(&mut tx, table, namespace, &DEFAULT_TABLE_NUMBERS)
+                .await?;
+        }
+    }
     database
         .commit_with_write_source(tx, "init_app_system_tables")
         .await?;
@@ -310,6 +338,8 @@ pub fn app_system_tables() -> Vec<&'static dyn SystemTable> {
         &AuthTable,
         &ExternalPackagesTable,
         &SessionRequestsTable,
+        &IdempotentRequestsTable,
+        &TableRestoreDocumentsTable,
         &BackendStateTable,
         &ExportsTable,
         &SnapshotImportsTable,
@@ -325,6 +355,7 @@ pub fn component_system_tables() -> Vec<&'static dyn SystemTable> {
     vec![
         &FileStorageTable,
         &ScheduledJobsTable,
+        &ScheduledJobDeadLettersTable,
         &CronJobsTable,
         &CronJobLogsTable,
         &ModulesTable,
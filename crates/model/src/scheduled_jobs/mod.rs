@@ -1,5 +1,8 @@
 use std::{
-    collections::BTreeMap,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
     sync::{
         Arc,
         LazyLock,
@@ -38,13 +41,17 @@ use common::{
 use database::{
     defaults::system_index,
     unauthorized_error,
+    IndexModel,
     ResolvedQuery,
     SystemMetadataModel,
     Transaction,
 };
 use errors::ErrorMetadata;
 use maplit::btreemap;
-use sync_types::Timestamp;
+use sync_types::{
+    CanonicalizedUdfPath,
+    Timestamp,
+};
 use value::{
     id_v6::DeveloperDocumentId,
     ConvexArray,
@@ -60,11 +67,17 @@ use self::{
     types::{
         ScheduledJob,
         ScheduledJobAttempts,
+        ScheduledJobDeadLetter,
         ScheduledJobState,
     },
     virtual_table::ScheduledJobsDocMapper,
 };
 use crate::{
+    modules::{
+        function_validators::ArgsValidator,
+        ModuleModel,
+    },
+    virtual_system_mapping,
     SystemIndex,
     SystemTable,
 };
@@ -100,6 +113,8 @@ pub static SCHEDULED_JOBS_INDEX_BY_UDF_PATH: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_udf_path_and_next_event_ts"));
 pub static SCHEDULED_JOBS_INDEX_BY_COMPLETED_TS: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_completed_ts"));
+pub static SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_dedup_key"));
 pub static NEXT_TS_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "nextTs".parse().expect("invalid nextTs field"));
 pub static COMPLETED_TS_FIELD: LazyLock<FieldPath> =
@@ -108,6 +123,28 @@ static UDF_PATH_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "udfPath".parse().expect("invalid udfPath field"));
 static COMPONENT_PATH_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "component".parse().expect("invalid component field"));
+static DEDUP_KEY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "dedupKey".parse().expect("invalid dedupKey field"));
+
+/// Maximum length of a client-supplied dedup key for a scheduled job.
+const MAX_DEDUP_KEY_LENGTH: usize = 255;
+
+pub static SCHEDULED_JOB_DEAD_LETTERS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_scheduled_job_dead_letters"
+        .parse()
+        .expect("_scheduled_job_dead_letters is not a valid system table name")
+});
+pub static SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_FAILED_TS: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&SCHEDULED_JOB_DEAD_LETTERS_TABLE, "by_failed_ts"));
+pub static SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_UDF_PATH: LazyLock<IndexName> =
+    LazyLock::new(|| {
+        system_index(
+            &SCHEDULED_JOB_DEAD_LETTERS_TABLE,
+            "by_udf_path_and_failed_ts",
+        )
+    });
+pub static FAILED_TS_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "failedTs".parse().expect("invalid failedTs field"));
 
 pub struct ScheduledJobsTable;
 impl SystemTable for ScheduledJobsTable {
@@ -135,6 +172,11 @@ impl SystemTable for ScheduledJobsTable {
                     .try_into()
                     .unwrap(),
             },
+            // By dedup key. Used to enqueue a job at most once per key.
+            SystemIndex {
+                name: SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY.clone(),
+                fields: vec![DEDUP_KEY_FIELD.clone()].try_into().unwrap(),
+            },
         ]
     }
 
@@ -162,6 +204,35 @@ impl SystemTable for ScheduledJobsTable {
     }
 }
 
+pub struct ScheduledJobDeadLettersTable;
+impl SystemTable for ScheduledJobDeadLettersTable {
+    fn table_name(&self) -> &'static TableName {
+        &SCHEDULED_JOB_DEAD_LETTERS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            // By failed ts. Used to garbage collect dead letters.
+            SystemIndex {
+                name: SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_FAILED_TS.clone(),
+                fields: vec![FAILED_TS_FIELD.clone()].try_into().unwrap(),
+            },
+            // By udf path and failed ts. Used to list dead letters for a
+            // function.
+            SystemIndex {
+                name: SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_UDF_PATH.clone(),
+                fields: vec![UDF_PATH_FIELD.clone(), FAILED_TS_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ScheduledJobDeadLetter>::try_from(document).map(|_| ())
+    }
+}
+
 // Maintains state for scheduling asynchronous functions (scheduled jobs).
 pub struct SchedulerModel<'a, RT: Runtime> {
     tx: &'a mut Transaction<RT>,
@@ -208,6 +279,91 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         args: ConvexArray,
         ts: UnixTimestamp,
         context: ExecutionContext,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        self.insert_job(path, args, ts, context, None).await
+    }
+
+    /// Schedules a job unless one with the same `dedup_key` already exists in
+    /// this component, in which case the existing job's id is returned.
+    ///
+    /// The lookup reads the dedup key index, so concurrent transactions using
+    /// the same key conflict and at most one of them inserts a job. A key can
+    /// be reused once its job has been garbage collected.
+    pub async fn schedule_with_dedup_key(
+        &mut self,
+        path: CanonicalizedComponentFunctionPath,
+        args: ConvexArray,
+        ts: UnixTimestamp,
+        context: ExecutionContext,
+        dedup_key: String,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        anyhow::ensure!(
+            !dedup_key.is_empty() && dedup_key.len() <= MAX_DEDUP_KEY_LENGTH,
+            ErrorMetadata::bad_request(
+                "InvalidDedupKey",
+                format!(
+                    "Scheduled function dedup keys must be between 1 and {MAX_DEDUP_KEY_LENGTH} \
+                     characters"
+                ),
+            )
+        );
+        if let Some(existing) = self.get_by_dedup_key(&dedup_key).await? {
+            anyhow::ensure!(
+                existing.path == path,
+                ErrorMetadata::bad_request(
+                    "DedupKeyReused",
+                    format!(
+                        "Dedup key {dedup_key:?} was already used to schedule {:?}",
+                        String::from(existing.path.udf_path.clone()),
+                    ),
+                )
+            );
+            return Ok(existing.id());
+        }
+        self.insert_job(path, args, ts, context, Some(dedup_key))
+            .await
+    }
+
+    pub async fn get_by_dedup_key(
+        &mut self,
+        dedup_key: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<ScheduledJob>>> {
+        // Deployments and components created before dedup keys existed get this
+        // index as backfilling at startup, and the index worker enables it once
+        // it's built.
+        anyhow::ensure!(
+            IndexModel::new(self.tx)
+                .enabled_index_metadata(self.namespace, &SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY)?
+                .is_some(),
+            ErrorMetadata::overloaded(
+                "DedupKeyIndexBackfilling",
+                "Scheduling with a dedup key is unavailable until the scheduler's dedup key index \
+                 finishes building. Try again shortly.",
+            )
+        );
+        let query = Query::index_range(IndexRange {
+            index_name: SCHEDULED_JOBS_INDEX_BY_DEDUP_KEY.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                DEDUP_KEY_FIELD.clone(),
+                ConvexValue::try_from(dedup_key)?.into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    async fn insert_job(
+        &mut self,
+        path: CanonicalizedComponentFunctionPath,
+        args: ConvexArray,
+        ts: UnixTimestamp,
+        context: ExecutionContext,
+        dedup_key: Option<String>,
     ) -> anyhow::Result<ResolvedDocumentId> {
         if path.udf_path.is_system()
             && !(self.tx.identity().is_admin() || self.tx.identity().is_system())
@@ -230,6 +386,7 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
             completed_ts: None,
             original_scheduled_ts,
             attempts: ScheduledJobAttempts::default(),
+            dedup_key: dedup_key.clone(),
         };
         let job = if let Some(parent_scheduled_job) = context.parent_scheduled_job {
             let table_mapping = self.tx.table_mapping();
@@ -253,6 +410,7 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
                             completed_ts: Some(*scheduled_ts),
                             original_scheduled_ts: *scheduled_ts,
                             attempts: ScheduledJobAttempts::default(),
+                            dedup_key,
                         }
                     },
                 }
@@ -323,6 +481,21 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         // job has already been processed
         job.next_ts = None;
         job.completed_ts = Some(*self.tx.begin_timestamp());
+        if let ScheduledJobState::Failed(ref error) = job.state {
+            let dead_letter = ScheduledJobDeadLetter {
+                job_id: id.into(),
+                path: job.path.clone(),
+                udf_args: job.udf_args.clone(),
+                error: error.clone(),
+                attempts: job.attempts.clone(),
+                original_scheduled_ts: job.original_scheduled_ts,
+                failed_ts: *self.tx.begin_timestamp(),
+                replayed_job_id: None,
+            };
+            SystemMetadataModel::new(self.tx, self.namespace)
+                .insert_metadata(&SCHEDULED_JOB_DEAD_LETTERS_TABLE, dead_letter.try_into()?)
+                .await?;
+        }
         SystemMetadataModel::new(self.tx, self.namespace)
             .replace(id, job.try_into()?)
             .await?;
@@ -365,6 +538,36 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         path: Option<CanonicalizedComponentFunctionPath>,
         limit: usize,
     ) -> anyhow::Result<usize> {
+        let (count, _) = self.cancel_all_in_range(path, None, None, limit).await?;
+        Ok(count)
+    }
+
+    // Cancel up to `limit` jobs for the UDF whose next run is in
+    // [`start_ts`, `end_ts`) and return how many were canceled, along with the
+    // next run time of the last canceled job. Jobs are canceled in order of
+    // their next run time. Missing bounds are unbounded.
+    // Note: the caller will assume all have been canceled if Result < `limit`.
+    pub async fn cancel_all_in_range(
+        &mut self,
+        path: Option<CanonicalizedComponentFunctionPath>,
+        start_ts: Option<Timestamp>,
+        end_ts: Option<Timestamp>,
+        limit: usize,
+    ) -> anyhow::Result<(usize, Option<Timestamp>)> {
+        // Completed jobs have a null next_ts, so always exclude those.
+        let mut next_ts_range = vec![match start_ts {
+            Some(start_ts) => IndexRangeExpression::Gte(
+                NEXT_TS_FIELD.clone(),
+                ConvexValue::Int64(start_ts.into()),
+            ),
+            None => IndexRangeExpression::Gt(NEXT_TS_FIELD.clone(), value::ConvexValue::Null),
+        }];
+        if let Some(end_ts) = end_ts {
+            next_ts_range.push(IndexRangeExpression::Lt(
+                NEXT_TS_FIELD.clone(),
+                ConvexValue::Int64(end_ts.into()),
+            ));
+        }
         let index_query = match path {
             Some(path) => {
                 let udf_path = path.udf_path;
@@ -382,13 +585,11 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
                         ),
                     ]);
                 }
-                let range = vec![
-                    IndexRangeExpression::Eq(
-                        UDF_PATH_FIELD.clone(),
-                        ConvexValue::try_from(udf_path.to_string())?.into(),
-                    ),
-                    IndexRangeExpression::Gt(NEXT_TS_FIELD.clone(), value::ConvexValue::Null),
-                ];
+                let mut range = vec![IndexRangeExpression::Eq(
+                    UDF_PATH_FIELD.clone(),
+                    ConvexValue::try_from(udf_path.to_string())?.into(),
+                )];
+                range.extend(next_ts_range);
                 Query::index_range(IndexRange {
                     index_name: SCHEDULED_JOBS_INDEX_BY_UDF_PATH.clone(),
                     range,
@@ -396,27 +597,136 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
                 })
                 .filter(component_path_filter)
             },
-            None => {
-                let range = vec![IndexRangeExpression::Gt(
-                    NEXT_TS_FIELD.clone(),
-                    value::ConvexValue::Null,
-                )];
-                Query::index_range(IndexRange {
-                    index_name: SCHEDULED_JOBS_INDEX.clone(),
-                    range,
-                    order: Order::Asc,
-                })
-            },
+            None => Query::index_range(IndexRange {
+                index_name: SCHEDULED_JOBS_INDEX.clone(),
+                range: next_ts_range,
+                order: Order::Asc,
+            }),
         };
         let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, index_query)?;
         let mut count = 0;
+        let mut last_next_ts = None;
         while count < limit
             && let Some(doc) = query_stream.next(self.tx, None).await?
         {
-            self.cancel(doc.id()).await?;
+            let job: ParsedDocument<ScheduledJob> = doc.try_into()?;
+            last_next_ts = job.next_ts;
+            self.cancel(job.id()).await?;
             count += 1;
         }
-        Ok(count)
+        Ok((count, last_next_ts))
+    }
+
+    pub async fn get_dead_letter(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<ScheduledJobDeadLetter>>> {
+        anyhow::ensure!(self
+            .tx
+            .table_mapping()
+            .namespace(self.namespace)
+            .tablet_matches_name(id.tablet_id, &SCHEDULED_JOB_DEAD_LETTERS_TABLE));
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Schedule a dead-lettered job to run again now, with its original
+    /// arguments or with `udf_args` if given. Returns the id of the new job.
+    pub async fn replay_dead_letter(
+        &mut self,
+        id: ResolvedDocumentId,
+        udf_args: Option<ConvexArray>,
+        context: ExecutionContext,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let Some(dead_letter) = self.get_dead_letter(id).await? else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "DeadLetterNotFound",
+                format!("Dead-lettered scheduled job {} not found", id.developer_id),
+            ));
+        };
+        let (id, mut dead_letter) = dead_letter.into_id_and_value();
+        let udf_args = match udf_args {
+            Some(udf_args) => {
+                self.check_replay_args(&dead_letter, &udf_args).await?;
+                udf_args
+            },
+            None => dead_letter.udf_args.clone(),
+        };
+        let ts = self.tx.runtime().unix_timestamp();
+        let job_id = self
+            .schedule(dead_letter.path.clone(), udf_args, ts, context)
+            .await?;
+        dead_letter.replayed_job_id = Some(job_id.into());
+        SystemMetadataModel::new(self.tx, self.namespace)
+            .replace(id, dead_letter.try_into()?)
+            .await?;
+        Ok(job_id)
+    }
+
+    /// Replacement arguments must still be a valid call of the failed function:
+    /// they're checked against its args validator, or if it has none, must
+    /// have the same fields as the arguments the job failed with.
+    async fn check_replay_args(
+        &mut self,
+        dead_letter: &ScheduledJobDeadLetter,
+        udf_args: &ConvexArray,
+    ) -> anyhow::Result<()> {
+        let args_validator = match ModuleModel::new(self.tx)
+            .get_analyzed_function(&dead_letter.path)
+            .await?
+        {
+            Ok(function) => function.args,
+            Err(_) => ArgsValidator::Unvalidated,
+        };
+        match args_validator {
+            ArgsValidator::Validated(_) => {
+                let table_mapping = self.tx.table_mapping().namespace(self.namespace);
+                if let Some(error) = args_validator.check_args(
+                    udf_args,
+                    &table_mapping,
+                    &virtual_system_mapping(),
+                )? {
+                    anyhow::bail!(ErrorMetadata::bad_request(
+                        "InvalidDeadLetterArgs",
+                        format!("ArgumentValidationError: {error}"),
+                    ));
+                }
+            },
+            ArgsValidator::Unvalidated => {
+                let field_names = |args: &ConvexArray| match &args[..] {
+                    [ConvexValue::Object(object)] => {
+                        Some(object.keys().cloned().collect::<BTreeSet<_>>())
+                    },
+                    _ => None,
+                };
+                let expected = field_names(&dead_letter.udf_args);
+                anyhow::ensure!(
+                    expected.is_some() && field_names(udf_args) == expected,
+                    ErrorMetadata::bad_request(
+                        "InvalidDeadLetterArgs",
+                        format!(
+                            "Replayed arguments for {} must have the same fields as the original \
+                             arguments: {}",
+                            dead_letter.path.udf_path, dead_letter.udf_args,
+                        ),
+                    )
+                );
+            },
+        }
+        Ok(())
+    }
+
+    pub async fn delete_dead_letter(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        anyhow::ensure!(self
+            .tx
+            .table_mapping()
+            .namespace(self.namespace)
+            .tablet_matches_name(id.tablet_id, &SCHEDULED_JOB_DEAD_LETTERS_TABLE));
+        self.tx.delete_inner(id).await?;
+        Ok(())
     }
 
     pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<ScheduledJob>>> {
@@ -430,6 +740,37 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         Ok(scheduled_jobs)
     }
 
+    /// List up to `limit` dead letters, most recently failed first, optionally
+    /// only those for `udf_path`.
+    pub async fn list_dead_letters(
+        &mut self,
+        udf_path: Option<CanonicalizedUdfPath>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<ScheduledJobDeadLetter>>> {
+        let query = match udf_path {
+            Some(udf_path) => Query::index_range(IndexRange {
+                index_name: SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_UDF_PATH.clone(),
+                range: vec![IndexRangeExpression::Eq(
+                    UDF_PATH_FIELD.clone(),
+                    ConvexValue::try_from(udf_path.to_string())?.into(),
+                )],
+                order: Order::Desc,
+            }),
+            None => Query::index_range(IndexRange {
+                index_name: SCHEDULED_JOB_DEAD_LETTERS_INDEX_BY_FAILED_TS.clone(),
+                range: vec![],
+                order: Order::Desc,
+            }),
+        }
+        .limit(limit);
+        let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, query)?;
+        let mut dead_letters = Vec::new();
+        while let Some(dead_letter) = query_stream.next(self.tx, None).await? {
+            dead_letters.push(dead_letter.try_into()?);
+        }
+        Ok(dead_letters)
+    }
+
     /// Checks the status of the scheduled job. If it has been garbage collected
     /// and the scheduled job is no longer in the table, it returns None.
     pub async fn check_status(
@@ -465,10 +806,17 @@ impl<'a, RT: Runtime> VirtualSchedulerModel<'a, RT> {
         args: ConvexArray,
         ts: UnixTimestamp,
         context: ExecutionContext,
+        dedup_key: Option<String>,
     ) -> anyhow::Result<DeveloperDocumentId> {
-        let system_id = SchedulerModel::new(self.tx, self.namespace)
-            .schedule(path, args, ts, context)
-            .await?;
+        let mut model = SchedulerModel::new(self.tx, self.namespace);
+        let system_id = match dedup_key {
+            Some(dedup_key) => {
+                model
+                    .schedule_with_dedup_key(path, args, ts, context, dedup_key)
+                    .await?
+            },
+            None => model.schedule(path, args, ts, context).await?,
+        };
         self.tx
             .virtual_system_mapping()
             .system_resolved_id_to_virtual_developer_id(system_id)
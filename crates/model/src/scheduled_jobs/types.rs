@@ -16,6 +16,7 @@ use serde_json::Value as JsonValue;
 use value::{
     codegen_convex_serialization,
     ConvexArray,
+    DeveloperDocumentId,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -45,6 +46,10 @@ pub struct ScheduledJob {
     pub original_scheduled_ts: Timestamp,
 
     pub attempts: ScheduledJobAttempts,
+
+    /// Client-supplied key that makes scheduling idempotent. At most one job
+    /// with a given key exists in a component's `_scheduled_jobs` table.
+    pub dedup_key: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,6 +63,8 @@ struct SerializedScheduledJob {
     completed_ts: Option<i64>,
     original_scheduled_ts: Option<i64>,
     attempts: Option<ScheduledJobAttempts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedup_key: Option<String>,
 }
 
 impl TryFrom<ScheduledJob> for SerializedScheduledJob {
@@ -77,6 +84,7 @@ impl TryFrom<ScheduledJob> for SerializedScheduledJob {
             completed_ts: job.completed_ts.map(|ts| ts.into()),
             original_scheduled_ts: Some(job.original_scheduled_ts.into()),
             attempts: Some(job.attempts),
+            dedup_key: job.dedup_key,
         })
     }
 }
@@ -120,6 +128,7 @@ impl TryFrom<SerializedScheduledJob> for ScheduledJob {
             completed_ts,
             original_scheduled_ts,
             attempts: value.attempts.unwrap_or_default(),
+            dedup_key: value.dedup_key,
         })
     }
 }
@@ -212,3 +221,97 @@ mod state {
 
     codegen_convex_serialization!(ScheduledJobState, SerializedScheduledJobState);
 }
+
+/// A copy of a scheduled job that failed. These are kept in
+/// `_scheduled_job_dead_letters` for longer than the failed job itself so they
+/// can be inspected and replayed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ScheduledJobDeadLetter {
+    /// The id of the failed job in `_scheduled_jobs`. The job itself may have
+    /// been garbage collected already.
+    pub job_id: DeveloperDocumentId,
+    pub path: CanonicalizedComponentFunctionPath,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::arbitrary::any_with::<ConvexArray>((0..4).into())")
+    )]
+    pub udf_args: ConvexArray,
+    pub error: String,
+    pub attempts: ScheduledJobAttempts,
+    pub original_scheduled_ts: Timestamp,
+    pub failed_ts: Timestamp,
+    /// The job scheduled by the most recent replay of this dead letter.
+    pub replayed_job_id: Option<DeveloperDocumentId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedScheduledJobDeadLetter {
+    job_id: String,
+    component: String,
+    udf_path: String,
+    udf_args: ByteBuf,
+    error: String,
+    attempts: ScheduledJobAttempts,
+    original_scheduled_ts: i64,
+    failed_ts: i64,
+    replayed_job_id: Option<String>,
+}
+
+impl TryFrom<ScheduledJobDeadLetter> for SerializedScheduledJobDeadLetter {
+    type Error = anyhow::Error;
+
+    fn try_from(dead_letter: ScheduledJobDeadLetter) -> anyhow::Result<Self> {
+        // Serialize the udf arguments as binary since we restrict what
+        // field names can be used in a `Document`'s top-level object.
+        let udf_args_json = JsonValue::from(dead_letter.udf_args);
+        let udf_args_bytes = serde_json::to_vec(&udf_args_json)?;
+        Ok(SerializedScheduledJobDeadLetter {
+            job_id: dead_letter.job_id.to_string(),
+            component: String::from(dead_letter.path.component),
+            udf_path: String::from(dead_letter.path.udf_path),
+            udf_args: ByteBuf::from(udf_args_bytes),
+            error: dead_letter.error,
+            attempts: dead_letter.attempts,
+            original_scheduled_ts: dead_letter.original_scheduled_ts.into(),
+            failed_ts: dead_letter.failed_ts.into(),
+            replayed_job_id: dead_letter.replayed_job_id.map(|id| id.to_string()),
+        })
+    }
+}
+
+impl TryFrom<SerializedScheduledJobDeadLetter> for ScheduledJobDeadLetter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SerializedScheduledJobDeadLetter) -> anyhow::Result<Self> {
+        let udf_args_json: JsonValue = serde_json::from_slice(&value.udf_args)?;
+        Ok(ScheduledJobDeadLetter {
+            job_id: DeveloperDocumentId::decode(&value.job_id)?,
+            path: CanonicalizedComponentFunctionPath {
+                component: value.component.parse()?,
+                udf_path: value.udf_path.parse()?,
+            },
+            udf_args: udf_args_json.try_into()?,
+            error: value.error,
+            attempts: value.attempts,
+            original_scheduled_ts: value.original_scheduled_ts.try_into()?,
+            failed_ts: value.failed_ts.try_into()?,
+            replayed_job_id: value
+                .replayed_job_id
+                .map(|id| DeveloperDocumentId::decode(&id))
+                .transpose()?,
+        })
+    }
+}
+
+mod dead_letter {
+    use value::codegen_convex_serialization;
+
+    use super::{
+        ScheduledJobDeadLetter,
+        SerializedScheduledJobDeadLetter,
+    };
+
+    codegen_convex_serialization!(ScheduledJobDeadLetter, SerializedScheduledJobDeadLetter);
+}
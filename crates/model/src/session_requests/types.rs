@@ -31,6 +31,16 @@ pub struct SessionRequestIdentifier {
     pub request_id: SessionRequestSeqNumber,
 }
 
+/// Identifier used to make a mutation idempotent.
+#[derive(Clone, Debug)]
+pub enum MutationIdentifier {
+    /// A request made over a sync protocol session.
+    Session(SessionRequestIdentifier),
+    /// A client-provided `Idempotency-Key` from the HTTP API. See
+    /// `crate::idempotent_requests`.
+    IdempotencyKey(String),
+}
+
 /// Information for a single session request
 ///
 /// This is used to determine whether a session request has already been
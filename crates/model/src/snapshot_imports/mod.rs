@@ -26,6 +26,7 @@ use sync_types::Timestamp;
 use value::{
     ConvexObject,
     ConvexValue,
+    FieldPath,
     ResolvedDocumentId,
     TableName,
     TableNamespace,
@@ -33,10 +34,12 @@ use value::{
 };
 
 use self::types::{
+    ImportCompression,
     ImportFormat,
     ImportMode,
     ImportState,
     ImportTableCheckpoint,
+    ImportValidationReport,
     SnapshotImport,
 };
 use crate::{
@@ -95,12 +98,18 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         &mut self,
         format: ImportFormat,
         mode: ImportMode,
+        upsert_key: Option<FieldPath>,
+        compression: Option<ImportCompression>,
+        dry_run: bool,
         object_key: ObjectKey,
     ) -> anyhow::Result<ResolvedDocumentId> {
         let snapshot_import = SnapshotImport {
             state: ImportState::Uploaded,
             format,
             mode,
+            upsert_key,
+            compression,
+            dry_run,
             object_key,
             member_id: self.tx.identity().member_id(),
             checkpoints: None,
@@ -137,6 +146,7 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         match (&current_state, &new_state) {
             (ImportState::Uploaded, ImportState::WaitingForConfirmation { .. })
             | (ImportState::Uploaded, ImportState::Failed(..))
+            | (ImportState::Uploaded, ImportState::Validated { .. })
             | (ImportState::WaitingForConfirmation { .. }, ImportState::InProgress { .. })
             | (ImportState::WaitingForConfirmation { .. }, ImportState::Failed { .. })
             | (ImportState::InProgress { .. }, ImportState::InProgress { .. })
@@ -194,8 +204,24 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         .await
     }
 
+    pub async fn mark_validated(
+        &mut self,
+        id: ResolvedDocumentId,
+        report: ImportValidationReport,
+    ) -> anyhow::Result<()> {
+        self.update_state(id, move |_| ImportState::Validated { report })
+            .await
+    }
+
     pub async fn confirm_import(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
         let current_state = self.must_get_state(id).await?;
+        if matches!(current_state, ImportState::Validated { .. }) {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "DryRunImport",
+                "A dry-run import can't be performed. Upload it again without dry run to import \
+                 it."
+            ));
+        }
         // No-op if the import is already in progress or finished since the CLI may
         // show a confirmation prompt when the import was confirmed in the dashboard.
         if matches!(current_state, ImportState::WaitingForConfirmation { .. }) {
@@ -224,6 +250,10 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
                 "CannotCancelImport",
                 "Cannot cancel an import that has failed"
             )),
+            ImportState::Validated { .. } => anyhow::bail!(ErrorMetadata::bad_request(
+                "CannotCancelImport",
+                "Cannot cancel a dry-run import"
+            )),
         }
         Ok(())
     }
@@ -13,6 +13,7 @@ use serde::{
 use sync_types::Timestamp;
 use value::{
     codegen_convex_serialization,
+    FieldPath,
     TabletId,
 };
 
@@ -22,6 +23,15 @@ pub struct SnapshotImport {
     pub state: ImportState,
     pub format: ImportFormat,
     pub mode: ImportMode,
+    /// The field that rows are matched on in [`ImportMode::Upsert`]. Rows are
+    /// matched by `_id` if this is `None`.
+    pub upsert_key: Option<FieldPath>,
+    /// How the uploaded file is compressed, if declared on upload. Otherwise
+    /// compression is detected from the file's contents.
+    pub compression: Option<ImportCompression>,
+    /// Validate the import and report what it would do, without writing
+    /// anything. Dry runs end in [`ImportState::Validated`].
+    pub dry_run: bool,
     pub object_key: ObjectKey,
     pub member_id: Option<MemberId>,
     pub checkpoints: Option<Vec<ImportTableCheckpoint>>,
@@ -32,6 +42,9 @@ struct SerializedSnapshotImport {
     state: SerializedImportState,
     format: SerializedImportFormat,
     mode: String,
+    upsert_key: Option<String>,
+    compression: Option<String>,
+    dry_run: Option<bool>,
     object_key: String,
     member_id: Option<i64>,
     checkpoints: Option<Vec<SerializedImportTableCheckpoint>>,
@@ -45,6 +58,11 @@ impl TryFrom<SnapshotImport> for SerializedSnapshotImport {
             state: import.state.try_into()?,
             format: import.format.try_into()?,
             mode: import.mode.to_string(),
+            upsert_key: import.upsert_key.map(String::from),
+            compression: import
+                .compression
+                .map(|compression| compression.to_string()),
+            dry_run: Some(import.dry_run),
             object_key: import.object_key.to_string(),
             member_id: import.member_id.map(|member_id| member_id.0 as i64),
             checkpoints: import
@@ -63,6 +81,15 @@ impl TryFrom<SerializedSnapshotImport> for SnapshotImport {
             state: import.state.try_into()?,
             format: import.format.try_into()?,
             mode: import.mode.parse()?,
+            upsert_key: import
+                .upsert_key
+                .map(|upsert_key| upsert_key.parse())
+                .transpose()?,
+            compression: import
+                .compression
+                .map(|compression| compression.parse())
+                .transpose()?,
+            dry_run: import.dry_run.unwrap_or_default(),
             object_key: import.object_key.try_into()?,
             member_id: import.member_id.map(|member_id| MemberId(member_id as u64)),
             checkpoints: import
@@ -82,6 +109,7 @@ pub enum ImportFormat {
     JsonLines(TableName),
     JsonArray(TableName),
     Zip,
+    Parquet(TableName),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -95,6 +123,8 @@ pub enum SerializedImportFormat {
     JsonArray { table: String },
     #[serde(rename = "zip")]
     Zip,
+    #[serde(rename = "parquet")]
+    Parquet { table: String },
 }
 
 impl TryFrom<ImportFormat> for SerializedImportFormat {
@@ -112,6 +142,9 @@ impl TryFrom<ImportFormat> for SerializedImportFormat {
                 table: table.to_string(),
             }),
             ImportFormat::Zip => Ok(SerializedImportFormat::Zip),
+            ImportFormat::Parquet(table) => Ok(SerializedImportFormat::Parquet {
+                table: table.to_string(),
+            }),
         }
     }
 }
@@ -129,10 +162,21 @@ impl TryFrom<SerializedImportFormat> for ImportFormat {
                 Ok(ImportFormat::JsonArray(table.parse()?))
             },
             SerializedImportFormat::Zip => Ok(ImportFormat::Zip),
+            SerializedImportFormat::Parquet { table } => Ok(ImportFormat::Parquet(table.parse()?)),
         }
     }
 }
 
+/// Compression of an uploaded CSV or JSONL file.
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq, strum::EnumString, strum::Display)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "snake_case")]
+pub enum ImportCompression {
+    Gzip,
+    Zstd,
+}
+
 mod import_format_serde {
     use value::codegen_convex_serialization;
 
@@ -152,12 +196,12 @@ mod import_format_serde {
 │ Uploaded  │
 └─────┬─────┘
       │
-Import│Worker parses
-      ├─────────────────────┐
-      │                     │
-┌─────▼────────────────┐    │
-│WaitingForConfirmation│    │
-└─────┬────────────────┘    │
+Import│Worker parses                    (dry run)
+      ├─────────────────────┬──────────────┐
+      │                     │              │
+┌─────▼────────────────┐    │        ┌─────▼─────┐
+│WaitingForConfirmation│    │        │ Validated │
+└─────┬────────────────┘    │        └───────────┘
       │                     │
 CLI requests confirmation   │
       │                     │
@@ -189,6 +233,9 @@ pub enum ImportState {
         num_rows_written: i64,
     },
     Failed(String),
+    Validated {
+        report: ImportValidationReport,
+    },
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -210,6 +257,9 @@ enum SerializedImportState {
     Failed {
         error_message: String,
     },
+    Validated {
+        report: SerializedImportValidationReport,
+    },
 }
 
 impl TryFrom<ImportState> for SerializedImportState {
@@ -242,6 +292,9 @@ impl TryFrom<ImportState> for SerializedImportState {
             ImportState::Failed(message) => Ok(SerializedImportState::Failed {
                 error_message: message,
             }),
+            ImportState::Validated { report } => Ok(SerializedImportState::Validated {
+                report: report.try_into()?,
+            }),
         }
     }
 }
@@ -276,10 +329,150 @@ impl TryFrom<SerializedImportState> for ImportState {
             SerializedImportState::Failed { error_message } => {
                 Ok(ImportState::Failed(error_message))
             },
+            SerializedImportState::Validated { report } => Ok(ImportState::Validated {
+                report: report.try_into()?,
+            }),
         }
     }
 }
 
+/// What a dry-run import found: the tables it would write and the rows that
+/// would fail to import.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ImportValidationReport {
+    pub tables: Vec<ImportValidationTable>,
+    /// The first errors found, in the order they were found.
+    pub errors: Vec<ImportValidationError>,
+    /// The number of errors found, including those left out of `errors`.
+    pub num_errors: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ImportValidationTable {
+    pub component_path: ComponentPath,
+    pub table_name: TableName,
+    pub action: ImportTableAction,
+    pub num_rows: i64,
+}
+
+/// What importing would do to a table.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum::EnumString, strum::Display)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[strum(serialize_all = "snake_case")]
+pub enum ImportTableAction {
+    Create,
+    Replace,
+    Append,
+    Upsert,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ImportValidationError {
+    pub component_path: ComponentPath,
+    /// The table being read when the error was found, if any.
+    pub table_name: Option<TableName>,
+    /// The 1-indexed row within the table, for errors in a single row.
+    pub row_number: Option<i64>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SerializedImportValidationReport {
+    tables: Vec<SerializedImportValidationTable>,
+    errors: Vec<SerializedImportValidationError>,
+    num_errors: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct SerializedImportValidationTable {
+    component_path: Option<String>,
+    table_name: String,
+    action: String,
+    num_rows: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct SerializedImportValidationError {
+    component_path: Option<String>,
+    table_name: Option<String>,
+    row_number: Option<i64>,
+    message: String,
+}
+
+impl TryFrom<ImportValidationReport> for SerializedImportValidationReport {
+    type Error = anyhow::Error;
+
+    fn try_from(report: ImportValidationReport) -> anyhow::Result<Self> {
+        Ok(SerializedImportValidationReport {
+            tables: report
+                .tables
+                .into_iter()
+                .map(|table| SerializedImportValidationTable {
+                    component_path: table.component_path.serialize(),
+                    table_name: table.table_name.to_string(),
+                    action: table.action.to_string(),
+                    num_rows: table.num_rows,
+                })
+                .collect(),
+            errors: report
+                .errors
+                .into_iter()
+                .map(|error| SerializedImportValidationError {
+                    component_path: error.component_path.serialize(),
+                    table_name: error.table_name.map(|table_name| table_name.to_string()),
+                    row_number: error.row_number,
+                    message: error.message,
+                })
+                .collect(),
+            num_errors: report.num_errors,
+        })
+    }
+}
+
+impl TryFrom<SerializedImportValidationReport> for ImportValidationReport {
+    type Error = anyhow::Error;
+
+    fn try_from(report: SerializedImportValidationReport) -> anyhow::Result<Self> {
+        Ok(ImportValidationReport {
+            tables: report
+                .tables
+                .into_iter()
+                .map(|table| {
+                    anyhow::Ok(ImportValidationTable {
+                        component_path: ComponentPath::deserialize(
+                            table.component_path.as_deref(),
+                        )?,
+                        table_name: table.table_name.parse()?,
+                        action: table.action.parse()?,
+                        num_rows: table.num_rows,
+                    })
+                })
+                .try_collect()?,
+            errors: report
+                .errors
+                .into_iter()
+                .map(|error| {
+                    anyhow::Ok(ImportValidationError {
+                        component_path: ComponentPath::deserialize(
+                            error.component_path.as_deref(),
+                        )?,
+                        table_name: error
+                            .table_name
+                            .map(|table_name| table_name.parse())
+                            .transpose()?,
+                        row_number: error.row_number,
+                        message: error.message,
+                    })
+                })
+                .try_collect()?,
+            num_errors: report.num_errors,
+        })
+    }
+}
+
 mod import_state_serde {
     use value::codegen_convex_serialization;
 
@@ -373,4 +566,6 @@ pub enum ImportMode {
     Replace,
     #[default]
     RequireEmpty,
+    /// Patch the documents that match an imported row and insert the rest.
+    Upsert,
 }
@@ -0,0 +1,208 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use sync_types::Timestamp;
+use value::{
+    id_v6::DeveloperDocumentId,
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+    TabletId,
+};
+
+pub mod types;
+
+use types::RestoredDocument;
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+/// Table name for documents that point-in-time table restores reinserted with
+/// new IDs.
+pub static TABLE_RESTORE_DOCUMENTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_table_restore_documents"
+        .parse()
+        .expect("Invalid built-in table restore documents table")
+});
+
+static TABLE_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "tableId".parse().expect("Invalid built-in field"));
+static RESTORE_TS_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "restoreTs".parse().expect("Invalid built-in field"));
+static ORIGINAL_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "originalId".parse().expect("Invalid built-in field"));
+static RESTORED_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "restoredId".parse().expect("Invalid built-in field"));
+
+pub static TABLE_RESTORE_DOCUMENTS_BY_ORIGINAL_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&TABLE_RESTORE_DOCUMENTS_TABLE, "by_original_id"));
+pub static TABLE_RESTORE_DOCUMENTS_BY_RESTORED_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&TABLE_RESTORE_DOCUMENTS_TABLE, "by_restored_id"));
+
+pub struct TableRestoreDocumentsTable;
+impl SystemTable for TableRestoreDocumentsTable {
+    fn table_name(&self) -> &'static TableName {
+        &TABLE_RESTORE_DOCUMENTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: TABLE_RESTORE_DOCUMENTS_BY_ORIGINAL_ID_INDEX.clone(),
+                fields: vec![
+                    TABLE_ID_FIELD.clone(),
+                    RESTORE_TS_FIELD.clone(),
+                    ORIGINAL_ID_FIELD.clone(),
+                ]
+                .try_into()
+                .unwrap(),
+            },
+            SystemIndex {
+                name: TABLE_RESTORE_DOCUMENTS_BY_RESTORED_ID_INDEX.clone(),
+                fields: vec![
+                    TABLE_ID_FIELD.clone(),
+                    RESTORE_TS_FIELD.clone(),
+                    RESTORED_ID_FIELD.clone(),
+                ]
+                .try_into()
+                .unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<RestoredDocument>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct TableRestoreModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> TableRestoreModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// The ID that restoring `table_id` to `restore_ts` reinserted
+    /// `original_id` with, if it was reinserted.
+    pub async fn restored_id(
+        &mut self,
+        table_id: TabletId,
+        restore_ts: Timestamp,
+        original_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<DeveloperDocumentId>> {
+        let record = self
+            .query_by_id(
+                &TABLE_RESTORE_DOCUMENTS_BY_ORIGINAL_ID_INDEX,
+                &ORIGINAL_ID_FIELD,
+                table_id,
+                restore_ts,
+                original_id,
+            )
+            .await?;
+        Ok(record.map(|record| record.restored_id))
+    }
+
+    /// Whether restoring `table_id` to `restore_ts` inserted `id`.
+    pub async fn is_restored_document(
+        &mut self,
+        table_id: TabletId,
+        restore_ts: Timestamp,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<bool> {
+        let record = self
+            .query_by_id(
+                &TABLE_RESTORE_DOCUMENTS_BY_RESTORED_ID_INDEX,
+                &RESTORED_ID_FIELD,
+                table_id,
+                restore_ts,
+                id,
+            )
+            .await?;
+        Ok(record.is_some())
+    }
+
+    /// Records that a restore reinserted a document, replacing the record of
+    /// an earlier reinsertion of the same document.
+    pub async fn record_restored_document(
+        &mut self,
+        record: RestoredDocument,
+    ) -> anyhow::Result<()> {
+        let existing = self
+            .query_by_id(
+                &TABLE_RESTORE_DOCUMENTS_BY_ORIGINAL_ID_INDEX,
+                &ORIGINAL_ID_FIELD,
+                record.table_id,
+                record.restore_ts,
+                record.original_id,
+            )
+            .await?;
+        match existing {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), record.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&TABLE_RESTORE_DOCUMENTS_TABLE, record.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    async fn query_by_id(
+        &mut self,
+        index_name: &IndexName,
+        id_field: &FieldPath,
+        table_id: TabletId,
+        restore_ts: Timestamp,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<RestoredDocument>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: index_name.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    TABLE_ID_FIELD.clone(),
+                    ConvexValue::try_from(table_id.to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    RESTORE_TS_FIELD.clone(),
+                    ConvexValue::from(i64::from(restore_ts)).into(),
+                ),
+                IndexRangeExpression::Eq(id_field.clone(), ConvexValue::from(id).into()),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .next(self.tx, Some(1))
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use common::{
+    obj,
+    value::ConvexValue,
+};
+use sync_types::Timestamp;
+use value::{
+    id_v6::DeveloperDocumentId,
+    ConvexObject,
+    TabletId,
+};
+
+/// A document that a point-in-time table restore reinserted with a new ID,
+/// because the document was deleted and its ID can't be reused.
+///
+/// Running the same restore again finds the reinserted document through this
+/// record instead of inserting another copy.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RestoredDocument {
+    pub table_id: TabletId,
+    pub restore_ts: Timestamp,
+    /// The ID of the document at `restore_ts`.
+    pub original_id: DeveloperDocumentId,
+    /// The ID the restore inserted the document with.
+    pub restored_id: DeveloperDocumentId,
+}
+
+impl TryFrom<RestoredDocument> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(record: RestoredDocument) -> anyhow::Result<Self> {
+        obj!(
+            "tableId" => record.table_id.to_string(),
+            "restoreTs" => i64::from(record.restore_ts),
+            "originalId" => record.original_id,
+            "restoredId" => record.restored_id,
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for RestoredDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(object: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields: BTreeMap<_, _> = object.into();
+
+        let table_id = match fields.remove("tableId") {
+            Some(ConvexValue::String(s)) => s.parse()?,
+            v => anyhow::bail!("Invalid tableId field for RestoredDocument: {:?}", v),
+        };
+        let restore_ts = match fields.remove("restoreTs") {
+            Some(ConvexValue::Int64(ts)) => ts.try_into()?,
+            v => anyhow::bail!("Invalid restoreTs field for RestoredDocument: {:?}", v),
+        };
+        let original_id = match fields.remove("originalId") {
+            Some(v @ ConvexValue::String(_)) => v.try_into()?,
+            v => anyhow::bail!("Invalid originalId field for RestoredDocument: {:?}", v),
+        };
+        let restored_id = match fields.remove("restoredId") {
+            Some(v @ ConvexValue::String(_)) => v.try_into()?,
+            v => anyhow::bail!("Invalid restoredId field for RestoredDocument: {:?}", v),
+        };
+
+        Ok(RestoredDocument {
+            table_id,
+            restore_ts,
+            original_id,
+            restored_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::testing::assert_roundtrips;
+    use proptest::prelude::*;
+    use value::ConvexObject;
+
+    use super::RestoredDocument;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+        #[test]
+        fn test_restored_document_roundtrips(v in any::<RestoredDocument>()) {
+            assert_roundtrips::<RestoredDocument, ConvexObject>(v);
+        }
+    }
+}
@@ -158,6 +158,9 @@ pub trait Storage: Send + Sync + Debug {
         &self,
         key: &ObjectKey,
     ) -> anyhow::Result<Option<ObjectAttributes>>;
+    /// Deletes an object. Deleting an object that doesn't exist is not an
+    /// error.
+    async fn delete_object(&self, key: &ObjectKey) -> anyhow::Result<()>;
     /// Not intended to be called directly.
     /// Use get_range() or get() instead.
     fn get_small_range(
@@ -1039,6 +1042,16 @@ impl<RT: Runtime> Storage for LocalDirStorage<RT> {
         }))
     }
 
+    async fn delete_object(&self, key: &ObjectKey) -> anyhow::Result<()> {
+        let key = self.path_for_key(key.clone());
+        let path = self.dir.join(key);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() == IoErrorKind::NotFound => Ok(()),
+            result => result
+                .with_context(|| format!("Local dir storage couldn't delete {}", path.display())),
+        }
+    }
+
     fn storage_type_proto(&self) -> pb::searchlight::StorageType {
         pb::searchlight::StorageType {
             storage_type: Some(pb::searchlight::storage_type::StorageType::Local(
@@ -1214,6 +1227,23 @@ mod local_storage_tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_delete_object(rt: TestRuntime) -> anyhow::Result<()> {
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt)?);
+        let mut upload = storage.start_upload().await?;
+        upload.write(Bytes::from_static(b"pinna park")).await?;
+        let key = upload.complete().await?;
+        assert!(storage.get_object_attributes(&key).await?.is_some());
+
+        storage.delete_object(&key).await?;
+        assert!(storage.get_object_attributes(&key).await?.is_none());
+        assert!(storage.get(&key).await?.is_none());
+
+        // Deleting it again is a no-op.
+        storage.delete_object(&key).await?;
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_storage_get_paginated(rt: TestRuntime) -> anyhow::Result<()> {
         // Test that chunks are stitched together in the right order.
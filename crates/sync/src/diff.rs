@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use common::value::{
+    ConvexValue,
+    Size,
+};
+use sync_types::ValueDiff;
+
+/// Compute a diff from `old` to `new` for a client that has `old`. Returns
+/// `None` if the diff isn't much smaller than `new`, so sending `new` in full
+/// is about as cheap.
+pub fn diff_values(old: &ConvexValue, new: &ConvexValue) -> Option<ValueDiff<ConvexValue>> {
+    let diff = diff(old, new);
+    if diff_size(&diff) * 2 > new.size() {
+        return None;
+    }
+    Some(diff)
+}
+
+fn diff(old: &ConvexValue, new: &ConvexValue) -> ValueDiff<ConvexValue> {
+    match (old, new) {
+        (ConvexValue::Object(old), ConvexValue::Object(new)) => {
+            let mut updated = BTreeMap::new();
+            for (field, value) in new.iter() {
+                let field_diff = match old.get(field) {
+                    Some(old_value) if old_value == value => continue,
+                    Some(old_value) => diff(old_value, value),
+                    None => ValueDiff::Replace(value.clone()),
+                };
+                updated.insert(field.to_string(), field_diff);
+            }
+            let removed = old
+                .keys()
+                .filter(|field| !new.contains_field(field))
+                .map(|field| field.to_string())
+                .collect();
+            ValueDiff::Object { updated, removed }
+        },
+        (ConvexValue::Array(old), ConvexValue::Array(new)) => {
+            // Lists usually change by a few elements being inserted, removed or
+            // replaced in one place, so only send what's between the common
+            // prefix and suffix.
+            let prefix = old
+                .iter()
+                .zip(new.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let suffix = old[prefix..]
+                .iter()
+                .rev()
+                .zip(new[prefix..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            ValueDiff::Array {
+                base_len: old.len() as u32,
+                start: prefix as u32,
+                deleted: (old.len() - prefix - suffix) as u32,
+                inserted: new[prefix..new.len() - suffix].to_vec(),
+            }
+        },
+        _ => ValueDiff::Replace(new.clone()),
+    }
+}
+
+/// Approximate the encoded size of a diff, in the same units as
+/// [`Size::size`].
+fn diff_size(diff: &ValueDiff<ConvexValue>) -> usize {
+    match diff {
+        ValueDiff::Replace(value) => value.size(),
+        ValueDiff::Object { updated, removed } => {
+            let updated_size: usize = updated
+                .iter()
+                .map(|(field, diff)| field.len() + 1 + diff_size(diff))
+                .sum();
+            let removed_size: usize = removed.iter().map(|field| field.len() + 1).sum();
+            2 + updated_size + removed_size
+        },
+        ValueDiff::Array { inserted, .. } => {
+            // Account for the three lengths.
+            2 + 3 * 8 + inserted.iter().map(|value| value.size()).sum::<usize>()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        assert_obj,
+        value::ConvexValue,
+    };
+    use maplit::btreemap;
+    use sync_types::ValueDiff;
+
+    use super::{
+        diff,
+        diff_values,
+    };
+
+    fn array(items: Vec<ConvexValue>) -> ConvexValue {
+        ConvexValue::Array(items.try_into().unwrap())
+    }
+
+    fn row(id: i64, body: &str) -> ConvexValue {
+        ConvexValue::Object(assert_obj!("id" => id, "body" => body))
+    }
+
+    #[test]
+    fn test_diff_array_changed_row() {
+        let old = array((0..100).map(|i| row(i, "unchanged")).collect());
+        let mut rows: Vec<_> = (0..100).map(|i| row(i, "unchanged")).collect();
+        rows[50] = row(50, "changed");
+        let new = array(rows);
+        assert_eq!(
+            diff_values(&old, &new),
+            Some(ValueDiff::Array {
+                base_len: 100,
+                start: 50,
+                deleted: 1,
+                inserted: vec![row(50, "changed")],
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_array_insert_and_remove() {
+        let old = array(vec![row(1, "a"), row(2, "b"), row(3, "c")]);
+        let new = array(vec![row(0, "z"), row(1, "a"), row(2, "b")]);
+        assert_eq!(
+            diff(&old, &new),
+            ValueDiff::Array {
+                base_len: 3,
+                start: 0,
+                deleted: 3,
+                inserted: vec![row(0, "z"), row(1, "a"), row(2, "b")],
+            }
+        );
+        let new = array(vec![row(1, "a"), row(3, "c")]);
+        assert_eq!(
+            diff(&old, &new),
+            ValueDiff::Array {
+                base_len: 3,
+                start: 1,
+                deleted: 1,
+                inserted: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_object() {
+        let old = ConvexValue::Object(assert_obj!(
+            "count" => 1i64,
+            "removed" => "x",
+            "nested" => {"a" => 1i64, "b" => 2i64},
+        ));
+        let new = ConvexValue::Object(assert_obj!(
+            "count" => 1i64,
+            "added" => "y",
+            "nested" => {"a" => 1i64, "b" => 3i64},
+        ));
+        assert_eq!(
+            diff(&old, &new),
+            ValueDiff::Object {
+                updated: btreemap! {
+                    "added".to_string() => ValueDiff::Replace("y".try_into().unwrap()),
+                    "nested".to_string() => ValueDiff::Object {
+                        updated: btreemap! {
+                            "b".to_string() => ValueDiff::Replace(3i64.into()),
+                        },
+                        removed: vec![],
+                    },
+                },
+                removed: vec!["removed".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_not_smaller() {
+        let old = ConvexValue::from(1i64);
+        let new = ConvexValue::from(2i64);
+        assert_eq!(diff_values(&old, &new), None);
+    }
+}
@@ -4,6 +4,7 @@
 #![feature(let_chains)]
 #![feature(try_blocks)]
 
+mod diff;
 mod metrics;
 mod state;
 pub mod worker;
@@ -91,6 +91,15 @@ pub fn log_query_result_dedup(same_value: bool) {
     log_counter(&SYNC_QUERY_RESULT_DEDUP_TOTAL, sample);
 }
 
+register_convex_counter!(
+    SYNC_QUERY_RESULT_DIFF_TOTAL,
+    "Number of query results sent as a diff against the previous result"
+);
+pub fn log_query_result_diff(diffed: bool) {
+    let sample = if diffed { 1 } else { 0 };
+    log_counter(&SYNC_QUERY_RESULT_DIFF_TOTAL, sample);
+}
+
 register_convex_counter!(SYNC_EMPTY_TRANSITION_TOTAL, "Number of empty transitions");
 pub fn log_empty_transition() {
     log_counter(&SYNC_EMPTY_TRANSITION_TOTAL, 1);
@@ -12,6 +12,11 @@ use application::{
     },
 };
 use common::{
+    heap_size::HeapSize,
+    knobs::{
+        SYNC_QUERY_DIFF_MAX_CONNECTION_BYTES,
+        SYNC_QUERY_DIFF_MAX_VALUE_BYTES,
+    },
     sha256::{
         Sha256,
         Sha256Digest,
@@ -43,7 +48,10 @@ use sync_types::{
     StateVersion,
 };
 
-use crate::metrics;
+use crate::{
+    diff::diff_values,
+    metrics,
+};
 
 type ValueDigest = Sha256Digest;
 type ErrorDigest = Sha256Digest;
@@ -69,12 +77,25 @@ pub struct SyncedQuery {
     ///   time.
     result_hash: Option<Result<ValueDigest, ErrorDigest>>,
 
+    /// The last value sent to the client, if the client accepts diffs and
+    /// the value fit within the connection's diff budget. The next value is
+    /// sent as a diff against it while `result_hash` still matches.
+    last_sent_value: Option<SentValue>,
+
     /// Handle to the query's current invalidation future. This future completes
     /// when `self.subscription` is no longer valid and the query should be
     /// rerun.
     invalidation_future: Option<AbortHandle>,
 }
 
+struct SentValue {
+    /// The hash of the result the value was sent with.
+    hash: ValueDigest,
+    value: ConvexValue,
+    /// `value`'s heap size, counted in `SyncState::last_sent_values_size`.
+    size: usize,
+}
+
 /// The client issues modifications to sync state predicated on a client
 /// version, and this represents the latest received version from the client.
 #[derive(Clone, Copy)]
@@ -122,6 +143,11 @@ pub struct SyncState {
     // ID for the current session. Will be None for old clients that connect
     // without specifying a session ID.
     session_id: Option<SessionId>,
+    /// Whether the client accepts `StateModification::QueryDiffed`.
+    query_diffs: bool,
+    /// The total size of every query's `last_sent_value`, bounded by
+    /// `SYNC_QUERY_DIFF_MAX_CONNECTION_BYTES`.
+    last_sent_values_size: usize,
     current_version: StateVersion,
     invalidation_futures:
         FuturesUnordered<BoxFuture<'static, Result<anyhow::Result<QueryId>, Aborted>>>,
@@ -148,6 +174,8 @@ impl SyncState {
     pub fn new() -> Self {
         Self {
             session_id: None,
+            query_diffs: false,
+            last_sent_values_size: 0,
             current_version: StateVersion::initial(),
             invalidation_futures: FuturesUnordered::new(),
             queries: BTreeMap::new(),
@@ -170,6 +198,10 @@ impl SyncState {
         self.session_id
     }
 
+    pub fn set_query_diffs(&mut self, query_diffs: bool) {
+        self.query_diffs = query_diffs;
+    }
+
     /// What is the current state version?
     pub fn current_version(&self) -> StateVersion {
         self.current_version
@@ -319,6 +351,9 @@ impl SyncState {
             if let Some(handle) = query.invalidation_future.take() {
                 handle.abort();
             }
+            if let Some(sent) = query.last_sent_value {
+                self.last_sent_values_size -= sent.size;
+            }
         } else if self.in_progress_queries.remove(&query_id).is_some() {
             // Removed in-progress query.
         } else {
@@ -386,6 +421,7 @@ impl SyncState {
                 query,
                 subscription: None,
                 result_hash: None,
+                last_sent_value: None,
                 invalidation_future: None,
             };
             if self.queries.insert(query_id, sq).is_some() {
@@ -414,21 +450,61 @@ impl SyncState {
         }
 
         let new_hash = hash_result(&result, &log_lines);
-        let same_result = query.result_hash.as_ref() == Some(&new_hash);
+        let previous_hash = query.result_hash.replace(new_hash);
+        let same_result = previous_hash == query.result_hash;
         metrics::log_query_result_dedup(same_result);
 
-        query.result_hash = Some(new_hash);
         query.subscription = Some(subscription);
 
         let result = if same_result {
             None
         } else {
+            // The client has the value we last sent if it's still the query's
+            // previous result.
+            let last_sent_value = query.last_sent_value.take();
+            if let Some(ref sent) = last_sent_value {
+                self.last_sent_values_size -= sent.size;
+            }
+            let base_value = match (previous_hash, last_sent_value) {
+                (Some(Ok(previous)), Some(sent)) if previous == sent.hash => Some(sent.value),
+                _ => None,
+            };
             let modification = match result {
-                Ok(value) => StateModification::QueryUpdated {
-                    query_id,
-                    value,
-                    log_lines: log_lines.into(),
-                    journal,
+                Ok(value) => {
+                    let diff = base_value.and_then(|base| diff_values(&base, &value));
+                    if self.query_diffs {
+                        metrics::log_query_result_diff(diff.is_some());
+                        // Keep the value to diff the next result against, unless
+                        // it would take the connection over its budget. Without
+                        // it, the next result is sent in full.
+                        let size = value.heap_size();
+                        if let Some(Ok(ref hash)) = query.result_hash
+                            && size <= *SYNC_QUERY_DIFF_MAX_VALUE_BYTES
+                            && self.last_sent_values_size + size
+                                <= *SYNC_QUERY_DIFF_MAX_CONNECTION_BYTES
+                        {
+                            self.last_sent_values_size += size;
+                            query.last_sent_value = Some(SentValue {
+                                hash: hash.clone(),
+                                value: value.clone(),
+                                size,
+                            });
+                        }
+                    }
+                    match diff {
+                        Some(diff) => StateModification::QueryDiffed {
+                            query_id,
+                            diff,
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                        None => StateModification::QueryUpdated {
+                            query_id,
+                            value,
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                    }
                 },
                 Err(error) => {
                     metrics::log_query_failed();
@@ -437,6 +513,7 @@ impl SyncState {
                         error_message: error.to_string(),
                         log_lines: log_lines.into(),
                         journal,
+                        error_code: error.query_error_code(),
                         error_data: error.custom_data_if_any(),
                     }
                 },
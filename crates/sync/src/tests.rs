@@ -67,6 +67,7 @@ use sync_types::{
     QuerySetModification,
     StateModification,
     UserIdentityAttributes,
+    ValueDiff,
 };
 
 use crate::{
@@ -129,13 +130,14 @@ impl SyncTest {
 
     fn new_worker(&self) -> anyhow::Result<TestSyncWorker> {
         let config = SyncWorkerConfig::default();
-        self.new_worker_with_config(config, None)
+        self.new_worker_with_config(config, None, false)
     }
 
     fn new_worker_with_config(
         &self,
         config: SyncWorkerConfig,
         max_observed_timestamp: Option<Timestamp>,
+        query_diffs: bool,
     ) -> anyhow::Result<TestSyncWorker> {
         let worker_failed = Arc::new(Mutex::new(None));
         let (client_tx, client_rx) = mpsc::unbounded();
@@ -172,6 +174,7 @@ impl SyncTest {
                 connection_count: 0,
                 last_close_reason: "InitialConnect".to_string(),
                 max_observed_timestamp,
+                query_diffs,
             },
             self.rt.monotonic_now(),
         ))?;
@@ -886,12 +889,70 @@ async fn test_udf_cache_out_of_order(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_query_diffs(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let mut sync_worker = test.new_worker_with_config(SyncWorkerConfig::default(), None, true)?;
+
+    for (i, name) in ["a", "b", "c", "d", "e", "f", "g", "h"]
+        .into_iter()
+        .enumerate()
+    {
+        sync_worker
+            .mutation(
+                "sync:initialize",
+                assert_obj!("name" => name, "balance" => 0.0),
+                i as SessionRequestSeqNumber,
+            )
+            .await?;
+        must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    }
+
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:listAccounts".parse()?,
+        args: vec![assert_obj!().into()],
+        journal: None,
+        component_path: None,
+    };
+    sync_worker.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    assert_eq!(modifications.len(), 1);
+    must_let!(let StateModification::QueryUpdated { value: ConvexValue::Array(accounts), .. } = &modifications[0]);
+    assert_eq!(accounts.len(), 8);
+
+    // Changing one account only sends that account.
+    sync_worker
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => "d", "balance" => 10.0),
+            8,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    assert_eq!(modifications.len(), 1);
+    must_let!(let StateModification::QueryDiffed { query_id, diff, .. } = &modifications[0]);
+    assert_eq!(*query_id, QueryId::new(0));
+    must_let!(let ValueDiff::Array { base_len, start, deleted, inserted } = diff);
+    assert_eq!((*base_len, *start, *deleted), (8, 3, 1));
+    assert_eq!(inserted.len(), 1);
+    must_let!(let ConvexValue::Object(account) = &inserted[0]);
+    assert_eq!(account.get("balance"), Some(&ConvexValue::from(10.0)));
+
+    sync_worker.shutdown().await?;
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_max_observed_timestamp(rt: TestRuntime) -> anyhow::Result<()> {
     let test = SyncTest::new(rt).await?;
 
     let config = SyncWorkerConfig::default();
-    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX))?;
+    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX), false)?;
     must_let!(let Err(err) = sync_worker.receive().await);
     assert!(
         format!("{err}")
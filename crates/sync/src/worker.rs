@@ -74,7 +74,10 @@ use futures::{
 use keybroker::Identity;
 use maplit::btreemap;
 use minitrace::prelude::*;
-use model::session_requests::types::SessionRequestIdentifier;
+use model::session_requests::types::{
+    MutationIdentifier,
+    SessionRequestIdentifier,
+};
 use sync_types::{
     ClientMessage,
     IdentityVersion,
@@ -418,11 +421,13 @@ impl<RT: Runtime> SyncWorker<RT> {
                 last_close_reason,
                 max_observed_timestamp,
                 connection_count,
+                query_diffs,
             } => {
                 if let Some(timer) = self.connect_timer.take() {
                     timer.finish();
                 }
                 self.state.set_session_id(session_id);
+                self.state.set_query_diffs(query_diffs);
                 if let Some(max_observed_timestamp) = max_observed_timestamp {
                     let latest_timestamp = *self
                         .api
@@ -463,11 +468,12 @@ impl<RT: Runtime> SyncWorker<RT> {
                 component_path,
             } => {
                 let identity = self.state.identity(self.rt.system_time())?;
-                let mutation_identifier =
-                    self.state.session_id().map(|id| SessionRequestIdentifier {
+                let mutation_identifier = self.state.session_id().map(|id| {
+                    MutationIdentifier::Session(SessionRequestIdentifier {
                         session_id: id,
                         request_id,
-                    });
+                    })
+                });
                 let server_request_id = match self.state.session_id() {
                     Some(id) => RequestId::new_for_ws_session(id, request_id),
                     None => RequestId::new(),
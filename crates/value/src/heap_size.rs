@@ -45,6 +45,7 @@ use sync_types::{
     StateVersion,
     Timestamp,
     UserIdentityAttributes,
+    ValueDiff,
 };
 
 pub trait HeapSize {
@@ -796,6 +797,7 @@ impl<V: HeapSize> HeapSize for StateModification<V> {
                 query_id: _,
                 error_message,
                 error_data,
+                error_code: _,
                 log_lines,
                 journal,
             } => {
@@ -805,6 +807,33 @@ impl<V: HeapSize> HeapSize for StateModification<V> {
                     + journal.heap_size()
             },
             StateModification::QueryRemoved { query_id: _ } => 0,
+            StateModification::QueryDiffed {
+                query_id: _,
+                diff,
+                log_lines,
+                journal,
+            } => diff.heap_size() + log_lines.heap_size() + journal.heap_size(),
+        }
+    }
+}
+
+impl<V: HeapSize> HeapSize for ValueDiff<V> {
+    fn heap_size(&self) -> usize {
+        match self {
+            ValueDiff::Replace(value) => value.heap_size(),
+            ValueDiff::Object { updated, removed } => {
+                let mut size = estimate_btree_heap_size::<String, ValueDiff<V>>(updated.len());
+                for (field, diff) in updated {
+                    size += field.heap_size() + diff.heap_size();
+                }
+                size + estimate_vec_size(removed)
+            },
+            ValueDiff::Array {
+                base_len: _,
+                start: _,
+                deleted: _,
+                inserted,
+            } => estimate_vec_size(inserted),
         }
     }
 }
@@ -245,6 +245,8 @@ type StateModification =
       errorData: JSONValue;
       // Optional because old backend versions don't send this.
       journal?: QueryJournal;
+      // Only set for errors clients can recover from.
+      errorCode?: "InvalidCursor";
     }
   | {
       type: "QueryRemoved";
@@ -153,8 +153,23 @@ export interface CronJob {
   name: string;
   args: JSONValue;
   schedule: Schedule;
+  overlapPolicy?: OverlapPolicy;
 }
 
+/**
+ * What to do when a cron job comes due while its previous run is still in
+ * progress. Only actions can overlap.
+ *
+ * - `"skip"` (the default) drops runs that come due while the previous run is
+ *   in progress.
+ * - `"queueOne"` runs once as soon as the previous run finishes if any runs
+ *   came due in the meantime.
+ * - `"allowConcurrent"` starts every run on schedule.
+ *
+ * @public
+ */
+export type OverlapPolicy = "skip" | "queueOne" | "allowConcurrent";
+
 /**
  * Create a CronJobs object to schedule recurring tasks.
  *
@@ -492,6 +507,31 @@ export class Crons {
     );
   }
 
+  /**
+   * Set what happens when a cron job comes due while its previous run is
+   * still in progress.
+   *
+   * ```js
+   * crons.hourly("sync", { minuteUTC: 0 }, api.sync.run);
+   * crons.setOverlapPolicy("sync", "queueOne");
+   * ```
+   *
+   * @param cronIdentifier - The name of a cron job registered on this object.
+   * @param overlapPolicy - One of `"skip"`, `"queueOne"`, or
+   * `"allowConcurrent"`.
+   */
+  setOverlapPolicy(cronIdentifier: string, overlapPolicy: OverlapPolicy) {
+    if (!(cronIdentifier in this.crons)) {
+      throw new Error(`Unknown cron identifier: ${cronIdentifier}`);
+    }
+    if (!["skip", "queueOne", "allowConcurrent"].includes(overlapPolicy)) {
+      throw new Error(
+        'Overlap policy must be "skip", "queueOne", or "allowConcurrent".',
+      );
+    }
+    this.crons[cronIdentifier].overlapPolicy = overlapPolicy;
+  }
+
   /** @internal */
   export() {
     return JSON.stringify(this.crons);
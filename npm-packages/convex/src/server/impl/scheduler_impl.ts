@@ -29,6 +29,18 @@ export function setupMutationScheduler(): Scheduler {
       );
       return await performAsyncSyscall("1.0/schedule", syscallArgs);
     },
+    enqueue: async (
+      dedupKey: string,
+      delayMs: number,
+      functionReference: SchedulableFunctionReference,
+      args?: Record<string, Value>,
+    ) => {
+      const syscallArgs = {
+        ...runAfterSyscallArgs(delayMs, functionReference, args),
+        dedupKey: validateDedupKey(dedupKey),
+      };
+      return await performAsyncSyscall("1.0/schedule", syscallArgs);
+    },
     cancel: async (id: Id<"_scheduled_functions">) => {
       validateArg(id, 1, "cancel", "id");
       const args = { id: convexToJson(id) };
@@ -61,6 +73,19 @@ export function setupActionScheduler(requestId: string): Scheduler {
       };
       return await performAsyncSyscall("1.0/actions/schedule", syscallArgs);
     },
+    enqueue: async (
+      dedupKey: string,
+      delayMs: number,
+      functionReference: SchedulableFunctionReference,
+      args?: Record<string, Value>,
+    ) => {
+      const syscallArgs = {
+        requestId,
+        ...runAfterSyscallArgs(delayMs, functionReference, args),
+        dedupKey: validateDedupKey(dedupKey),
+      };
+      return await performAsyncSyscall("1.0/actions/schedule", syscallArgs);
+    },
     cancel: async (id: Id<"_scheduled_functions">) => {
       validateArg(id, 1, "cancel", "id");
       const syscallArgs = { id: convexToJson(id) };
@@ -69,6 +94,13 @@ export function setupActionScheduler(requestId: string): Scheduler {
   };
 }
 
+function validateDedupKey(dedupKey: string) {
+  if (typeof dedupKey !== "string" || dedupKey.length === 0) {
+    throw new Error("`dedupKey` must be a non-empty string");
+  }
+  return dedupKey;
+}
+
 function runAfterSyscallArgs(
   delayMs: number,
   functionReference: SchedulableFunctionReference,
@@ -129,7 +129,7 @@ export * from "./search_filter_builder.js";
 export * from "./storage.js";
 export type { Scheduler, SchedulableFunctionReference } from "./scheduler.js";
 export { cronJobs } from "./cron.js";
-export type { CronJob, Crons } from "./cron.js";
+export type { CronJob, Crons, OverlapPolicy } from "./cron.js";
 export type {
   SystemFields,
   IdField,
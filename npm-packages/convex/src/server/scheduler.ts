@@ -61,6 +61,33 @@ export interface Scheduler {
     ...args: OptionalRestArgs<FuncRef>
   ): Promise<Id<"_scheduled_functions">>;
 
+  /**
+   * Schedule a function to execute after a delay, at most once per
+   * `dedupKey`.
+   *
+   * If a function was already scheduled with the same `dedupKey` (and hasn't
+   * been garbage collected yet), no new function is scheduled and the id of the
+   * existing one is returned. This makes it safe to retry scheduling from an
+   * action, e.g. after handing off work from an external system.
+   *
+   * This method is optional so that existing implementations of `Scheduler`,
+   * such as test doubles, keep compiling. It is always defined on the
+   * scheduler passed to Convex functions.
+   *
+   * @param dedupKey - A key identifying this unit of work. Must be at most 255
+   * characters.
+   * @param delayMs - Delay in milliseconds. Must be non-negative.
+   * @param functionReference - A {@link FunctionReference} for the function
+   * to schedule.
+   * @param args - Arguments to call the scheduled functions with.
+   **/
+  enqueue?<FuncRef extends SchedulableFunctionReference>(
+    dedupKey: string,
+    delayMs: number,
+    functionReference: FuncRef,
+    ...args: OptionalRestArgs<FuncRef>
+  ): Promise<Id<"_scheduled_functions">>;
+
   /**
    * Cancels a previously scheduled function if it has not started yet. If the
    * scheduled function is already in progress, it will continue running but
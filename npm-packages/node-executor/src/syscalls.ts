@@ -35,6 +35,7 @@ const scheduleSchema = z.object({
   name: z.string(),
   ts: z.number(),
   args: z.any(),
+  dedupKey: z.optional(z.string()),
   version: z.string(),
 });
 
@@ -489,6 +490,7 @@ export class SyscallsImpl {
         udfPath: scheduleArgs.name,
         udfArgs: scheduleArgs.args,
         scheduledTs: scheduleArgs.ts,
+        dedupKey: scheduleArgs.dedupKey,
       },
       path: "/api/actions/schedule_job",
       operationName,
@@ -96,6 +96,13 @@ const analyzedCronSpec = v.object({
   udfPath: v.string(),
   udfArgs: v.bytes(),
   cronSchedule: CronSchedule,
+  overlapPolicy: v.optional(
+    v.union(
+      v.object({ type: v.literal("skip") }),
+      v.object({ type: v.literal("queueOne") }),
+      v.object({ type: v.literal("allowConcurrent") }),
+    ),
+  ),
 });
 
 const mappedModule = v.object({
@@ -165,6 +172,10 @@ const cronJobStatus = v.union(
     type: v.literal("canceled"),
     num_canceled: v.int64(),
   }),
+  v.object({
+    type: v.literal("skipped"),
+    num_skipped: v.int64(),
+  }),
 );
 
 // Log sinks
@@ -299,9 +310,27 @@ export default defineSchema({
     ),
     udfArgs: v.bytes(),
     component: v.optional(v.string()),
+    dedupKey: v.optional(v.string()),
   })
     .index("by_udf_path_and_next_event_ts", ["udfPath", "nextTs"])
-    .index("by_next_ts", ["nextTs"]),
+    .index("by_next_ts", ["nextTs"])
+    .index("by_dedup_key", ["dedupKey"]),
+  _scheduled_job_dead_letters: defineTable({
+    jobId: v.string(),
+    component: v.string(),
+    udfPath: v.string(),
+    udfArgs: v.bytes(),
+    error: v.string(),
+    attempts: v.object({
+      systemErrors: v.int64(),
+      occErrors: v.int64(),
+    }),
+    originalScheduledTs: v.int64(),
+    failedTs: v.int64(),
+    replayedJobId: v.union(v.string(), v.null()),
+  })
+    .index("by_failed_ts", ["failedTs"])
+    .index("by_udf_path_and_failed_ts", ["udfPath", "failedTs"]),
   _cron_jobs: defineTable({
     name: v.string(),
     cronSpec: analyzedCronSpec,
@@ -311,6 +340,7 @@ export default defineSchema({
     ),
     nextTs: v.int64(),
     prevTs: v.union(v.int64(), v.null()),
+    concurrentRuns: v.optional(v.array(v.int64())),
   })
     .index("by_next_ts", ["nextTs"])
     .index("by_name", ["name"]),